@@ -28,7 +28,7 @@ pub fn command_line() -> CommandLineArguments {
         )
         .arg(
             Arg::new(media.clone())
-                .help(gettext("Path to the input media file"))
+                .help(gettext("Path to the input media or project file"))
                 .last(false),
         );
     cmd.build();
@@ -1,13 +1,14 @@
 use directories::ProjectDirs;
-use log::{debug, error};
+use log::{debug, error, warn};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 
 use std::{
+    collections::BTreeMap,
     fs::{create_dir_all, File},
     io::Write,
     ops::{Deref, DerefMut},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::RwLock,
 };
 
@@ -15,26 +16,258 @@ use crate::{gettext, APP_NAME, SLD, TLD};
 
 const CONFIG_FILENAME: &str = "config.ron";
 
+/// Version of the configuration schema.
+///
+/// Increment when a change requires migrating the configurations
+/// written by previous versions (see `Config::migrate`).
+const VERSION: u32 = 1;
+const MAX_RECENT_MEDIA: usize = 10;
+
 pub static CONFIG: Lazy<RwLock<GlobalConfig>> = Lazy::new(|| RwLock::new(GlobalConfig::new()));
 
+/// An RGB color with components in the range [0.0, 1.0].
+pub type Color = (f64, f64, f64);
+
+/// User defined colors for the waveform and its overlay.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ThemeColors {
+    pub background: Color,
+    pub axis: Color,
+    pub center: Color,
+    pub left: Color,
+    pub right: Color,
+    pub not_localized: Color,
+    pub cursor: Color,
+    pub boundaries: Color,
+    pub selection: Color,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Theme {
+    /// Dark or light depending on the GTK preference.
+    System,
+    Dark,
+    Light,
+    HighContrast,
+    Custom(ThemeColors),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::System
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct UI {
     pub width: i32,
     pub height: i32,
     pub paned_pos: i32,
     pub is_chapters_list_hidden: bool,
+    #[serde(default)]
+    pub theme: Theme,
+}
+
+/// A recently opened media or project.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RecentMedia {
+    pub path: PathBuf,
+    /// Last playback position in nanoseconds.
+    #[serde(default)]
+    pub position: u64,
+    /// Whether the table of contents was edited when the media was last closed.
+    #[serde(default)]
+    pub is_toc_edited: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Media {
+    /// Moved to `Preferences::is_gl_disabled` in version 1.
+    #[serde(default, skip_serializing)]
     pub is_gl_disabled: bool,
     pub last_path: Option<PathBuf>,
+    /// Most recently used first.
+    #[serde(default)]
+    pub recent: Vec<RecentMedia>,
+    #[serde(default)]
+    pub reopen_last: bool,
+}
+
+impl Media {
+    pub fn recent(&self, path: &Path) -> Option<&RecentMedia> {
+        self.recent.iter().find(|recent| recent.path == path)
+    }
+
+    /// Moves the media at `path` to the top of the recent list.
+    pub fn add_recent(&mut self, path: &Path) {
+        let recent = match self.recent.iter().position(|recent| recent.path == path) {
+            Some(idx) => self.recent.remove(idx),
+            None => RecentMedia {
+                path: path.to_owned(),
+                position: 0,
+                is_toc_edited: false,
+            },
+        };
+
+        self.recent.insert(0, recent);
+        self.recent.truncate(MAX_RECENT_MEDIA);
+    }
+
+    /// Records the state of the media at `path` when it is closed.
+    pub fn update_recent(&mut self, path: &Path, position: u64, is_toc_edited: bool) {
+        if let Some(recent) = self.recent.iter_mut().find(|recent| recent.path == path) {
+            recent.position = position;
+            recent.is_toc_edited = is_toc_edited;
+        }
+    }
+}
+
+/// Where to snap the chapter boundaries to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapMode {
+    ZeroCrossing,
+    Silence,
+    Onset,
+}
+
+impl Default for SnapMode {
+    fn default() -> Self {
+        SnapMode::ZeroCrossing
+    }
+}
+
+/// Step by which a chapter boundary is nudged from the keyboard.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NudgeStep {
+    /// One audio sample.
+    Sample,
+    Millisecond,
+    TenMilliseconds,
+    /// One frame of the selected video stream.
+    Frame,
+}
+
+impl NudgeStep {
+    /// Returns the step which follows `self` in the steps cycle.
+    pub fn next(self) -> Self {
+        match self {
+            NudgeStep::Sample => NudgeStep::Millisecond,
+            NudgeStep::Millisecond => NudgeStep::TenMilliseconds,
+            NudgeStep::TenMilliseconds => NudgeStep::Frame,
+            NudgeStep::Frame => NudgeStep::Sample,
+        }
+    }
+}
+
+impl Default for NudgeStep {
+    fn default() -> Self {
+        NudgeStep::TenMilliseconds
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Editing {
+    pub is_snapping_enabled: bool,
+    pub snap_mode: SnapMode,
+    pub snap_radius_ms: u64,
+    pub nudge_step: NudgeStep,
+    /// Played before the loop region each time it starts over, in milliseconds.
+    pub loop_preroll_ms: u64,
+    /// Played before and after a boundary when auditioning it, in milliseconds.
+    pub audition_radius_ms: u64,
+}
+
+impl Default for Editing {
+    fn default() -> Self {
+        Editing {
+            is_snapping_enabled: false,
+            snap_mode: SnapMode::default(),
+            snap_radius_ms: 20,
+            nudge_step: NudgeStep::default(),
+            loop_preroll_ms: 0,
+            audition_radius_ms: 1500,
+        }
+    }
+}
+
+/// User defined accelerators.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Keymap {
+    /// Accelerators by action name, e.g. `"play_pause": ["space"]`.
+    ///
+    /// Actions which are not listed use their default accelerators.
+    pub accels: BTreeMap<String, Vec<String>>,
+}
+
+/// User preferences, edited in the preferences dialog.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Preferences {
+    pub is_gl_disabled: bool,
+    /// Max duration that the playback queues can hold, in milliseconds.
+    pub queue_size_ms: u64,
+    /// Duration of the samples kept for the waveform, in milliseconds.
+    pub buffer_size_ms: u64,
+    /// Duration of the waveform displayed in 1000 pixels when a media is opened,
+    /// in milliseconds.
+    pub zoom_duration_ms: u64,
+    /// Name of the format selected in the export perspective at startup.
+    pub export_format: Option<String>,
+    /// Name of the format selected in the split perspective at startup.
+    pub split_format: Option<String>,
+    /// Folder for the exported & split files. Defaults to the folder of the media.
+    pub output_dir: Option<PathBuf>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Preferences {
+            is_gl_disabled: false,
+            queue_size_ms: 5_000,
+            buffer_size_ms: 5_000,
+            zoom_duration_ms: 4_000,
+            export_format: None,
+            split_format: None,
+            output_dir: None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Config {
+    /// Configurations written before versioning was introduced default to 0.
+    #[serde(default)]
+    pub version: u32,
     pub ui: UI,
     pub media: Media,
+    #[serde(default)]
+    pub editing: Editing,
+    #[serde(default)]
+    pub keymap: Keymap,
+    #[serde(default)]
+    pub preferences: Preferences,
+}
+
+impl Config {
+    /// Converts a configuration written by a previous version to current schema.
+    fn migrate(&mut self) {
+        if self.version > VERSION {
+            warn!(
+                "configuration version {} is newer than supported version {}",
+                self.version, VERSION,
+            );
+            return;
+        }
+
+        if self.version < 1 {
+            self.preferences.is_gl_disabled = self.media.is_gl_disabled;
+            self.media.is_gl_disabled = false;
+        }
+
+        self.version = VERSION;
+    }
 }
 
 pub struct GlobalConfig {
@@ -72,9 +305,12 @@ impl GlobalConfig {
             Err(_) => Config::default(),
         };
 
+        let mut current = last.clone();
+        current.migrate();
+
         GlobalConfig {
             path,
-            current: last.clone(),
+            current,
             last,
         }
     }
@@ -143,3 +379,46 @@ impl DerefMut for GlobalConfig {
         &mut self.current
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_unversioned() {
+        let mut config: Config = ron::de::from_str(
+            "(
+                ui: (width: 800, height: 600, paned_pos: 400, is_chapters_list_hidden: false),
+                media: (is_gl_disabled: true, last_path: None),
+            )",
+        )
+        .unwrap();
+        assert_eq!(config.version, 0);
+
+        config.migrate();
+        assert_eq!(config.version, VERSION);
+        assert!(config.preferences.is_gl_disabled);
+        assert!(!config.media.is_gl_disabled);
+        assert_eq!(config.ui.width, 800);
+
+        // The legacy field is no longer written
+        let config_str = ron::ser::to_string(&config).unwrap();
+        let mut config: Config = ron::de::from_str(&config_str).unwrap();
+        assert!(!config.media.is_gl_disabled);
+        config.migrate();
+        assert!(config.preferences.is_gl_disabled);
+    }
+
+    #[test]
+    fn keep_newer_version() {
+        let mut config = Config {
+            version: VERSION + 1,
+            ..Config::default()
+        };
+        config.media.is_gl_disabled = true;
+
+        config.migrate();
+        assert_eq!(config.version, VERSION + 1);
+        assert!(!config.preferences.is_gl_disabled);
+    }
+}
@@ -19,7 +19,9 @@ mod command_line;
 pub use self::command_line::{command_line, CommandLineArguments};
 
 mod configuration;
-pub use self::configuration::CONFIG;
+pub use self::configuration::{
+    Keymap, NudgeStep, Preferences, RecentMedia, SnapMode, Theme, ThemeColors, CONFIG,
+};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "gettext")] {
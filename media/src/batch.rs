@@ -0,0 +1,569 @@
+use futures::{
+    channel::mpsc as async_mpsc,
+    future::{self, Either},
+    prelude::*,
+    stream,
+};
+use gst::glib;
+use log::{error, info, warn};
+
+use std::{
+    fs,
+    ops::Deref,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use application::gettext;
+use metadata::{Exporter, Factory, Format, MatroskaTocFormat, MediaInfo, TocVisitor};
+use renderers::Timestamp;
+
+use crate::{pipeline, MediaEvent};
+
+const MEDIA_EVENT_CHANNEL_CAPACITY: usize = 1;
+const PROGRESS_PERIOD: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobStatus {
+    Pending,
+    /// With the progress in the range `[0, 1]`.
+    Running(f64),
+    Done,
+    Failed(String),
+    Canceled,
+}
+
+impl JobStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(self, JobStatus::Done)
+    }
+
+    pub fn is_over(&self) -> bool {
+        !matches!(self, JobStatus::Pending | JobStatus::Running(_))
+    }
+}
+
+/// Status update for the job at `idx` in the batch.
+#[derive(Clone, Debug)]
+pub struct JobEvent {
+    pub idx: usize,
+    pub status: JobStatus,
+}
+
+/// Exports the table of contents of a media or splits it into chapters.
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub media_path: PathBuf,
+    /// The table of contents to use.
+    ///
+    /// If `None`, the table of contents file next to the media is used if any,
+    /// otherwise, the media's own table of contents.
+    pub toc_path: Option<PathBuf>,
+    pub format: Format,
+    /// The directory for the output files. Defaults to the media's directory.
+    pub output_dir: Option<PathBuf>,
+    pub overwrite: bool,
+}
+
+impl Job {
+    pub fn new(media_path: impl Into<PathBuf>, format: Format) -> Self {
+        Job {
+            media_path: media_path.into(),
+            toc_path: None,
+            format,
+            output_dir: None,
+            overwrite: false,
+        }
+    }
+
+    pub fn check_requirements(format: Format) -> Result<(), String> {
+        match format {
+            Format::Matroska => pipeline::TocSetter::check_requirements(),
+            Format::Flac | Format::Wave | Format::Opus | Format::Vorbis | Format::MP3 => {
+                pipeline::Splitter::check_requirements(format)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn output_path(&self, file_name: &str) -> PathBuf {
+        match self.output_dir.as_ref() {
+            Some(output_dir) => output_dir.join(file_name),
+            None => self.media_path.with_file_name(file_name),
+        }
+    }
+
+    /// Loads the table of contents to use for this job in `info`.
+    fn load_toc(&self, info: &mut MediaInfo) -> Result<(), String> {
+        let toc_path = self.toc_path.clone().or_else(|| {
+            Factory::extensions()
+                .into_iter()
+                .map(|(extension, _)| {
+                    info.path
+                        .with_file_name(format!("{}.{}", info.name, extension))
+                })
+                .find(|path| path.is_file())
+        });
+
+        if let Some(toc_path) = toc_path {
+            let format = Factory::format_for(&toc_path).ok_or_else(|| {
+                gettext("Unknown table of contents format for {}").replacen(
+                    "{}",
+                    &toc_path.to_string_lossy(),
+                    1,
+                )
+            })?;
+
+            let mut toc_file = fs::File::open(&toc_path).map_err(|err| {
+                gettext("Failed to open {}: {}")
+                    .replacen("{}", &toc_path.to_string_lossy(), 1)
+                    .replacen("{}", &err.to_string(), 1)
+            })?;
+
+            info.toc = Factory::reader(format).read(info, &mut toc_file)?;
+        } else if info.toc.is_none() && info.is_mp3() {
+            let mut media_file = fs::File::open(&info.path).map_err(|err| {
+                gettext("Failed to open {}: {}")
+                    .replacen("{}", &info.path.to_string_lossy(), 1)
+                    .replacen("{}", &err.to_string(), 1)
+            })?;
+            info.toc = Factory::reader(Format::Id3v2).read(info, &mut media_file)?;
+        }
+
+        let toc = info
+            .toc
+            .as_ref()
+            .ok_or_else(|| gettext("The table of contents is empty"))?;
+
+        let mut chapter_count = 0;
+        let mut toc_visitor = TocVisitor::new(toc);
+        while toc_visitor.next_chapter().is_some() {
+            chapter_count += 1;
+        }
+        info.chapter_count = Some(chapter_count);
+
+        Ok(())
+    }
+
+    async fn run(&self, report: &mut dyn FnMut(f64)) -> Result<(), String> {
+        let mut info = pipeline::Probe::run(&self.media_path)
+            .await
+            .map_err(|err| gettext("Error opening file. {}").replacen("{}", &err, 1))?;
+        self.load_toc(&mut info)?;
+
+        match self.format {
+            Format::CueSheet
+            | Format::Description
+            | Format::FFMetadata
+            | Format::Id3v2
+            | Format::MKVMergeText
+            | Format::PodcastChapters
+            | Format::Srt
+            | Format::WebVtt => self.write_toc(&info),
+            Format::Matroska => self.export_matroska(&info, report).await,
+            Format::Flac | Format::Wave | Format::Opus | Format::Vorbis | Format::MP3 => {
+                self.split(&info, report).await
+            }
+        }
+    }
+
+    fn write_toc(&self, info: &MediaInfo) -> Result<(), String> {
+        let (_, content) = info.streams.ids_to_export(self.format);
+        let output = Output::try_new(
+            self.output_path(&format!(
+                "{}.{}",
+                info.name,
+                Factory::extension(self.format, content)
+            )),
+            self.overwrite,
+        )?;
+
+        let mut output_file = fs::File::create(&output.path)
+            .map_err(|_| gettext("Failed to create the file for the table of contents"))?;
+        Factory::writer(self.format).write(info, &mut output_file)?;
+
+        output.complete();
+        Ok(())
+    }
+
+    async fn export_matroska(
+        &self,
+        info: &MediaInfo,
+        report: &mut dyn FnMut(f64),
+    ) -> Result<(), String> {
+        let (stream_ids, content) = info.streams.ids_to_export(self.format);
+        let output = Output::try_new(
+            self.output_path(&format!(
+                "{}.{}",
+                info.name,
+                Factory::extension(self.format, content)
+            )),
+            self.overwrite,
+        )?;
+
+        let (sender, mut receiver) = async_mpsc::channel(MEDIA_EVENT_CHANNEL_CAPACITY);
+        let mut toc_setter = PipelineGuard(
+            pipeline::TocSetter::try_new(
+                &info.path,
+                &output.path,
+                Arc::new(RwLock::new(stream_ids)),
+                sender,
+            )
+            .map_err(|err| gettext("Failed to prepare for export. {}").replacen("{}", &err, 1))?,
+        );
+
+        loop {
+            match next_event(&mut receiver, &*toc_setter, info, report).await? {
+                MediaEvent::InitDone => {
+                    MatroskaTocFormat::new().export(info, toc_setter.muxer().unwrap());
+                    toc_setter.0.export().map_err(|err| {
+                        gettext("Failed to export media. {}").replacen("{}", &err, 1)
+                    })?;
+                }
+                MediaEvent::Eos => break,
+                MediaEvent::FailedToExport(err) => {
+                    return Err(gettext("Failed to export media. {}").replacen("{}", &err, 1));
+                }
+                _ => (),
+            }
+        }
+
+        output.complete();
+        Ok(())
+    }
+
+    async fn split(&self, info: &MediaInfo, report: &mut dyn FnMut(f64)) -> Result<(), String> {
+        if info.streams.selected_audio().is_none() {
+            return Err(gettext("No audio stream to split"));
+        }
+
+        let stream_id = if info.streams.collection(gst::StreamType::AUDIO).len() > 1 {
+            info.streams
+                .selected_audio()
+                .map(|stream| stream.id.to_string())
+        } else {
+            // See `split::Processor::process`
+            None
+        };
+
+        let (_, content) = info.streams.ids_to_export(self.format);
+        let extension = Factory::extension(self.format, content);
+
+        let mut track_number = 0;
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            track_number += 1;
+
+            let output = Output::try_new(
+                self.output_path(&info.split_file_name(&chapter, Some(track_number), extension)),
+                self.overwrite,
+            )?;
+
+            let (sender, mut receiver) = async_mpsc::channel(MEDIA_EVENT_CHANNEL_CAPACITY);
+            let splitter = PipelineGuard(
+                pipeline::Splitter::try_new(
+                    &info.path,
+                    &output.path,
+                    stream_id.clone(),
+                    self.format,
+                    info.chapter_with_track_tags(&chapter, track_number),
+                    sender,
+                )
+                .map_err(|err| {
+                    gettext("Failed to prepare for split. {}").replacen("{}", &err, 1)
+                })?,
+            );
+
+            loop {
+                match next_event(&mut receiver, &*splitter, info, report).await? {
+                    MediaEvent::Eos => break,
+                    MediaEvent::FailedToExport(err) => {
+                        return Err(gettext("Failed to split media. {}").replacen("{}", &err, 1));
+                    }
+                    _ => (),
+                }
+            }
+
+            output.complete();
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs the `jobs` with at most `max_parallel` of them at once.
+///
+/// Each job is identified by its index in the batch. Failing jobs don't prevent
+/// the others from running. The status of the jobs is reported using `sender`.
+///
+/// Dropping the returned `Future` cancels the running jobs.
+pub async fn run(
+    jobs: Vec<(usize, Job)>,
+    max_parallel: usize,
+    sender: async_mpsc::UnboundedSender<JobEvent>,
+) {
+    stream::iter(jobs)
+        .for_each_concurrent(max_parallel.max(1), |(idx, job)| {
+            let mut reporter = StatusReporter::new(idx, sender.clone());
+            async move {
+                info!(
+                    "{}",
+                    gettext("Processing {}...").replacen(
+                        "{}",
+                        &job.media_path.to_string_lossy(),
+                        1
+                    )
+                );
+
+                reporter.send(JobStatus::Running(0f64));
+                let res = job
+                    .run(&mut |progress| reporter.send(JobStatus::Running(progress)))
+                    .await;
+
+                reporter.finish(match res {
+                    Ok(()) => JobStatus::Done,
+                    Err(err) => {
+                        error!(
+                            "{}",
+                            gettext("Failed to process {}: {}")
+                                .replacen("{}", &job.media_path.to_string_lossy(), 1)
+                                .replacen("{}", &err, 1)
+                        );
+                        JobStatus::Failed(err)
+                    }
+                });
+            }
+        })
+        .await;
+}
+
+/// Waits for the next `MediaEvent`, reporting the progress of `pipeline` meanwhile.
+async fn next_event(
+    receiver: &mut async_mpsc::Receiver<MediaEvent>,
+    pipeline: &dyn JobPipeline,
+    info: &MediaInfo,
+    report: &mut dyn FnMut(f64),
+) -> Result<MediaEvent, String> {
+    let mut ticks = glib::interval_stream(PROGRESS_PERIOD);
+    loop {
+        match future::select(receiver.next(), ticks.next()).await {
+            Either::Left((Some(event), _)) => return Ok(event),
+            Either::Left((None, _)) => return Err(gettext("The media processing stopped")),
+            Either::Right(_) => {
+                if info.duration > metadata::Duration::default() {
+                    if let Some(ts) = pipeline.current_ts() {
+                        report(ts.as_f64() / info.duration.as_f64());
+                    }
+                }
+            }
+        }
+    }
+}
+
+trait JobPipeline {
+    fn current_ts(&self) -> Option<Timestamp>;
+    fn cancel(&self);
+}
+
+impl JobPipeline for pipeline::TocSetter {
+    fn current_ts(&self) -> Option<Timestamp> {
+        Some(pipeline::TocSetter::current_ts(self))
+    }
+
+    fn cancel(&self) {
+        pipeline::TocSetter::cancel(self);
+    }
+}
+
+impl JobPipeline for pipeline::Splitter {
+    fn current_ts(&self) -> Option<Timestamp> {
+        pipeline::Splitter::current_ts(self)
+    }
+
+    fn cancel(&self) {
+        pipeline::Splitter::cancel(self);
+    }
+}
+
+/// Stops the pipeline when the job is over or dropped.
+struct PipelineGuard<P: JobPipeline>(P);
+
+impl<P: JobPipeline> Deref for PipelineGuard<P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+impl<P: JobPipeline> Drop for PipelineGuard<P> {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Removes the output file unless it is completed.
+struct Output {
+    path: PathBuf,
+    is_complete: bool,
+}
+
+impl Output {
+    fn try_new(path: PathBuf, overwrite: bool) -> Result<Self, String> {
+        if !overwrite && path.exists() {
+            return Err(gettext("{output_file}\nalready exists.").replacen(
+                "{output_file}",
+                &path.to_string_lossy(),
+                1,
+            ));
+        }
+
+        Ok(Output {
+            path,
+            is_complete: false,
+        })
+    }
+
+    fn complete(mut self) {
+        self.is_complete = true;
+    }
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        if !self.is_complete && self.path.exists() && fs::remove_file(&self.path).is_err() {
+            warn!(
+                "Failed to remove incomplete output file {}",
+                self.path.to_string_lossy()
+            );
+        }
+    }
+}
+
+/// Reports `Canceled` if the job is dropped before it is over.
+struct StatusReporter {
+    idx: usize,
+    sender: async_mpsc::UnboundedSender<JobEvent>,
+    is_over: bool,
+}
+
+impl StatusReporter {
+    fn new(idx: usize, sender: async_mpsc::UnboundedSender<JobEvent>) -> Self {
+        StatusReporter {
+            idx,
+            sender,
+            is_over: false,
+        }
+    }
+
+    fn send(&self, status: JobStatus) {
+        let _ = self.sender.unbounded_send(JobEvent {
+            idx: self.idx,
+            status,
+        });
+    }
+
+    fn finish(&mut self, status: JobStatus) {
+        self.is_over = true;
+        self.send(status);
+    }
+}
+
+impl Drop for StatusReporter {
+    fn drop(&mut self) {
+        if !self.is_over {
+            self.send(JobStatus::Canceled);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use metadata::{Duration, Format, MediaInfo};
+
+    use super::{Job, JobStatus, Output};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("media-toc-batch-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn status() {
+        assert!(!JobStatus::Pending.is_over());
+        assert!(!JobStatus::Running(0.5f64).is_over());
+        assert!(JobStatus::Done.is_over());
+        assert!(JobStatus::Failed("error".to_string()).is_over());
+        assert!(!JobStatus::Canceled.is_done());
+    }
+
+    #[test]
+    fn output() {
+        let dir = test_dir("output");
+        let path = dir.join("album.cue");
+
+        // Incomplete outputs are removed
+        let output = Output::try_new(path.clone(), false).unwrap();
+        fs::write(&output.path, "incomplete").unwrap();
+        drop(output);
+        assert!(!path.exists());
+
+        let output = Output::try_new(path.clone(), false).unwrap();
+        fs::write(&output.path, "complete").unwrap();
+        output.complete();
+        assert!(path.exists());
+
+        // Don't overwrite existing files unless requested
+        assert!(Output::try_new(path.clone(), false).is_err());
+        assert!(Output::try_new(path.clone(), true).is_ok());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_toc() {
+        gst::init().unwrap();
+
+        let dir = test_dir("load_toc");
+        let media_path = dir.join("album.flac");
+        fs::write(
+            dir.join("album.chapters.txt"),
+            "00:00 Intro\n01:00 Topic\n02:00 Outro\n",
+        )
+        .unwrap();
+        let other_toc_path = dir.join("other.chapters.txt");
+        fs::write(&other_toc_path, "00:00 Intro\n01:00 Outro\n").unwrap();
+
+        let new_info = || MediaInfo {
+            duration: Duration::from_secs(300),
+            ..MediaInfo::new(&media_path)
+        };
+
+        // Sidecar table of contents
+        let job = Job::new(&media_path, Format::CueSheet);
+        let mut info = new_info();
+        job.load_toc(&mut info).unwrap();
+        assert_eq!(Some(3), info.chapter_count);
+
+        // Explicit table of contents
+        let job = Job {
+            toc_path: Some(other_toc_path),
+            ..Job::new(&media_path, Format::CueSheet)
+        };
+        let mut info = new_info();
+        job.load_toc(&mut info).unwrap();
+        assert_eq!(Some(2), info.chapter_count);
+
+        // No table of contents
+        let job = Job::new(dir.join("single.flac"), Format::CueSheet);
+        let mut info = MediaInfo::new(&job.media_path);
+        assert!(job.load_toc(&mut info).is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,7 +1,10 @@
+pub mod batch;
+
 pub mod pipeline;
 pub use pipeline::{MissingPlugins, OpenError, SeekError, SelectStreamsError};
 
 use metadata::Duration;
+use renderers::Timestamp;
 
 /// Max duration that queues can hold.
 pub const QUEUE_SIZE: Duration = Duration::from_secs(5);
@@ -16,4 +19,16 @@ pub enum MediaEvent {
     InitDone,
     PlayRangeDone,
     StateChanged,
+    StepDone,
+    Subtitle(Subtitle),
+}
+
+/// A subtitle to display when the video stream can't overlay it.
+#[derive(Clone, Debug)]
+pub struct Subtitle {
+    pub text: String,
+    /// Whether `text` uses the Pango markup.
+    pub is_markup: bool,
+    pub start: Timestamp,
+    pub end: Option<Timestamp>,
 }
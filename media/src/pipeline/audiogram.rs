@@ -0,0 +1,338 @@
+use futures::channel::mpsc as async_mpsc;
+use gst::{glib, prelude::*, ClockTime};
+use log::{debug, error, info, warn};
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use application::gettext;
+use renderers::{plugin, Timestamp};
+
+use crate::MediaEvent;
+
+// Encoders are tried in this order
+const AAC_ENCODERS: [&str; 3] = ["fdkaacenc", "avenc_aac", "voaacenc"];
+
+/// Video container & codecs for the audiogram.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AudiogramFormat {
+    /// H.264 & AAC.
+    MP4,
+    /// VP8 & Opus.
+    WebM,
+}
+
+impl AudiogramFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudiogramFormat::MP4 => "mp4",
+            AudiogramFormat::WebM => "webm",
+        }
+    }
+}
+
+/// Exports a chapter or a range as a video of its waveform with the original audio.
+pub struct Audiogram {
+    pipeline: gst::Pipeline,
+    format: AudiogramFormat,
+    chapter: gst::TocEntry,
+}
+
+impl Audiogram {
+    pub fn check_requirements(format: AudiogramFormat) -> Result<(), String> {
+        plugin::init();
+
+        let missing = |element: &str, plugins: &str| {
+            gettext("Missing `{element}`\ncheck your {plugins} install")
+                .replacen("{element}", element, 1)
+                .replacen("{plugins}", plugins, 1)
+        };
+
+        match format {
+            AudiogramFormat::MP4 => {
+                Self::make_first(&AAC_ENCODERS)
+                    .ok_or_else(|| missing(AAC_ENCODERS[1], "gst-libav"))?;
+                gst::ElementFactory::make("x264enc")
+                    .build()
+                    .map_err(|_| missing("x264enc", "gst-plugins-ugly"))?;
+                gst::ElementFactory::make("mp4mux")
+                    .build()
+                    .map(drop)
+                    .map_err(|_| missing("mp4mux", "gst-plugins-good"))
+            }
+            AudiogramFormat::WebM => {
+                gst::ElementFactory::make("opusenc")
+                    .build()
+                    .map_err(|_| missing("opusenc", "gst-plugins-base"))?;
+                gst::ElementFactory::make("vp8enc")
+                    .build()
+                    .map_err(|_| missing("vp8enc", "gst-plugins-good"))?;
+                gst::ElementFactory::make("webmmux")
+                    .build()
+                    .map(drop)
+                    .map_err(|_| missing("webmmux", "gst-plugins-good"))
+            }
+        }
+    }
+
+    /// Builds the export pipeline for the `chapter`.
+    ///
+    /// The `chapter` can also be any range in the media.
+    /// Its title, if any, is displayed in the video.
+    /// If the `stream_id` is `None`, the first audio stream is used.
+    pub fn try_new(
+        input_path: &Path,
+        output_path: &Path,
+        stream_id: Option<String>,
+        format: AudiogramFormat,
+        chapter: gst::TocEntry,
+        sender: async_mpsc::Sender<MediaEvent>,
+    ) -> Result<Audiogram, String> {
+        info!(
+            "{}",
+            gettext("Exporting audiogram {}...").replacen("{}", output_path.to_str().unwrap(), 1)
+        );
+        debug!("stream id {:?}", stream_id);
+
+        plugin::init();
+
+        let mut this = Audiogram {
+            pipeline: gst::Pipeline::new(Some("audiogram_pipeline")),
+            format,
+            chapter,
+        };
+
+        this.build_pipeline(input_path, output_path, stream_id);
+        this.register_bus_inspector(sender);
+
+        this.pipeline
+            .set_state(gst::State::Paused)
+            .map(|_| this)
+            .map_err(|_| gettext("do you have permission to write the file?"))
+    }
+
+    pub fn current_ts(&self) -> Option<Timestamp> {
+        // See `Splitter::current_ts`
+        let mut position_query = gst::query::Position::new(gst::Format::Time);
+        self.pipeline.query(&mut position_query);
+        let position = position_query.result().value();
+        if position >= 0 {
+            Some(position.into())
+        } else {
+            None
+        }
+    }
+
+    fn make_first(names: &[&str]) -> Option<gst::Element> {
+        names
+            .iter()
+            .find_map(|name| gst::ElementFactory::make(name).build().ok())
+    }
+
+    fn build_pipeline(&mut self, input_path: &Path, output_path: &Path, stream_id: Option<String>) {
+        // Input
+        let filesrc = gst::ElementFactory::make("filesrc")
+            .property("location", input_path.to_str().unwrap())
+            .build()
+            .unwrap();
+        let decodebin = gst::ElementFactory::make("decodebin").build().unwrap();
+        let audio_conv = gst::ElementFactory::make("audioconvert").build().unwrap();
+        let audio_resample = gst::ElementFactory::make("audioresample").build().unwrap();
+        let tee = gst::ElementFactory::make("tee").build().unwrap();
+
+        self.pipeline
+            .add_many(&[&filesrc, &decodebin, &audio_conv, &audio_resample, &tee])
+            .unwrap();
+        filesrc.link(&decodebin).unwrap();
+        gst::Element::link_many(&[&audio_conv, &audio_resample, &tee]).unwrap();
+
+        // Encoders & muxer
+        let (audio_enc, video_enc, muxer) = match self.format {
+            AudiogramFormat::MP4 => (
+                Self::make_first(&AAC_ENCODERS).unwrap(),
+                gst::ElementFactory::make("x264enc").build().unwrap(),
+                gst::ElementFactory::make("mp4mux").build().unwrap(),
+            ),
+            AudiogramFormat::WebM => (
+                gst::ElementFactory::make("opusenc").build().unwrap(),
+                gst::ElementFactory::make("vp8enc")
+                    .property("deadline", 1i64)
+                    .build()
+                    .unwrap(),
+                gst::ElementFactory::make("webmmux").build().unwrap(),
+            ),
+        };
+
+        // Audio branch
+        let audio_queue = gst::ElementFactory::make("queue").build().unwrap();
+        let audio_enc_conv = gst::ElementFactory::make("audioconvert").build().unwrap();
+        self.pipeline
+            .add_many(&[&audio_queue, &audio_enc_conv, &audio_enc])
+            .unwrap();
+        gst::Element::link_many(&[&tee, &audio_queue, &audio_enc_conv, &audio_enc]).unwrap();
+
+        // Video branch
+        let title = self.chapter.tags().and_then(|tags| {
+            tags.get::<gst::tags::Title>()
+                .map(|tag| tag.get().to_owned())
+        });
+        let video_queue = gst::ElementFactory::make("queue").build().unwrap();
+        let audiogram_conv = gst::ElementFactory::make("audioconvert").build().unwrap();
+        let audiogram = gst::ElementFactory::make(plugin::AUDIOGRAM_NAME)
+            .property(plugin::TITLE_PROP, title)
+            .build()
+            .unwrap();
+        let video_conv = gst::ElementFactory::make("videoconvert").build().unwrap();
+        self.pipeline
+            .add_many(&[
+                &video_queue,
+                &audiogram_conv,
+                &audiogram,
+                &video_conv,
+                &video_enc,
+            ])
+            .unwrap();
+        gst::Element::link_many(&[
+            &tee,
+            &video_queue,
+            &audiogram_conv,
+            &audiogram,
+            &video_conv,
+            &video_enc,
+        ])
+        .unwrap();
+
+        // Output
+        let outsink = gst::ElementFactory::make("filesink")
+            .name("filesink")
+            .property("location", output_path.to_str().unwrap())
+            .build()
+            .unwrap();
+        self.pipeline.add_many(&[&muxer, &outsink]).unwrap();
+        audio_enc.link(&muxer).unwrap();
+        video_enc.link(&muxer).unwrap();
+        muxer.link(&outsink).unwrap();
+
+        use gst::PadProbeData::*;
+
+        // Drop the buffers before the seek to the chapter's start,
+        // see the comments in `Splitter::build_pipeline`.
+        let (start, end) = self.chapter.start_stop_times().unwrap();
+
+        #[allow(clippy::mutex_atomic)]
+        let seek_done = Arc::new(Mutex::new(false));
+        let pipeline = self.pipeline.clone();
+        let tee_sink_pad = tee.static_pad("sink").unwrap();
+        tee_sink_pad.add_probe(gst::PadProbeType::BUFFER, move |_pad, probe_info| {
+            if let Some(Buffer(ref buffer)) = probe_info.data {
+                if buffer.flags() & gst::BufferFlags::DISCONT == gst::BufferFlags::DISCONT {
+                    let mut seek_done_grp = seek_done.lock().unwrap();
+                    if !*seek_done_grp {
+                        let _res = pipeline
+                            .seek(
+                                1f64,
+                                gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                                gst::SeekType::Set,
+                                ClockTime::try_from(start as u64).unwrap(),
+                                gst::SeekType::Set,
+                                ClockTime::try_from(end as u64).unwrap(),
+                            )
+                            .map_err(|_| {
+                                error!("{}", gettext("Failed to intialize the audiogram export"));
+                            });
+                        *seek_done_grp = true;
+                    } else {
+                        return gst::PadProbeReturn::Remove;
+                    }
+                }
+            }
+            gst::PadProbeReturn::Drop
+        });
+
+        // Drop the upstream Tags & TOC
+        tee_sink_pad.add_probe(gst::PadProbeType::EVENT_DOWNSTREAM, |_pad, probe_info| {
+            if let Some(Event(ref event)) = probe_info.data {
+                match event.view() {
+                    gst::EventView::Tag(_tag) => return gst::PadProbeReturn::Drop,
+                    gst::EventView::Toc(_toc) => return gst::PadProbeReturn::Drop,
+                    _ => (),
+                }
+            }
+            gst::PadProbeReturn::Ok
+        });
+
+        let pipeline_cb = self.pipeline.clone();
+        let is_linked = Arc::new(Mutex::new(false));
+        decodebin.connect_pad_added(move |_element, pad| {
+            let caps = pad.current_caps().unwrap();
+            let structure = caps.structure(0).unwrap();
+            let name = structure.name();
+
+            let is_selected_stream_id = stream_id.as_ref().map_or(true, |stream_id| {
+                pad.stream_id()
+                    .map_or(false, |pad_stream_id| pad_stream_id.as_str() == stream_id)
+            });
+
+            let mut is_linked = is_linked.lock().unwrap();
+            if name.starts_with("audio/") && is_selected_stream_id && !*is_linked {
+                pad.link(&audio_conv.static_pad("sink").unwrap()).unwrap();
+                *is_linked = true;
+            } else {
+                let fakesink = gst::ElementFactory::make("fakesink").build().unwrap();
+                pipeline_cb.add(&fakesink).unwrap();
+                pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+                fakesink.sync_state_with_parent().unwrap();
+            }
+        });
+    }
+
+    pub fn cancel(&self) {
+        if self.pipeline.set_state(gst::State::Null).is_err() {
+            warn!("could not stop the media");
+        }
+    }
+
+    // Uses sender to notify the UI controllers
+    fn register_bus_inspector(&self, mut sender: async_mpsc::Sender<MediaEvent>) {
+        let pipeline = self.pipeline.clone();
+        self.pipeline
+            .bus()
+            .unwrap()
+            .add_watch(move |_, msg| {
+                match msg.view() {
+                    gst::MessageView::Eos(..) => {
+                        if pipeline.set_state(gst::State::Null).is_err() {
+                            sender
+                                .try_send(MediaEvent::FailedToExport(gettext(
+                                    "Failed to terminate properly. Check the resulting file.",
+                                )))
+                                .unwrap();
+                        }
+                        sender.try_send(MediaEvent::Eos).unwrap();
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::Error(err) => {
+                        let _ =
+                            sender.try_send(MediaEvent::FailedToExport(err.error().to_string()));
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::AsyncDone(_) => {
+                        // Start exporting
+                        if pipeline.set_state(gst::State::Playing).is_err() {
+                            sender
+                                .try_send(MediaEvent::FailedToExport(gettext(
+                                    "Failed to start exporting the audiogram.",
+                                )))
+                                .unwrap();
+                        }
+                    }
+                    _ => (),
+                }
+
+                glib::Continue(true)
+            })
+            .unwrap();
+    }
+}
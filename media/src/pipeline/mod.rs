@@ -1,10 +1,26 @@
+mod audiogram;
+pub use audiogram::{Audiogram, AudiogramFormat};
+
 mod playback;
 pub use playback::{
-    MissingPlugins, OpenError, Playback, SeekError, SelectStreamsError, StateChangeError,
+    BufferSizes, Direction, MissingPlugins, OpenError, Playback, SeekError, SelectStreamsError,
+    StateChangeError, MAX_PLAYBACK_RATE, MIN_PLAYBACK_RATE,
 };
 
+mod probe;
+pub use probe::Probe;
+
+mod scene_detector;
+pub use scene_detector::{SceneAnalyzer, SceneCandidate, SceneDetector};
+
 mod splitter;
 pub use splitter::Splitter;
 
+mod subtitle_extractor;
+pub use subtitle_extractor::SubtitleExtractor;
+
+mod thumbnailer;
+pub use thumbnailer::{Thumbnailer, THUMBNAIL_HEIGHT};
+
 mod toc_setter;
 pub use toc_setter::TocSetter;
@@ -17,19 +17,23 @@ use std::{
     convert::AsRef,
     fmt,
     path::Path,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use application::gettext;
-use metadata::{media_info, MediaInfo};
+use metadata::{media_info, Duration, MediaInfo};
 use renderers::{
     generic::{self, prelude::*},
-    plugin, Timestamp,
+    plugin, SnapMode, Timestamp,
 };
 
-use crate::{MediaEvent, QUEUE_SIZE};
+use crate::{MediaEvent, Subtitle, QUEUE_SIZE};
 
 const RENDERER_BIN_NAME: &str = "media-toc-renderer-bin";
+const AUDIO_SINK_NAME: &str = "audio-playback-sink";
+
+pub const MIN_PLAYBACK_RATE: f64 = 0.5f64;
+pub const MAX_PLAYBACK_RATE: f64 = 3f64;
 
 pub struct MissingPlugins(HashSet<String>);
 
@@ -180,9 +184,39 @@ impl fmt::Display for SelectStreamsError {
 }
 impl std::error::Error for SelectStreamsError {}
 
+/// Durations of the buffers used while playing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BufferSizes {
+    /// Max duration that the queues can hold.
+    pub queue: Duration,
+    /// Duration of the samples kept for the waveform.
+    pub renderer: Duration,
+}
+
+impl Default for BufferSizes {
+    fn default() -> Self {
+        BufferSizes {
+            queue: QUEUE_SIZE,
+            renderer: QUEUE_SIZE,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
 pub struct Playback {
     pipeline: gst::Pipeline,
     renderer: gst::Element,
+    video_sink: Option<gst::Element>,
+    rate: f64,
+    direction: Direction,
+    /// Position of the video sink after frames were stepped:
+    /// the streams must be resynchronized on this position.
+    stepped_ts: Option<Timestamp>,
 
     pub info: Arc<RwLock<MediaInfo>>,
     pub missing_plugins: MissingPlugins,
@@ -196,6 +230,7 @@ impl Playback {
         path: &Path,
         dbl_visu_renderer: Box<dyn DoubleRendererImpl>,
         video_sink: &Option<gst::Element>,
+        buffer_sizes: BufferSizes,
     ) -> Result<(Playback, async_mpsc::UnboundedReceiver<MediaEvent>), OpenError> {
         plugin::init();
 
@@ -208,7 +243,7 @@ impl Playback {
                 plugin::DBL_RENDERER_IMPL_PROP,
                 &generic::GBoxedDoubleRendererImpl::from(dbl_visu_renderer),
             )
-            .property(plugin::BUFFER_SIZE_PROP, QUEUE_SIZE.as_u64())
+            .property(plugin::BUFFER_SIZE_PROP, buffer_sizes.renderer.as_u64())
             .build()
             .unwrap();
 
@@ -237,13 +272,17 @@ impl Playback {
         let mut this = Playback {
             pipeline: gst::Pipeline::new(Some("playback_pipeline")),
             renderer,
+            video_sink: video_sink.clone(),
+            rate: 1f64,
+            direction: Direction::Forward,
+            stepped_ts: None,
             info: Arc::new(RwLock::new(MediaInfo::new(path))),
             missing_plugins: MissingPlugins::new(),
             int_evt_rx,
             bus_watch_src_id: None,
         };
 
-        this.build_pipeline(path, video_sink);
+        this.build_pipeline(path, video_sink, buffer_sizes.queue, ext_evt_tx.clone());
 
         let this = Self::open(this, int_evt_tx, ext_evt_tx).await?;
         Ok((this, ext_evt_rx))
@@ -254,6 +293,10 @@ impl Playback {
             .build()
             .map(drop)
             .map_err(|_| gettext("Missing `decodebin3`\ncheck your gst-plugins-base install"))?;
+        gst::ElementFactory::make("scaletempo")
+            .build()
+            .map(drop)
+            .map_err(|_| gettext("Missing `scaletempo`\ncheck your gst-plugins-good install"))?;
         gst::ElementFactory::make("gtksink")
             .build()
             .map(drop)
@@ -286,10 +329,10 @@ impl Playback {
         dbl_visu_renderer_impl.expect("double visu renderer impl already taken")
     }
 
-    fn setup_queue(queue: &gst::Element) {
+    fn setup_queue(queue: &gst::Element, queue_size: Duration) {
         queue.set_property("max-size-bytes", &0u32);
         queue.set_property("max-size-buffers", &0u32);
-        queue.set_property("max-size-time", &QUEUE_SIZE.as_u64());
+        queue.set_property("max-size-time", &queue_size.as_u64());
 
         #[cfg(feature = "trace-playback-queues")]
         queue.connect_closure(
@@ -321,7 +364,13 @@ impl Playback {
         );
     }
 
-    fn build_pipeline(&mut self, path: &Path, video_sink: &Option<gst::Element>) {
+    fn build_pipeline(
+        &mut self,
+        path: &Path,
+        video_sink: &Option<gst::Element>,
+        queue_size: Duration,
+        ext_evt_tx: async_mpsc::UnboundedSender<MediaEvent>,
+    ) {
         let decodebin = gst::ElementFactory::make("decodebin3")
             .name("decodebin")
             .build()
@@ -330,7 +379,7 @@ impl Playback {
         // From decodebin3's documentation: "Children: multiqueue0"
         let decodebin_as_bin = decodebin.clone().downcast::<gst::Bin>().ok().unwrap();
         let decodebin_multiqueue = &decodebin_as_bin.children()[0];
-        Playback::setup_queue(decodebin_multiqueue);
+        Playback::setup_queue(decodebin_multiqueue, queue_size);
         // Discard "interleave" as it modifies "max-size-time"
         decodebin_multiqueue.set_property("use-interleave", &false);
 
@@ -343,14 +392,25 @@ impl Playback {
         file_src.link(&decodebin).unwrap();
 
         let audio_sink = gst::ElementFactory::make("autoaudiosink")
-            .name("audio-playback-sink")
+            .name(AUDIO_SINK_NAME)
             .build()
             .unwrap();
 
+        // The video and text pads can be added in any order,
+        // so the overlay is shared by both branches.
+        let subtitle_overlay = video_sink.as_ref().and_then(|_| {
+            gst::ElementFactory::make("subtitleoverlay")
+                .name("subtitle-overlay")
+                .build()
+                .map_err(|_| warn!("{}", gettext("Couldn't overlay subtitles on video")))
+                .ok()
+        });
+
         // Prepare pad configuration callback
         let pipeline_clone = self.pipeline.clone();
         let video_sink = video_sink.clone();
         let renderer = self.renderer.clone();
+        let overlay_lock = Mutex::new(());
 
         decodebin.connect_pad_added(move |_decodebin, src_pad| {
             let pipeline = &pipeline_clone;
@@ -360,19 +420,113 @@ impl Playback {
                 Playback::build_audio_pipeline(pipeline, src_pad, &audio_sink, &renderer);
             } else if name.starts_with("video_") {
                 if let Some(ref video_sink) = video_sink {
-                    Playback::build_video_pipeline(pipeline, src_pad, video_sink, &renderer);
+                    let _guard = overlay_lock.lock().unwrap();
+                    Playback::build_video_pipeline(
+                        pipeline,
+                        src_pad,
+                        video_sink,
+                        subtitle_overlay.as_ref(),
+                        &renderer,
+                    );
+                }
+            } else if name.starts_with("text_") {
+                let has_video = src_pad
+                    .sticky_event::<gst::event::StreamCollection>(0)
+                    .map_or(false, |evt| {
+                        evt.stream_collection()
+                            .iter()
+                            .any(|stream| stream.stream_type() == gst::StreamType::VIDEO)
+                    });
+
+                match subtitle_overlay.as_ref() {
+                    Some(subtitle_overlay) if has_video => {
+                        let _guard = overlay_lock.lock().unwrap();
+                        Playback::link_subtitle_overlay(pipeline, src_pad, subtitle_overlay);
+                    }
+                    _ => Playback::build_text_pipeline(pipeline, src_pad, ext_evt_tx.clone()),
                 }
-            } else {
-                // TODO: handle subtitles
-                /*let fakesink = gst::ElementFactory::make("fakesink", None).unwrap();
-                pipeline.add(&fakesink).unwrap();
-                let fakesink_sink_pad = fakesink.get_static_pad("sink").unwrap();
-                assert_eq!(src_pad.link(&fakesink_sink_pad), gst::PadLinkReturn::Ok);
-                fakesink.sync_state_with_parent().unwrap();*/
             }
         });
     }
 
+    fn add_subtitle_overlay(pipeline: &gst::Pipeline, subtitle_overlay: &gst::Element) {
+        if subtitle_overlay.parent().is_none() {
+            pipeline.add(subtitle_overlay).unwrap();
+        }
+    }
+
+    fn link_subtitle_overlay(
+        pipeline: &gst::Pipeline,
+        src_pad: &gst::Pad,
+        subtitle_overlay: &gst::Element,
+    ) {
+        Playback::add_subtitle_overlay(pipeline, subtitle_overlay);
+
+        src_pad
+            .link(&subtitle_overlay.static_pad("subtitle_sink").unwrap())
+            .unwrap();
+
+        subtitle_overlay.sync_state_with_parent().unwrap();
+    }
+
+    /// Sends the subtitles to the UI, for media without a video stream.
+    fn build_text_pipeline(
+        pipeline: &gst::Pipeline,
+        src_pad: &gst::Pad,
+        ext_evt_tx: async_mpsc::UnboundedSender<MediaEvent>,
+    ) {
+        let text_sink = gst::ElementFactory::make("fakesink")
+            .name("text-sink")
+            .property("sync", true)
+            // Subtitles are sparse: don't wait for a buffer to preroll
+            .property("async", false)
+            .property("signal-handoffs", true)
+            .build()
+            .unwrap();
+
+        text_sink.connect("handoff", false, move |args| {
+            let buffer = args[1].get::<gst::Buffer>().unwrap();
+            let pad = args[2].get::<gst::Pad>().unwrap();
+
+            let start = match buffer.pts() {
+                Some(pts) => Timestamp::from(pts),
+                None => return None,
+            };
+            let end = buffer
+                .duration()
+                .map(|duration| start + Duration::from(duration));
+
+            let is_markup = pad.current_caps().map_or(false, |caps| {
+                caps.structure(0).map_or(false, |structure| {
+                    structure
+                        .get::<&str>("format")
+                        .map_or(false, |format| format == "pango-markup")
+                })
+            });
+
+            if let Ok(map) = buffer.map_readable() {
+                let text = String::from_utf8_lossy(map.as_slice())
+                    .trim_end_matches('\0')
+                    .to_string();
+
+                let _ = ext_evt_tx.unbounded_send(MediaEvent::Subtitle(Subtitle {
+                    text,
+                    is_markup,
+                    start,
+                    end,
+                }));
+            }
+
+            None
+        });
+
+        pipeline.add(&text_sink).unwrap();
+        src_pad
+            .link(&text_sink.static_pad("sink").unwrap())
+            .unwrap();
+        text_sink.sync_state_with_parent().unwrap();
+    }
+
     fn build_audio_pipeline(
         pipeline: &gst::Pipeline,
         src_pad: &gst::Pad,
@@ -389,12 +543,27 @@ impl Playback {
             .name("audio-audioconvert")
             .build()
             .unwrap();
+        // Preserves the pitch when playing at a rate other than 1
+        let scaletempo = gst::ElementFactory::make("scaletempo")
+            .name("audio-scaletempo")
+            .build()
+            .unwrap();
+        let scaletempo_convert = gst::ElementFactory::make("audioconvert")
+            .name("audio-scaletempo-audioconvert")
+            .build()
+            .unwrap();
         let audio_resample = gst::ElementFactory::make("audioresample")
             .name("audio-audioresample")
             .build()
             .unwrap();
 
-        let elements = &[&audio_convert, &audio_resample, audio_sink];
+        let elements = &[
+            &audio_convert,
+            &scaletempo,
+            &scaletempo_convert,
+            &audio_resample,
+            audio_sink,
+        ];
         pipeline.add_many(elements).unwrap();
 
         src_pad
@@ -405,7 +574,7 @@ impl Playback {
             .link_pads(Some("audio_src"), &audio_convert, Some("sink"))
             .unwrap();
 
-        gst::Element::link_many(&[&audio_convert, &audio_resample, audio_sink]).unwrap();
+        gst::Element::link_many(elements).unwrap();
 
         renderer.sync_state_with_parent().unwrap();
         for e in elements {
@@ -417,6 +586,7 @@ impl Playback {
         pipeline: &gst::Pipeline,
         src_pad: &gst::Pad,
         video_sink: &gst::Element,
+        subtitle_overlay: Option<&gst::Element>,
         renderer: &gst::Element,
     ) {
         if pipeline.by_name(RENDERER_BIN_NAME).is_none() {
@@ -434,9 +604,22 @@ impl Playback {
         let elements = &[&convert, &scale, video_sink];
         pipeline.add_many(elements).unwrap();
 
-        renderer
-            .link_pads(Some("video_src"), &convert, Some("sink"))
-            .unwrap();
+        match subtitle_overlay {
+            Some(subtitle_overlay) => {
+                // Passes the video through as long as no subtitles are linked
+                Playback::add_subtitle_overlay(pipeline, subtitle_overlay);
+                renderer
+                    .link_pads(Some("video_src"), subtitle_overlay, Some("video_sink"))
+                    .unwrap();
+                subtitle_overlay.link(&convert).unwrap();
+                subtitle_overlay.sync_state_with_parent().unwrap();
+            }
+            None => {
+                renderer
+                    .link_pads(Some("video_src"), &convert, Some("sink"))
+                    .unwrap();
+            }
+        }
 
         gst::Element::link_many(elements).unwrap();
 
@@ -614,6 +797,9 @@ impl Playback {
                     Eos(_) => {
                         ext_evt_tx.unbounded_send(MediaEvent::Eos).unwrap();
                     }
+                    StepDone(_) => {
+                        int_evt_tx.unbounded_send(MediaEvent::StepDone).unwrap();
+                    }
                     Error(err) => {
                         // FIXME avoid copying the error (use an Rc?)
                         ext_evt_tx
@@ -634,15 +820,203 @@ impl Playback {
         self.bus_watch_src_id = Some(bus_watch_src_id);
     }
 
+    /// Returns the current position.
+    ///
+    /// After frames were stepped, this is the position of the video sink.
     pub fn current_ts(&self) -> Option<Timestamp> {
+        if self.stepped_ts.is_some() {
+            return self.stepped_ts;
+        }
+
+        Self::position(&self.pipeline)
+    }
+
+    fn position(element: &impl IsA<gst::Element>) -> Option<Timestamp> {
         let mut position_query = gst::query::Position::new(gst::Format::Time);
-        self.pipeline.query(&mut position_query);
+        if !element.query(&mut position_query) {
+            return None;
+        }
+
         match position_query.result() {
             gst::GenericFormattedValue::Time(opt_ct) => opt_ct.map(Timestamp::from),
             other => unreachable!("got {:?}", other),
         }
     }
 
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Sets the playback rate, clamped to [`MIN_PLAYBACK_RATE`, `MAX_PLAYBACK_RATE`].
+    ///
+    /// The rate is applied on next seek.
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE);
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Whether frames can be stepped, i.e. a video stream is selected.
+    pub fn can_step_frames(&self) -> bool {
+        self.video_sink.is_some() && self.info.read().unwrap().streams.is_video_selected()
+    }
+
+    /// Returns the duration of a frame of the selected video stream.
+    pub fn frame_duration(&self) -> Option<Duration> {
+        if !self.can_step_frames() {
+            return None;
+        }
+
+        let caps = self
+            .video_sink
+            .as_ref()?
+            .static_pad("sink")?
+            .current_caps()?;
+        let framerate = caps.structure(0)?.get::<gst::Fraction>("framerate").ok()?;
+        if framerate.numer() <= 0 || framerate.denom() <= 0 {
+            // Variable frame rate
+            return None;
+        }
+
+        Some(Duration::from_nanos(
+            1_000_000_000 * framerate.denom() as u64 / framerate.numer() as u64,
+        ))
+    }
+
+    /// Sets the playback direction from current position.
+    ///
+    /// This also resynchronizes the streams after frames were stepped.
+    pub async fn set_direction(&mut self, direction: Direction) -> Result<(), SeekError> {
+        if direction == self.direction && self.stepped_ts.is_none() {
+            return Ok(());
+        }
+
+        let current_ts = self.current_ts().unwrap_or_default();
+
+        self.purge_int_evt_()?;
+
+        let flags = gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE;
+        let seek_evt = match direction {
+            Direction::Forward => gst::event::Seek::new(
+                self.rate,
+                flags,
+                gst::SeekType::Set,
+                ClockTime::from(current_ts),
+                gst::SeekType::None,
+                ClockTime::NONE,
+            ),
+            // Reverse playback: play from `current_ts` back to the beginning.
+            Direction::Backward => gst::event::Seek::new(
+                -self.rate,
+                flags,
+                gst::SeekType::Set,
+                ClockTime::ZERO,
+                gst::SeekType::Set,
+                ClockTime::from(current_ts),
+            ),
+        };
+
+        let seqnum = seek_evt.seqnum();
+        if !self.pipeline.send_event(seek_evt) {
+            panic!("failed to seek {:?} {:?}", direction, seqnum);
+        }
+
+        while let Some(event) = self.int_evt_rx.next().await {
+            use MediaEvent::*;
+            match event {
+                AsyncDone => break,
+                Error(_) => return Err(SeekError::Unrecoverable),
+                _ => (),
+            }
+        }
+
+        self.direction = direction;
+        self.stepped_ts = None;
+
+        Ok(())
+    }
+
+    /// Steps one video frame in the `direction`.
+    ///
+    /// The audio sink is stepped up to the video position so that the waveform
+    /// cursor follows the frames. The streams are resynchronized on the video
+    /// position on next call to [`set_direction`](Self::set_direction).
+    pub async fn step_frame(&mut self, direction: Direction) -> Result<(), SeekError> {
+        if !self.can_step_frames() {
+            return Ok(());
+        }
+
+        if direction != self.direction {
+            // Stepping backward requires a reverse segment and conversely.
+            self.set_direction(direction).await?;
+        }
+
+        let video_sink = self.video_sink.clone().unwrap();
+        self.step_sink(
+            &video_sink,
+            gst::event::Step::new(gst::format::Buffers::ONE, 1f64, true, false),
+        )
+        .await?;
+
+        let ts = Self::position(&video_sink).ok_or(SeekError::Unrecoverable)?;
+        self.stepped_ts = Some(ts);
+
+        // Keep the audio sink, hence the waveform cursor, at the video position
+        if let Some(audio_sink) = self.pipeline.by_name(AUDIO_SINK_NAME) {
+            let lag = Self::position(&audio_sink).and_then(|audio_ts| match direction {
+                Direction::Forward if ts > audio_ts => Some(ts - audio_ts),
+                Direction::Backward if audio_ts > ts => Some(audio_ts - ts),
+                _ => None,
+            });
+            if let Some(lag) = lag {
+                let step_evt = gst::event::Step::new(
+                    ClockTime::from_nseconds(lag.as_u64()),
+                    1f64,
+                    true,
+                    false,
+                );
+                self.step_sink(&audio_sink, step_evt).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn step_sink(
+        &mut self,
+        sink: &gst::Element,
+        step_evt: gst::Event,
+    ) -> Result<(), SeekError> {
+        self.purge_int_evt_()?;
+
+        if !sink.send_event(step_evt) {
+            return Err(SeekError::Unrecoverable);
+        }
+
+        while let Some(event) = self.int_evt_rx.next().await {
+            use MediaEvent::*;
+            match event {
+                StepDone => break,
+                Error(_) => return Err(SeekError::Unrecoverable),
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Snaps `ts` to the audio signal within `radius` using `mode`.
+    ///
+    /// Returns `ts` unchanged if the samples around `ts` are not available.
+    pub fn snap(&self, ts: Timestamp, radius: Duration, mode: SnapMode) -> Timestamp {
+        Timestamp::new(self.renderer.emit_by_name::<u64>(
+            plugin::SNAP_TS_SIGNAL,
+            &[&ts.as_u64(), &radius.as_u64(), &mode.as_u32()],
+        ))
+    }
+
     /// Purges previous internal messages if any.
     fn purge_int_evt_(&mut self) -> Result<(), PurgeError> {
         while let Ok(event) = self.int_evt_rx.try_next() {
@@ -712,7 +1086,7 @@ impl Playback {
         self.purge_int_evt_()?;
 
         let seek_evt = gst::event::Seek::new(
-            1f64,
+            self.rate,
             gst::SeekFlags::FLUSH | flags,
             gst::SeekType::Set,
             ClockTime::from(target),
@@ -725,6 +1099,8 @@ impl Playback {
         if !self.pipeline.send_event(seek_evt) {
             panic!("failed to seek {:?}", seqnum);
         }
+        self.direction = Direction::Forward;
+        self.stepped_ts = None;
 
         if target >= self.info.read().unwrap().duration {
             return Err(SeekError::Eos);
@@ -746,7 +1122,7 @@ impl Playback {
         self.purge_int_evt_()?;
 
         let seek_evt = gst::event::Seek::builder(
-            1f64,
+            self.rate,
             gst::SeekFlags::ACCURATE,
             gst::SeekType::Set,
             ClockTime::from(start),
@@ -760,6 +1136,8 @@ impl Playback {
         if !self.pipeline.send_event(seek_evt) {
             panic!("failed to seek for play range {:?}", seqnum);
         }
+        self.direction = Direction::Forward;
+        self.stepped_ts = None;
 
         if start >= self.info.read().unwrap().duration {
             return Err(SeekError::Eos);
@@ -0,0 +1,129 @@
+use futures::channel::oneshot;
+use gst::{glib, prelude::*, ClockTime};
+use log::{info, warn};
+
+use std::path::Path;
+
+use application::gettext;
+use metadata::MediaInfo;
+
+/// Retrieves the streams, tags, table of contents & duration of a media without playing it.
+pub struct Probe {
+    pipeline: gst::Pipeline,
+}
+
+impl Probe {
+    pub async fn run(path: &Path) -> Result<MediaInfo, String> {
+        info!(
+            "{}",
+            gettext("Probing {}...").replacen("{}", path.to_str().unwrap(), 1)
+        );
+
+        let this = Probe {
+            pipeline: gst::Pipeline::new(Some("probe_pipeline")),
+        };
+        this.build_pipeline(path);
+
+        let (res_tx, res_rx) = oneshot::channel();
+        this.register_bus_inspector(MediaInfo::new(path), res_tx);
+
+        this.pipeline
+            .set_state(gst::State::Paused)
+            .map_err(|_| gettext("Failed to switch the media to Paused"))?;
+
+        res_rx
+            .await
+            .unwrap_or_else(|_| Err(gettext("Failed to probe the media")))
+    }
+
+    fn build_pipeline(&self, path: &Path) {
+        let filesrc = gst::ElementFactory::make("filesrc")
+            .property("location", path.to_str().unwrap())
+            .build()
+            .unwrap();
+        let decodebin = gst::ElementFactory::make("decodebin3").build().unwrap();
+
+        self.pipeline.add_many(&[&filesrc, &decodebin]).unwrap();
+        filesrc.link(&decodebin).unwrap();
+
+        let pipeline_cb = self.pipeline.clone();
+        decodebin.connect_pad_added(move |_element, pad| {
+            let fakesink = gst::ElementFactory::make("fakesink").build().unwrap();
+            pipeline_cb.add(&fakesink).unwrap();
+            pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+            fakesink.sync_state_with_parent().unwrap();
+        });
+    }
+
+    fn register_bus_inspector(
+        &self,
+        info: MediaInfo,
+        res_tx: oneshot::Sender<Result<MediaInfo, String>>,
+    ) {
+        let pipeline = self.pipeline.clone();
+        let mut info = Some(info);
+        let mut res_tx = Some(res_tx);
+
+        self.pipeline
+            .bus()
+            .unwrap()
+            .add_watch(move |_, msg| {
+                use gst::MessageView::*;
+
+                match msg.view() {
+                    Error(err) => {
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = res_tx.take().unwrap().send(Err(err.error().to_string()));
+                        return glib::Continue(false);
+                    }
+                    StreamCollection(stream_collection) => {
+                        let info = info.as_mut().unwrap();
+                        stream_collection
+                            .stream_collection()
+                            .iter()
+                            .for_each(|stream| info.add_stream(&stream));
+                    }
+                    Tag(msg_tag) => {
+                        let tags = msg_tag.tags();
+                        if tags.scope() == gst::TagScope::Global {
+                            info.as_mut().unwrap().add_tags(&tags);
+                        }
+                    }
+                    Toc(msg_toc) => {
+                        let info = info.as_mut().unwrap();
+                        if info.toc.is_none() {
+                            let (toc, _updated) = msg_toc.toc();
+                            if toc.scope() == gst::TocScope::Global {
+                                info.toc = Some(toc);
+                            } else {
+                                warn!("skipping toc with scope: {:?}", toc.scope());
+                            }
+                        }
+                    }
+                    AsyncDone(_) => {
+                        let mut info = info.take().unwrap();
+                        info.duration = pipeline
+                            .query_duration::<ClockTime>()
+                            .unwrap_or(ClockTime::ZERO)
+                            .into();
+
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = res_tx.take().unwrap().send(Ok(info));
+                        return glib::Continue(false);
+                    }
+                    _ => (),
+                }
+
+                glib::Continue(true)
+            })
+            .unwrap();
+    }
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        if self.pipeline.set_state(gst::State::Null).is_err() {
+            warn!("could not stop probing the media");
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use futures::channel::mpsc as async_mpsc;
+use gst::{glib, prelude::*};
+use log::{debug, info, warn};
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use application::gettext;
+use metadata::Duration;
+use renderers::Timestamp;
+
+use crate::MediaEvent;
+
+/// Dimensions of the frames used for the analysis.
+const FRAME_WIDTH: usize = 160;
+const FRAME_HEIGHT: usize = 90;
+
+const HISTOGRAM_BINS: usize = 64;
+
+/// Minimum score for a frame to be considered as a scene change.
+const MIN_SCORE: f64 = 0.15f64;
+
+/// Minimum duration between two candidates.
+///
+/// Only the candidate with the highest confidence is kept within this duration.
+const MIN_SCENE_DURATION: Duration = Duration::from_millis(500);
+
+/// A position where the video stream is likely to change scene.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SceneCandidate {
+    pub ts: Timestamp,
+    /// Confidence in the range [0.0, 1.0].
+    pub confidence: f64,
+}
+
+/// Compares successive luma frames to detect scene changes.
+///
+/// The score for a frame is the mean of:
+///
+/// - the earth mover's distance between its luma histogram and the previous frame's,
+///   which is robust to small brightness variations such as fades.
+/// - the mean absolute difference of its pixels with the previous frame's,
+///   which catches cuts between scenes with similar luma distributions.
+#[derive(Debug, Default)]
+pub struct SceneAnalyzer {
+    prev_cdf: Vec<f64>,
+    prev_frame: Vec<u8>,
+    candidates: Vec<SceneCandidate>,
+}
+
+impl SceneAnalyzer {
+    /// Pushes the luma plane of the frame at `ts`.
+    ///
+    /// Frames are expected to be pushed in ascending `ts` order
+    /// and to share the same dimensions.
+    pub fn push_frame(&mut self, ts: Timestamp, luma: &[u8]) {
+        if luma.is_empty() {
+            return;
+        }
+
+        let pixels = luma.len() as f64;
+
+        let mut histogram = [0u32; HISTOGRAM_BINS];
+        for value in luma {
+            histogram[*value as usize * HISTOGRAM_BINS / 256] += 1;
+        }
+
+        // Normalized cumulative distribution
+        let mut cdf = Vec::with_capacity(HISTOGRAM_BINS);
+        let mut acc = 0u32;
+        for count in histogram {
+            acc += count;
+            cdf.push(f64::from(acc) / pixels);
+        }
+
+        if self.prev_frame.len() == luma.len() {
+            let histogram_diff = cdf
+                .iter()
+                .zip(self.prev_cdf.iter())
+                .map(|(cur, prev)| (cur - prev).abs())
+                .sum::<f64>()
+                / (HISTOGRAM_BINS - 1) as f64;
+
+            let pixel_diff = luma
+                .iter()
+                .zip(self.prev_frame.iter())
+                .map(|(cur, prev)| cur.abs_diff(*prev) as f64)
+                .sum::<f64>()
+                / (255f64 * pixels);
+
+            let score = (histogram_diff + pixel_diff) / 2f64;
+            if score >= MIN_SCORE {
+                self.add_candidate(SceneCandidate {
+                    ts,
+                    confidence: score.min(1f64),
+                });
+            }
+        }
+
+        self.prev_cdf = cdf;
+        self.prev_frame.clear();
+        self.prev_frame.extend_from_slice(luma);
+    }
+
+    fn add_candidate(&mut self, candidate: SceneCandidate) {
+        if let Some(last) = self.candidates.last_mut() {
+            if candidate.ts < last.ts + MIN_SCENE_DURATION {
+                if candidate.confidence > last.confidence {
+                    *last = candidate;
+                }
+                return;
+            }
+        }
+
+        self.candidates.push(candidate);
+    }
+
+    pub fn candidates(&self) -> &[SceneCandidate] {
+        &self.candidates
+    }
+}
+
+/// Analyzes a video stream in order to propose chapter boundaries at scene changes.
+pub struct SceneDetector {
+    pipeline: gst::Pipeline,
+    analyzer: Arc<Mutex<SceneAnalyzer>>,
+}
+
+impl SceneDetector {
+    /// Builds the analysis pipeline.
+    ///
+    /// If the `stream_id` is `None`, the first video stream is used.
+    pub fn try_new(
+        input_path: &Path,
+        stream_id: Option<String>,
+        sender: async_mpsc::Sender<MediaEvent>,
+    ) -> Result<SceneDetector, String> {
+        info!(
+            "{}",
+            gettext("Detecting scene changes in {}...").replacen(
+                "{}",
+                input_path.to_str().unwrap(),
+                1
+            )
+        );
+        debug!("stream id {:?}", stream_id);
+
+        let this = SceneDetector {
+            pipeline: gst::Pipeline::new(Some("scene_detector_pipeline")),
+            analyzer: Arc::new(Mutex::new(SceneAnalyzer::default())),
+        };
+
+        this.build_pipeline(input_path, stream_id);
+        this.register_bus_inspector(sender);
+
+        this.pipeline
+            .set_state(gst::State::Paused)
+            .map(|_| this)
+            .map_err(|_| gettext("Failed to start detecting scene changes"))
+    }
+
+    pub fn current_ts(&self) -> Option<Timestamp> {
+        // See `Splitter::current_ts`
+        let mut position_query = gst::query::Position::new(gst::Format::Time);
+        self.pipeline.query(&mut position_query);
+        let position = position_query.result().value();
+        if position >= 0 {
+            Some(position.into())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the candidates detected so far.
+    pub fn candidates(&self) -> Vec<SceneCandidate> {
+        self.analyzer.lock().unwrap().candidates().to_vec()
+    }
+
+    fn build_pipeline(&self, input_path: &Path, stream_id: Option<String>) {
+        let filesrc = gst::ElementFactory::make("filesrc")
+            .property("location", input_path.to_str().unwrap())
+            .build()
+            .unwrap();
+        let decodebin = gst::ElementFactory::make("decodebin").build().unwrap();
+        let video_conv = gst::ElementFactory::make("videoconvert").build().unwrap();
+        let video_scale = gst::ElementFactory::make("videoscale").build().unwrap();
+        let capsfilter = gst::ElementFactory::make("capsfilter")
+            .property(
+                "caps",
+                gst::Caps::builder("video/x-raw")
+                    .field("format", "GRAY8")
+                    .field("width", FRAME_WIDTH as i32)
+                    .field("height", FRAME_HEIGHT as i32)
+                    .field("pixel-aspect-ratio", gst::Fraction::new(1, 1))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+        let sink = gst::ElementFactory::make("fakesink")
+            .property("sync", false)
+            .property("signal-handoffs", true)
+            .build()
+            .unwrap();
+
+        self.pipeline
+            .add_many(&[
+                &filesrc,
+                &decodebin,
+                &video_conv,
+                &video_scale,
+                &capsfilter,
+                &sink,
+            ])
+            .unwrap();
+        filesrc.link(&decodebin).unwrap();
+        gst::Element::link_many(&[&video_conv, &video_scale, &capsfilter, &sink]).unwrap();
+
+        let analyzer = Arc::clone(&self.analyzer);
+        sink.connect("handoff", false, move |args| {
+            let buffer = args[1].get::<gst::Buffer>().unwrap();
+            if let Some(pts) = buffer.pts() {
+                if let Ok(map) = buffer.map_readable() {
+                    // GRAY8 rows are 4 bytes aligned, FRAME_WIDTH is a multiple of 4
+                    analyzer
+                        .lock()
+                        .unwrap()
+                        .push_frame(Timestamp::from(pts), map.as_slice());
+                }
+            }
+
+            None
+        });
+
+        let pipeline_cb = self.pipeline.clone();
+        let is_linked = Arc::new(Mutex::new(false));
+        decodebin.connect_pad_added(move |_element, pad| {
+            let caps = pad.current_caps().unwrap();
+            let structure = caps.structure(0).unwrap();
+            let name = structure.name();
+
+            let is_selected_stream_id = stream_id.as_ref().map_or(true, |stream_id| {
+                pad.stream_id()
+                    .map_or(false, |pad_stream_id| pad_stream_id.as_str() == stream_id)
+            });
+
+            let mut is_linked = is_linked.lock().unwrap();
+            if name.starts_with("video/") && is_selected_stream_id && !*is_linked {
+                pad.link(&video_conv.static_pad("sink").unwrap()).unwrap();
+                *is_linked = true;
+            } else {
+                let fakesink = gst::ElementFactory::make("fakesink").build().unwrap();
+                pipeline_cb.add(&fakesink).unwrap();
+                pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+                fakesink.sync_state_with_parent().unwrap();
+            }
+        });
+    }
+
+    pub fn cancel(&self) {
+        if self.pipeline.set_state(gst::State::Null).is_err() {
+            warn!("could not stop the scene detection");
+        }
+    }
+
+    // Uses sender to notify the UI controllers
+    fn register_bus_inspector(&self, mut sender: async_mpsc::Sender<MediaEvent>) {
+        let pipeline = self.pipeline.clone();
+        self.pipeline
+            .bus()
+            .unwrap()
+            .add_watch(move |_, msg| {
+                match msg.view() {
+                    gst::MessageView::Eos(..) => {
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = sender.try_send(MediaEvent::Eos);
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::Error(err) => {
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = sender.try_send(MediaEvent::Error(err.error().to_string()));
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::AsyncDone(_) => {
+                        // Start analyzing
+                        if pipeline.set_state(gst::State::Playing).is_err() {
+                            let _ = sender.try_send(MediaEvent::Error(gettext(
+                                "Failed to start detecting scene changes",
+                            )));
+                        }
+                    }
+                    _ => (),
+                }
+
+                glib::Continue(true)
+            })
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use renderers::Timestamp;
+
+    use super::{SceneAnalyzer, FRAME_HEIGHT, FRAME_WIDTH};
+
+    const FRAME_LEN: usize = FRAME_WIDTH * FRAME_HEIGHT;
+
+    fn ts(ms: u64) -> Timestamp {
+        Timestamp::new(ms * 1_000_000)
+    }
+
+    #[test]
+    fn cuts() {
+        let mut analyzer = SceneAnalyzer::default();
+
+        let dark = vec![16u8; FRAME_LEN];
+        let bright = vec![235u8; FRAME_LEN];
+
+        // 25 fps
+        for idx in 0..50 {
+            analyzer.push_frame(ts(idx * 40), &dark);
+        }
+        for idx in 50..100 {
+            analyzer.push_frame(ts(idx * 40), &bright);
+        }
+        for idx in 100..150 {
+            analyzer.push_frame(ts(idx * 40), &dark);
+        }
+
+        let candidates = analyzer.candidates();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].ts, ts(2_000));
+        assert_eq!(candidates[1].ts, ts(4_000));
+        assert!(candidates[0].confidence > 0.8);
+    }
+
+    #[test]
+    fn fade_and_close_cuts() {
+        let mut analyzer = SceneAnalyzer::default();
+
+        // Slow fade: no candidates
+        for idx in 0..100u64 {
+            analyzer.push_frame(ts(idx * 40), &vec![(idx * 2) as u8; FRAME_LEN]);
+        }
+        assert!(analyzer.candidates().is_empty());
+
+        // Two cuts within less than the minimum scene duration:
+        // only keep the most significant one
+        let mut half = vec![0u8; FRAME_LEN];
+        half[..FRAME_LEN / 2]
+            .iter_mut()
+            .for_each(|value| *value = 255);
+        analyzer.push_frame(ts(4_000), &half);
+        analyzer.push_frame(ts(4_040), &vec![0u8; FRAME_LEN]);
+        analyzer.push_frame(ts(4_080), &vec![255u8; FRAME_LEN]);
+
+        let candidates = analyzer.candidates();
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].ts, ts(4_080));
+    }
+}
@@ -0,0 +1,167 @@
+use futures::channel::mpsc as async_mpsc;
+use gst::{glib, prelude::*};
+use log::{debug, info, warn};
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use application::gettext;
+use metadata::{Duration, SubtitleCue};
+
+use crate::MediaEvent;
+
+/// Reads the cues of a subtitle stream.
+pub struct SubtitleExtractor {
+    pipeline: gst::Pipeline,
+    cues: Arc<Mutex<Vec<SubtitleCue>>>,
+}
+
+impl SubtitleExtractor {
+    /// Builds the extraction pipeline.
+    ///
+    /// If the `stream_id` is `None`, the first text stream is used.
+    pub fn try_new(
+        input_path: &Path,
+        stream_id: Option<String>,
+        sender: async_mpsc::Sender<MediaEvent>,
+    ) -> Result<SubtitleExtractor, String> {
+        info!(
+            "{}",
+            gettext("Reading subtitles from {}...").replacen("{}", input_path.to_str().unwrap(), 1)
+        );
+        debug!("stream id {:?}", stream_id);
+
+        let this = SubtitleExtractor {
+            pipeline: gst::Pipeline::new(Some("subtitle_extractor_pipeline")),
+            cues: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        this.build_pipeline(input_path, stream_id);
+        this.register_bus_inspector(sender);
+
+        this.pipeline
+            .set_state(gst::State::Paused)
+            .map(|_| this)
+            .map_err(|_| gettext("Failed to start reading subtitles"))
+    }
+
+    /// Returns the cues read so far.
+    pub fn cues(&self) -> Vec<SubtitleCue> {
+        self.cues.lock().unwrap().clone()
+    }
+
+    fn build_pipeline(&self, input_path: &Path, stream_id: Option<String>) {
+        let filesrc = gst::ElementFactory::make("filesrc")
+            .property("location", input_path.to_str().unwrap())
+            .build()
+            .unwrap();
+        let decodebin = gst::ElementFactory::make("decodebin").build().unwrap();
+        let sink = gst::ElementFactory::make("fakesink")
+            .property("sync", false)
+            .property("signal-handoffs", true)
+            .build()
+            .unwrap();
+
+        self.pipeline
+            .add_many(&[&filesrc, &decodebin, &sink])
+            .unwrap();
+        filesrc.link(&decodebin).unwrap();
+
+        // Don't decode the audio & video streams
+        decodebin.connect("autoplug-continue", false, |args| {
+            let caps = args[2].get::<gst::Caps>().unwrap();
+            let is_audio_video = caps.structure(0).map_or(false, |structure| {
+                let name = structure.name();
+                name.starts_with("audio/") || name.starts_with("video/")
+            });
+
+            Some((!is_audio_video).to_value())
+        });
+
+        let cues = Arc::clone(&self.cues);
+        sink.connect("handoff", false, move |args| {
+            let buffer = args[1].get::<gst::Buffer>().unwrap();
+            if let Some(pts) = buffer.pts() {
+                if let Ok(map) = buffer.map_readable() {
+                    let start = Duration::from(pts);
+                    let end = buffer.duration().map(|duration| {
+                        Duration::from_nanos(start.as_u64() + Duration::from(duration).as_u64())
+                    });
+                    let text = String::from_utf8_lossy(map.as_slice())
+                        .trim_end_matches('\0')
+                        .to_string();
+
+                    cues.lock().unwrap().push(SubtitleCue { start, end, text });
+                }
+            }
+
+            None
+        });
+
+        // See `SceneDetector::build_pipeline`
+        let pipeline_cb = self.pipeline.clone();
+        let is_linked = Arc::new(Mutex::new(false));
+        decodebin.connect_pad_added(move |_element, pad| {
+            let caps = pad.current_caps().unwrap();
+            let structure = caps.structure(0).unwrap();
+            let name = structure.name();
+
+            let is_selected_stream_id = stream_id.as_ref().map_or(true, |stream_id| {
+                pad.stream_id()
+                    .map_or(false, |pad_stream_id| pad_stream_id.as_str() == stream_id)
+            });
+
+            let mut is_linked = is_linked.lock().unwrap();
+            if name.starts_with("text/") && is_selected_stream_id && !*is_linked {
+                pad.link(&sink.static_pad("sink").unwrap()).unwrap();
+                *is_linked = true;
+            } else {
+                let fakesink = gst::ElementFactory::make("fakesink").build().unwrap();
+                pipeline_cb.add(&fakesink).unwrap();
+                pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+                fakesink.sync_state_with_parent().unwrap();
+            }
+        });
+    }
+
+    pub fn cancel(&self) {
+        if self.pipeline.set_state(gst::State::Null).is_err() {
+            warn!("could not stop reading subtitles");
+        }
+    }
+
+    // Uses sender to notify the UI controllers
+    fn register_bus_inspector(&self, mut sender: async_mpsc::Sender<MediaEvent>) {
+        let pipeline = self.pipeline.clone();
+        self.pipeline
+            .bus()
+            .unwrap()
+            .add_watch(move |_, msg| {
+                match msg.view() {
+                    gst::MessageView::Eos(..) => {
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = sender.try_send(MediaEvent::Eos);
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::Error(err) => {
+                        let _ = pipeline.set_state(gst::State::Null);
+                        let _ = sender.try_send(MediaEvent::Error(err.error().to_string()));
+                        return glib::Continue(false);
+                    }
+                    gst::MessageView::AsyncDone(_) => {
+                        if pipeline.set_state(gst::State::Playing).is_err() {
+                            let _ = sender.try_send(MediaEvent::Error(gettext(
+                                "Failed to start reading subtitles",
+                            )));
+                        }
+                    }
+                    _ => (),
+                }
+
+                glib::Continue(true)
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use futures::{channel::mpsc as async_mpsc, prelude::*};
+use gst::{glib, prelude::*, ClockTime};
+use log::{debug, warn};
+
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use application::gettext;
+use renderers::{Image, Timestamp};
+
+use crate::MediaEvent;
+
+/// Height of the thumbnails. The width depends on the display aspect ratio.
+pub const THUMBNAIL_HEIGHT: i32 = 48;
+
+// Cairo's `Rgb24` layout in memory
+#[cfg(target_endian = "little")]
+const THUMBNAIL_FORMAT: &str = "BGRx";
+#[cfg(target_endian = "big")]
+const THUMBNAIL_FORMAT: &str = "xRGB";
+
+/// Captures thumbnails from a video stream.
+///
+/// The pipeline is independent from `Playback` so that capturing
+/// doesn't interfere with the playback position.
+pub struct Thumbnailer {
+    pipeline: gst::Pipeline,
+    sink: gst::Element,
+    int_evt_rx: async_mpsc::UnboundedReceiver<MediaEvent>,
+    bus_watch_src_id: Option<glib::SourceId>,
+}
+
+impl Thumbnailer {
+    /// Builds the capture pipeline and waits for it to be ready.
+    ///
+    /// If the `stream_id` is `None`, the first video stream is used.
+    pub async fn try_new(
+        input_path: &Path,
+        stream_id: Option<String>,
+    ) -> Result<Thumbnailer, String> {
+        debug!("creating thumbnailer for stream id {:?}", stream_id);
+
+        let (int_evt_tx, int_evt_rx) = async_mpsc::unbounded();
+
+        let sink = gst::ElementFactory::make("fakesink")
+            .property("sync", false)
+            .property("enable-last-sample", true)
+            .build()
+            .unwrap();
+
+        let mut this = Thumbnailer {
+            pipeline: gst::Pipeline::new(Some("thumbnailer_pipeline")),
+            sink,
+            int_evt_rx,
+            bus_watch_src_id: None,
+        };
+
+        this.build_pipeline(input_path, stream_id);
+        this.register_bus_watch(int_evt_tx);
+
+        let err_msg = || gettext("Failed to prepare video thumbnails");
+
+        this.pipeline
+            .set_state(gst::State::Paused)
+            .map_err(|_| err_msg())?;
+
+        while let Some(event) = this.int_evt_rx.next().await {
+            match event {
+                MediaEvent::AsyncDone => break,
+                MediaEvent::Error(_) => return Err(err_msg()),
+                _ => (),
+            }
+        }
+
+        Ok(this)
+    }
+
+    fn build_pipeline(&self, input_path: &Path, stream_id: Option<String>) {
+        let filesrc = gst::ElementFactory::make("filesrc")
+            .property("location", input_path.to_str().unwrap())
+            .build()
+            .unwrap();
+        let decodebin = gst::ElementFactory::make("decodebin").build().unwrap();
+        let video_conv = gst::ElementFactory::make("videoconvert").build().unwrap();
+        let video_scale = gst::ElementFactory::make("videoscale").build().unwrap();
+        let capsfilter = gst::ElementFactory::make("capsfilter")
+            .property(
+                "caps",
+                gst::Caps::builder("video/x-raw")
+                    .field("format", THUMBNAIL_FORMAT)
+                    .field("height", THUMBNAIL_HEIGHT)
+                    .field("pixel-aspect-ratio", gst::Fraction::new(1, 1))
+                    .build(),
+            )
+            .build()
+            .unwrap();
+
+        self.pipeline
+            .add_many(&[
+                &filesrc,
+                &decodebin,
+                &video_conv,
+                &video_scale,
+                &capsfilter,
+                &self.sink,
+            ])
+            .unwrap();
+        filesrc.link(&decodebin).unwrap();
+        gst::Element::link_many(&[&video_conv, &video_scale, &capsfilter, &self.sink]).unwrap();
+
+        // See `SceneDetector::build_pipeline`
+        let pipeline_cb = self.pipeline.clone();
+        let is_linked = Arc::new(Mutex::new(false));
+        decodebin.connect_pad_added(move |_element, pad| {
+            let caps = pad.current_caps().unwrap();
+            let structure = caps.structure(0).unwrap();
+            let name = structure.name();
+
+            let is_selected_stream_id = stream_id.as_ref().map_or(true, |stream_id| {
+                pad.stream_id()
+                    .map_or(false, |pad_stream_id| pad_stream_id.as_str() == stream_id)
+            });
+
+            let mut is_linked = is_linked.lock().unwrap();
+            if name.starts_with("video/") && is_selected_stream_id && !*is_linked {
+                pad.link(&video_conv.static_pad("sink").unwrap()).unwrap();
+                *is_linked = true;
+            } else {
+                let fakesink = gst::ElementFactory::make("fakesink").build().unwrap();
+                pipeline_cb.add(&fakesink).unwrap();
+                pad.link(&fakesink.static_pad("sink").unwrap()).unwrap();
+                fakesink.sync_state_with_parent().unwrap();
+            }
+        });
+    }
+
+    fn register_bus_watch(&mut self, int_evt_tx: async_mpsc::UnboundedSender<MediaEvent>) {
+        let bus_watch_src_id = self
+            .pipeline
+            .bus()
+            .unwrap()
+            .add_watch(move |_, msg| {
+                match msg.view() {
+                    gst::MessageView::AsyncDone(_) => {
+                        let _ = int_evt_tx.unbounded_send(MediaEvent::AsyncDone);
+                    }
+                    gst::MessageView::Error(err) => {
+                        let _ =
+                            int_evt_tx.unbounded_send(MediaEvent::Error(err.error().to_string()));
+                    }
+                    _ => (),
+                }
+
+                glib::Continue(true)
+            })
+            .unwrap();
+
+        self.bus_watch_src_id = Some(bus_watch_src_id);
+    }
+
+    /// Captures the frame displayed at `ts`.
+    pub async fn snapshot(&mut self, ts: Timestamp) -> Result<Image, String> {
+        let err_msg = || {
+            gettext("Failed to capture a thumbnail at {}").replacen(
+                "{}",
+                &ts.for_humans().to_string(),
+                1,
+            )
+        };
+
+        // Purge previous internal messages if any
+        while let Ok(Some(_)) = self.int_evt_rx.try_next() {}
+
+        let seek_evt = gst::event::Seek::new(
+            1f64,
+            gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+            gst::SeekType::Set,
+            ClockTime::from(ts),
+            gst::SeekType::None,
+            ClockTime::NONE,
+        );
+        if !self.pipeline.send_event(seek_evt) {
+            return Err(err_msg());
+        }
+
+        while let Some(event) = self.int_evt_rx.next().await {
+            match event {
+                MediaEvent::AsyncDone => break,
+                MediaEvent::Error(_) => return Err(err_msg()),
+                _ => (),
+            }
+        }
+
+        let sample = self
+            .sink
+            .property::<Option<gst::Sample>>("last-sample")
+            .ok_or_else(err_msg)?;
+
+        let (width, height) = sample
+            .caps()
+            .and_then(|caps| caps.structure(0))
+            .and_then(|structure| {
+                let width = structure.get::<i32>("width").ok()?;
+                let height = structure.get::<i32>("height").ok()?;
+                Some((width, height))
+            })
+            .ok_or_else(err_msg)?;
+
+        let buffer = sample.buffer().ok_or_else(err_msg)?;
+        let map = buffer.map_readable().map_err(|_| err_msg())?;
+
+        // 4 bytes per pixel: rows are already 4 bytes aligned
+        Image::from_rgb24(width, height, width as usize * 4, map.as_slice()).map_err(|err| {
+            warn!("{}", err);
+            err_msg()
+        })
+    }
+}
+
+impl Drop for Thumbnailer {
+    fn drop(&mut self) {
+        if let Some(bus_watch_src_id) = self.bus_watch_src_id.take() {
+            bus_watch_src_id.remove();
+        }
+
+        if self.pipeline.set_state(gst::State::Null).is_err() {
+            warn!("could not stop the thumbnailer");
+        }
+    }
+}
@@ -1,9 +1,14 @@
-use super::{default_chapter_title, MediaInfo, Timestamp4Humans, TocVisitor, Writer};
+use super::{default_chapter_title, MediaInfo, TocVisitor, Writer};
 use application::gettext;
-use log::error;
 use std::{io::Write, string::ToString};
 
 static EXTENSION: &str = "cue";
+// Cue Sheets timestamps are expressed in frames of 1/75 s
+const FRAMES_PER_SECOND: u64 = 75;
+
+fn to_frames(nano: u64) -> u64 {
+    (nano as f64 / 1_000_000_000f64 * FRAMES_PER_SECOND as f64).round() as u64
+}
 
 #[derive(Debug, Default)]
 pub struct CueSheetFormat {}
@@ -14,26 +19,20 @@ impl CueSheetFormat {
     }
 }
 
-macro_rules! write_fmt(
-    ($dest:ident, $fmt:expr, $( $item:expr ),*) => {
-        $dest.write_fmt(format_args!($fmt, $( $item ),*)).map_err(|_| {
-            let msg = gettext("Failed to write Cue Sheet file");
-            error!("{}", msg);
-            msg
-        })?;
-    };
-);
+fn write_error() -> String {
+    gettext("Failed to write Cue Sheet file")
+}
 
 impl Writer for CueSheetFormat {
     fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
         let media_title = info.media_title();
         if let Some(title) = &media_title {
-            write_fmt!(destination, "TITLE \"{}\"\n", title);
+            write_fmt!(destination, write_error(), "TITLE \"{}\"\n", title);
         }
 
         let media_artist = info.media_artist();
         if let Some(artist) = &media_artist {
-            write_fmt!(destination, "PERFORMER \"{}\"\n", artist);
+            write_fmt!(destination, write_error(), "PERFORMER \"{}\"\n", artist);
         }
 
         let audio_codec = info.streams.audio_codec().map_or("WAVE", |audio_codec| {
@@ -47,17 +46,20 @@ impl Writer for CueSheetFormat {
         });
         write_fmt!(
             destination,
+            write_error(),
             "FILE \"{}\" {}\n",
             info.file_name(),
             audio_codec
         );
 
         let mut index = 0;
+        // End of previous track, used to detect the gaps between the tracks
+        let mut prev_end_frames = 0;
         let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
         while let Some(chapter) = toc_visitor.next_chapter() {
             index += 1;
             // FIXME: are there other TRACK types than AUDIO?
-            write_fmt!(destination, "  TRACK{:02} AUDIO\n", index);
+            write_fmt!(destination, write_error(), "  TRACK{:02} AUDIO\n", index);
 
             let title = chapter
                 .tags()
@@ -67,7 +69,7 @@ impl Writer for CueSheetFormat {
                 })
                 .or_else(|| media_title.clone())
                 .unwrap_or_else(default_chapter_title);
-            write_fmt!(destination, "    TITLE \"{}\"\n", &title);
+            write_fmt!(destination, write_error(), "    TITLE \"{}\"\n", &title);
 
             let artist = chapter
                 .tags()
@@ -77,25 +79,86 @@ impl Writer for CueSheetFormat {
                 })
                 .or_else(|| media_artist.clone())
                 .unwrap_or_else(default_chapter_title);
-            write_fmt!(destination, "    PERFORMER \"{}\"\n", &artist);
-
-            if let Some((start, _end)) = chapter.start_stop_times() {
-                let start_ts = Timestamp4Humans::from_nano(start as u64);
-                write_fmt!(
-                    destination,
-                    "    INDEX 01 {:02}:{:02}:{:02}\n",
-                    start_ts.h * 60 + start_ts.m,
-                    start_ts.s,
-                    (f64::from(
-                        (start_ts.ms as u32 * 1_000 + start_ts.us as u32) * 1_000
-                            + start_ts.nano as u32
-                    ) / 1_000_000_000f64
-                        * 75f64)
-                        .round() // frame nb (75 frames/s for Cue Sheets)
-                );
+            write_fmt!(
+                destination,
+                write_error(),
+                "    PERFORMER \"{}\"\n",
+                &artist
+            );
+
+            if let Some((start, end)) = chapter.start_stop_times() {
+                let start_frames = to_frames(start as u64);
+                if prev_end_frames < start_frames {
+                    // The gap before the track is its pregap
+                    write_index(destination, 0, prev_end_frames)?;
+                }
+                write_index(destination, 1, start_frames)?;
+
+                prev_end_frames = to_frames(end as u64);
             }
         }
 
         Ok(())
     }
 }
+
+fn write_index(destination: &mut dyn Write, number: u8, frames: u64) -> Result<(), String> {
+    write_fmt!(
+        destination,
+        write_error(),
+        "    INDEX {:02} {:02}:{:02}:{:02}\n",
+        number,
+        frames / FRAMES_PER_SECOND / 60,
+        frames / FRAMES_PER_SECOND % 60,
+        frames % FRAMES_PER_SECOND
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cue_sheet_pregaps_test() {
+    gst::init().unwrap();
+
+    let mut toc_edition = gst::TocEntry::new(gst::TocEntryType::Edition, "");
+    for (start, end) in [(2, 60), (62, 120), (120, 3_700)] {
+        let mut chapter = gst::TocEntry::new(gst::TocEntryType::Chapter, &start.to_string());
+        chapter
+            .get_mut()
+            .unwrap()
+            .set_start_stop_times(start * 1_000_000_000, end * 1_000_000_000);
+        toc_edition.get_mut().unwrap().append_sub_entry(chapter);
+    }
+    let mut toc = gst::Toc::new(gst::TocScope::Global);
+    toc.get_mut().unwrap().append_entry(toc_edition);
+
+    let info = MediaInfo {
+        file_name: "album.flac".to_string(),
+        toc: Some(toc),
+        ..MediaInfo::default()
+    };
+
+    let mut output = Vec::new();
+    CueSheetFormat::default().write(&info, &mut output).unwrap();
+
+    let indices = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .filter(|line| line.trim_start().starts_with("INDEX"))
+        .map(str::trim)
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            // Hidden track before the first track
+            "INDEX 00 00:00:00",
+            "INDEX 01 00:02:00",
+            // Gap between the first & second tracks
+            "INDEX 00 01:00:00",
+            "INDEX 01 01:02:00",
+            // Contiguous tracks
+            "INDEX 01 02:00:00",
+        ],
+        indices,
+    );
+}
@@ -0,0 +1,222 @@
+use log::error;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::space0,
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+use std::io::{Read, Write};
+
+use super::{
+    chapter_title, cues_to_toc, subtitle_cues::parse_cue_timestamp, Duration, MediaInfo, Reader,
+    SubtitleCue, Timestamp4Humans, TocVisitor, Writer,
+};
+use application::gettext;
+
+static EXTENSION: &str = "chapters.txt";
+
+static LIST_BULLETS: &str = "-*•";
+static TITLE_SEPARATORS: &str = "-–—:|";
+
+/// Chapters list as pasted in YouTube video descriptions or podcast show notes.
+///
+/// E.g.:
+///
+/// ```text
+/// 00:00 Intro
+/// 05:32 Topic
+/// ```
+#[derive(Debug, Default)]
+pub struct DescriptionFormat {}
+
+impl DescriptionFormat {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+/// Parses the timestamp at the beginning of a line, e.g.: `05:32`, `[12:34]` or `(1:02:03)`.
+fn parse_line_timestamp(i: &str) -> IResult<&str, Duration> {
+    preceded(
+        space0,
+        alt((
+            delimited(tag("["), parse_cue_timestamp, tag("]")),
+            delimited(tag("("), parse_cue_timestamp, tag(")")),
+            parse_cue_timestamp,
+        )),
+    )(i)
+}
+
+/// Returns the start and title of the chapter described by `line` if any.
+///
+/// Lines which don't start with a timestamp are ignored, so that the chapters
+/// can be picked out of a whole description.
+fn parse_line(line: &str) -> Option<(Duration, &str)> {
+    // Skip list bullets
+    let line = line.trim_start_matches(|c: char| c.is_whitespace() || LIST_BULLETS.contains(c));
+    let (rest, start) = parse_line_timestamp(line).ok()?;
+
+    // Rule out things like `12:30pm`
+    if !rest.is_empty()
+        && !rest.starts_with(|c: char| c.is_whitespace() || TITLE_SEPARATORS.contains(c))
+    {
+        return None;
+    }
+
+    let title = rest
+        .trim_start_matches(|c: char| c.is_whitespace() || TITLE_SEPARATORS.contains(c))
+        .trim_end();
+
+    Some((start, title))
+}
+
+impl Reader for DescriptionFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let mut content = String::new();
+        source.read_to_string(&mut content).map_err(|_| {
+            let msg = gettext("unexpected error reading chapters list.");
+            error!("{}", msg);
+            msg
+        })?;
+
+        let cues: Vec<SubtitleCue> = content
+            .lines()
+            .filter_map(parse_line)
+            .map(|(start, title)| SubtitleCue {
+                start,
+                end: None,
+                text: title.to_string(),
+            })
+            .collect();
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+fn write_error() -> String {
+    gettext("Failed to write chapters list")
+}
+
+impl Writer for DescriptionFormat {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        let mut chapters = Vec::new();
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            if let Some((start, _end)) = chapter.start_stop_times() {
+                let title = chapter_title(&chapter);
+                chapters.push((start as u64, title));
+            }
+        }
+
+        for (index, (start, title)) in chapters.iter().enumerate() {
+            // Players expect the first chapter to start at the beginning
+            let start = if index == 0 { 0 } else { *start };
+            let ts = Timestamp4Humans::from_nano(start);
+            // Only display the hours when needed, on a per line basis
+            if ts.h > 0 {
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "{}:{:02}:{:02} {}\n",
+                    ts.h,
+                    ts.m,
+                    ts.s,
+                    title
+                );
+            } else {
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "{:02}:{:02} {}\n",
+                    ts.m,
+                    ts.s,
+                    title
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_line_test() {
+    assert_eq!(
+        Some((Duration::from_secs(0), "Intro")),
+        parse_line("00:00 Intro"),
+    );
+    assert_eq!(
+        Some((Duration::from_secs((60 + 2) * 60 + 3), "Title")),
+        parse_line("1:02:03 - Title"),
+    );
+    assert_eq!(
+        Some((Duration::from_secs(12 * 60 + 34), "Title")),
+        parse_line("[12:34] Title"),
+    );
+    assert_eq!(
+        Some((Duration::from_secs(5 * 60 + 32), "Topic")),
+        parse_line("  • (5:32): Topic "),
+    );
+    assert_eq!(
+        Some((Duration::from_secs(5 * 60 + 32), "")),
+        parse_line("05:32"),
+    );
+    assert_eq!(None, parse_line("Follow us!"));
+    assert_eq!(None, parse_line("12:30pm live stream"));
+}
+
+#[test]
+fn description_round_trip_test() {
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(3_700),
+        ..MediaInfo::default()
+    };
+
+    let content = concat!(
+        "In this episode:\n",
+        "\n",
+        "0:03 Intro\n",
+        "05:32 - Topic\n",
+        "[1:02:03] Conclusion\n",
+        "\n",
+        "Thanks for listening!\n",
+    );
+
+    let toc = DescriptionFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    DescriptionFormat::default()
+        .write(&info, &mut output)
+        .unwrap();
+
+    assert_eq!(
+        concat!("00:00 Intro\n", "05:32 Topic\n", "1:02:03 Conclusion\n"),
+        String::from_utf8(output).unwrap(),
+    );
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(600),
+        ..MediaInfo::default()
+    };
+    let toc = DescriptionFormat::default()
+        .read(&info, &mut "00:00 Intro\n05:32 Topic\n".as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    DescriptionFormat::default()
+        .write(&info, &mut output)
+        .unwrap();
+
+    assert_eq!(
+        "00:00 Intro\n05:32 Topic\n",
+        String::from_utf8(output).unwrap(),
+    );
+}
@@ -1,21 +1,44 @@
-use std::boxed::Box;
+use std::{boxed::Box, path::Path};
 
 use super::{
-    CueSheetFormat, Format, MKVMergeTextFormat, MatroskaTocFormat, MediaContent, Reader, Writer,
+    CueSheetFormat, DescriptionFormat, FFMetadataFormat, Format, Id3v2Format, MKVMergeTextFormat,
+    MatroskaTocFormat, MediaContent, PodcastChaptersFormat, Reader, SrtFormat, WebVttFormat,
+    Writer,
 };
 
 pub struct Factory {}
 
 impl Factory {
     pub fn extensions() -> Vec<(&'static str, Format)> {
-        // Only MKVMergeTextFormat implemented for Read ATM
-        vec![(MKVMergeTextFormat::extension(), Format::MKVMergeText)]
+        vec![
+            (MKVMergeTextFormat::extension(), Format::MKVMergeText),
+            (WebVttFormat::extension(), Format::WebVtt),
+            (SrtFormat::extension(), Format::Srt),
+            (DescriptionFormat::extension(), Format::Description),
+            (PodcastChaptersFormat::extension(), Format::PodcastChapters),
+            (FFMetadataFormat::extension(), Format::FFMetadata),
+        ]
+    }
+
+    /// Returns the format of the table of contents file at `path` if it can be read.
+    pub fn format_for(path: &Path) -> Option<Format> {
+        let file_name = path.file_name()?.to_str()?.to_lowercase();
+
+        // Prefer the most specific extension, e.g. `chapters.txt` over `txt`
+        Self::extensions()
+            .into_iter()
+            .filter(|(extension, _)| file_name.ends_with(&format!(".{}", extension)))
+            .max_by_key(|(extension, _)| extension.len())
+            .map(|(_, format)| format)
     }
 
     pub fn extension(format: Format, content: MediaContent) -> &'static str {
         match format {
             Format::CueSheet => CueSheetFormat::extension(),
+            Format::Description => DescriptionFormat::extension(),
+            Format::FFMetadata => FFMetadataFormat::extension(),
             Format::Flac => "flac",
+            Format::Id3v2 => Id3v2Format::extension(),
             Format::Matroska => match content {
                 MediaContent::Audio => MatroskaTocFormat::audio_extension(),
                 _ => MatroskaTocFormat::extension(),
@@ -23,14 +46,23 @@ impl Factory {
             Format::MKVMergeText => MKVMergeTextFormat::extension(),
             Format::MP3 => "mp3",
             Format::Opus => "opus",
+            Format::PodcastChapters => PodcastChaptersFormat::extension(),
+            Format::Srt => SrtFormat::extension(),
             Format::Vorbis => "oga",
             Format::Wave => "wave",
+            Format::WebVtt => WebVttFormat::extension(),
         }
     }
 
     pub fn reader(format: Format) -> Box<dyn Reader> {
         match format {
+            Format::Description => Box::<DescriptionFormat>::default(),
+            Format::FFMetadata => Box::<FFMetadataFormat>::default(),
+            Format::Id3v2 => Box::<Id3v2Format>::default(),
             Format::MKVMergeText => Box::<MKVMergeTextFormat>::default(),
+            Format::PodcastChapters => Box::<PodcastChaptersFormat>::default(),
+            Format::Srt => Box::<SrtFormat>::default(),
+            Format::WebVtt => Box::<WebVttFormat>::default(),
             format => unimplemented!("Reader for {:?}", format),
         }
     }
@@ -38,8 +70,35 @@ impl Factory {
     pub fn writer(format: Format) -> Box<dyn Writer> {
         match format {
             Format::CueSheet => Box::<CueSheetFormat>::default(),
+            Format::Description => Box::<DescriptionFormat>::default(),
+            Format::FFMetadata => Box::<FFMetadataFormat>::default(),
+            Format::Id3v2 => Box::<Id3v2Format>::default(),
             Format::MKVMergeText => Box::<MKVMergeTextFormat>::default(),
+            Format::PodcastChapters => Box::<PodcastChaptersFormat>::default(),
+            Format::Srt => Box::<SrtFormat>::default(),
+            Format::WebVtt => Box::<WebVttFormat>::default(),
             format => unimplemented!("Writer for {:?}", format),
         }
     }
 }
+
+#[test]
+fn format_for_test() {
+    assert_eq!(
+        Some(Format::MKVMergeText),
+        Factory::format_for(Path::new("/music/album.txt"))
+    );
+    assert_eq!(
+        Some(Format::Description),
+        Factory::format_for(Path::new("/music/album.chapters.txt"))
+    );
+    assert_eq!(
+        Some(Format::FFMetadata),
+        Factory::format_for(Path::new("album.FFMETADATA"))
+    );
+    assert_eq!(
+        Some(Format::Srt),
+        Factory::format_for(Path::new("album.chapters.srt"))
+    );
+    assert_eq!(None, Factory::format_for(Path::new("album.flac")));
+}
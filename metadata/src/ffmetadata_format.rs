@@ -0,0 +1,424 @@
+use log::error;
+
+use std::io::{Read, Write};
+
+use super::{
+    chapter_title, cues_to_toc, Duration, MediaInfo, Reader, SubtitleCue, TocVisitor, Writer,
+};
+use application::gettext;
+
+static EXTENSION: &str = "ffmetadata";
+
+static HEADER: &str = ";FFMETADATA1";
+static CHAPTER_SECTION: &str = "CHAPTER";
+static TIMEBASE_KEY: &str = "TIMEBASE";
+static START_KEY: &str = "START";
+static END_KEY: &str = "END";
+static TITLE_KEY: &str = "title";
+static ARTIST_KEY: &str = "artist";
+
+// The timebase used when writing the chapters
+const TIMEBASE_DEN: u64 = 1_000;
+// The timebase FFmpeg assumes when none is specified
+const DEFAULT_TIMEBASE: (u64, u64) = (1, 1_000_000_000);
+
+const SPECIAL_CHARS: &str = "=;#\\\n";
+
+/// FFmpeg metadata file, as used with `ffmpeg -i media -i file -map_metadata 1`.
+///
+/// See <https://ffmpeg.org/ffmpeg-formats.html#Metadata-1>.
+#[derive(Debug, Default)]
+pub struct FFMetadataFormat {}
+
+impl FFMetadataFormat {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Line {
+    Section(String),
+    Entry(String, String),
+}
+
+/// Splits `content` in sections & entries, resolving the escaped characters.
+fn parse_lines(content: &str) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut chars = content.chars().peekable();
+
+    while let Some(first) = chars.peek() {
+        match first {
+            ';' | '#' => {
+                // Comment
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                continue;
+            }
+            '\r' | '\n' => {
+                chars.next();
+                continue;
+            }
+            _ => (),
+        }
+
+        let mut key = String::new();
+        let mut value: Option<String> = None;
+        while let Some(c) = chars.next() {
+            let c = match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => escaped,
+                    None => break,
+                },
+                '\n' => break,
+                '\r' if chars.peek() == Some(&'\n') => continue,
+                '=' if value.is_none() => {
+                    value = Some(String::new());
+                    continue;
+                }
+                c => c,
+            };
+
+            match value.as_mut() {
+                Some(value) => value.push(c),
+                None => key.push(c),
+            }
+        }
+
+        match value {
+            Some(value) => lines.push(Line::Entry(key, value)),
+            None => {
+                if let Some(section) = key.strip_prefix('[').and_then(|key| key.strip_suffix(']')) {
+                    lines.push(Line::Section(section.to_string()));
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if SPECIAL_CHARS.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+fn parse_timebase(value: &str) -> Option<(u64, u64)> {
+    let (num, den) = value.split_once('/')?;
+    let num = num.trim().parse::<u64>().ok()?;
+    let den = den.trim().parse::<u64>().ok()?;
+    if num == 0 || den == 0 {
+        return None;
+    }
+
+    Some((num, den))
+}
+
+fn to_duration(value: &str, (num, den): (u64, u64)) -> Option<Duration> {
+    let value = value.trim().parse::<u64>().ok()?;
+    let nanos = value as u128 * num as u128 * 1_000_000_000u128 / den as u128;
+
+    Some(Duration::from_nanos(u64::try_from(nanos).ok()?))
+}
+
+#[derive(Default)]
+struct Chapter {
+    timebase: Option<(u64, u64)>,
+    start: Option<String>,
+    end: Option<String>,
+    title: String,
+}
+
+impl Chapter {
+    fn into_cue(self) -> Result<SubtitleCue, String> {
+        let timebase = self.timebase.unwrap_or(DEFAULT_TIMEBASE);
+        let start = self
+            .start
+            .as_deref()
+            .and_then(|start| to_duration(start, timebase))
+            .ok_or_else(|| {
+                let msg = gettext("invalid or missing chapter {}").replacen("{}", START_KEY, 1);
+                error!("{}", msg);
+                msg
+            })?;
+
+        Ok(SubtitleCue {
+            start,
+            end: self
+                .end
+                .as_deref()
+                .and_then(|end| to_duration(end, timebase)),
+            text: self.title,
+        })
+    }
+}
+
+impl Reader for FFMetadataFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let mut content = String::new();
+        source.read_to_string(&mut content).map_err(|_| {
+            let msg = gettext("unexpected error reading FFmpeg metadata file.");
+            error!("{}", msg);
+            msg
+        })?;
+
+        if !content.starts_with(HEADER) {
+            let msg = gettext("missing {} header").replacen("{}", HEADER, 1);
+            error!("{}", msg);
+            return Err(msg);
+        }
+
+        let mut cues = Vec::new();
+        let mut cur_chapter: Option<Chapter> = None;
+        for line in parse_lines(&content) {
+            match line {
+                Line::Section(section) => {
+                    if let Some(chapter) = cur_chapter.take() {
+                        cues.push(chapter.into_cue()?);
+                    }
+
+                    if section == CHAPTER_SECTION {
+                        cur_chapter = Some(Chapter::default());
+                    }
+                }
+                Line::Entry(key, value) => {
+                    // Global & stream metadata are handled by the media itself
+                    if let Some(chapter) = cur_chapter.as_mut() {
+                        if key == TIMEBASE_KEY {
+                            chapter.timebase = Some(parse_timebase(&value).ok_or_else(|| {
+                                let msg = gettext("invalid timebase: {}").replacen("{}", &value, 1);
+                                error!("{}", msg);
+                                msg
+                            })?);
+                        } else if key == START_KEY {
+                            chapter.start = Some(value);
+                        } else if key == END_KEY {
+                            chapter.end = Some(value);
+                        } else if key == TITLE_KEY {
+                            chapter.title = value;
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(chapter) = cur_chapter.take() {
+            cues.push(chapter.into_cue()?);
+        }
+        cues.sort_by_key(|cue| cue.start);
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+fn write_error() -> String {
+    gettext("Failed to write FFmpeg metadata file")
+}
+
+/// Converts `nanos` to the timebase used when writing the chapters.
+fn to_timebase(nanos: i64) -> Result<u64, String> {
+    u64::try_from(nanos)
+        .map(|nanos| nanos / (1_000_000_000 / TIMEBASE_DEN))
+        .map_err(|_| {
+            let msg = gettext("chapter starts before the media");
+            error!("{}", msg);
+            msg
+        })
+}
+
+impl Writer for FFMetadataFormat {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        write_fmt!(destination, write_error(), "{}\n", HEADER);
+
+        if let Some(title) = info.media_title() {
+            write_fmt!(
+                destination,
+                write_error(),
+                "{}={}\n",
+                TITLE_KEY,
+                escape(&title)
+            );
+        }
+        if let Some(artist) = info.media_artist() {
+            write_fmt!(
+                destination,
+                write_error(),
+                "{}={}\n",
+                ARTIST_KEY,
+                escape(&artist)
+            );
+        }
+
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            if let Some((start, end)) = chapter.start_stop_times() {
+                let end = if end > start {
+                    end
+                } else {
+                    info.duration.as_i64()
+                };
+
+                let title = chapter_title(&chapter);
+
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "\n[{}]\n{}=1/{}\n{}={}\n{}={}\n{}={}\n",
+                    CHAPTER_SECTION,
+                    TIMEBASE_KEY,
+                    TIMEBASE_DEN,
+                    START_KEY,
+                    to_timebase(start)?,
+                    END_KEY,
+                    to_timebase(end)?,
+                    TITLE_KEY,
+                    escape(&title)
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn parse_lines_test() {
+    let lines = parse_lines(concat!(
+        ";FFMETADATA1\n",
+        "title=Some \\= \\; \\# \\\\ title\\\n",
+        "on two lines\r\n",
+        "# a comment\n",
+        "\n",
+        "[CHAPTER]\n",
+        "START=0\n",
+    ));
+
+    assert_eq!(
+        vec![
+            Line::Entry(
+                "title".to_string(),
+                "Some = ; # \\ title\non two lines".to_string()
+            ),
+            Line::Section("CHAPTER".to_string()),
+            Line::Entry("START".to_string(), "0".to_string()),
+        ],
+        lines,
+    );
+
+    assert_eq!("a\\=b\\;c\\#d\\\\e\\\nf", escape("a=b;c#d\\e\nf"));
+}
+
+#[test]
+fn to_timebase_test() {
+    assert_eq!(Ok(0), to_timebase(0));
+    assert_eq!(Ok(65_500), to_timebase(65_500_999_999));
+    assert_eq!(Ok(i64::MAX as u64 / 1_000_000), to_timebase(i64::MAX));
+    assert!(to_timebase(-1).is_err());
+}
+
+#[test]
+fn ffmetadata_round_trip_test() {
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(600),
+        ..MediaInfo::default()
+    };
+
+    let content = concat!(
+        ";FFMETADATA1\n",
+        "title=Podcast\n",
+        "\n",
+        "[CHAPTER]\n",
+        "TIMEBASE=1/10\n",
+        "START=655\n",
+        "END=6000\n",
+        "title=Q&A\\; with \\= signs\n",
+        "\n",
+        "[CHAPTER]\n",
+        "START=0\n",
+        "END=65500000000\n",
+        "title=Intro\n",
+        "\n",
+        "[STREAM]\n",
+        "title=Audio\n",
+    );
+
+    let toc = FFMetadataFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    FFMetadataFormat::default()
+        .write(&info, &mut output)
+        .unwrap();
+
+    assert_eq!(
+        concat!(
+            ";FFMETADATA1\n",
+            "\n",
+            "[CHAPTER]\n",
+            "TIMEBASE=1/1000\n",
+            "START=0\n",
+            "END=65500\n",
+            "title=Intro\n",
+            "\n",
+            "[CHAPTER]\n",
+            "TIMEBASE=1/1000\n",
+            "START=65500\n",
+            "END=600000\n",
+            "title=Q&A\\; with \\= signs\n",
+        ),
+        String::from_utf8(output).unwrap(),
+    );
+}
+
+#[test]
+fn ffmetadata_gap_round_trip_test() {
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(600),
+        ..MediaInfo::default()
+    };
+
+    // A gap between the chapters & the last one ends before the media
+    let content = concat!(
+        ";FFMETADATA1\n",
+        "\n",
+        "[CHAPTER]\n",
+        "TIMEBASE=1/1000\n",
+        "START=0\n",
+        "END=60000\n",
+        "title=Song\n",
+        "\n",
+        "[CHAPTER]\n",
+        "TIMEBASE=1/1000\n",
+        "START=90000\n",
+        "END=120500\n",
+        "title=Hidden track\n",
+    );
+
+    let toc = FFMetadataFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    FFMetadataFormat::default()
+        .write(&info, &mut output)
+        .unwrap();
+
+    assert_eq!(content, String::from_utf8(output).unwrap());
+}
@@ -0,0 +1,523 @@
+use log::error;
+
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+};
+
+use super::{
+    chapter_title, cues_to_toc, Duration, MediaInfo, Reader, SubtitleCue, TocVisitor, Writer,
+};
+use application::gettext;
+
+// Don't overwrite the original media
+static EXTENSION: &str = "chapters.mp3";
+
+const HEADER_LEN: usize = 10;
+const FRAME_HEADER_LEN: usize = 10;
+
+const FLAG_UNSYNCHRONISATION: u8 = 0x80;
+const FLAG_EXTENDED_HEADER: u8 = 0x40;
+const FLAG_FOOTER: u8 = 0x10;
+
+// Used when the original media has no ID3v2 tag
+const DEFAULT_VERSION: u8 = 3;
+
+const CHAPTER_FRAME_ID: &[u8; 4] = b"CHAP";
+const TOC_FRAME_ID: &[u8; 4] = b"CTOC";
+const TITLE_FRAME_ID: &[u8; 4] = b"TIT2";
+
+const TOC_ELEMENT_ID: &str = "toc";
+// Top level & ordered
+const TOC_FLAGS: u8 = 0x03;
+const NO_OFFSET: u32 = 0xffff_ffff;
+
+/// MP3 file with the chapters in ID3v2 CHAP & CTOC frames.
+///
+/// The writer copies the original media, replacing the chapters of its ID3v2 tag
+/// and keeping its other frames.
+#[derive(Debug, Default)]
+pub struct Id3v2Format {}
+
+impl Id3v2Format {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+struct Frame {
+    id: [u8; 4],
+    flags: [u8; 2],
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn new(id: &[u8; 4], data: Vec<u8>) -> Self {
+        Frame {
+            id: *id,
+            flags: [0, 0],
+            data,
+        }
+    }
+}
+
+struct Tag {
+    version: u8,
+    frames: Vec<Frame>,
+}
+
+fn unsupported_tag() -> String {
+    let msg = gettext("unsupported ID3v2 tag");
+    error!("{}", msg);
+    msg
+}
+
+fn read_error() -> String {
+    let msg = gettext("unexpected error reading ID3v2 tag");
+    error!("{}", msg);
+    msg
+}
+
+fn from_syncsafe(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take(4)
+        .fold(0, |size, byte| (size << 7) | (*byte & 0x7f) as usize)
+}
+
+fn to_syncsafe(size: usize) -> Result<[u8; 4], String> {
+    if size >= 1 << 28 {
+        let msg = gettext("ID3v2 tag too large");
+        error!("{}", msg);
+        return Err(msg);
+    }
+
+    Ok([
+        (size >> 21) as u8 & 0x7f,
+        (size >> 14) as u8 & 0x7f,
+        (size >> 7) as u8 & 0x7f,
+        size as u8 & 0x7f,
+    ])
+}
+
+fn frame_size(version: u8, bytes: &[u8]) -> usize {
+    if version >= 4 {
+        from_syncsafe(bytes)
+    } else {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize
+    }
+}
+
+fn parse_frames(version: u8, mut data: &[u8]) -> Vec<Frame> {
+    let mut frames = Vec::new();
+
+    while data.len() >= FRAME_HEADER_LEN && data[0] != 0 {
+        let size = frame_size(version, &data[4..8]);
+        if FRAME_HEADER_LEN + size > data.len() {
+            break;
+        }
+
+        frames.push(Frame {
+            id: [data[0], data[1], data[2], data[3]],
+            flags: [data[8], data[9]],
+            data: data[FRAME_HEADER_LEN..FRAME_HEADER_LEN + size].to_vec(),
+        });
+        data = &data[FRAME_HEADER_LEN + size..];
+    }
+
+    frames
+}
+
+fn serialize_frames(version: u8, frames: &[Frame], output: &mut Vec<u8>) -> Result<(), String> {
+    for frame in frames {
+        output.extend_from_slice(&frame.id);
+        if version >= 4 {
+            output.extend_from_slice(&to_syncsafe(frame.data.len())?);
+        } else {
+            output.extend_from_slice(&(frame.data.len() as u32).to_be_bytes());
+        }
+        output.extend_from_slice(&frame.flags);
+        output.extend_from_slice(&frame.data);
+    }
+
+    Ok(())
+}
+
+/// Reads the ID3v2 tag at the beginning of `source` if any.
+///
+/// Returns the tag and the bytes read past it.
+fn read_tag(source: &mut dyn Read) -> Result<(Option<Tag>, Vec<u8>), String> {
+    let mut header = [0u8; HEADER_LEN];
+    let mut header_len = 0;
+    while header_len < HEADER_LEN {
+        match source
+            .read(&mut header[header_len..])
+            .map_err(|_| read_error())?
+        {
+            0 => break,
+            len => header_len += len,
+        }
+    }
+
+    if header_len < HEADER_LEN || &header[..3] != b"ID3" {
+        return Ok((None, header[..header_len].to_vec()));
+    }
+
+    let version = header[3];
+    let flags = header[5];
+    if !(3..=4).contains(&version) || flags & FLAG_UNSYNCHRONISATION != 0 {
+        return Err(unsupported_tag());
+    }
+
+    let mut size = from_syncsafe(&header[6..10]);
+    if version >= 4 && flags & FLAG_FOOTER != 0 {
+        size += HEADER_LEN;
+    }
+
+    let mut data = vec![0u8; size];
+    source.read_exact(&mut data).map_err(|_| read_error())?;
+
+    let mut frames_data = data.as_slice();
+    if flags & FLAG_EXTENDED_HEADER != 0 {
+        if frames_data.len() < 4 {
+            return Err(unsupported_tag());
+        }
+
+        let ext_header_len = if version >= 4 {
+            from_syncsafe(&frames_data[..4])
+        } else {
+            frame_size(version, &frames_data[..4]) + 4
+        };
+        frames_data = frames_data
+            .get(ext_header_len..)
+            .ok_or_else(unsupported_tag)?;
+    }
+
+    Ok((
+        Some(Tag {
+            version,
+            frames: parse_frames(version, frames_data),
+        }),
+        Vec::new(),
+    ))
+}
+
+fn decode_text(data: &[u8]) -> String {
+    let (encoding, text) = match data.split_first() {
+        Some(split) => split,
+        None => return String::new(),
+    };
+
+    let utf16 = |text: &[u8], is_big_endian: bool| {
+        let units: Vec<u16> = text
+            .chunks_exact(2)
+            .map(|unit| {
+                if is_big_endian {
+                    u16::from_be_bytes([unit[0], unit[1]])
+                } else {
+                    u16::from_le_bytes([unit[0], unit[1]])
+                }
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    let text = match encoding {
+        0 => text.iter().map(|byte| *byte as char).collect(),
+        1 => match text {
+            [0xff, 0xfe, text @ ..] => utf16(text, false),
+            [0xfe, 0xff, text @ ..] => utf16(text, true),
+            _ => utf16(text, true),
+        },
+        2 => utf16(text, true),
+        _ => String::from_utf8_lossy(text).to_string(),
+    };
+
+    text.trim_end_matches('\0').to_string()
+}
+
+fn encode_text(text: &str) -> Vec<u8> {
+    if text.chars().all(|c| (c as u32) < 0x100) {
+        // ISO-8859-1
+        std::iter::once(0)
+            .chain(text.chars().map(|c| c as u8))
+            .collect()
+    } else {
+        // UTF-16 with BOM, supported by both ID3v2.3 & ID3v2.4
+        let mut data = vec![1, 0xff, 0xfe];
+        for unit in text.encode_utf16() {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        data
+    }
+}
+
+fn parse_chapter(version: u8, data: &[u8]) -> Option<SubtitleCue> {
+    let element_id_end = data.iter().position(|byte| *byte == 0)?;
+    let times = data.get(element_id_end + 1..element_id_end + 17)?;
+    let start = u32::from_be_bytes([times[0], times[1], times[2], times[3]]);
+    let end = u32::from_be_bytes([times[4], times[5], times[6], times[7]]);
+
+    let title = parse_frames(version, &data[element_id_end + 17..])
+        .into_iter()
+        .find(|frame| &frame.id == TITLE_FRAME_ID)
+        .map(|frame| decode_text(&frame.data))
+        .unwrap_or_default();
+
+    Some(SubtitleCue {
+        start: Duration::from_millis(start as u64),
+        end: Some(Duration::from_millis(end as u64)),
+        text: title,
+    })
+}
+
+impl Reader for Id3v2Format {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let tag = match read_tag(source)? {
+            (Some(tag), _) => tag,
+            (None, _) => return Ok(None),
+        };
+
+        let mut cues: Vec<SubtitleCue> = tag
+            .frames
+            .iter()
+            .filter(|frame| &frame.id == CHAPTER_FRAME_ID)
+            .filter_map(|frame| parse_chapter(tag.version, &frame.data))
+            .collect();
+        cues.sort_by_key(|cue| cue.start);
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+/// Converts `nanos` to the milliseconds of a CHAP frame, rounding to the nearest.
+fn to_chapter_time(nanos: i64) -> Result<u32, String> {
+    u32::try_from((nanos.max(0) as u64 + 500_000) / 1_000_000).map_err(|_| {
+        let msg = gettext("chapter too far in the media for an ID3v2 tag");
+        error!("{}", msg);
+        msg
+    })
+}
+
+/// Builds the CTOC & CHAP frames for the chapters of `info`.
+fn chapter_frames(version: u8, info: &MediaInfo) -> Result<Vec<Frame>, String> {
+    let mut chapter_frames = Vec::new();
+    let mut element_ids = Vec::new();
+
+    let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+    while let Some(chapter) = toc_visitor.next_chapter() {
+        if let Some((start, end)) = chapter.start_stop_times() {
+            let end = if end > start {
+                end
+            } else {
+                info.duration.as_i64()
+            };
+
+            let title = chapter_title(&chapter);
+
+            let element_id = format!("chp{}", element_ids.len());
+            let mut data = element_id.as_bytes().to_vec();
+            data.push(0);
+            data.extend_from_slice(&to_chapter_time(start)?.to_be_bytes());
+            data.extend_from_slice(&to_chapter_time(end)?.to_be_bytes());
+            data.extend_from_slice(&NO_OFFSET.to_be_bytes());
+            data.extend_from_slice(&NO_OFFSET.to_be_bytes());
+            serialize_frames(
+                version,
+                &[Frame::new(TITLE_FRAME_ID, encode_text(&title))],
+                &mut data,
+            )?;
+
+            chapter_frames.push(Frame::new(CHAPTER_FRAME_ID, data));
+            element_ids.push(element_id);
+        }
+    }
+
+    if element_ids.len() > u8::MAX as usize {
+        let msg = gettext("ID3v2 tags can't hold more than {} chapters").replacen(
+            "{}",
+            &u8::MAX.to_string(),
+            1,
+        );
+        error!("{}", msg);
+        return Err(msg);
+    }
+
+    let mut toc_data = TOC_ELEMENT_ID.as_bytes().to_vec();
+    toc_data.push(0);
+    toc_data.push(TOC_FLAGS);
+    toc_data.push(element_ids.len() as u8);
+    for element_id in element_ids {
+        toc_data.extend_from_slice(element_id.as_bytes());
+        toc_data.push(0);
+    }
+
+    let mut frames = vec![Frame::new(TOC_FRAME_ID, toc_data)];
+    frames.extend(chapter_frames);
+
+    Ok(frames)
+}
+
+fn write_error() -> String {
+    let msg = gettext("Failed to write MP3 file");
+    error!("{}", msg);
+    msg
+}
+
+impl Writer for Id3v2Format {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        let mut source = File::open(&info.path).map_err(|_| read_error())?;
+        let (tag, mut audio_start) = read_tag(&mut source)?;
+
+        let tag = tag.unwrap_or(Tag {
+            version: DEFAULT_VERSION,
+            frames: Vec::new(),
+        });
+
+        // Make sure we are dealing with MPEG audio frames
+        while audio_start.len() < 2 {
+            let mut byte = [0u8];
+            if source.read(&mut byte).map_err(|_| read_error())? == 0 {
+                break;
+            }
+            audio_start.push(byte[0]);
+        }
+        if audio_start.len() < 2 || audio_start[0] != 0xff || audio_start[1] & 0xe0 != 0xe0 {
+            let msg = gettext("{} is not an MP3 file").replacen("{}", info.file_name(), 1);
+            error!("{}", msg);
+            return Err(msg);
+        }
+
+        let mut frames: Vec<Frame> = tag
+            .frames
+            .into_iter()
+            .filter(|frame| &frame.id != CHAPTER_FRAME_ID && &frame.id != TOC_FRAME_ID)
+            .collect();
+        frames.extend(chapter_frames(tag.version, info)?);
+
+        let mut frames_data = Vec::new();
+        serialize_frames(tag.version, &frames, &mut frames_data)?;
+
+        let mut header = b"ID3".to_vec();
+        header.extend_from_slice(&[tag.version, 0, 0]);
+        header.extend_from_slice(&to_syncsafe(frames_data.len())?);
+
+        destination.write_all(&header).map_err(|_| write_error())?;
+        destination
+            .write_all(&frames_data)
+            .map_err(|_| write_error())?;
+        destination
+            .write_all(&audio_start)
+            .map_err(|_| write_error())?;
+        io::copy(&mut source, destination).map_err(|_| write_error())?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn syncsafe_test() {
+    assert_eq!([0, 0, 0x02, 0x01], to_syncsafe(257).unwrap());
+    assert_eq!(257, from_syncsafe(&[0, 0, 0x02, 0x01]));
+    assert!(to_syncsafe(1 << 28).is_err());
+}
+
+#[test]
+fn chapter_time_test() {
+    assert_eq!(65_500, to_chapter_time(65_500_000_000).unwrap());
+    assert_eq!(1, to_chapter_time(999_999).unwrap());
+    assert_eq!(0, to_chapter_time(499_999).unwrap());
+    assert_eq!(
+        u32::MAX,
+        to_chapter_time(u32::MAX as i64 * 1_000_000).unwrap()
+    );
+    assert!(to_chapter_time((u32::MAX as i64 + 1) * 1_000_000).is_err());
+}
+
+#[test]
+fn text_encoding_test() {
+    assert_eq!(vec![0, b'I', b'n', b't', b'r', b'o'], encode_text("Intro"));
+    assert_eq!("Intro", decode_text(&encode_text("Intro")));
+    assert_eq!("Déjà vu", decode_text(&encode_text("Déjà vu")));
+    assert_eq!("Ὀδυσσεύς", decode_text(&encode_text("Ὀδυσσεύς")));
+    assert_eq!("UTF-8 é", decode_text(b"\x03UTF-8 \xc3\xa9\0"));
+}
+
+#[test]
+fn id3v2_chapters_round_trip_test() {
+    gst::init().unwrap();
+
+    let duration = Duration::from_secs(600);
+    let cues = [
+        // Leave a gap after this one
+        SubtitleCue {
+            start: Duration::from_secs(0),
+            end: Some(Duration::from_secs(60)),
+            text: "Intro".to_string(),
+        },
+        SubtitleCue {
+            start: Duration::from_millis(65_500),
+            end: None,
+            text: "Ὀδυσσεύς".to_string(),
+        },
+    ];
+    let info = MediaInfo {
+        duration,
+        toc: cues_to_toc(&cues.iter().collect::<Vec<_>>(), duration),
+        ..MediaInfo::default()
+    };
+
+    for version in 3..=4 {
+        // An ID3v2 tag with a title frame
+        let title_frame = Frame::new(b"TIT1", encode_text("Podcast"));
+        let mut frames_data = Vec::new();
+        serialize_frames(version, &[title_frame], &mut frames_data).unwrap();
+        serialize_frames(
+            version,
+            &chapter_frames(version, &info).unwrap(),
+            &mut frames_data,
+        )
+        .unwrap();
+
+        let mut tag = b"ID3".to_vec();
+        tag.extend_from_slice(&[version, 0, 0]);
+        tag.extend_from_slice(&to_syncsafe(frames_data.len()).unwrap());
+        tag.extend_from_slice(&frames_data);
+        // MPEG audio frame header
+        tag.extend_from_slice(&[0xff, 0xfb, 0x90, 0x00]);
+
+        let (parsed_tag, remainder) = read_tag(&mut tag.as_slice()).unwrap();
+        assert!(remainder.is_empty());
+        let parsed_tag = parsed_tag.unwrap();
+        assert_eq!(version, parsed_tag.version);
+        assert_eq!(4, parsed_tag.frames.len());
+        assert_eq!(b"TIT1", &parsed_tag.frames[0].id);
+        assert_eq!(TOC_FRAME_ID, &parsed_tag.frames[1].id);
+
+        let toc = Id3v2Format::default()
+            .read(&info, &mut tag.as_slice())
+            .unwrap()
+            .unwrap();
+
+        let mut toc_visitor = TocVisitor::new(&toc);
+        let mut chapters = Vec::new();
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            let title = chapter
+                .tags()
+                .and_then(|tags| {
+                    tags.get::<gst::tags::Title>()
+                        .map(|tag| tag.get().to_owned())
+                })
+                .unwrap();
+            chapters.push((chapter.start_stop_times().unwrap(), title));
+        }
+
+        assert_eq!(
+            vec![
+                ((0, 60_000_000_000), "Intro".to_string()),
+                ((65_500_000_000, 600_000_000_000), "Ὀδυσσεύς".to_string()),
+            ],
+            chapters,
+        );
+    }
+}
@@ -1,43 +1,110 @@
+/// Writes the formatted `$item`s to `$dest`.
+///
+/// On failure, logs `$err_msg` and returns it from the enclosing function.
+macro_rules! write_fmt(
+    ($dest:ident, $err_msg:expr, $fmt:expr $(, $item:expr )* $(,)?) => {
+        $dest.write_fmt(format_args!($fmt $(, $item )*)).map_err(|_| {
+            let msg = $err_msg;
+            log::error!("{}", msg);
+            msg
+        })?;
+    };
+);
+
 mod cue_sheet_format;
 pub use self::cue_sheet_format::CueSheetFormat;
 
+mod description_format;
+pub use self::description_format::DescriptionFormat;
+
 mod duration;
 pub use duration::Duration;
 
+mod ffmetadata_format;
+pub use self::ffmetadata_format::FFMetadataFormat;
+
 pub mod factory;
 pub use self::factory::Factory;
 
 mod format;
 pub use self::format::{Exporter, Reader, Writer};
 
+mod id3v2_format;
+pub use self::id3v2_format::Id3v2Format;
+
 mod matroska_toc_format;
 pub use self::matroska_toc_format::MatroskaTocFormat;
 
+mod markers;
+pub use self::markers::{Marker, MarkerColor, MarkersSidecar};
+
 pub mod media_info;
 pub use self::media_info::{default_chapter_title, MediaInfo, Stream, Streams};
 
 mod mkvmerge_text_format;
 pub use self::mkvmerge_text_format::MKVMergeTextFormat;
 
+mod podcast_chapters_format;
+pub use self::podcast_chapters_format::PodcastChaptersFormat;
+
+mod project;
+pub use self::project::{Project, ProjectOutput, ProjectStreams, ProjectView};
+
+mod srt_format;
+pub use self::srt_format::SrtFormat;
+
+mod subtitle_cues;
+pub use self::subtitle_cues::{
+    cue_starts_to_toc, cues_to_toc, parse_cues, read_cues, CueSelection, SubtitleCue,
+    SubtitleFormat,
+};
+
 mod timestamp_4_humans;
 pub use self::timestamp_4_humans::{parse_timestamp, Timestamp4Humans};
 
 mod toc_visitor;
-pub use self::toc_visitor::{TocVisit, TocVisitor};
+pub(crate) use self::toc_visitor::new_chapter;
+pub use self::toc_visitor::{chapter_title, TocVisit, TocVisitor};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+mod web_vtt_format;
+pub use self::web_vtt_format::WebVttFormat;
+
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Format {
     CueSheet,
+    /// Chapters list for video descriptions or podcast show notes.
+    Description,
+    /// FFmpeg metadata file.
+    FFMetadata,
     Flac,
+    /// MP3 with the chapters in ID3v2 frames.
+    Id3v2,
     Matroska,
     MKVMergeText,
     MP3,
     Opus,
+    /// Podcasting 2.0 JSON chapters.
+    PodcastChapters,
+    /// SubRip text format.
+    Srt,
     Vorbis,
     Wave,
+    /// WebVTT chapters track.
+    WebVtt,
 }
 
 impl Format {
+    /// Returns the name used to store the format in the configuration.
+    pub fn name(self) -> String {
+        ron::ser::to_string(&self).expect("Format serialization failed")
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        ron::de::from_str(name).ok()
+    }
+
     fn is_audio_only(self) -> bool {
         matches!(
             self,
@@ -106,3 +173,14 @@ fn parse_to<T: std::str::FromStr>(i: &str) -> IResult<&str, T> {
         .map(move |value| (i, value))
         .map_err(move |_| Err::Error(Error::new(i, ErrorKind::Digit)))
 }
+
+#[test]
+fn format_name_test() {
+    assert_eq!(Format::CueSheet.name(), "CueSheet");
+    assert_eq!(Format::from_name("CueSheet"), Some(Format::CueSheet));
+    assert_eq!(
+        Format::from_name(&Format::PodcastChapters.name()),
+        Some(Format::PodcastChapters)
+    );
+    assert_eq!(Format::from_name("Unknown"), None);
+}
@@ -0,0 +1,206 @@
+use log::{debug, error};
+use serde_derive::{Deserialize, Serialize};
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use application::gettext;
+
+use super::Duration;
+
+static EXTENSION: &str = "markers";
+
+/// Version of the markers file format.
+///
+/// Increment when a change prevents older versions from reading the file.
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerColor {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl MarkerColor {
+    pub fn all() -> [MarkerColor; 6] {
+        use MarkerColor::*;
+        [Red, Orange, Yellow, Green, Blue, Purple]
+    }
+
+    pub fn rgb(self) -> (f64, f64, f64) {
+        match self {
+            MarkerColor::Red => (0.93f64, 0.2f64, 0.2f64),
+            MarkerColor::Orange => (0.96f64, 0.55f64, 0.1f64),
+            MarkerColor::Yellow => (0.93f64, 0.84f64, 0.15f64),
+            MarkerColor::Green => (0.3f64, 0.8f64, 0.3f64),
+            MarkerColor::Blue => (0.25f64, 0.55f64, 0.95f64),
+            MarkerColor::Purple => (0.7f64, 0.4f64, 0.9f64),
+        }
+    }
+}
+
+impl Default for MarkerColor {
+    fn default() -> Self {
+        MarkerColor::Red
+    }
+}
+
+/// A note dropped at a position in the media, independently of the chapters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    /// Position in nanoseconds.
+    pub ts: u64,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub color: MarkerColor,
+}
+
+impl Marker {
+    pub fn new(ts: u64, label: impl ToString, color: MarkerColor) -> Self {
+        Marker {
+            ts,
+            label: label.to_string(),
+            color,
+        }
+    }
+
+    pub fn ts(&self) -> Duration {
+        Duration::from_nanos(self.ts)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct MarkersFile {
+    version: u32,
+    markers: Vec<Marker>,
+}
+
+/// Markers sidecar file, saved next to the media.
+pub struct MarkersSidecar;
+
+impl MarkersSidecar {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+
+    /// Returns the path of the markers sidecar file for the media at `media_path`.
+    pub fn sidecar_path(media_path: &Path) -> PathBuf {
+        media_path.with_extension(EXTENSION)
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<Marker>, String> {
+        let markers_file = File::open(path).map_err(|err| {
+            let msg =
+                gettext("Failed to open markers file: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        let markers: MarkersFile = ron::de::from_reader(markers_file).map_err(|err| {
+            let msg =
+                gettext("Failed to read markers file: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        if markers.version > VERSION {
+            let msg = gettext("Unsupported markers file version: {}").replacen(
+                "{}",
+                &markers.version.to_string(),
+                1,
+            );
+            error!("{}", msg);
+            return Err(msg);
+        }
+
+        debug!("loaded {} markers from {:?}", markers.markers.len(), path);
+
+        Ok(markers.markers)
+    }
+
+    /// Saves the `markers` to `path`.
+    ///
+    /// The file is removed when there are no markers left.
+    pub fn save(path: &Path, markers: &[Marker]) -> Result<(), String> {
+        if markers.is_empty() {
+            if path.is_file() {
+                fs::remove_file(path).map_err(|err| {
+                    let msg = gettext("Failed to remove markers file: {}").replacen(
+                        "{}",
+                        &err.to_string(),
+                        1,
+                    );
+                    error!("{}", msg);
+                    msg
+                })?;
+            }
+
+            return Ok(());
+        }
+
+        let markers_str = ron::ser::to_string_pretty(
+            &MarkersFile {
+                version: VERSION,
+                markers: markers.to_vec(),
+            },
+            ron::ser::PrettyConfig::default(),
+        )
+        .map_err(|err| {
+            let msg =
+                gettext("Failed to serialize markers: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        File::create(path)
+            .and_then(|mut markers_file| markers_file.write_all(markers_str.as_bytes()))
+            .map_err(|err| {
+                let msg =
+                    gettext("Failed to write markers file: {}").replacen("{}", &err.to_string(), 1);
+                error!("{}", msg);
+                msg
+            })
+    }
+}
+
+#[test]
+fn markers_round_trip_test() {
+    let dir = std::env::temp_dir().join(format!("media-toc-markers-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let markers_path = MarkersSidecar::sidecar_path(&dir.join("album.flac"));
+    assert_eq!(dir.join("album.markers"), markers_path);
+
+    let markers = vec![
+        Marker::new(1_000_000_000, "check this", MarkerColor::Orange),
+        Marker::new(61_500_000_000, "ad break here", MarkerColor::default()),
+    ];
+    MarkersSidecar::save(&markers_path, &markers).unwrap();
+    assert_eq!(markers, MarkersSidecar::load(&markers_path).unwrap());
+
+    // Markers saved without a colour
+    std::fs::write(
+        &markers_path,
+        "(version: 1, markers: [(ts: 2000000000, label: \"intro\")])",
+    )
+    .unwrap();
+    let loaded = MarkersSidecar::load(&markers_path).unwrap();
+    assert_eq!(Duration::from_secs(2), loaded[0].ts());
+    assert_eq!(MarkerColor::Red, loaded[0].color);
+
+    std::fs::write(&markers_path, "(version: 2, markers: [])").unwrap();
+    assert!(MarkersSidecar::load(&markers_path).is_err());
+
+    // No markers left: the sidecar is removed
+    MarkersSidecar::save(&markers_path, &[]).unwrap();
+    assert!(!markers_path.exists());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -9,7 +9,7 @@ use std::{
     sync::Arc,
 };
 
-use super::{Duration, Format, MediaContent};
+use super::{chapter_title, Duration, Format, MediaContent};
 use application::gettext;
 
 #[derive(Debug)]
@@ -631,6 +631,52 @@ impl MediaInfo {
         track_chapter
     }
 
+    /// Builds the name of the file for `chapter` when splitting the media.
+    ///
+    /// `track_number` is only used when the media is split according to its table of contents.
+    pub fn split_file_name(
+        &self,
+        chapter: &gst::TocEntry,
+        track_number: Option<usize>,
+        extension: &str,
+    ) -> String {
+        let mut split_name = String::new();
+
+        // TODO: make format customisable
+        let artist = self.media_artist_sortname().or_else(|| self.media_artist());
+        if let Some(artist) = artist {
+            split_name += &format!("{} - ", artist);
+        }
+
+        let album_title = self.media_title_sortname().or_else(|| self.media_title());
+        if let Some(album_title) = album_title {
+            split_name += &format!("{} - ", album_title);
+        }
+
+        if let Some(track_number) = track_number {
+            split_name += &format!("{:02}. ", track_number);
+        }
+
+        let track_title = chapter_title(&chapter);
+
+        split_name += &track_title;
+
+        let lang = self.streams.selected_audio().and_then(|stream| {
+            stream
+                .tags
+                .index::<tags::LanguageName>(0)
+                .or_else(|| stream.tags.index::<tags::LanguageCode>(0))
+                .map(|value| value.get().to_string())
+        });
+        if let Some(lang) = lang {
+            split_name += &format!(" ({})", lang);
+        }
+
+        split_name += &format!(".{}", extension);
+
+        split_name
+    }
+
     pub fn media_artist(&self) -> Option<String> {
         get_tag_for_display!(self, tags::Artist, tags::AlbumArtist)
     }
@@ -651,13 +697,19 @@ impl MediaInfo {
         get_tag_for_display!(self, tags::Image, tags::PreviewImage)
     }
 
+    /// Whether the media is an mp3 audio file.
+    pub fn is_mp3(&self) -> bool {
+        self.streams.video_codec().is_none()
+            && self.streams.audio_codec().map_or(false, |audio_codec| {
+                audio_codec.to_lowercase().contains("mp3")
+            })
+    }
+
     pub fn container(&self) -> Option<&str> {
         // in case of an mp3 audio file, container comes as `ID3 label`
         // => bypass it
-        if let Some(audio_codec) = self.streams.audio_codec() {
-            if self.streams.video_codec().is_none() && audio_codec.to_lowercase().contains("mp3") {
-                return None;
-            }
+        if self.is_mp3() {
+            return None;
         }
 
         self.tags
@@ -11,8 +11,8 @@ use nom::{
 use std::io::{Read, Write};
 
 use super::{
-    default_chapter_title, parse_timestamp, parse_to, MediaInfo, Reader, Timestamp4Humans,
-    TocVisitor, Writer,
+    chapter_title, new_chapter, parse_timestamp, parse_to, Duration, MediaInfo, Reader,
+    Timestamp4Humans, TocVisitor, Writer,
 };
 use application::gettext;
 
@@ -30,23 +30,6 @@ impl MKVMergeTextFormat {
     }
 }
 
-fn new_chapter(nb: usize, start_ts: Timestamp4Humans, title: &str) -> gst::TocEntry {
-    let mut chapter = gst::TocEntry::new(gst::TocEntryType::Chapter, &format!("{:02}", nb));
-    let start = start_ts.nano_total() as i64;
-    chapter
-        .get_mut()
-        .unwrap()
-        .set_start_stop_times(start, start);
-
-    let mut tag_list = gst::TagList::new();
-    tag_list
-        .get_mut()
-        .unwrap()
-        .add::<gst::tags::Title>(&title, gst::TagMergeMode::Replace);
-    chapter.get_mut().unwrap().set_tags(tag_list);
-    chapter
-}
-
 fn parse_chapter(i: &str) -> IResult<&str, gst::TocEntry> {
     let mut parse_first_line = terminated(
         preceded(
@@ -70,7 +53,10 @@ fn parse_chapter(i: &str) -> IResult<&str, gst::TocEntry> {
         opt(line_ending),
     );
 
-    parse_second_line(i).map(|(i, (_, title))| (i, new_chapter(nb, start_ts, title)))
+    parse_second_line(i).map(|(i, (_, title))| {
+        let start = Duration::from_nanos(start_ts.nano_total());
+        (i, new_chapter(nb, start, start, title))
+    })
 }
 
 #[test]
@@ -220,15 +206,9 @@ impl Reader for MKVMergeTextFormat {
     }
 }
 
-macro_rules! write_fmt(
-    ($dest:ident, $fmt:expr, $( $item:expr ),*) => {
-        $dest.write_fmt(format_args!($fmt, $( $item ),*)).map_err(|_| {
-            let msg = gettext("Failed to write mkvmerge text file");
-            error!("{}", msg);
-            msg
-        })?;
-    };
-);
+fn write_error() -> String {
+    gettext("Failed to write mkvmerge text file")
+}
 
 impl Writer for MKVMergeTextFormat {
     fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
@@ -240,6 +220,7 @@ impl Writer for MKVMergeTextFormat {
                 let prefix = format!("{}{:02}", CHAPTER_TAG, index);
                 write_fmt!(
                     destination,
+                    write_error(),
                     "{}={}\n",
                     prefix,
                     Timestamp4Humans::from_nano(start as u64)
@@ -247,14 +228,15 @@ impl Writer for MKVMergeTextFormat {
                         .to_string()
                 );
 
-                let title = chapter
-                    .tags()
-                    .and_then(|tags| {
-                        tags.get::<gst::tags::Title>()
-                            .map(|tag| tag.get().to_owned())
-                    })
-                    .unwrap_or_else(default_chapter_title);
-                write_fmt!(destination, "{}{}={}\n", prefix, NAME_TAG, &title);
+                let title = chapter_title(&chapter);
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "{}{}={}\n",
+                    prefix,
+                    NAME_TAG,
+                    &title
+                );
             }
         }
 
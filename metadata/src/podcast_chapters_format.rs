@@ -0,0 +1,181 @@
+use log::error;
+use serde_derive::{Deserialize, Serialize};
+
+use std::io::{Read, Write};
+
+use super::{
+    chapter_title, cues_to_toc, Duration, MediaInfo, Reader, SubtitleCue, TocVisitor, Writer,
+};
+use application::gettext;
+
+static EXTENSION: &str = "chapters.json";
+
+static VERSION: &str = "1.2.0";
+
+/// A chapter in the Podcasting 2.0 JSON chapters file.
+///
+/// `img` & `url` can't be represented in the table of contents, they are ignored when reading.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PodcastChapter {
+    /// In seconds.
+    start_time: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end_time: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    img: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    /// Silent chapters (`false`) are not meant to be displayed in the table of contents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    toc: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PodcastChapters {
+    version: String,
+    chapters: Vec<PodcastChapter>,
+}
+
+/// Podcasting 2.0 JSON chapters file.
+///
+/// See <https://github.com/Podcastindex-org/podcast-namespace/blob/main/chapters/jsonChapters.md>.
+#[derive(Debug, Default)]
+pub struct PodcastChaptersFormat {}
+
+impl PodcastChaptersFormat {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+fn secs_to_duration(secs: f64) -> Duration {
+    Duration::from_nanos((secs.max(0f64) * 1_000_000_000f64).round() as u64)
+}
+
+fn nanos_to_secs(nanos: i64) -> f64 {
+    // Keep the millisecond resolution
+    (nanos / 1_000_000) as f64 / 1_000f64
+}
+
+impl Reader for PodcastChaptersFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let podcast_chapters: PodcastChapters = serde_json::from_reader(source).map_err(|err| {
+            let msg = gettext("unexpected error reading podcast chapters file: {}").replacen(
+                "{}",
+                &err.to_string(),
+                1,
+            );
+            error!("{}", msg);
+            msg
+        })?;
+
+        let mut cues: Vec<SubtitleCue> = podcast_chapters
+            .chapters
+            .into_iter()
+            .filter(|chapter| chapter.toc.unwrap_or(true))
+            .map(|chapter| SubtitleCue {
+                start: secs_to_duration(chapter.start_time),
+                end: chapter.end_time.map(secs_to_duration),
+                text: chapter.title.unwrap_or_default(),
+            })
+            .collect();
+        // The specification doesn't require the chapters to be sorted
+        cues.sort_by_key(|cue| cue.start);
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+impl Writer for PodcastChaptersFormat {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        let mut chapters = Vec::new();
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            if let Some((start, end)) = chapter.start_stop_times() {
+                let title = chapter_title(&chapter);
+
+                chapters.push(PodcastChapter {
+                    start_time: nanos_to_secs(start),
+                    end_time: if end > start {
+                        Some(nanos_to_secs(end))
+                    } else {
+                        None
+                    },
+                    title: Some(title),
+                    ..PodcastChapter::default()
+                });
+            }
+        }
+
+        let podcast_chapters = PodcastChapters {
+            version: VERSION.to_string(),
+            chapters,
+        };
+
+        serde_json::to_writer_pretty(&mut *destination, &podcast_chapters)
+            .map_err(|err| err.to_string())
+            .and_then(|_| destination.write_all(b"\n").map_err(|err| err.to_string()))
+            .map_err(|err| {
+                let msg =
+                    gettext("Failed to write podcast chapters file: {}").replacen("{}", &err, 1);
+                error!("{}", msg);
+                msg
+            })
+    }
+}
+
+#[test]
+fn podcast_chapters_round_trip_test() {
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(600),
+        ..MediaInfo::default()
+    };
+
+    let content = r#"{
+        "version": "1.2.0",
+        "chapters": [
+            { "startTime": 65.5, "endTime": 300, "title": "Topic", "url": "https://example.org" },
+            { "startTime": 320.25, "title": "Outro" },
+            { "startTime": 0, "title": "Intro", "img": "https://example.org/intro.jpg" },
+            { "startTime": 30, "title": "Sponsor", "toc": false }
+        ]
+    }"#;
+
+    let toc = PodcastChaptersFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    PodcastChaptersFormat::default()
+        .write(&info, &mut output)
+        .unwrap();
+
+    let podcast_chapters: PodcastChapters = serde_json::from_slice(&output).unwrap();
+    assert_eq!(VERSION, podcast_chapters.version);
+
+    let chapters: Vec<(f64, Option<f64>, Option<&str>)> = podcast_chapters
+        .chapters
+        .iter()
+        .map(|chapter| {
+            (
+                chapter.start_time,
+                chapter.end_time,
+                chapter.title.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (0f64, Some(65.5f64), Some("Intro")),
+            (65.5f64, Some(300f64), Some("Topic")),
+            (320.25f64, Some(600f64), Some("Outro")),
+        ],
+        chapters,
+    );
+}
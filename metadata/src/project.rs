@@ -0,0 +1,236 @@
+use log::{debug, error};
+use serde_derive::{Deserialize, Serialize};
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use application::gettext;
+
+use super::{Duration, Format};
+
+static EXTENSION: &str = "mediatoc";
+
+/// Version of the project file format.
+///
+/// Increment when a change prevents older versions from reading the file.
+const VERSION: u32 = 1;
+
+/// Streams selection and export flags.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ProjectStreams {
+    pub selected: Vec<String>,
+    /// Streams for which the export flag was cleared.
+    pub not_exported: Vec<String>,
+}
+
+/// Formats selected in the export & split perspectives.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ProjectOutput {
+    pub export_format: Option<Format>,
+    pub split_format: Option<Format>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ProjectView {
+    /// Playback position in nanoseconds.
+    pub position: u64,
+    /// Duration of the waveform displayed in 1000 pixels, in nanoseconds.
+    pub duration_per_1000px: Option<u64>,
+}
+
+impl ProjectView {
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.position)
+    }
+
+    pub fn duration_per_1000px(&self) -> Option<Duration> {
+        self.duration_per_1000px.map(Duration::from_nanos)
+    }
+}
+
+/// The whole editing session for a media.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Project {
+    pub version: u32,
+    pub media_path: PathBuf,
+    /// The edited table of contents with all its tags.
+    #[serde(default)]
+    pub toc: Option<gst::Toc>,
+    #[serde(default)]
+    pub streams: ProjectStreams,
+    #[serde(default)]
+    pub output: ProjectOutput,
+    #[serde(default)]
+    pub view: ProjectView,
+}
+
+impl Project {
+    pub fn new(media_path: &Path) -> Self {
+        Project {
+            version: VERSION,
+            media_path: media_path.to_owned(),
+            toc: None,
+            streams: ProjectStreams::default(),
+            output: ProjectOutput::default(),
+            view: ProjectView::default(),
+        }
+    }
+
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+
+    pub fn is_project(path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .map_or(false, |extension| extension.eq_ignore_ascii_case(EXTENSION))
+    }
+
+    /// Returns the default path of the project for the media at `media_path`.
+    pub fn default_path(media_path: &Path) -> PathBuf {
+        media_path.with_extension(EXTENSION)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let project_file = File::open(path).map_err(|err| {
+            let msg =
+                gettext("Failed to open project file: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        let mut project: Project = ron::de::from_reader(project_file).map_err(|err| {
+            let msg =
+                gettext("Failed to read project file: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        if project.version > VERSION {
+            let msg = gettext("Unsupported project file version: {}").replacen(
+                "{}",
+                &project.version.to_string(),
+                1,
+            );
+            error!("{}", msg);
+            return Err(msg);
+        }
+
+        // Media path relative to the project file
+        if project.media_path.is_relative() {
+            if let Some(parent) = path.parent() {
+                project.media_path = parent.join(&project.media_path);
+            }
+        }
+
+        debug!("loaded project {:?}", project);
+
+        Ok(project)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        // Keep the project usable when the media and project directory is moved
+        let media_path = if self.media_path.parent() == path.parent() {
+            self.media_path
+                .file_name()
+                .map_or_else(|| self.media_path.clone(), PathBuf::from)
+        } else {
+            self.media_path.clone()
+        };
+
+        let project_str = ron::ser::to_string_pretty(
+            &Project {
+                version: VERSION,
+                media_path,
+                ..self.clone()
+            },
+            ron::ser::PrettyConfig::default(),
+        )
+        .map_err(|err| {
+            let msg =
+                gettext("Failed to serialize project: {}").replacen("{}", &err.to_string(), 1);
+            error!("{}", msg);
+            msg
+        })?;
+
+        File::create(path)
+            .and_then(|mut project_file| project_file.write_all(project_str.as_bytes()))
+            .map_err(|err| {
+                let msg =
+                    gettext("Failed to write project file: {}").replacen("{}", &err.to_string(), 1);
+                error!("{}", msg);
+                msg
+            })
+    }
+}
+
+#[test]
+fn project_round_trip_test() {
+    gst::init().unwrap();
+
+    let dir = std::env::temp_dir().join(format!("media-toc-project-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let project_path = dir.join("album.mediatoc");
+
+    let mut toc = gst::Toc::new(gst::TocScope::Global);
+    {
+        let mut edition = gst::TocEntry::new(gst::TocEntryType::Edition, "");
+        let mut chapter = gst::TocEntry::new(gst::TocEntryType::Chapter, "0");
+        chapter
+            .get_mut()
+            .unwrap()
+            .set_start_stop_times(0, 1_000_000_000);
+        let mut tags = gst::TagList::new();
+        {
+            let tags = tags.get_mut().unwrap();
+            tags.add::<gst::tags::Title>(&"Intro", gst::TagMergeMode::Replace);
+            tags.add::<gst::tags::Artist>(&"Artist", gst::TagMergeMode::Replace);
+        }
+        chapter.get_mut().unwrap().set_tags(tags);
+        edition.get_mut().unwrap().append_sub_entry(chapter);
+        toc.get_mut().unwrap().append_entry(edition);
+    }
+
+    let project = Project {
+        toc: Some(toc),
+        streams: ProjectStreams {
+            selected: vec!["audio/0".to_string()],
+            not_exported: vec!["video/0".to_string()],
+        },
+        output: ProjectOutput {
+            export_format: Some(Format::CueSheet),
+            split_format: Some(Format::Flac),
+        },
+        view: ProjectView {
+            position: 500_000_000,
+            duration_per_1000px: Some(2_000_000_000),
+        },
+        ..Project::new(&dir.join("album.flac"))
+    };
+    project.save(&project_path).unwrap();
+    assert!(std::fs::read_to_string(&project_path)
+        .unwrap()
+        .contains("media_path: \"album.flac\""));
+
+    assert!(Project::is_project(&project_path));
+    let loaded = Project::load(&project_path).unwrap();
+    assert_eq!(dir.join("album.flac"), loaded.media_path);
+    assert_eq!(project.streams, loaded.streams);
+    assert_eq!(project.output, loaded.output);
+    assert_eq!(project.view, loaded.view);
+
+    let toc = loaded.toc.unwrap();
+    let chapter = &toc.entries()[0].sub_entries()[0];
+    assert_eq!(Some((0, 1_000_000_000)), chapter.start_stop_times());
+    let tags = chapter.tags().unwrap();
+    assert_eq!("Intro", tags.get::<gst::tags::Title>().unwrap().get());
+    assert_eq!("Artist", tags.get::<gst::tags::Artist>().unwrap().get());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,166 @@
+use std::io::{Read, Write};
+
+use super::{
+    chapter_title, cues_to_toc, read_cues, MediaInfo, Reader, SubtitleFormat, Timestamp4Humans,
+    TocVisitor, Writer,
+};
+use application::gettext;
+
+// Don't overwrite the subtitles which might come along with the media
+static EXTENSION: &str = "chapters.srt";
+
+/// SubRip text format with a cue per chapter.
+#[derive(Debug, Default)]
+pub struct SrtFormat {}
+
+impl SrtFormat {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+fn format_timestamp(ts: &Timestamp4Humans) -> String {
+    format!("{:02}:{:02}:{:02},{:03}", ts.h, ts.m, ts.s, ts.ms)
+}
+
+impl Reader for SrtFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let cues = read_cues(SubtitleFormat::Srt, source)?;
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+fn write_error() -> String {
+    gettext("Failed to write SubRip file")
+}
+
+impl Writer for SrtFormat {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        let mut index = 0;
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            if let Some((start, end)) = chapter.start_stop_times() {
+                // Cues are separated by an empty line
+                let separator = if index > 0 { "\n" } else { "" };
+                index += 1;
+                let end = if end > start {
+                    end
+                } else {
+                    info.duration.as_i64()
+                };
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "{}{}\n{} --> {}\n",
+                    separator,
+                    index,
+                    format_timestamp(&Timestamp4Humans::from_nano(start as u64)),
+                    format_timestamp(&Timestamp4Humans::from_nano(end as u64))
+                );
+
+                let title = chapter_title(&chapter);
+                write_fmt!(destination, write_error(), "{}\n", &title);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn write_srt_test() {
+    use super::{Duration, SubtitleCue};
+
+    gst::init().unwrap();
+
+    let cues = [
+        SubtitleCue {
+            start: Duration::from_secs(0),
+            end: None,
+            text: "Intro".to_string(),
+        },
+        SubtitleCue {
+            start: Duration::from_millis(61_250),
+            end: None,
+            text: "Main topic".to_string(),
+        },
+    ];
+    let duration = Duration::from_secs(3_700);
+
+    let info = MediaInfo {
+        duration,
+        toc: cues_to_toc(&cues.iter().collect::<Vec<_>>(), duration),
+        ..MediaInfo::default()
+    };
+
+    let mut output = Vec::new();
+    SrtFormat::default().write(&info, &mut output).unwrap();
+
+    assert_eq!(
+        concat!(
+            "1\n",
+            "00:00:00,000 --> 00:01:01,250\n",
+            "Intro\n",
+            "\n",
+            "2\n",
+            "00:01:01,250 --> 01:01:40,000\n",
+            "Main topic\n",
+        ),
+        String::from_utf8(output).unwrap(),
+    );
+}
+
+#[test]
+fn srt_round_trip_test() {
+    use super::Duration;
+
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(3_700),
+        ..MediaInfo::default()
+    };
+
+    // Non-contiguous cues: a gap, then an overlap
+    let content = concat!(
+        "1\n",
+        "00:00:00,000 --> 00:00:50,000\n",
+        "Intro\n",
+        "\n",
+        "2\n",
+        "00:01:00,500 --> 00:30:00,000\n",
+        "Main topic\n",
+        "\n",
+        "3\n",
+        "00:29:00,000 --> 00:40:00,000\n",
+        "Questions\n",
+    );
+
+    let toc = SrtFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap()
+        .unwrap();
+
+    let chapters = toc.entries()[0].sub_entries();
+    assert_eq!(
+        vec![
+            Some((0, 50_000_000_000)),
+            Some((60_500_000_000, 1_800_000_000_000)),
+            Some((1_740_000_000_000, 2_400_000_000_000)),
+        ],
+        chapters
+            .iter()
+            .map(|chapter| chapter.start_stop_times())
+            .collect::<Vec<_>>(),
+    );
+
+    let info = MediaInfo {
+        toc: Some(toc),
+        ..info
+    };
+    let mut output = Vec::new();
+    SrtFormat::default().write(&info, &mut output).unwrap();
+
+    assert_eq!(content, String::from_utf8(output).unwrap());
+}
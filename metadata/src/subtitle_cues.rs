@@ -0,0 +1,533 @@
+use log::error;
+use nom::{
+    bytes::complete::tag,
+    character::complete::{digit1, one_of, space0},
+    combinator::opt,
+    error::{Error, ErrorKind},
+    sequence::{preceded, separated_pair, tuple},
+    IResult,
+};
+use regex::Regex;
+
+use std::io::Read;
+
+use super::{default_chapter_title, new_chapter, parse_to, Duration};
+use application::gettext;
+
+static WEBVTT_HEADER: &str = "WEBVTT";
+static LRC_OFFSET_TAG: &str = "offset";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    /// Lyrics: `[mm:ss.xx]text`.
+    Lrc,
+    /// SubRip.
+    Srt,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    pub fn extensions() -> [(&'static str, SubtitleFormat); 3] {
+        [
+            ("lrc", SubtitleFormat::Lrc),
+            ("srt", SubtitleFormat::Srt),
+            ("vtt", SubtitleFormat::WebVtt),
+        ]
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::extensions()
+            .into_iter()
+            .find(|(candidate, _)| candidate.eq_ignore_ascii_case(extension))
+            .map(|(_, format)| format)
+    }
+}
+
+/// A timed text from a subtitle or lyrics stream.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubtitleCue {
+    pub start: Duration,
+    pub end: Option<Duration>,
+    pub text: String,
+}
+
+impl SubtitleCue {
+    /// Returns the text on a single line, without formatting tags.
+    pub fn title(&self) -> String {
+        let mut title = String::with_capacity(self.text.len());
+        let mut closing = None;
+        for c in self.text.chars() {
+            match closing {
+                Some(expected) => {
+                    if c == expected {
+                        closing = None;
+                    }
+                }
+                None => match c {
+                    // HTML like tags, LRC word timestamps
+                    '<' => closing = Some('>'),
+                    // SSA overrides found in some SubRip files
+                    '{' => closing = Some('}'),
+                    _ => title.push(c),
+                },
+            }
+        }
+
+        title.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+}
+
+/// Parses `[hh:]mm:ss[.fff]`, accepting `,` as the decimal separator.
+pub(crate) fn parse_cue_timestamp(i: &str) -> IResult<&str, Duration> {
+    let (i, (first, second, third)) = tuple((
+        parse_to::<u64>,
+        preceded(tag(":"), parse_to::<u64>),
+        opt(preceded(tag(":"), parse_to::<u64>)),
+    ))(i)?;
+
+    let (h, m, s) = match third {
+        Some(s) => (first, second, s),
+        None => (0, first, second),
+    };
+
+    let (i, fraction) = opt(preceded(one_of(".,"), digit1))(i)?;
+    let nanos = fraction.map_or(0, |fraction: &str| {
+        let digits = &fraction[..fraction.len().min(9)];
+        digits.parse::<u64>().unwrap() * 10u64.pow(9 - digits.len() as u32)
+    });
+
+    // Don't overflow on malformed input
+    let nanos = h
+        .checked_mul(60)
+        .and_then(|mins| mins.checked_add(m))
+        .and_then(|mins| mins.checked_mul(60))
+        .and_then(|secs| secs.checked_add(s))
+        .and_then(|secs| secs.checked_mul(1_000_000_000))
+        .and_then(|secs_nanos| secs_nanos.checked_add(nanos))
+        .ok_or_else(|| nom::Err::Error(Error::new(i, ErrorKind::TooLarge)))?;
+
+    Ok((i, Duration::from_nanos(nanos)))
+}
+
+fn parse_timing(i: &str) -> IResult<&str, (Duration, Duration)> {
+    separated_pair(
+        parse_cue_timestamp,
+        tuple((space0, tag("-->"), space0)),
+        parse_cue_timestamp,
+    )(i)
+}
+
+/// Parses SubRip and WebVTT cues: blocks separated by empty lines.
+fn parse_blocks(content: &str, format: SubtitleFormat) -> Result<Vec<SubtitleCue>, String> {
+    let mut lines = content.lines().peekable();
+
+    if format == SubtitleFormat::WebVtt {
+        match lines.next() {
+            Some(header) if header.starts_with(WEBVTT_HEADER) => (),
+            _ => {
+                let msg = gettext("missing WEBVTT header");
+                error!("{}", msg);
+                return Err(msg);
+            }
+        }
+    }
+
+    let mut cues = Vec::new();
+    loop {
+        while lines.peek().map_or(false, |line| line.trim().is_empty()) {
+            lines.next();
+        }
+
+        let mut block = Vec::new();
+        while let Some(line) = lines.next_if(|line| !line.trim().is_empty()) {
+            block.push(line);
+        }
+        if block.is_empty() {
+            break;
+        }
+
+        // The timing line is preceded by an optional identifier
+        let timing = block
+            .iter()
+            .take(2)
+            .enumerate()
+            .find_map(|(idx, line)| parse_timing(line.trim()).ok().map(|(_, ts)| (idx, ts)));
+
+        match timing {
+            Some((idx, (start, end))) => cues.push(SubtitleCue {
+                start,
+                end: Some(end),
+                text: block[idx + 1..].join("\n"),
+            }),
+            // NOTE, STYLE or REGION block
+            None if format == SubtitleFormat::WebVtt => (),
+            None => {
+                let line = block[0];
+                let msg = gettext("unexpected sequence starting with: {}").replacen(
+                    "{}",
+                    &line[..line.len().min(10)],
+                    1,
+                );
+                error!("{}", msg);
+                return Err(msg);
+            }
+        }
+    }
+
+    Ok(cues)
+}
+
+fn parse_lrc(content: &str) -> Result<Vec<SubtitleCue>, String> {
+    let mut offset_ms = 0i64;
+    let mut timed_lines = Vec::new();
+
+    for line in content.lines() {
+        let mut rest = line.trim();
+        let mut starts = Vec::new();
+
+        while let Some(tag_content) = rest.strip_prefix('[') {
+            let tag_end = match tag_content.find(']') {
+                Some(tag_end) => tag_end,
+                None => break,
+            };
+            let (tag_value, remainder) = (&tag_content[..tag_end], &tag_content[tag_end + 1..]);
+
+            match parse_cue_timestamp(tag_value) {
+                Ok(("", start)) => starts.push(start),
+                _ => {
+                    // ID tag, e.g. `[ar:Artist]`
+                    if let Some((name, value)) = tag_value.split_once(':') {
+                        if name.trim() == LRC_OFFSET_TAG {
+                            offset_ms = value.trim().parse::<i64>().map_err(|_| {
+                                let msg = gettext("expecting a number, found: {}")
+                                    .replacen("{}", value, 1);
+                                error!("{}", msg);
+                                msg
+                            })?;
+                        }
+                    }
+                }
+            }
+
+            rest = remainder;
+        }
+
+        for start in starts {
+            timed_lines.push((start, rest.trim().to_string()));
+        }
+    }
+
+    // A positive offset shifts the lyrics up
+    let offset_ns = offset_ms * 1_000_000;
+    timed_lines.sort_by_key(|(start, _)| *start);
+
+    let mut cues: Vec<SubtitleCue> = Vec::with_capacity(timed_lines.len());
+    for (start, text) in timed_lines {
+        let start = Duration::from_nanos((start.as_i64() - offset_ns).max(0) as u64);
+        if let Some(prev) = cues.last_mut() {
+            prev.end = Some(start);
+        }
+
+        cues.push(SubtitleCue {
+            start,
+            end: None,
+            text,
+        });
+    }
+
+    Ok(cues)
+}
+
+pub fn parse_cues(format: SubtitleFormat, content: &str) -> Result<Vec<SubtitleCue>, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format {
+        SubtitleFormat::Lrc => parse_lrc(content),
+        SubtitleFormat::Srt | SubtitleFormat::WebVtt => parse_blocks(content, format),
+    }
+}
+
+pub fn read_cues(
+    format: SubtitleFormat,
+    source: &mut dyn Read,
+) -> Result<Vec<SubtitleCue>, String> {
+    let mut content = String::new();
+    source.read_to_string(&mut content).map_err(|_| {
+        let msg = gettext("unexpected error reading subtitle file.");
+        error!("{}", msg);
+        msg
+    })?;
+
+    parse_cues(format, &content)
+}
+
+/// Which cues are converted into chapters.
+#[derive(Clone, Debug)]
+pub enum CueSelection {
+    All,
+    /// One cue out of `n`, starting with the first one.
+    EveryNth(usize),
+    /// The cues whose title matches the expression.
+    Matching(Regex),
+}
+
+impl CueSelection {
+    pub fn matching(pattern: &str) -> Result<Self, String> {
+        Regex::new(pattern)
+            .map(CueSelection::Matching)
+            .map_err(|err| {
+                gettext("invalid expression \"{}\": {}")
+                    .replacen("{}", pattern, 1)
+                    .replacen("{}", &err.to_string(), 1)
+            })
+    }
+
+    /// Returns the selected cues, skipping those without text.
+    pub fn select<'cues>(&self, cues: &'cues [SubtitleCue]) -> Vec<&'cues SubtitleCue> {
+        let non_empty = cues.iter().filter(|cue| !cue.title().is_empty());
+
+        match self {
+            CueSelection::All => non_empty.collect(),
+            CueSelection::EveryNth(n) => non_empty.step_by((*n).max(1)).collect(),
+            CueSelection::Matching(regex) => non_empty
+                .filter(|cue| regex.is_match(&cue.title()))
+                .collect(),
+        }
+    }
+}
+
+/// Builds a toc with a chapter starting at each cue.
+///
+/// A chapter ends with its cue when the cue's end is known, otherwise at the start
+/// of the next chapter. The last one ends with the media.
+pub fn cues_to_toc(cues: &[&SubtitleCue], duration: Duration) -> Option<gst::Toc> {
+    build_toc(cues, duration, true)
+}
+
+/// Builds a toc with a chapter starting at each cue, ignoring the cues' end.
+///
+/// Each chapter ends at the start of the next one, the last one ends with the media.
+/// This is used to split the media at some of the cues of a subtitle stream.
+pub fn cue_starts_to_toc(cues: &[&SubtitleCue], duration: Duration) -> Option<gst::Toc> {
+    build_toc(cues, duration, false)
+}
+
+fn build_toc(cues: &[&SubtitleCue], duration: Duration, use_cue_ends: bool) -> Option<gst::Toc> {
+    let mut chapters: Vec<(Duration, Option<Duration>, String)> = Vec::with_capacity(cues.len());
+    for cue in cues {
+        if cue.start >= duration {
+            break;
+        }
+
+        // Only keep the first cue starting at a given position
+        if chapters
+            .last()
+            .map_or(true, |(prev_start, _, _)| *prev_start < cue.start)
+        {
+            let title = cue.title();
+            let title = if title.is_empty() {
+                default_chapter_title()
+            } else {
+                title
+            };
+
+            let end = cue
+                .end
+                .filter(|end| use_cue_ends && *end > cue.start)
+                .map(|end| end.min(duration));
+            chapters.push((cue.start, end, title));
+        }
+    }
+
+    if chapters.is_empty() {
+        return None;
+    }
+
+    let mut toc_edition = gst::TocEntry::new(gst::TocEntryType::Edition, "");
+    for (idx, (start, end, title)) in chapters.iter().enumerate() {
+        let end = end.unwrap_or_else(|| {
+            chapters
+                .get(idx + 1)
+                .map_or(duration, |(next_start, _, _)| *next_start)
+        });
+        toc_edition
+            .get_mut()
+            .unwrap()
+            .append_sub_entry(new_chapter(idx + 1, *start, end, title));
+    }
+
+    let mut toc = gst::Toc::new(gst::TocScope::Global);
+    toc.get_mut().unwrap().append_entry(toc_edition);
+    Some(toc)
+}
+
+#[test]
+fn parse_cue_timestamp_test() {
+    assert_eq!(
+        parse_cue_timestamp("01:02:03,450").unwrap().1,
+        Duration::from_millis(((60 + 2) * 60 + 3) * 1_000 + 450),
+    );
+    assert_eq!(
+        parse_cue_timestamp("02:03.45").unwrap().1,
+        Duration::from_millis((2 * 60 + 3) * 1_000 + 450),
+    );
+    assert_eq!(
+        parse_cue_timestamp("02:03").unwrap().1,
+        Duration::from_secs(2 * 60 + 3),
+    );
+    assert!(parse_cue_timestamp("ar:Artist").is_err());
+    assert!(parse_cue_timestamp("18446744073709551615:00:00.000").is_err());
+    assert!(parse_cue_timestamp("5124095576030431:00:00.000").is_err());
+    assert!(parse_cues(
+        SubtitleFormat::Srt,
+        "1\n99999999999999999:00:00,000 --> 99999999999999999:00:01,000\nOops\n"
+    )
+    .is_err());
+}
+
+#[test]
+fn parse_srt_test() {
+    let content = concat!(
+        "\u{feff}1\r\n",
+        "00:00:01,000 --> 00:00:02,500\r\n",
+        "<i>Welcome</i>\r\n",
+        "everybody\r\n",
+        "\r\n",
+        "2\r\n",
+        "00:01:00,000 --> 00:01:04,000\r\n",
+        "{\\an8}First topic\r\n",
+    );
+
+    let cues = parse_cues(SubtitleFormat::Srt, content).unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].start, Duration::from_secs(1));
+    assert_eq!(cues[0].end, Some(Duration::from_millis(2_500)));
+    assert_eq!(cues[0].title(), "Welcome everybody");
+    assert_eq!(cues[1].start, Duration::from_secs(60));
+    assert_eq!(cues[1].title(), "First topic");
+
+    assert!(parse_cues(SubtitleFormat::Srt, "1\nnot a timing\ntext\n").is_err());
+}
+
+#[test]
+fn parse_webvtt_test() {
+    let content = concat!(
+        "WEBVTT - talk\n",
+        "\n",
+        "NOTE this is a comment\n",
+        "\n",
+        "intro\n",
+        "00:01.000 --> 00:02.000 align:start\n",
+        "Introduction\n",
+        "\n",
+        "01:00:00.000 --> 01:00:05.000\n",
+        "Questions\n",
+    );
+
+    let cues = parse_cues(SubtitleFormat::WebVtt, content).unwrap();
+    assert_eq!(cues.len(), 2);
+    assert_eq!(cues[0].start, Duration::from_secs(1));
+    assert_eq!(cues[0].text, "Introduction");
+    assert_eq!(cues[1].start, Duration::from_secs(3_600));
+
+    assert!(parse_cues(SubtitleFormat::WebVtt, "00:01.000 --> 00:02.000\n").is_err());
+}
+
+#[test]
+fn parse_lrc_test() {
+    let content = concat!(
+        "[ar:Artist]\n",
+        "[offset:500]\n",
+        "[00:12.00]First line\n",
+        "[00:20.50][01:10.00]Chorus\n",
+        "[00:30.00]\n",
+    );
+
+    let cues = parse_cues(SubtitleFormat::Lrc, content).unwrap();
+    assert_eq!(cues.len(), 4);
+    assert_eq!(cues[0].start, Duration::from_millis(11_500));
+    assert_eq!(cues[0].end, Some(Duration::from_millis(20_000)));
+    assert_eq!(cues[1].text, "Chorus");
+    assert_eq!(cues[2].text, "");
+    assert_eq!(cues[3].start, Duration::from_millis(69_500));
+    assert_eq!(cues[3].end, None);
+}
+
+#[test]
+fn select_cues_test() {
+    let cues: Vec<SubtitleCue> = ["Intro", "", "Song 1", "talk", "Song 2"]
+        .iter()
+        .enumerate()
+        .map(|(idx, text)| SubtitleCue {
+            start: Duration::from_secs(idx as u64 * 10),
+            end: None,
+            text: text.to_string(),
+        })
+        .collect();
+
+    let titles = |selection: CueSelection| {
+        selection
+            .select(&cues)
+            .iter()
+            .map(|cue| cue.title())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        titles(CueSelection::All),
+        ["Intro", "Song 1", "talk", "Song 2"]
+    );
+    assert_eq!(titles(CueSelection::EveryNth(2)), ["Intro", "talk"]);
+    assert_eq!(
+        titles(CueSelection::Matching(Regex::new("^Song").unwrap())),
+        ["Song 1", "Song 2"]
+    );
+
+    gst::init().unwrap();
+    let selected = CueSelection::EveryNth(2).select(&cues);
+    let toc = cue_starts_to_toc(&selected, Duration::from_secs(35)).unwrap();
+    let editions = toc.entries();
+    let chapters = editions[0].sub_entries();
+    assert_eq!(chapters.len(), 2);
+    assert_eq!(chapters[0].start_stop_times(), Some((0, 30_000_000_000)));
+    assert_eq!(
+        chapters[1].start_stop_times(),
+        Some((30_000_000_000, 35_000_000_000))
+    );
+}
+
+#[test]
+fn cues_to_toc_test() {
+    gst::init().unwrap();
+
+    let cue = |start: u64, end: Option<u64>, text: &str| SubtitleCue {
+        start: Duration::from_secs(start),
+        end: end.map(Duration::from_secs),
+        text: text.to_string(),
+    };
+    let cues = [
+        cue(0, Some(10), "Intro"),
+        // Gap before this one, overlap with the next one
+        cue(20, Some(45), "Topic"),
+        cue(40, None, "Questions"),
+        cue(50, Some(90), "Outro"),
+    ];
+    let cues = cues.iter().collect::<Vec<_>>();
+
+    let times = |toc: gst::Toc| {
+        toc.entries()[0]
+            .sub_entries()
+            .iter()
+            .map(|chapter| chapter.start_stop_times().unwrap())
+            .collect::<Vec<(i64, i64)>>()
+    };
+    let s = |secs: i64| secs * 1_000_000_000;
+
+    assert_eq!(
+        times(cues_to_toc(&cues, Duration::from_secs(60)).unwrap()),
+        [(0, s(10)), (s(20), s(45)), (s(40), s(50)), (s(50), s(60))],
+    );
+    assert_eq!(
+        times(cue_starts_to_toc(&cues, Duration::from_secs(60)).unwrap()),
+        [(0, s(20)), (s(20), s(40)), (s(40), s(50)), (s(50), s(60))],
+    );
+}
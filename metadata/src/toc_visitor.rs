@@ -1,3 +1,33 @@
+use super::{default_chapter_title, Duration};
+
+/// Returns the title of `chapter` or the default title if it has none.
+pub fn chapter_title(chapter: &gst::TocEntryRef) -> String {
+    chapter
+        .tags()
+        .and_then(|tags| {
+            tags.get::<gst::tags::Title>()
+                .map(|tag| tag.get().to_owned())
+        })
+        .unwrap_or_else(default_chapter_title)
+}
+
+/// Builds the chapter `nb` with the given times and title.
+pub(crate) fn new_chapter(nb: usize, start: Duration, end: Duration, title: &str) -> gst::TocEntry {
+    let mut chapter = gst::TocEntry::new(gst::TocEntryType::Chapter, &format!("{:02}", nb));
+    chapter
+        .get_mut()
+        .unwrap()
+        .set_start_stop_times(start.as_i64(), end.as_i64());
+
+    let mut tag_list = gst::TagList::new();
+    tag_list
+        .get_mut()
+        .unwrap()
+        .add::<gst::tags::Title>(&title, gst::TagMergeMode::Replace);
+    chapter.get_mut().unwrap().set_tags(tag_list);
+    chapter
+}
+
 #[derive(Debug)]
 pub enum TocVisit {
     EnteringChildren,
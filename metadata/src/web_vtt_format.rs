@@ -0,0 +1,162 @@
+use std::io::{Read, Write};
+
+use super::{
+    chapter_title, cues_to_toc, read_cues, MediaInfo, Reader, SubtitleFormat, Timestamp4Humans,
+    TocVisitor, Writer,
+};
+use application::gettext;
+
+// Web players expect the chapters in a dedicated track
+static EXTENSION: &str = "chapters.vtt";
+
+static HEADER: &str = "WEBVTT";
+
+/// WebVTT chapters track, as used with `<track kind="chapters">`.
+#[derive(Debug, Default)]
+pub struct WebVttFormat {}
+
+impl WebVttFormat {
+    pub fn extension() -> &'static str {
+        EXTENSION
+    }
+}
+
+fn format_timestamp(ts: &Timestamp4Humans) -> String {
+    format!("{:02}:{:02}:{:02}.{:03}", ts.h, ts.m, ts.s, ts.ms)
+}
+
+impl Reader for WebVttFormat {
+    fn read(&self, info: &MediaInfo, source: &mut dyn Read) -> Result<Option<gst::Toc>, String> {
+        let cues = read_cues(SubtitleFormat::WebVtt, source)?;
+
+        Ok(cues_to_toc(&cues.iter().collect::<Vec<_>>(), info.duration))
+    }
+}
+
+fn write_error() -> String {
+    gettext("Failed to write WebVTT file")
+}
+
+impl Writer for WebVttFormat {
+    fn write(&self, info: &MediaInfo, destination: &mut dyn Write) -> Result<(), String> {
+        write_fmt!(destination, write_error(), "{}\n", HEADER);
+
+        let mut index = 0;
+        let mut toc_visitor = TocVisitor::new(info.toc.as_ref().unwrap());
+        while let Some(chapter) = toc_visitor.next_chapter() {
+            if let Some((start, end)) = chapter.start_stop_times() {
+                index += 1;
+                let end = if end > start {
+                    end
+                } else {
+                    info.duration.as_i64()
+                };
+                write_fmt!(
+                    destination,
+                    write_error(),
+                    "\n{}\n{} --> {}\n",
+                    index,
+                    format_timestamp(&Timestamp4Humans::from_nano(start as u64)),
+                    format_timestamp(&Timestamp4Humans::from_nano(end as u64))
+                );
+
+                let title = chapter_title(&chapter);
+                write_fmt!(destination, write_error(), "{}\n", &title);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn web_vtt_round_trip_test() {
+    use super::Duration;
+
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(3_700),
+        ..MediaInfo::default()
+    };
+
+    let content = concat!(
+        "WEBVTT\n",
+        "\n",
+        "NOTE chapters\n",
+        "\n",
+        "1\n",
+        "00:00:00.000 --> 00:01:00.500\n",
+        "Intro\n",
+        "\n",
+        "2\n",
+        "00:01:00.500 --> 01:01:40.000\n",
+        "Main topic\n",
+    );
+
+    let toc = WebVttFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap();
+
+    let info = MediaInfo { toc, ..info };
+    let mut output = Vec::new();
+    WebVttFormat::default().write(&info, &mut output).unwrap();
+
+    assert_eq!(
+        concat!(
+            "WEBVTT\n",
+            "\n",
+            "1\n",
+            "00:00:00.000 --> 00:01:00.500\n",
+            "Intro\n",
+            "\n",
+            "2\n",
+            "00:01:00.500 --> 01:01:40.000\n",
+            "Main topic\n",
+        ),
+        String::from_utf8(output).unwrap(),
+    );
+}
+
+#[test]
+fn web_vtt_non_contiguous_cues_test() {
+    use super::Duration;
+
+    gst::init().unwrap();
+
+    let info = MediaInfo {
+        duration: Duration::from_secs(600),
+        ..MediaInfo::default()
+    };
+
+    let content = concat!(
+        "WEBVTT\n",
+        "\n",
+        "00:00.000 --> 00:30.000\n",
+        "Intro\n",
+        "\n",
+        "01:00.000 --> 02:00.000\n",
+        "Topic\n",
+        "\n",
+        "01:30.000 --> 05:00.000\n",
+        "Aside\n",
+    );
+
+    let toc = WebVttFormat::default()
+        .read(&info, &mut content.as_bytes())
+        .unwrap()
+        .unwrap();
+
+    let chapters = toc.entries()[0].sub_entries();
+    assert_eq!(
+        vec![
+            Some((0, 30_000_000_000)),
+            Some((60_000_000_000, 120_000_000_000)),
+            Some((90_000_000_000, 300_000_000_000)),
+        ],
+        chapters
+            .iter()
+            .map(|chapter| chapter.start_stop_times())
+            .collect::<Vec<_>>(),
+    );
+}
@@ -126,10 +126,12 @@ fn render_buffers(
         DURATION_FOR_1000,
         &mut vec![
             AudioChannel {
+                position: gst_audio::AudioChannelPosition::FrontLeft,
                 side: AudioChannelSide::Left,
                 factor: 1f64,
             },
             AudioChannel {
+                position: gst_audio::AudioChannelPosition::FrontRight,
                 side: AudioChannelSide::Right,
                 factor: 1f64,
             },
@@ -3,7 +3,7 @@ use gst_audio::AudioChannelPosition as Position;
 /// Inline length for `SmallVec`s with channels.
 pub const INLINE_CHANNELS: usize = 5 + 1;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub enum AudioChannelSide {
     Center,
     Left,
@@ -12,6 +12,7 @@ pub enum AudioChannelSide {
 }
 
 pub struct AudioChannel {
+    pub position: Position,
     pub side: AudioChannelSide,
     pub factor: f64,
 }
@@ -51,6 +52,46 @@ impl AudioChannel {
             _ => (AudioChannelSide::NotLocalized, 0.7f64),
         };
 
-        AudioChannel { side, factor }
+        AudioChannel {
+            position,
+            side,
+            factor,
+        }
+    }
+
+    /// Returns a short label for the channel position, e.g. "FL" for `FrontLeft`.
+    pub fn label(&self) -> &'static str {
+        match self.position {
+            Position::Mono => "M",
+            Position::FrontLeft => "FL",
+            Position::FrontRight => "FR",
+            Position::FrontCenter => "FC",
+            Position::Lfe1 => "LFE",
+            Position::RearLeft => "RL",
+            Position::RearRight => "RR",
+            Position::FrontLeftOfCenter => "FLC",
+            Position::FrontRightOfCenter => "FRC",
+            Position::RearCenter => "RC",
+            Position::Lfe2 => "LFE2",
+            Position::SideLeft => "SL",
+            Position::SideRight => "SR",
+            Position::TopFrontLeft => "TFL",
+            Position::TopFrontRight => "TFR",
+            Position::TopFrontCenter => "TFC",
+            Position::TopCenter => "TC",
+            Position::TopRearLeft => "TRL",
+            Position::TopRearRight => "TRR",
+            Position::TopSideLeft => "TSL",
+            Position::TopSideRight => "TSR",
+            Position::TopRearCenter => "TRC",
+            Position::BottomFrontCenter => "BFC",
+            Position::BottomFrontLeft => "BFL",
+            Position::BottomFrontRight => "BFR",
+            Position::WideLeft => "WL",
+            Position::WideRight => "WR",
+            Position::SurroundLeft => "SUL",
+            Position::SurroundRight => "SUR",
+            _ => "?",
+        }
     }
 }
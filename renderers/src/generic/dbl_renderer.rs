@@ -5,7 +5,10 @@ use log::info;
 use std::sync::{Arc, Mutex};
 
 use super::Renderer;
-use crate::{AudioBuffer, AudioChannel, SampleIndex, SampleIndexRange, Timestamp, INLINE_CHANNELS};
+use crate::{
+    snap, AudioBuffer, AudioChannel, SampleIndex, SampleIndexRange, SnapMode, Timestamp,
+    INLINE_CHANNELS,
+};
 use metadata::Duration;
 
 const EXTRACTION_THRESHOLD: SampleIndexRange = SampleIndexRange::new(4096);
@@ -160,6 +163,7 @@ impl DoubleRenderer {
 
     pub fn have_segment(&mut self, segment: &gst::FormattedSegment<gst::ClockTime>) {
         self.audio_buffer.have_segment(segment);
+        self.impl_.working_mut().set_rate(segment.rate());
         self.sample_gauge = Some(SampleIndex::default());
     }
 
@@ -238,4 +242,11 @@ impl DoubleRenderer {
                 .unwrap(),
         })
     }
+
+    /// Snaps `ts` to the nearest remarkable point within `radius`.
+    ///
+    /// See [`snap::snap`].
+    pub fn snap(&self, ts: Timestamp, radius: Duration, mode: SnapMode) -> Option<Timestamp> {
+        snap::snap(&self.audio_buffer, self.sample_duration, ts, radius, mode)
+    }
 }
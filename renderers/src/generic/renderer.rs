@@ -43,6 +43,9 @@ pub trait Renderer: Send {
 
     fn reset_sample_cndt(&mut self);
 
+    /// Sets the playback rate of current segment.
+    fn set_rate(&mut self, _rate: f64) {}
+
     fn first_visible_sample(&self) -> Option<SampleIndex>;
 
     fn current_ts(&self) -> Option<Timestamp> {
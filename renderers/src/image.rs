@@ -121,6 +121,37 @@ impl Image {
         }
     }
 
+    /// Builds an `Image` from raw pixels laid out as Cairo's `Rgb24`.
+    ///
+    /// Rows are copied so as to comply with Cairo's stride requirements.
+    pub fn from_rgb24(
+        width: i32,
+        height: i32,
+        src_stride: usize,
+        data: &[u8],
+    ) -> Result<Self, String> {
+        let image = Image::try_new(width, height)?;
+
+        let row_len = width as usize * 4;
+        if src_stride < row_len || data.len() < (height as usize - 1) * src_stride + row_len {
+            return Err(format!(
+                "Not enough data for a {}x{} image with stride {}",
+                width, height, src_stride,
+            ));
+        }
+
+        let mut pixels = image.pixels.take().unwrap();
+        for (dst_row, src_row) in pixels
+            .chunks_mut(image.stride as usize)
+            .zip(data.chunks(src_stride))
+        {
+            dst_row[..row_len].copy_from_slice(&src_row[..row_len]);
+        }
+        image.pixels.set(Some(pixels));
+
+        Ok(image)
+    }
+
     pub fn width(&self) -> i32 {
         self.width
     }
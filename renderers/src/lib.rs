@@ -21,9 +21,14 @@ pub use sample_index_range::SampleIndexRange;
 pub mod sample_value;
 pub use sample_value::SampleValue;
 
+pub mod snap;
+pub use snap::SnapMode;
+
 pub mod timestamp;
 pub use timestamp::Timestamp;
 
 pub mod waveform;
-pub use waveform::image::{WaveformImage, BACKGROUND_COLOR};
+pub use waveform::audiogram::AudiogramRenderer;
+pub use waveform::image::WaveformImage;
 pub use waveform::renderer::{DoubleWaveformRenderer, ImagePositions, WaveformRenderer};
+pub use waveform::{AmplitudeScale, Color, LaneMode, Theme};
@@ -0,0 +1,491 @@
+use gst::{
+    self, glib, prelude::*, subclass::prelude::*, ClockTime, Element, Event, FlowError, Pad,
+};
+
+use once_cell::sync::Lazy;
+
+use std::sync::Mutex;
+
+use metadata::Duration;
+
+use crate::{plugin, AudioBuffer, AudioChannel, AudiogramRenderer, Timestamp, INLINE_CHANNELS};
+
+pub const NAME: &str = "mediatocaudiogram";
+
+/// Width of the produced video frames.
+pub const WIDTH_PROP: &str = "width";
+pub const DEFAULT_WIDTH: i32 = 1280;
+/// Height of the produced video frames.
+pub const HEIGHT_PROP: &str = "height";
+pub const DEFAULT_HEIGHT: i32 = 720;
+/// Frames per second of the produced video.
+pub const FRAMERATE_PROP: &str = "framerate";
+pub const DEFAULT_FRAMERATE: i32 = 25;
+/// Duration of the audio displayed in a frame in ns.
+pub const WINDOW_DURATION_PROP: &str = "window-duration";
+pub const DEFAULT_WINDOW_DURATION: Duration = Duration::from_secs(4);
+/// Text displayed at the bottom of the frames.
+pub const TITLE_PROP: &str = "title";
+
+#[cfg(target_endian = "little")]
+const VIDEO_FORMAT: &str = "BGRx";
+#[cfg(target_endian = "big")]
+const VIDEO_FORMAT: &str = "xRGB";
+
+static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
+    gst::DebugCategory::new(
+        NAME,
+        gst::DebugColorFlags::empty(),
+        Some("media-toc Audiogram"),
+    )
+});
+
+#[derive(Debug, Clone)]
+struct Settings {
+    width: i32,
+    height: i32,
+    framerate: i32,
+    window_duration: Duration,
+    title: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            framerate: DEFAULT_FRAMERATE,
+            window_duration: DEFAULT_WINDOW_DURATION,
+            title: None,
+        }
+    }
+}
+
+impl Settings {
+    fn video_caps(&self) -> gst::Caps {
+        gst::Caps::new_simple(
+            "video/x-raw",
+            &[
+                ("format", &VIDEO_FORMAT),
+                ("width", &self.width),
+                ("height", &self.height),
+                ("framerate", &gst::Fraction::new(self.framerate, 1)),
+                ("pixel-aspect-ratio", &gst::Fraction::new(1, 1)),
+            ],
+        )
+    }
+}
+
+#[derive(Debug)]
+struct Stream {
+    audio_buffer: AudioBuffer,
+    renderer: AudiogramRenderer,
+    sample_duration: Duration,
+    frame_duration: Duration,
+    next_frame_ts: Option<Timestamp>,
+}
+
+impl Stream {
+    fn new(settings: &Settings, audio_info: &gst_audio::AudioInfo) -> Self {
+        // Keep enough samples to render the window on both sides of the cursor
+        let mut audio_buffer =
+            AudioBuffer::new(settings.window_duration * 2 + Duration::from_secs(1));
+        audio_buffer.init(audio_info);
+
+        let sample_duration = Duration::from_frequency(u64::from(audio_info.rate()));
+
+        let mut renderer = AudiogramRenderer::new(
+            settings.width,
+            settings.height,
+            settings.window_duration,
+            settings.title.clone(),
+        );
+        let mut positions_opt = audio_info.positions().map(|positions| positions.iter());
+        renderer.set_sample_cndt(
+            sample_duration,
+            positions_opt
+                .iter_mut()
+                .flatten()
+                .take(INLINE_CHANNELS)
+                .map(|position| AudioChannel::new(*position)),
+        );
+
+        Stream {
+            audio_buffer,
+            renderer,
+            sample_duration,
+            frame_duration: Duration::from_frequency(settings.framerate as u64),
+            next_frame_ts: None,
+        }
+    }
+
+    fn have_segment(&mut self, segment: &gst::FormattedSegment<ClockTime>) {
+        self.audio_buffer.have_segment(segment);
+        self.next_frame_ts = segment.start().map(Timestamp::from);
+    }
+
+    fn push_buffer(&mut self, buffer: &gst::Buffer) {
+        let lower_to_keep = match self.next_frame_ts {
+            Some(next_frame_ts) => self.renderer.sample_window(next_frame_ts).0,
+            None => self.audio_buffer.lower,
+        };
+        self.audio_buffer.push_buffer(buffer, lower_to_keep);
+    }
+
+    /// Renders the frames for which all the samples are available.
+    ///
+    /// On EOS, the frames are rendered up to the last sample.
+    fn pending_frames(&mut self, is_eos: bool) -> Result<Vec<gst::Buffer>, String> {
+        let mut frames = Vec::new();
+
+        while let Some(frame_ts) = self.next_frame_ts {
+            if is_eos {
+                if frame_ts.sample_index(self.sample_duration) >= self.audio_buffer.upper {
+                    break;
+                }
+            } else if self.renderer.sample_window(frame_ts).1 > self.audio_buffer.upper {
+                break;
+            }
+
+            let surface = self.renderer.render_frame(&self.audio_buffer, frame_ts)?;
+            let data = surface
+                .take_data()
+                .map_err(|err| format!("Couldn't retrieve audiogram frame: {:?}", err))?;
+
+            let mut frame = gst::Buffer::from_mut_slice(data);
+            {
+                let frame = frame.get_mut().unwrap();
+                frame.set_pts(ClockTime::from(frame_ts));
+                frame.set_duration(ClockTime::from_nseconds(self.frame_duration.as_u64()));
+            }
+            frames.push(frame);
+
+            self.next_frame_ts = Some(frame_ts + self.frame_duration);
+        }
+
+        Ok(frames)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Context {
+    settings: Settings,
+    stream: Option<Stream>,
+}
+
+pub struct Audiogram {
+    sinkpad: Pad,
+    srcpad: Pad,
+    ctx: Mutex<Context>,
+}
+
+/// Pads handler.
+impl Audiogram {
+    fn push_frames(&self, frames: Result<Vec<gst::Buffer>, String>) -> Result<(), FlowError> {
+        let frames = frames.map_err(|err| {
+            gst::element_imp_error!(self, gst::StreamError::Failed, ["{}", err]);
+            FlowError::Error
+        })?;
+
+        for frame in frames {
+            self.srcpad.push(frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn sink_chain(
+        &self,
+        _pad: &Pad,
+        buffer: gst::Buffer,
+    ) -> Result<gst::FlowSuccess, gst::FlowError> {
+        let frames = {
+            let mut ctx = self.ctx.lock().unwrap();
+            let stream = ctx.stream.as_mut().ok_or(FlowError::NotNegotiated)?;
+            stream.push_buffer(&buffer);
+            stream.pending_frames(false)
+        };
+
+        self.push_frames(frames)?;
+
+        Ok(gst::FlowSuccess::Ok)
+    }
+
+    fn sink_event(&self, pad: &Pad, element: &plugin::Audiogram, event: Event) -> bool {
+        use gst::EventView::*;
+
+        match event.view() {
+            Caps(caps_evt) => {
+                let audio_info = match gst_audio::AudioInfo::from_caps(caps_evt.caps()) {
+                    Ok(audio_info) => audio_info,
+                    Err(err) => {
+                        gst::error!(CAT, obj: pad, "invalid caps: {}", err);
+                        return false;
+                    }
+                };
+
+                let video_caps = {
+                    let mut ctx = self.ctx.lock().unwrap();
+                    ctx.stream = Some(Stream::new(&ctx.settings, &audio_info));
+                    ctx.settings.video_caps()
+                };
+
+                gst::debug!(CAT, obj: pad, "pushing {:?}", video_caps);
+
+                return self.srcpad.push_event(gst::event::Caps::new(&video_caps));
+            }
+            Segment(evt) => {
+                if let Some(segment) = evt.segment().downcast_ref::<gst::format::Time>() {
+                    if let Some(stream) = self.ctx.lock().unwrap().stream.as_mut() {
+                        stream.have_segment(segment);
+                    }
+                }
+            }
+            Eos(_) => {
+                let frames = self
+                    .ctx
+                    .lock()
+                    .unwrap()
+                    .stream
+                    .as_mut()
+                    .map(|stream| {
+                        stream.audio_buffer.handle_eos();
+                        stream.pending_frames(true)
+                    })
+                    .unwrap_or_else(|| Ok(Vec::new()));
+
+                if self.push_frames(frames).is_err() {
+                    gst::warning!(CAT, obj: pad, "couldn't push last frames");
+                }
+
+                gst::debug!(CAT, obj: pad, "reached EOS");
+            }
+            FlushStop(_) => {
+                if let Some(stream) = self.ctx.lock().unwrap().stream.as_mut() {
+                    stream.audio_buffer.clean_samples();
+                    stream.next_frame_ts = None;
+                }
+            }
+            _ => (),
+        }
+
+        gst::Pad::event_default(pad, Some(element), event)
+    }
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Audiogram {
+    const NAME: &'static str = "MediaTocAudiogram";
+    type Type = plugin::Audiogram;
+    type ParentType = Element;
+
+    fn with_class(klass: &Self::Class) -> Self {
+        let templ = klass.pad_template("sink").unwrap();
+        let sinkpad = Pad::builder_with_template(&templ, Some("sink"))
+            .chain_function(|pad, parent, buffer| {
+                Audiogram::catch_panic_pad_function(
+                    parent,
+                    || Err(gst::FlowError::Error),
+                    |this| this.sink_chain(pad, buffer),
+                )
+            })
+            .event_function(|pad, parent, event| {
+                Audiogram::catch_panic_pad_function(
+                    parent,
+                    || false,
+                    |this| this.sink_event(pad, &this.obj(), event),
+                )
+            })
+            .build();
+
+        let templ = klass.pad_template("src").unwrap();
+        let srcpad = Pad::builder_with_template(&templ, Some("src")).build();
+
+        Audiogram {
+            sinkpad,
+            srcpad,
+            ctx: Mutex::new(Context::default()),
+        }
+    }
+}
+
+impl ObjectImpl for Audiogram {
+    fn properties() -> &'static [glib::ParamSpec] {
+        static PROPERTIES: Lazy<Vec<glib::ParamSpec>> = Lazy::new(|| {
+            vec![
+                glib::ParamSpecInt::new(
+                    WIDTH_PROP,
+                    "Width",
+                    "Width of the video frames",
+                    16,
+                    i16::MAX as i32,
+                    DEFAULT_WIDTH,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    HEIGHT_PROP,
+                    "Height",
+                    "Height of the video frames",
+                    16,
+                    i16::MAX as i32,
+                    DEFAULT_HEIGHT,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecInt::new(
+                    FRAMERATE_PROP,
+                    "Frame rate",
+                    "Frames per second",
+                    1,
+                    120,
+                    DEFAULT_FRAMERATE,
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecUInt64::new(
+                    WINDOW_DURATION_PROP,
+                    "Window duration (ns)",
+                    "Duration of the audio displayed in a frame in ns",
+                    1_000_000u64,
+                    u64::MAX / 4,
+                    DEFAULT_WINDOW_DURATION.as_u64(),
+                    glib::ParamFlags::READWRITE,
+                ),
+                glib::ParamSpecString::new(
+                    TITLE_PROP,
+                    "Title",
+                    "Text displayed at the bottom of the frames",
+                    None,
+                    glib::ParamFlags::READWRITE,
+                ),
+            ]
+        });
+
+        PROPERTIES.as_ref()
+    }
+
+    fn set_property(&self, _id: usize, value: &glib::Value, pspec: &glib::ParamSpec) {
+        let mut ctx = self.ctx.lock().unwrap();
+        if ctx.stream.is_some() {
+            gst::warning!(
+                CAT,
+                imp: self,
+                "{} will apply on next caps",
+                pspec.name()
+            );
+        }
+
+        let settings = &mut ctx.settings;
+        match pspec.name() {
+            WIDTH_PROP => settings.width = value.get().expect("type checked upstream"),
+            HEIGHT_PROP => settings.height = value.get().expect("type checked upstream"),
+            FRAMERATE_PROP => settings.framerate = value.get().expect("type checked upstream"),
+            WINDOW_DURATION_PROP => {
+                let window_duration = value.get::<u64>().expect("type checked upstream");
+                settings.window_duration = Duration::from_nanos(window_duration);
+            }
+            TITLE_PROP => settings.title = value.get().expect("type checked upstream"),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn property(&self, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
+        let settings = &self.ctx.lock().unwrap().settings;
+        match pspec.name() {
+            WIDTH_PROP => settings.width.to_value(),
+            HEIGHT_PROP => settings.height.to_value(),
+            FRAMERATE_PROP => settings.framerate.to_value(),
+            WINDOW_DURATION_PROP => settings.window_duration.as_u64().to_value(),
+            TITLE_PROP => settings.title.to_value(),
+            _ => unimplemented!(),
+        }
+    }
+
+    fn constructed(&self) {
+        self.parent_constructed();
+        let obj = self.obj();
+        obj.add_pad(&self.sinkpad).unwrap();
+        obj.add_pad(&self.srcpad).unwrap();
+    }
+}
+
+impl GstObjectImpl for Audiogram {}
+
+impl ElementImpl for Audiogram {
+    fn metadata() -> Option<&'static gst::subclass::ElementMetadata> {
+        static ELEMENT_METADATA: Lazy<gst::subclass::ElementMetadata> = Lazy::new(|| {
+            gst::subclass::ElementMetadata::new(
+                "media-toc Audiogram",
+                "Visualization",
+                "Renders a video of the waveform centered on current position",
+                "François Laignel <fengalin@free.fr>",
+            )
+        });
+
+        Some(&*ELEMENT_METADATA)
+    }
+
+    fn pad_templates() -> &'static [gst::PadTemplate] {
+        static PAD_TEMPLATES: Lazy<Vec<gst::PadTemplate>> = Lazy::new(|| {
+            let sink_caps = gst::Caps::new_simple(
+                "audio/x-raw",
+                &[
+                    (
+                        "format",
+                        &gst::List::new([&gst_audio::AudioFormat::S16le.to_str()]),
+                    ),
+                    ("channels", &gst::IntRange::<i32>::new(1, 8)),
+                    ("layout", &"interleaved"),
+                ],
+            );
+
+            let src_caps = gst::Caps::new_simple(
+                "video/x-raw",
+                &[
+                    ("format", &VIDEO_FORMAT),
+                    ("width", &gst::IntRange::<i32>::new(16, i16::MAX as i32)),
+                    ("height", &gst::IntRange::<i32>::new(16, i16::MAX as i32)),
+                    (
+                        "framerate",
+                        &gst::FractionRange::new(
+                            gst::Fraction::new(1, 1),
+                            gst::Fraction::new(120, 1),
+                        ),
+                    ),
+                ],
+            );
+
+            vec![
+                gst::PadTemplate::new(
+                    "sink",
+                    gst::PadDirection::Sink,
+                    gst::PadPresence::Always,
+                    &sink_caps,
+                )
+                .unwrap(),
+                gst::PadTemplate::new(
+                    "src",
+                    gst::PadDirection::Src,
+                    gst::PadPresence::Always,
+                    &src_caps,
+                )
+                .unwrap(),
+            ]
+        });
+
+        PAD_TEMPLATES.as_ref()
+    }
+
+    fn change_state(
+        &self,
+        transition: gst::StateChange,
+    ) -> Result<gst::StateChangeSuccess, gst::StateChangeError> {
+        gst::trace!(CAT, imp: self, "Changing state {:?}", transition);
+
+        let success = self.parent_change_state(transition)?;
+
+        if let gst::StateChange::PausedToReady = transition {
+            self.ctx.lock().unwrap().stream = None;
+        }
+
+        Ok(success)
+    }
+}
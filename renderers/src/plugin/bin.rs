@@ -111,11 +111,24 @@ impl PlaybackState {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 struct State {
     playback: PlaybackState,
     seek: SeekState,
     stream_count: usize,
+    /// Rate of the last user seek, applied to the seeks issued by the bin.
+    rate: f64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            playback: PlaybackState::default(),
+            seek: SeekState::default(),
+            stream_count: 0,
+            rate: 1f64,
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -482,7 +495,7 @@ impl RendererBin {
 
         let seqnum = event.seqnum();
 
-        let (_rate, _flags, start_type, start, stop_type, stop) = match event.view() {
+        let (rate, _flags, start_type, start, stop_type, stop) = match event.view() {
             gst::EventView::Seek(seek) => seek.get(),
             evt => panic!("Unexpected {:?} in handle_seek", evt),
         };
@@ -500,6 +513,19 @@ impl RendererBin {
 
         let mut state = self.state.lock().unwrap();
 
+        // Keep the rate for the seeks issued on behalf of the user
+        // (stage 2, play range and position restoration).
+        state.rate = rate;
+
+        if rate < 0f64 {
+            // Reverse playback: samples are received backward
+            // so there is no point in prefetching the renderer's window.
+            gst::debug!(CAT, obj: pad, "Reverse seek {:?}", seqnum);
+            state.seek = Uncontrolled;
+            drop(state);
+            return gst::Pad::event_default(pad, Some(bin), event);
+        }
+
         match start_type {
             gst::SeekType::Set => (),
             other => {
@@ -643,6 +669,7 @@ impl RendererBin {
             stage_1_end,
             target_ts,
         );
+        // Stage 1 only feeds the renderer, no need to apply the user rate
         event = gst::event::Seek::builder(
             1f64,
             gst::SeekFlags::ACCURATE | gst::SeekFlags::SEGMENT | gst::SeekFlags::FLUSH,
@@ -747,7 +774,7 @@ impl RendererBin {
         }
 
         gst::event::Seek::builder(
-            1f64,
+            state.rate,
             gst::SeekFlags::ACCURATE | gst::SeekFlags::FLUSH | gst::SeekFlags::SEGMENT,
             gst::SeekType::Set,
             Some(target_ts),
@@ -766,7 +793,7 @@ impl RendererBin {
         stop_to_restore: Option<ClockTime>,
     ) {
         let seek_event = gst::event::Seek::new(
-            1f64,
+            state.rate,
             gst::SeekFlags::ACCURATE | gst::SeekFlags::FLUSH,
             gst::SeekType::Set,
             Some(target_ts),
@@ -809,7 +836,7 @@ impl RendererBin {
                 } = state.seek
                 {
                     let seek = gst::event::Seek::new(
-                        1f64,
+                        state.rate,
                         gst::SeekFlags::ACCURATE | gst::SeekFlags::FLUSH,
                         gst::SeekType::Set,
                         Some(ts_to_restore),
@@ -959,13 +986,15 @@ impl RendererBin {
             )
             .unwrap();
 
-        renderer.set_property(
-            plugin::renderer::DBL_RENDERER_IMPL_PROP,
-            renderer_init.dbl_renderer_impl.as_ref().unwrap(),
-        );
+        // When used as a standalone plugin, these properties might not be set.
+        // The renderer then falls back to its defaults.
+        if let Some(dbl_renderer_impl) = renderer_init.dbl_renderer_impl.as_ref() {
+            renderer.set_property(plugin::renderer::DBL_RENDERER_IMPL_PROP, dbl_renderer_impl);
+        }
 
-        let clock_ref = renderer_init.clock_ref.as_ref().unwrap().clone();
-        renderer.set_property(plugin::renderer::CLOCK_REF_PROP, &clock_ref);
+        if let Some(clock_ref) = renderer_init.clock_ref.as_ref() {
+            renderer.set_property(plugin::renderer::CLOCK_REF_PROP, clock_ref);
+        }
 
         renderer.connect(
             plugin::renderer::SEGMENT_DONE_SIGNAL,
@@ -1173,6 +1202,29 @@ impl ObjectImpl for RendererBin {
                 Signal::builder(plugin::PLAY_RANGE_DONE_SIGNAL)
                     .run_last()
                     .build(),
+                Signal::builder(plugin::SNAP_TS_SIGNAL)
+                    .param_types([u64::static_type(), u64::static_type(), u32::static_type()])
+                    .return_type::<u64>()
+                    .run_last()
+                    .action()
+                    .class_handler(|_token, args| {
+                        let bin = args[0]
+                            .get::<<RendererBin as ObjectSubclass>::Type>()
+                            .expect("Failed to get args[0]");
+                        let ts = args[1].get::<u64>().expect("Failed to get args[1]");
+
+                        // Forward to the renderer which holds the samples
+                        let snapped = match *bin.imp().audio.read().unwrap() {
+                            Audio::Initialized(ref audio) => audio.renderer.emit_by_name::<u64>(
+                                plugin::SNAP_TS_SIGNAL,
+                                &[&ts, &args[2], &args[3]],
+                            ),
+                            _ => ts,
+                        };
+
+                        Some(snapped.to_value())
+                    })
+                    .build(),
                 // FIXME this one could be avoided with a dedicated widget
                 Signal::builder(plugin::MUST_REFRESH_SIGNAL)
                     .run_last()
@@ -1,3 +1,72 @@
+//! media-toc GStreamer rendering plugin.
+//!
+//! The elements are registered in-process with [`init`]. They are also
+//! available as a standalone plugin: build the `media-toc-renderers` crate
+//! and add the directory containing `libmediatocrenderers.so` (or the platform
+//! equivalent) to
+//! `GST_PLUGIN_PATH`, e.g.:
+//!
+//! ```text
+//! GST_PLUGIN_PATH=target/release gst-inspect-1.0 mediatocrenderers
+//! GST_PLUGIN_PATH=target/release gst-launch-1.0 \
+//!     audiotestsrc ! audioconvert ! mediatocrenderer
+//! ```
+//!
+//! # Elements
+//!
+//! - `mediatocrenderer`: renders the incoming audio samples around the
+//!   current position (S16LE interleaved, 1 to 8 channels).
+//! - `mediatocrendererbin`: wraps `mediatocrenderer` with queues so that an
+//!   audio stream and an optional video stream are passed through while the
+//!   audio is being rendered. It also handles the 2 stages seeks and ranges
+//!   playback.
+//! - `mediatocaudiogram`: renders a video of the waveform centered on the
+//!   current position, a.k.a. an "audiogram" (see below).
+//!
+//! # Properties (`mediatocrenderer` and `mediatocrendererbin`)
+//!
+//! - `dbl-renderer-impl` (`DoubleRendererImpl` boxed type, read-write):
+//!   the double buffering rendering implementation. Defaults to a waveform
+//!   renderer when not set.
+//! - `clock-ref` (`GstElement`, write-only): the element to query for the
+//!   current position. Defaults to the renderer itself, in which case the
+//!   position queries are forwarded upstream.
+//! - `buffer-size` (`guint64`, write-only): internal buffer size in ns.
+//!   Defaults to 5s.
+//!
+//! # Signals
+//!
+//! - `get-window-ts` (`mediatocrenderer`, action): returns the
+//!   `WindowTimestamps` (start, end and range) of the samples which can be
+//!   displayed or `NULL` if the rendering conditions are not known yet.
+//! - `snap-ts` (both elements, action): snaps a position (ns) to the nearest
+//!   zero crossing (mode 0), silence (mode 1) or onset (mode 2) within a
+//!   radius (ns). Returns the position unchanged if no candidate is found.
+//! - `segment-done` (`mediatocrenderer`): emitted when the renderer receives
+//!   the segment done event of the 1st stage of a 2 stages seek.
+//! - `must-refresh` (both elements): emitted when new samples were rendered
+//!   while not playing, meaning the visualization should be redrawn.
+//! - `play-range-done` (`mediatocrendererbin`): emitted when the playback of
+//!   a range completes and the position is restored.
+//!
+//! # Audiogram
+//!
+//! `mediatocaudiogram` accepts the same audio format as `mediatocrenderer`
+//! and produces raw video frames (`BGRx` on little-endian platforms) at the
+//! timestamps of the audio stream. Properties:
+//!
+//! - `width`, `height` (`gint`): frame dimensions. Defaults to 1280x720.
+//! - `framerate` (`gint`): frames per second. Defaults to 25.
+//! - `window-duration` (`guint64`): duration of the audio displayed in a
+//!   frame in ns. Defaults to 4s.
+//! - `title` (`gchararray`): text displayed at the bottom of the frames.
+//!
+//! ```text
+//! GST_PLUGIN_PATH=target/release gst-launch-1.0 \
+//!     audiotestsrc num-buffers=500 ! audioconvert ! mediatocaudiogram title=Test \
+//!     ! videoconvert ! autovideosink
+//! ```
+
 use gst::{glib, prelude::*};
 
 pub(crate) mod bin;
@@ -35,15 +104,30 @@ unsafe impl Sync for Renderer {}
 pub(crate) mod renderer;
 pub use renderer::{
     SeekField, SegmentField, BUFFER_SIZE_PROP, CLOCK_REF_PROP, DBL_RENDERER_IMPL_PROP,
-    MUST_REFRESH_SIGNAL, NAME as RENDERER_NAME, SEGMENT_DONE_SIGNAL,
+    GET_WINDOW_TIMESTAMPS_SIGNAL, MUST_REFRESH_SIGNAL, NAME as RENDERER_NAME, SEGMENT_DONE_SIGNAL,
+    SNAP_TS_SIGNAL,
 };
 
-pub(crate) use renderer::GET_WINDOW_TIMESTAMPS_SIGNAL;
+glib::wrapper! {
+    pub struct Audiogram(ObjectSubclass<audiogram::Audiogram>) @extends gst::Element, gst::Object;
+}
+
+unsafe impl Send for Audiogram {}
+unsafe impl Sync for Audiogram {}
+
+pub(crate) mod audiogram;
+pub use audiogram::{
+    FRAMERATE_PROP, HEIGHT_PROP, NAME as AUDIOGRAM_NAME, TITLE_PROP, WIDTH_PROP,
+    WINDOW_DURATION_PROP,
+};
 
+/// Emitted by the bin when the playback of a range completes.
 pub const PLAY_RANGE_DONE_SIGNAL: &str = "play-range-done";
 
+// The plugin name must match the library name
+// so that GStreamer can find the plugin's entry point.
 gst::plugin_define!(
-    mediatocvisu,
+    mediatocrenderers,
     env!("CARGO_PKG_DESCRIPTION"),
     plugin_init,
     concat!(env!("CARGO_PKG_VERSION"), "-", env!("COMMIT_ID")),
@@ -77,5 +161,11 @@ fn plugin_init(plugin: &gst::Plugin) -> Result<(), glib::BoolError> {
         gst::Rank::None,
         Renderer::static_type(),
     )?;
+    gst::Element::register(
+        Some(plugin),
+        audiogram::NAME,
+        gst::Rank::None,
+        Audiogram::static_type(),
+    )?;
     Ok(())
 }
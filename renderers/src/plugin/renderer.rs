@@ -14,20 +14,32 @@ use metadata::Duration;
 
 use crate::{
     generic::{prelude::*, DoubleRenderer, GBoxedDoubleRendererImpl, WindowTimestamps},
-    plugin,
+    plugin, DoubleWaveformRenderer, SnapMode, Timestamp,
 };
 
 pub const NAME: &str = "mediatocrenderer";
 
 // FIXME use an enum just like for SegmentField
+/// The double buffering rendering implementation (defaults to a waveform).
 pub const DBL_RENDERER_IMPL_PROP: &str = "dbl-renderer-impl";
+/// The element to query for the current position (defaults to the renderer).
 pub const CLOCK_REF_PROP: &str = "clock-ref";
+/// Internal buffer size in ns.
 pub const BUFFER_SIZE_PROP: &str = "buffer-size";
 pub const DEFAULT_BUFFER_SIZE: Duration = Duration::from_secs(5);
 
+/// Action signal returning the `WindowTimestamps` of the displayable samples.
 pub const GET_WINDOW_TIMESTAMPS_SIGNAL: &str = "get-window-ts";
+/// Action signal snapping a position to the audio signal.
+///
+/// Arguments: the position (`u64` ns), the search radius (`u64` ns) and
+/// the `SnapMode` (`u32`). Returns the snapped position or the position
+/// itself if no candidate could be found.
+pub const SNAP_TS_SIGNAL: &str = "snap-ts";
+/// Emitted on the segment done event of the 1st stage of a 2 stages seek.
 pub const SEGMENT_DONE_SIGNAL: &str = "segment-done";
 // FIXME remove when widget is handled locally
+/// Emitted when new samples were rendered while not playing.
 pub const MUST_REFRESH_SIGNAL: &str = "must-refresh";
 
 static CAT: Lazy<gst::DebugCategory> = Lazy::new(|| {
@@ -445,14 +457,15 @@ impl ObjectImpl for Renderer {
                     );
                 }
 
-                let dbl_renderer_impl = ctx
-                    .settings
-                    .dbl_renderer_impl
-                    .take()
-                    // FIXME don't panic log an error
-                    .expect("dbl renderer impl already taken");
-                let gboxed: GBoxedDoubleRendererImpl = dbl_renderer_impl.into();
-                gboxed.to_value()
+                // Note: tools such as `gst-inspect-1.0` read the property
+                // before any implementation is set.
+                match ctx.settings.dbl_renderer_impl.take() {
+                    Some(dbl_renderer_impl) => {
+                        let gboxed: GBoxedDoubleRendererImpl = dbl_renderer_impl.into();
+                        gboxed.to_value()
+                    }
+                    None => GBoxedDoubleRendererImpl::none().to_value(),
+                }
             }
             _ => unimplemented!(),
         }
@@ -482,6 +495,41 @@ impl ObjectImpl for Renderer {
                         Some(window_ts.as_ref().to_value())
                     })
                     .build(),
+                Signal::builder(SNAP_TS_SIGNAL)
+                    .param_types([u64::static_type(), u64::static_type(), u32::static_type()])
+                    .return_type::<u64>()
+                    .run_last()
+                    .action()
+                    .class_handler(|_token, args| {
+                        let element = args[0]
+                            .get::<<Renderer as ObjectSubclass>::Type>()
+                            .expect("Failed to get args[0]");
+                        let ts = args[1].get::<u64>().expect("Failed to get args[1]");
+                        let radius = args[2].get::<u64>().expect("Failed to get args[2]");
+                        let mode = SnapMode::from_u32(
+                            args[3].get::<u32>().expect("Failed to get args[3]"),
+                        );
+
+                        let snapped = mode.and_then(|mode| {
+                            element
+                                .imp()
+                                .ctx
+                                .lock()
+                                .unwrap()
+                                .dbl_renderer
+                                .as_ref()
+                                .and_then(|dbl_renderer| {
+                                    dbl_renderer.snap(
+                                        Timestamp::new(ts),
+                                        Duration::from_nanos(radius),
+                                        mode,
+                                    )
+                                })
+                        });
+
+                        Some(snapped.map_or(ts, Timestamp::as_u64).to_value())
+                    })
+                    .build(),
                 Signal::builder(SEGMENT_DONE_SIGNAL).run_last().build(),
                 // FIXME this one could be avoided with a dedicated widget
                 Signal::builder(MUST_REFRESH_SIGNAL).run_last().build(),
@@ -503,22 +551,25 @@ impl Renderer {
         gst::debug!(CAT, imp: self, "Preparing");
         let mut ctx = self.ctx.lock().unwrap();
 
-        let dbl_renderer_impl = ctx.settings.dbl_renderer_impl.take().ok_or_else(|| {
-            let msg = "Double Renderer implementation not set";
-            gst::error!(CAT, "{}", &msg);
-            gst::error_msg!(gst::CoreError::StateChange, ["{}", &msg])
-        })?;
+        let dbl_renderer_impl = ctx.settings.dbl_renderer_impl.take().unwrap_or_else(|| {
+            gst::info!(CAT, imp: self, "Double Renderer implementation not set, using waveform");
+            Box::<DoubleWaveformRenderer>::default()
+        });
 
-        let clock_ref = ctx.settings.clock_ref.as_ref().ok_or_else(|| {
-            let msg = "Clock reference element not set";
-            gst::error!(CAT, "{}", &msg);
-            gst::error_msg!(gst::CoreError::StateChange, ["{}", &msg])
-        })?;
+        // Without an explicit clock reference, position queries are
+        // forwarded upstream from our sink pad.
+        let clock_ref = match ctx.settings.clock_ref.as_ref() {
+            Some(clock_ref) => clock_ref.clone(),
+            None => {
+                gst::info!(CAT, imp: self, "Clock reference not set, using self");
+                self.obj().clone().upcast::<gst::Element>()
+            }
+        };
 
         ctx.dbl_renderer = Some(DoubleRenderer::new(
             dbl_renderer_impl,
             ctx.settings.buffer_size,
-            clock_ref,
+            &clock_ref,
         ));
 
         ctx.state = State::Prepared;
@@ -558,8 +609,19 @@ impl Renderer {
     fn unprepare(&self) {
         gst::debug!(CAT, imp: self, "Unpreparing");
         let mut ctx = self.ctx.lock().unwrap();
-        let dbl_renderer_impl = ctx.dbl_renderer.take().map(DoubleRenderer::into_impl);
+        let mut dbl_renderer_impl = ctx.dbl_renderer.take().map(DoubleRenderer::into_impl);
         assert!(dbl_renderer_impl.is_some());
+        if ctx.settings.clock_ref.is_none() {
+            // We were our own clock reference: break the reference cycle
+            if let Some(dbl_renderer_impl) = dbl_renderer_impl.as_mut() {
+                dbl_renderer_impl
+                    .working_mut()
+                    .state()
+                    .write()
+                    .unwrap()
+                    .cleanup();
+            }
+        }
         ctx.settings.dbl_renderer_impl = dbl_renderer_impl;
         ctx.state = State::Unprepared;
         gst::debug!(CAT, imp: self, "Unprepared");
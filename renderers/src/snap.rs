@@ -0,0 +1,365 @@
+//! Snapping positions to remarkable points of the audio signal.
+//!
+//! This is used to place chapter boundaries where a cut doesn't produce
+//! audible clicks.
+
+use metadata::Duration;
+
+use crate::{AudioBuffer, SampleIndex, SampleIndexRange, Timestamp};
+
+/// Duration of the windows used to evaluate the signal energy.
+const ENERGY_WINDOW: Duration = Duration::from_millis(10);
+
+/// Minimum energy ratio between two successive windows to detect an onset.
+const ONSET_MIN_RATIO: f64 = 4f64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapMode {
+    /// Nearest sample where the signal changes its sign.
+    ZeroCrossing,
+    /// Center of the nearest window with the lowest energy.
+    Silence,
+    /// Beginning of the nearest window with a sharp increase in energy.
+    Onset,
+}
+
+impl SnapMode {
+    pub fn as_u32(self) -> u32 {
+        match self {
+            SnapMode::ZeroCrossing => 0,
+            SnapMode::Silence => 1,
+            SnapMode::Onset => 2,
+        }
+    }
+
+    pub fn from_u32(value: u32) -> Option<Self> {
+        match value {
+            0 => Some(SnapMode::ZeroCrossing),
+            1 => Some(SnapMode::Silence),
+            2 => Some(SnapMode::Onset),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the position to snap `ts` to within `radius` using `mode`.
+///
+/// Returns `None` if no candidate could be found, e.g. when the samples
+/// around `ts` are not available in the `audio_buffer`.
+pub fn snap(
+    audio_buffer: &AudioBuffer,
+    sample_duration: Duration,
+    ts: Timestamp,
+    radius: Duration,
+    mode: SnapMode,
+) -> Option<Timestamp> {
+    if sample_duration == Duration::default() || audio_buffer.channels == 0 {
+        return None;
+    }
+
+    let target = ts.sample_index(sample_duration);
+    let radius = SampleIndexRange::from_duration(radius, sample_duration);
+    let lower = target.saturating_sub_range(radius).max(audio_buffer.lower);
+    let upper = (target + radius).min(audio_buffer.upper);
+    if target < lower || target >= upper {
+        return None;
+    }
+
+    let window = SampleIndexRange::from_duration(ENERGY_WINDOW, sample_duration)
+        .as_usize()
+        .max(2);
+
+    let snapped = match mode {
+        SnapMode::ZeroCrossing => zero_crossing(audio_buffer, target, lower, upper),
+        SnapMode::Silence => silence(audio_buffer, target, lower, upper, window),
+        SnapMode::Onset => onset(audio_buffer, target, lower, upper, window),
+    }?;
+
+    Some(snapped.as_ts(sample_duration))
+}
+
+/// Sum of the channels at `idx`.
+fn mixed(audio_buffer: &AudioBuffer, idx: SampleIndex) -> i32 {
+    audio_buffer.get(idx).map_or(0, |values| {
+        values.iter().map(|value| i32::from(value.as_i16())).sum()
+    })
+}
+
+/// Energy of the mixed signal in `[lower, lower + len)`.
+fn energy(audio_buffer: &AudioBuffer, lower: SampleIndex, len: usize) -> f64 {
+    (lower.as_usize()..lower.as_usize() + len)
+        .map(|idx| {
+            let value = f64::from(mixed(audio_buffer, SampleIndex::new(idx)));
+            value * value
+        })
+        .sum()
+}
+
+fn distance(idx: SampleIndex, target: SampleIndex) -> usize {
+    idx.as_usize().abs_diff(target.as_usize())
+}
+
+fn zero_crossing(
+    audio_buffer: &AudioBuffer,
+    target: SampleIndex,
+    lower: SampleIndex,
+    upper: SampleIndex,
+) -> Option<SampleIndex> {
+    let mut best: Option<SampleIndex> = None;
+
+    let mut prev = mixed(audio_buffer, lower);
+    for idx in lower.as_usize() + 1..upper.as_usize() {
+        let idx = SampleIndex::new(idx);
+        let cur = mixed(audio_buffer, idx);
+        if (prev < 0 && cur >= 0) || (prev > 0 && cur <= 0) {
+            // Keep the sample closest to 0
+            let crossing = if prev.abs() < cur.abs() {
+                SampleIndex::new(idx.as_usize() - 1)
+            } else {
+                idx
+            };
+            if best.map_or(true, |best| {
+                distance(crossing, target) < distance(best, target)
+            }) {
+                best = Some(crossing);
+            }
+        }
+        prev = cur;
+    }
+
+    best
+}
+
+fn silence(
+    audio_buffer: &AudioBuffer,
+    target: SampleIndex,
+    lower: SampleIndex,
+    upper: SampleIndex,
+    window: usize,
+) -> Option<SampleIndex> {
+    let mut best: Option<(f64, SampleIndex)> = None;
+
+    let mut start = lower.as_usize();
+    while start + window <= upper.as_usize() {
+        let level = energy(audio_buffer, SampleIndex::new(start), window);
+        let center = SampleIndex::new(start + window / 2);
+        let is_better = best.map_or(true, |(best_level, best_center)| {
+            level < best_level
+                || (level == best_level && distance(center, target) < distance(best_center, target))
+        });
+        if is_better {
+            best = Some((level, center));
+        }
+
+        start += window / 2;
+    }
+
+    best.map(|(_, center)| center)
+}
+
+fn onset(
+    audio_buffer: &AudioBuffer,
+    target: SampleIndex,
+    lower: SampleIndex,
+    upper: SampleIndex,
+    window: usize,
+) -> Option<SampleIndex> {
+    let mut best: Option<SampleIndex> = None;
+
+    let mut start = lower.as_usize();
+    let mut prev_level: Option<f64> = None;
+    while start + window <= upper.as_usize() {
+        let level = energy(audio_buffer, SampleIndex::new(start), window);
+        if let Some(prev_level) = prev_level {
+            // Make sure a rise from digital silence is detected
+            let ratio = level / prev_level.max(window as f64);
+            if ratio >= ONSET_MIN_RATIO {
+                let candidate = SampleIndex::new(start);
+                if best.map_or(true, |best| {
+                    distance(candidate, target) < distance(best, target)
+                }) {
+                    best = Some(candidate);
+                }
+            }
+        }
+
+        prev_level = Some(level);
+        start += window;
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{ByteOrder, LittleEndian};
+    use gst_audio::AUDIO_FORMAT_S16;
+
+    use crate::{AudioBuffer, SampleIndex, Timestamp};
+    use metadata::Duration;
+
+    use super::{snap, SnapMode};
+
+    const SAMPLE_RATE: u32 = 1_000;
+    const SAMPLE_DURATION: Duration = Duration::from_frequency(SAMPLE_RATE as u64);
+
+    // 1s of mono samples built by `sample_at`
+    fn init(sample_at: impl Fn(usize) -> i16) -> AudioBuffer {
+        gst::init().unwrap();
+
+        let mut audio_buffer = AudioBuffer::new(Duration::from_secs(2));
+        audio_buffer.init(
+            &gst_audio::AudioInfo::builder(AUDIO_FORMAT_S16, SAMPLE_RATE, 1)
+                .build()
+                .unwrap(),
+        );
+
+        let len = SAMPLE_RATE as usize;
+        let mut buffer = gst::Buffer::with_size(len * 2).unwrap();
+        {
+            let buffer_mut = buffer.get_mut().unwrap();
+            buffer_mut.set_pts(gst::ClockTime::ZERO);
+            let mut buffer_map = buffer_mut.map_writable().unwrap();
+            let buffer_slice = buffer_map.as_mut_slice();
+            for idx in 0..len {
+                LittleEndian::write_i16(&mut buffer_slice[idx * 2..], sample_at(idx));
+            }
+        }
+
+        let segment = gst::FormattedSegment::<gst::ClockTime>::new();
+        audio_buffer.have_segment(&segment);
+        audio_buffer.push_buffer(&buffer, SampleIndex::default());
+
+        audio_buffer
+    }
+
+    fn ts(ms: u64) -> Timestamp {
+        Timestamp::new(ms * 1_000_000)
+    }
+
+    #[test]
+    fn zero_crossing() {
+        // Square signal with a period of 100 samples
+        let audio_buffer = init(|idx| if (idx / 50) % 2 == 0 { 1_000 } else { -1_000 });
+
+        let radius = Duration::from_millis(20);
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(240),
+                radius,
+                SnapMode::ZeroCrossing
+            ),
+            Some(ts(250)),
+        );
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(110),
+                radius,
+                SnapMode::ZeroCrossing
+            ),
+            Some(ts(100)),
+        );
+        // No crossing within radius
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(125),
+                radius,
+                SnapMode::ZeroCrossing
+            ),
+            None,
+        );
+        // Out of the buffer
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(1_500),
+                radius,
+                SnapMode::ZeroCrossing
+            ),
+            None,
+        );
+    }
+
+    #[test]
+    fn silence_and_onset() {
+        // Silence in [400, 500), loud elsewhere
+        let audio_buffer = init(|idx| {
+            if (400..500).contains(&idx) {
+                0
+            } else if idx % 2 == 0 {
+                10_000
+            } else {
+                -10_000
+            }
+        });
+
+        let radius = Duration::from_millis(100);
+        let silence = snap(
+            &audio_buffer,
+            SAMPLE_DURATION,
+            ts(380),
+            radius,
+            SnapMode::Silence,
+        )
+        .unwrap();
+        assert!(silence >= ts(400) && silence < ts(500));
+
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(520),
+                radius,
+                SnapMode::Onset
+            ),
+            Some(ts(500)),
+        );
+    }
+
+    #[test]
+    fn nearest_onset() {
+        // Sharp rise from silence at 400, weaker rise at 500
+        let audio_buffer = init(|idx| {
+            let level = if idx < 400 {
+                0
+            } else if idx < 500 {
+                1_000
+            } else {
+                10_000
+            };
+            if idx % 2 == 0 {
+                level
+            } else {
+                -level
+            }
+        });
+
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(480),
+                Duration::from_millis(100),
+                SnapMode::Onset
+            ),
+            Some(ts(500)),
+        );
+        assert_eq!(
+            snap(
+                &audio_buffer,
+                SAMPLE_DURATION,
+                ts(420),
+                Duration::from_millis(100),
+                SnapMode::Onset
+            ),
+            Some(ts(400)),
+        );
+    }
+}
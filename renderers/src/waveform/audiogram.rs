@@ -0,0 +1,262 @@
+use log::debug;
+use metadata::Duration;
+
+use std::sync::{Arc, Mutex};
+
+use crate::{AudioBuffer, AudioChannel, SampleIndex, Timestamp};
+
+use super::{
+    image::{ChannelColors, WaveformImage},
+    AmplitudeScale, Color, Dimensions, LaneMode,
+};
+
+const TEXT_COLOR: Color = (1f64, 1f64, 1f64);
+
+/// Renders fixed size frames with the waveform centered on a cursor.
+///
+/// This is used to produce "audiograms": videos made of the waveform
+/// of an audio stream, usually to share an excerpt of a podcast.
+#[derive(Debug)]
+pub struct AudiogramRenderer {
+    width: i32,
+    height: i32,
+    window_duration: Duration,
+    title: Option<String>,
+    font_size: f64,
+
+    d: Dimensions,
+    image: WaveformImage,
+}
+
+impl AudiogramRenderer {
+    pub fn new(width: i32, height: i32, window_duration: Duration, title: Option<String>) -> Self {
+        AudiogramRenderer {
+            width,
+            height,
+            window_duration,
+            title,
+            font_size: (f64::from(height) / 20f64).max(10f64),
+
+            d: Dimensions::default(),
+            image: WaveformImage::new(
+                0,
+                Arc::new(Mutex::new(ChannelColors::default())),
+                Arc::new(Mutex::new(None)),
+            ),
+        }
+    }
+
+    pub fn set_sample_cndt(
+        &mut self,
+        sample_duration: Duration,
+        channels: impl Iterator<Item = AudioChannel>,
+    ) {
+        debug!("set_sample_cndt {}", sample_duration);
+
+        self.image.cleanup();
+        self.d = Dimensions::for_window(
+            sample_duration,
+            self.window_duration,
+            self.width,
+            self.height,
+            LaneMode::Overlaid,
+            AmplitudeScale::Linear,
+        );
+        self.image.set_channels(channels);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.d.sample_duration != Duration::default()
+    }
+
+    /// Returns the samples range to render for the frame at `cursor_ts`.
+    pub fn sample_window(&self, cursor_ts: Timestamp) -> (SampleIndex, SampleIndex) {
+        let cursor = cursor_ts.sample_index(self.d.sample_duration);
+        (
+            cursor.saturating_sub_range(self.d.half_req_sample_window),
+            cursor + self.d.half_req_sample_window,
+        )
+    }
+
+    /// Renders the frame for `cursor_ts` from the samples available in `audio_buffer`.
+    ///
+    /// The frame is returned as a cairo `Rgb24` surface,
+    /// i.e. `BGRx` on little-endian and `xRGB` on big-endian.
+    pub fn render_frame(
+        &mut self,
+        audio_buffer: &AudioBuffer,
+        cursor_ts: Timestamp,
+    ) -> Result<cairo::ImageSurface, String> {
+        let surface = cairo::ImageSurface::create(cairo::Format::Rgb24, self.width, self.height)
+            .map_err(|err| format!("Couldn't create audiogram frame: {:?}", err))?;
+        let cr = cairo::Context::new(&surface)
+            .map_err(|err| format!("Couldn't create audiogram context: {:?}", err))?;
+
+        let theme = self.image.theme();
+        cr.set_source_rgb(theme.background.0, theme.background.1, theme.background.2);
+        cr.paint().unwrap();
+
+        let (lower, upper) = self.sample_window(cursor_ts);
+        let (render_lower, render_upper) =
+            (lower.max(audio_buffer.lower), upper.min(audio_buffer.upper));
+        if render_lower < render_upper {
+            self.image
+                .render(self.d, audio_buffer, render_lower, render_upper);
+            // Only force redraw on the first frame after conditions changed
+            self.d.force_redraw_2 = false;
+        }
+
+        if self.image.is_ready && self.image.lower < upper && self.image.upper > lower {
+            let x = (self.image.lower.as_usize() as f64 - lower.as_usize() as f64)
+                / self.d.sample_step_f;
+            self.image.image().with_surface(|image_surface| {
+                cr.set_source_surface(image_surface, x, 0f64).unwrap();
+                cr.paint().unwrap();
+            });
+        }
+
+        cr.set_line_width(2f64);
+        cr.set_source_rgb(theme.cursor.0, theme.cursor.1, theme.cursor.2);
+        let cursor_x = f64::from(self.width) / 2f64;
+        cr.move_to(cursor_x, 0f64);
+        cr.line_to(cursor_x, f64::from(self.height));
+        cr.stroke().unwrap();
+
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(self.font_size);
+
+        cr.move_to(self.font_size / 2f64, 1.5f64 * self.font_size);
+        cr.show_text(&cursor_ts.for_humans().to_string()).unwrap();
+
+        if let Some(title) = self.title.as_ref() {
+            cr.set_source_rgb(TEXT_COLOR.0, TEXT_COLOR.1, TEXT_COLOR.2);
+            cr.set_font_size(1.5f64 * self.font_size);
+            let extents = cr.text_extents(title).unwrap();
+            cr.move_to(
+                (f64::from(self.width) - extents.width()) / 2f64,
+                f64::from(self.height) - self.font_size,
+            );
+            cr.show_text(title).unwrap();
+        }
+
+        drop(cr);
+        surface.flush();
+
+        Ok(surface)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gst_audio::AUDIO_FORMAT_S16;
+
+    use crate::{AudioBuffer, AudioChannel, Timestamp};
+    use metadata::Duration;
+
+    use super::AudiogramRenderer;
+
+    const SAMPLE_RATE: u32 = 1_000;
+
+    #[test]
+    fn frame() {
+        gst::init().unwrap();
+
+        let mut audio_buffer = AudioBuffer::new(Duration::from_secs(10));
+        audio_buffer.init(
+            &gst_audio::AudioInfo::builder(AUDIO_FORMAT_S16, SAMPLE_RATE, 1)
+                .build()
+                .unwrap(),
+        );
+
+        let mut renderer = AudiogramRenderer::new(
+            400,
+            225,
+            Duration::from_secs(2),
+            Some("media-toc".to_string()),
+        );
+        assert!(!renderer.is_ready());
+
+        renderer.set_sample_cndt(
+            Duration::from_frequency(SAMPLE_RATE as u64),
+            std::iter::once(AudioChannel::new(gst_audio::AudioChannelPosition::Mono)),
+        );
+        assert!(renderer.is_ready());
+
+        // 2s window centered on the cursor
+        let (lower, upper) = renderer.sample_window(Timestamp::new(5_000_000_000));
+        assert_eq!(lower.as_usize(), 4_000);
+        assert_eq!(upper.as_usize(), 6_000);
+
+        // Window truncated at the beginning of the stream
+        let (lower, upper) = renderer.sample_window(Timestamp::new(500_000_000));
+        assert_eq!(lower.as_usize(), 0);
+        assert_eq!(upper.as_usize(), 1_500);
+
+        // No samples yet: frame with the background, cursor and texts only
+        let surface = renderer
+            .render_frame(&audio_buffer, Timestamp::new(0))
+            .unwrap();
+        assert_eq!(surface.width(), 400);
+        assert_eq!(surface.height(), 225);
+    }
+
+    #[test]
+    fn title_overlay() {
+        gst::init().unwrap();
+
+        const WIDTH: i32 = 400;
+        const HEIGHT: i32 = 225;
+
+        let mut audio_buffer = AudioBuffer::new(Duration::from_secs(10));
+        audio_buffer.init(
+            &gst_audio::AudioInfo::builder(AUDIO_FORMAT_S16, SAMPLE_RATE, 1)
+                .build()
+                .unwrap(),
+        );
+
+        let render = |title: Option<&str>| {
+            let mut renderer = AudiogramRenderer::new(
+                WIDTH,
+                HEIGHT,
+                Duration::from_secs(2),
+                title.map(ToString::to_string),
+            );
+            renderer.set_sample_cndt(
+                Duration::from_frequency(SAMPLE_RATE as u64),
+                std::iter::once(AudioChannel::new(gst_audio::AudioChannelPosition::Mono)),
+            );
+
+            let mut surface = renderer
+                .render_frame(&audio_buffer, Timestamp::new(0))
+                .unwrap();
+            let stride = surface.stride() as usize;
+            let data = surface.data().unwrap().to_vec();
+            (stride, data)
+        };
+
+        let (stride, titled) = render(Some("media-toc"));
+        let (_, untitled) = render(None);
+
+        // The title is drawn in the bottom band, centered horizontally
+        let font_size = (f64::from(HEIGHT) / 20f64).max(10f64);
+        let band_top = (f64::from(HEIGHT) - 2.5f64 * font_size) as usize;
+
+        let mut title_pixels = 0;
+        for row in 0..HEIGHT as usize {
+            let line = row * stride..row * stride + WIDTH as usize * 4;
+            let differs = titled[line.clone()]
+                .chunks(4)
+                .zip(untitled[line].chunks(4))
+                .filter(|(titled, untitled)| titled != untitled)
+                .count();
+
+            if row < band_top {
+                // Background, cursor & timestamp are the same in both frames
+                assert_eq!(differs, 0, "row {}", row);
+            } else {
+                title_pixels += differs;
+            }
+        }
+        assert!(title_pixels > 0);
+    }
+}
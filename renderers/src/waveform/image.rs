@@ -1,5 +1,5 @@
 use log::debug;
-use smallvec::{smallvec, SmallVec};
+use smallvec::SmallVec;
 
 use crate::{
     AudioBuffer, AudioChannel, AudioChannelSide, SampleIndex, SampleIndexRange, INLINE_CHANNELS,
@@ -7,15 +7,16 @@ use crate::{
 
 use std::sync::{Arc, Mutex};
 
-use super::{super::Image, Dimensions};
-
-pub const BACKGROUND_COLOR: (f64, f64, f64) = (0.2f64, 0.2235f64, 0.2314f64);
-pub const AXIS_COLOR: (f64, f64, f64) = (0.5f64, 0.5f64, 0f64);
+use super::{super::Image, AmplitudeScale, Color, Dimensions, LaneMode, Theme};
 
 // Translating samples in the negative range when scaling for display
 // improves the rendering bench by 10%
 const SAMPLE_AMPLITUDE: i32 = std::i16::MAX as i32;
 const SAMPLE_RANGE: f64 = 2f64 * (std::i16::MIN as f64);
+const SAMPLE_FULL_SCALE: f64 = -(std::i16::MIN as f64);
+
+// Samples below this level are drawn on the axis when using the dBFS scale
+const DBFS_FLOOR: f64 = -60f64;
 
 // Initial image dimensions
 // will dynamically adapt if needed
@@ -29,11 +30,81 @@ pub struct WaveformSample {
 }
 
 #[derive(Debug)]
-pub struct ChannelColors(SmallVec<[(f64, f64, f64); INLINE_CHANNELS]>);
+pub struct ChannelColors {
+    theme: Theme,
+    sides: SmallVec<[(AudioChannelSide, f64); INLINE_CHANNELS]>,
+    colors: SmallVec<[Color; INLINE_CHANNELS]>,
+    labels: SmallVec<[&'static str; INLINE_CHANNELS]>,
+}
 
 impl Default for ChannelColors {
     fn default() -> Self {
-        ChannelColors(SmallVec::<[(f64, f64, f64); INLINE_CHANNELS]>::with_capacity(0))
+        ChannelColors {
+            theme: Theme::default(),
+            sides: SmallVec::with_capacity(0),
+            colors: SmallVec::with_capacity(0),
+            labels: SmallVec::with_capacity(0),
+        }
+    }
+}
+
+impl ChannelColors {
+    fn update_colors(&mut self) {
+        let theme = self.theme;
+        self.colors = self
+            .sides
+            .iter()
+            .map(|(side, factor)| theme.channel_color(*side, *factor))
+            .collect();
+    }
+}
+
+/// Vertical layout of the channel lanes.
+#[derive(Clone, Copy, Debug)]
+struct Lanes {
+    count: usize,
+    height: f64,
+    half_height: f64,
+}
+
+impl Lanes {
+    fn new(lane_mode: LaneMode, channels: usize, full_range_y: f64) -> Self {
+        let count = match lane_mode {
+            LaneMode::Overlaid => 1,
+            LaneMode::Separate => channels.max(1),
+        };
+        let height = full_range_y / count as f64;
+
+        Lanes {
+            count,
+            height,
+            half_height: height / 2f64,
+        }
+    }
+
+    #[inline]
+    fn center(&self, channel: usize) -> f64 {
+        if self.count > 1 {
+            self.height * channel as f64 + self.half_height
+        } else {
+            self.half_height
+        }
+    }
+}
+
+/// Returns the sample amplitude in the range [-1.0, 1.0] on the dBFS scale.
+#[inline]
+fn dbfs_amplitude(sample: i16) -> f64 {
+    if sample == 0 {
+        return 0f64;
+    }
+
+    let sample = f64::from(sample);
+    let level = 20f64 * (sample.abs() / SAMPLE_FULL_SCALE).log10();
+    if level <= DBFS_FLOOR {
+        0f64
+    } else {
+        (1f64 - level / DBFS_FLOOR).copysign(sample)
     }
 }
 
@@ -46,7 +117,6 @@ pub struct WaveformImage {
     image_width_f: f64,
 
     image_height: i32,
-    half_range_y: f64,
     full_range_y: f64,
     sample_display_scale: f64,
 
@@ -100,7 +170,6 @@ impl WaveformImage {
         self.image_width = 0;
         self.image_width_f = 0f64;
         self.image_height = 0;
-        self.half_range_y = 0f64;
         self.full_range_y = 0f64;
         self.sample_display_scale = 0f64;
 
@@ -122,18 +191,43 @@ impl WaveformImage {
     pub fn set_channels(&self, channels: impl Iterator<Item = AudioChannel>) {
         let mut channel_colors = self.channel_colors.lock().unwrap();
 
-        channel_colors.0.clear();
+        channel_colors.sides.clear();
+        channel_colors.labels.clear();
         for channel in channels {
             debug!("{}_set_channels {:?}", self.id, channel.side);
-            channel_colors.0.push(match channel.side {
-                AudioChannelSide::Center => (0f64, channel.factor, 0f64),
-                AudioChannelSide::Left => (channel.factor, channel.factor, channel.factor),
-                AudioChannelSide::NotLocalized => (0f64, 0f64, channel.factor),
-                AudioChannelSide::Right => (channel.factor, 0f64, 0f64),
-            });
+            channel_colors.labels.push(channel.label());
+            channel_colors.sides.push((channel.side, channel.factor));
+        }
+        channel_colors.update_colors();
+    }
+
+    /// Sets the colors to use from now on.
+    ///
+    /// Note: the samples already rendered are not redrawn.
+    pub fn set_theme(&self, theme: Theme) {
+        let mut channel_colors = self.channel_colors.lock().unwrap();
+        if channel_colors.theme != theme {
+            debug!("{}_set_theme {:?}", self.id, theme);
+            channel_colors.theme = theme;
+            channel_colors.update_colors();
         }
     }
 
+    pub fn theme(&self) -> Theme {
+        self.channel_colors.lock().unwrap().theme
+    }
+
+    /// Returns the label and color of each channel.
+    pub fn channels(&self) -> SmallVec<[(&'static str, Color); INLINE_CHANNELS]> {
+        let channel_colors = self.channel_colors.lock().unwrap();
+        channel_colors
+            .labels
+            .iter()
+            .cloned()
+            .zip(channel_colors.colors.iter().cloned())
+            .collect()
+    }
+
     pub fn image(&self) -> &Image {
         self.exposed_image.as_ref().unwrap()
     }
@@ -260,7 +354,6 @@ impl WaveformImage {
                 self.image_width_f = f64::from(target_width);
                 self.image_height = d.req_height;
                 self.full_range_y = f64::from(d.req_height);
-                self.half_range_y = self.full_range_y / 2f64;
                 self.sample_display_scale = self.full_range_y / SAMPLE_RANGE;
 
                 debug!(
@@ -332,13 +425,17 @@ impl WaveformImage {
         lower: SampleIndex,
         upper: SampleIndex,
     ) {
+        let lanes = Lanes::new(d.lane_mode, audio_buffer.channels, self.full_range_y);
         self.last.x = 0f64;
-        self.last.y_values = smallvec![self.half_range_y; audio_buffer.channels];
+        self.last.y_values = (0..audio_buffer.channels)
+            .map(|channel| lanes.center(channel))
+            .collect();
 
+        let background = self.theme().background;
         exposed_image.with_surface(|image_surface| {
             let cr = cairo::Context::new(image_surface).unwrap();
 
-            cr.set_source_rgb(BACKGROUND_COLOR.0, BACKGROUND_COLOR.1, BACKGROUND_COLOR.2);
+            cr.set_source_rgb(background.0, background.1, background.2);
             cr.paint().unwrap();
 
             self.draw_samples(d, &cr, audio_buffer, lower, upper);
@@ -428,7 +525,7 @@ impl WaveformImage {
             // the start and end of each chunk
             cr.set_source_rgb(0f64, 0f64, 1f64);
             cr.move_to(self.last.x + d.x_step_f, 0f64);
-            cr.line_to(self.last.x + d.x_step_f, 0.5f64 * self.half_range_y);
+            cr.line_to(self.last.x + d.x_step_f, 0.25f64 * self.full_range_y);
             cr.stroke().unwrap();
         }
 
@@ -446,6 +543,7 @@ impl WaveformImage {
         // Selected approach (2) because artifacts give a cheap impression.
 
         let sample_display_scale = self.sample_display_scale;
+        let lanes = Lanes::new(d.lane_mode, audio_buffer.channels, self.full_range_y);
         let samples_iter = audio_buffer
             .try_iter(lower, upper, d.sample_step)
             .unwrap_or_else(|err| panic!("{}_draw_samples: {}", self.id, err));
@@ -454,14 +552,24 @@ impl WaveformImage {
         let channel_colors = self.channel_colors.lock().unwrap();
 
         for samples in samples_iter {
-            let y_iter = samples.iter().map(|sample| {
-                f64::from(i32::from(sample.as_i16()) - SAMPLE_AMPLITUDE) * sample_display_scale
-            });
-
             let x = self.last.x + d.x_step_f;
-            for (channel, y) in y_iter.enumerate() {
+            for (channel, sample) in samples.iter().enumerate() {
+                let sample = sample.as_i16();
+                let y = match (d.lane_mode, d.amplitude_scale) {
+                    (LaneMode::Overlaid, AmplitudeScale::Linear) => {
+                        f64::from(i32::from(sample) - SAMPLE_AMPLITUDE) * sample_display_scale
+                    }
+                    (_, AmplitudeScale::Linear) => {
+                        lanes.center(channel)
+                            - f64::from(sample) / SAMPLE_FULL_SCALE * lanes.half_height
+                    }
+                    (_, AmplitudeScale::Dbfs) => {
+                        lanes.center(channel) - dbfs_amplitude(sample) * lanes.half_height
+                    }
+                };
+
                 let (r, g, b) = channel_colors
-                    .0
+                    .colors
                     .get(channel)
                     .unwrap_or_else(|| panic!("no color for channel {}", channel));
                 cr.set_source_rgb(*r, *g, *b);
@@ -476,6 +584,7 @@ impl WaveformImage {
             self.last.x = x;
         }
 
+        let axis = channel_colors.theme.axis;
         drop(channel_colors);
 
         #[cfg(test)]
@@ -483,26 +592,39 @@ impl WaveformImage {
             // in test mode, draw marks at
             // the start and end of each chunk
             cr.set_source_rgb(1f64, 0f64, 1f64);
-            cr.move_to(self.last.x, 1.5f64 * self.half_range_y);
+            cr.move_to(self.last.x, 0.75f64 * self.full_range_y);
             cr.line_to(self.last.x, self.full_range_y);
             cr.stroke().unwrap();
         }
 
         // FIXME: draw axis first (get x range from samples_iter)
-        // Draw the axis
+        // Draw the axis for each lane and the separation between lanes
         cr.set_line_width(1f64);
-        cr.set_source_rgb(AXIS_COLOR.0, AXIS_COLOR.1, AXIS_COLOR.2);
+        cr.set_source_rgb(axis.0, axis.1, axis.2);
 
-        cr.move_to(start_x, self.half_range_y);
-        cr.line_to(self.last.x, self.half_range_y);
-        cr.stroke().unwrap();
+        for lane in 0..lanes.count {
+            let center = lanes.center(lane);
+            cr.move_to(start_x, center);
+            cr.line_to(self.last.x, center);
+            cr.stroke().unwrap();
+
+            if lane > 0 {
+                let lane_top = center - lanes.half_height;
+                cr.set_dash(&[2f64, 4f64], 0f64);
+                cr.move_to(start_x, lane_top);
+                cr.line_to(self.last.x, lane_top);
+                cr.stroke().unwrap();
+                cr.set_dash(&[], 0f64);
+            }
+        }
 
         self.upper = upper;
     }
 
     // clear samples previously rendered
     fn clear_area(&self, cr: &cairo::Context, first_x: f64, limit_x: f64) {
-        cr.set_source_rgb(BACKGROUND_COLOR.0, BACKGROUND_COLOR.1, BACKGROUND_COLOR.2);
+        let background = self.theme().background;
+        cr.set_source_rgb(background.0, background.1, background.2);
         cr.rectangle(first_x, 0f64, limit_x - first_x, self.full_range_y);
         cr.fill().unwrap();
     }
@@ -576,10 +698,12 @@ mod tests {
         );
         let channels = vec![
             AudioChannel {
+                position: gst_audio::AudioChannelPosition::FrontLeft,
                 side: AudioChannelSide::Left,
                 factor: 1f64,
             },
             AudioChannel {
+                position: gst_audio::AudioChannelPosition::FrontRight,
                 side: AudioChannelSide::Right,
                 factor: 1f64,
             },
@@ -873,4 +997,110 @@ mod tests {
             SampleIndex::new(400),
         );
     }
+
+    #[test]
+    fn dbfs() {
+        assert_eq!(0f64, dbfs_amplitude(0));
+        assert_eq!(-1f64, dbfs_amplitude(std::i16::MIN));
+        assert!((dbfs_amplitude(std::i16::MAX) - 1f64).abs() < 0.001f64);
+        // -6 dBFS => 0.9
+        assert!((dbfs_amplitude(16_423) - 0.9f64).abs() < 0.001f64);
+        assert!((dbfs_amplitude(-16_423) + 0.9f64).abs() < 0.001f64);
+        // below the floor
+        assert_eq!(0f64, dbfs_amplitude(30));
+        assert_eq!(0f64, dbfs_amplitude(-30));
+
+        // clamped at the floor: -60 dBFS is reached for 32.768
+        let floor_sample = SAMPLE_FULL_SCALE * 10f64.powf(DBFS_FLOOR / 20f64);
+        assert!((floor_sample - 32.768f64).abs() < 0.001f64);
+        assert_eq!(0f64, dbfs_amplitude(32));
+        assert_eq!(0f64, dbfs_amplitude(-32));
+        assert!(dbfs_amplitude(33) > 0f64);
+        assert!(dbfs_amplitude(33) < 0.01f64);
+        assert!(dbfs_amplitude(-33) < 0f64);
+    }
+
+    #[test]
+    fn lanes_layout() {
+        let lanes = Lanes::new(LaneMode::Overlaid, 2, 300f64);
+        assert_eq!(1, lanes.count);
+        assert_eq!(300f64, lanes.height);
+        assert_eq!(150f64, lanes.center(0));
+        assert_eq!(150f64, lanes.center(1));
+
+        let lanes = Lanes::new(LaneMode::Separate, 2, 300f64);
+        assert_eq!(2, lanes.count);
+        assert_eq!(150f64, lanes.height);
+        assert_eq!(75f64, lanes.half_height);
+        assert_eq!(75f64, lanes.center(0));
+        assert_eq!(225f64, lanes.center(1));
+
+        let lanes = Lanes::new(LaneMode::Separate, 3, 300f64);
+        assert_eq!(3, lanes.count);
+        assert_eq!(100f64, lanes.height);
+        assert_eq!(
+            vec![50f64, 150f64, 250f64],
+            (0..3).map(|lane| lanes.center(lane)).collect::<Vec<f64>>(),
+        );
+
+        // No channels yet
+        let lanes = Lanes::new(LaneMode::Separate, 0, 300f64);
+        assert_eq!(1, lanes.count);
+        assert_eq!(150f64, lanes.center(0));
+    }
+
+    #[test]
+    fn separate_lanes() {
+        let (mut audio_buffer, mut waveform, mut d) = init(1f64, 300);
+        d.lane_mode = LaneMode::Separate;
+
+        render(
+            "lanes_linear",
+            &mut waveform,
+            d,
+            &mut audio_buffer,
+            build_buffer(0, 300),
+            SampleIndex::new(0),
+        );
+        assert_eq!(
+            vec!["FL", "FR"],
+            waveform
+                .channels()
+                .iter()
+                .map(|(label, _)| *label)
+                .collect::<Vec<&str>>(),
+        );
+
+        // Each channel is drawn in its own lane: left on top, right below
+        let lanes = Lanes::new(LaneMode::Separate, CHANNELS, waveform.full_range_y);
+        assert_eq!(f64::from(SAMPLE_DYN) / 2f64, lanes.height);
+        let last_sample = (299f64 / SAMPLE_RATE as f64 * f64::from(std::i16::MAX)) as i16;
+        let amplitude = f64::from(last_sample) / SAMPLE_FULL_SCALE * lanes.half_height;
+        let y_values = &waveform.last.y_values;
+        assert!((y_values[0] - (lanes.center(0) - amplitude)).abs() < 0.001f64);
+        assert!((y_values[1] - (lanes.center(1) + amplitude)).abs() < 0.001f64);
+        assert!(y_values[0] >= 0f64 && y_values[0] < lanes.height);
+        assert!(y_values[1] > lanes.height && y_values[1] <= 2f64 * lanes.height);
+
+        let (mut audio_buffer, mut waveform, mut d) = init(1f64, 300);
+        d.lane_mode = LaneMode::Separate;
+        d.amplitude_scale = AmplitudeScale::Dbfs;
+
+        render(
+            "lanes_dbfs",
+            &mut waveform,
+            d,
+            &mut audio_buffer,
+            build_buffer(0, 300),
+            SampleIndex::new(0),
+        );
+
+        let lanes = Lanes::new(LaneMode::Separate, CHANNELS, waveform.full_range_y);
+        let amplitude = dbfs_amplitude(last_sample) * lanes.half_height;
+        let y_values = &waveform.last.y_values;
+        assert!((y_values[0] - (lanes.center(0) - amplitude)).abs() < 0.001f64);
+        assert!((y_values[1] - (lanes.center(1) + amplitude)).abs() < 0.001f64);
+        assert!(y_values[0] >= 0f64 && y_values[0] < lanes.height);
+        assert!(y_values[1] > lanes.height && y_values[1] <= 2f64 * lanes.height);
+    }
 }
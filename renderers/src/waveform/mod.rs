@@ -1,10 +1,42 @@
+pub mod audiogram;
 pub mod image;
 pub mod renderer;
 pub use renderer::RefreshError;
+pub mod theme;
+pub use theme::{Color, Theme};
 
 use crate::SampleIndexRange;
 use metadata::Duration;
 
+/// How the channels are laid out in the waveform image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaneMode {
+    /// All the channels are drawn on top of each other in a single lane.
+    Overlaid,
+    /// Each channel is drawn in its own lane.
+    Separate,
+}
+
+impl Default for LaneMode {
+    fn default() -> Self {
+        LaneMode::Overlaid
+    }
+}
+
+/// The vertical scale used to draw the samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AmplitudeScale {
+    Linear,
+    /// Logarithmic scale in dB relative to full scale.
+    Dbfs,
+}
+
+impl Default for AmplitudeScale {
+    fn default() -> Self {
+        AmplitudeScale::Linear
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Dimensions {
     pub(super) sample_duration: Duration,
@@ -27,11 +59,81 @@ pub struct Dimensions {
 
     pub(super) duration_per_1000_samples: Duration,
     pub(super) req_duration_per_1000px: Duration,
+
+    pub(super) lane_mode: LaneMode,
+    pub(super) amplitude_scale: AmplitudeScale,
 }
 
 impl Dimensions {
+    /// Builds the dimensions to render `window_duration` in a `width` x `height` image.
+    ///
+    /// This is used when rendering at a fixed scale, without a `WaveformRenderer`.
+    pub(crate) fn for_window(
+        sample_duration: Duration,
+        window_duration: Duration,
+        width: i32,
+        height: i32,
+        lane_mode: LaneMode,
+        amplitude_scale: AmplitudeScale,
+    ) -> Self {
+        let mut d = Dimensions {
+            sample_duration,
+            duration_per_1000_samples: sample_duration * 1000,
+            req_duration_per_1000px: window_duration * 1000 / width.max(1) as u64,
+            req_width: width,
+            req_width_f: f64::from(width),
+            req_height: height,
+            lane_mode,
+            amplitude_scale,
+            ..Default::default()
+        };
+
+        d.update_sample_step();
+        d.update_sample_window();
+
+        d
+    }
+
+    /// Computes a sample step which will produce an integral number of
+    /// samples per pixel or an integral number of pixels per samples.
+    pub(super) fn update_sample_step(&mut self) {
+        let prev_sample_step_f = self.sample_step_f;
+
+        self.sample_step_f = if self.req_duration_per_1000px >= self.duration_per_1000_samples {
+            (self.req_duration_per_1000px.as_f64() / self.duration_per_1000_samples.as_f64())
+                .floor()
+        } else {
+            1f64 / (self.duration_per_1000_samples.as_f64() / self.req_duration_per_1000px.as_f64())
+                .ceil()
+        };
+
+        self.sample_step = (self.sample_step_f as usize).max(1).into();
+        self.x_step_f = if self.sample_step_f < 1f64 {
+            (1f64 / self.sample_step_f).round()
+        } else {
+            1f64
+        };
+        self.x_step = self.x_step_f as usize;
+
+        let force_redraw = (self.sample_step_f - prev_sample_step_f).abs() > 0.01f64;
+        self.force_redraw_1 |= force_redraw;
+        self.force_redraw_2 |= force_redraw;
+    }
+
+    pub(super) fn update_sample_window(&mut self) {
+        let half_req_sample_window = (self.sample_step_f * self.req_width_f / 2f64) as usize;
+
+        self.req_sample_window = (half_req_sample_window * 2).into();
+        self.half_req_sample_window = half_req_sample_window.into();
+        self.quarter_req_sample_window = (half_req_sample_window / 2).into();
+    }
+
     pub(super) fn reset(&mut self) {
+        // Display modes are user preferences: keep them across medias
+        let (lane_mode, amplitude_scale) = (self.lane_mode, self.amplitude_scale);
         *self = Default::default();
+        self.lane_mode = lane_mode;
+        self.amplitude_scale = amplitude_scale;
     }
 
     pub(super) fn reset_sample_cndt(&mut self) {
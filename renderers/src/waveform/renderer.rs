@@ -15,7 +15,7 @@ use metadata::Duration;
 use super::{
     super::Image,
     image::{ChannelColors, WaveformImage},
-    Dimensions,
+    AmplitudeScale, Dimensions, LaneMode, Theme,
 };
 
 #[derive(Debug)]
@@ -98,13 +98,28 @@ pub struct SamplePosition {
     pub ts: Timestamp,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct ImagePositions {
     pub offset: SamplePosition,
     pub cursor: Option<SamplePosition>,
     pub last: SamplePosition,
     pub sample_duration: Duration,
     pub sample_step: f64,
+    /// Playback rate: the cursor moves `rate` times faster than real time.
+    pub rate: f64,
+}
+
+impl Default for ImagePositions {
+    fn default() -> Self {
+        ImagePositions {
+            offset: SamplePosition::default(),
+            cursor: None,
+            last: SamplePosition::default(),
+            sample_duration: Duration::default(),
+            sample_step: 0f64,
+            rate: 1f64,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -223,9 +238,10 @@ impl State {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct SharedState {
     state: State,
+    rate: f64,
 
     pub cursor_sample: SampleIndex,
     cursor_ts: Timestamp,
@@ -239,6 +255,19 @@ pub struct SharedState {
     pub playback_needs_refresh: bool,
 }
 
+impl Default for SharedState {
+    fn default() -> Self {
+        SharedState {
+            state: State::default(),
+            rate: 1f64,
+            cursor_sample: SampleIndex::default(),
+            cursor_ts: Timestamp::default(),
+            first_visible_sample: None,
+            playback_needs_refresh: false,
+        }
+    }
+}
+
 impl SharedState {
     fn reset(&mut self) {
         *self = Self::default();
@@ -627,48 +656,54 @@ impl WaveformRenderer {
         }
     }
 
-    #[inline]
-    fn update_sample_step(&self, d: &mut Dimensions) {
-        // compute a sample step which will produce an integral number of
-        // samples per pixel or an integral number of pixels per samples
-        let prev_sample_step_f = d.sample_step_f;
+    /// Updates the channel lanes layout and the amplitude scale.
+    pub fn set_display_mode(&mut self, lane_mode: LaneMode, amplitude_scale: AmplitudeScale) {
+        let mut d = self.dimensions.write().unwrap();
 
-        d.sample_step_f = if d.req_duration_per_1000px >= d.duration_per_1000_samples {
-            (d.req_duration_per_1000px.as_f64() / d.duration_per_1000_samples.as_f64()).floor()
-        } else {
-            1f64 / (d.duration_per_1000_samples.as_f64() / d.req_duration_per_1000px.as_f64())
-                .ceil()
-        };
+        if lane_mode != d.lane_mode || amplitude_scale != d.amplitude_scale {
+            debug!(
+                "{}_set_display_mode {:?} {:?}",
+                self.image.id, lane_mode, amplitude_scale,
+            );
 
-        d.sample_step = (d.sample_step_f as usize).max(1).into();
-        d.x_step_f = if d.sample_step_f < 1f64 {
-            (1f64 / d.sample_step_f).round()
-        } else {
-            1f64
-        };
-        d.x_step = d.x_step_f as usize;
+            d.lane_mode = lane_mode;
+            d.amplitude_scale = amplitude_scale;
+            d.force_redraw_1 = true;
+            d.force_redraw_2 = true;
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        if theme != self.image.theme() {
+            self.image.set_theme(theme);
 
-        let force_redraw = (d.sample_step_f - prev_sample_step_f).abs() > 0.01f64;
-        d.force_redraw_1 |= force_redraw;
-        d.force_redraw_2 |= force_redraw;
+            let mut d = self.dimensions.write().unwrap();
+            d.force_redraw_1 = true;
+            d.force_redraw_2 = true;
+        }
+    }
+
+    pub fn lane_mode(&self) -> LaneMode {
+        self.dimensions.read().unwrap().lane_mode
+    }
+
+    #[inline]
+    fn update_sample_step(&self, d: &mut Dimensions) {
+        d.update_sample_step();
     }
 
     #[inline]
     fn update_sample_window(&self, d: &mut Dimensions) {
-        let half_req_sample_window = (d.sample_step_f * d.req_width_f / 2f64) as usize;
-        let req_sample_window = half_req_sample_window * 2;
+        let prev_req_sample_window = d.req_sample_window;
+        d.update_sample_window();
 
-        if req_sample_window != d.req_sample_window.as_usize() {
+        if prev_req_sample_window != d.req_sample_window {
             debug!(
                 "{}_update_sample_window smpl.window prev. {} -> {}",
-                self.image.id, d.req_sample_window, req_sample_window
+                self.image.id, prev_req_sample_window, d.req_sample_window
             );
         }
 
-        d.req_sample_window = req_sample_window.into();
-        d.half_req_sample_window = half_req_sample_window.into();
-        d.quarter_req_sample_window = (half_req_sample_window / 2).into();
-
         debug!("{}_update_sample_window {:?}", self.image.id, *d);
     }
 
@@ -725,6 +760,7 @@ impl WaveformRenderer {
                 last,
                 sample_duration: d.sample_duration,
                 sample_step: d.sample_step_f,
+                rate: shared_state.rate,
             },
             shared_state.state,
         ))
@@ -963,6 +999,14 @@ impl Renderer for WaveformRenderer {
         self.image.set_channels(channels);
     }
 
+    fn set_rate(&mut self, rate: f64) {
+        let mut shared_state = self.shared_state.write().unwrap();
+        if rate != shared_state.rate {
+            debug!("{}_set_rate {}", self.image.id, rate);
+            shared_state.rate = rate;
+        }
+    }
+
     fn first_visible_sample(&self) -> Option<SampleIndex> {
         self.shared_state.read().unwrap().first_visible_sample
     }
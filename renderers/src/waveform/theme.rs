@@ -0,0 +1,80 @@
+use crate::AudioChannelSide;
+
+/// An RGB color with components in the range [0.0, 1.0].
+pub type Color = (f64, f64, f64);
+
+/// Colors used to render the waveform and its overlay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub axis: Color,
+    /// Color for the channels located at the center.
+    ///
+    /// The channels colors are scaled depending on their position,
+    /// see [`AudioChannel::factor`](crate::AudioChannel).
+    pub center: Color,
+    pub left: Color,
+    pub right: Color,
+    /// Color for the channels without a location (e.g. LFE).
+    pub not_localized: Color,
+    pub cursor: Color,
+    pub boundaries: Color,
+    /// Color for the highlighted ranges.
+    pub selection: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        background: (0.2f64, 0.2235f64, 0.2314f64),
+        axis: (0.5f64, 0.5f64, 0f64),
+        center: (0f64, 1f64, 0f64),
+        left: (1f64, 1f64, 1f64),
+        right: (1f64, 0f64, 0f64),
+        not_localized: (0f64, 0f64, 1f64),
+        cursor: (1f64, 1f64, 0f64),
+        boundaries: (0.5f64, 0.6f64, 1f64),
+        selection: (0.5f64, 0.6f64, 1f64),
+    };
+
+    pub const LIGHT: Theme = Theme {
+        background: (0.98f64, 0.98f64, 0.98f64),
+        axis: (0.6f64, 0.6f64, 0.4f64),
+        center: (0f64, 0.55f64, 0f64),
+        left: (0.2f64, 0.2f64, 0.2f64),
+        right: (0.8f64, 0f64, 0f64),
+        not_localized: (0f64, 0f64, 0.8f64),
+        cursor: (0.85f64, 0.45f64, 0f64),
+        boundaries: (0.1f64, 0.3f64, 0.8f64),
+        selection: (0.1f64, 0.3f64, 0.8f64),
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        background: (0f64, 0f64, 0f64),
+        axis: (1f64, 1f64, 1f64),
+        center: (0f64, 1f64, 0f64),
+        left: (1f64, 1f64, 1f64),
+        right: (1f64, 0.2f64, 0.2f64),
+        not_localized: (0f64, 1f64, 1f64),
+        cursor: (1f64, 1f64, 0f64),
+        boundaries: (0f64, 1f64, 1f64),
+        selection: (1f64, 0f64, 1f64),
+    };
+
+    /// Returns the color for a channel on the `side` scaled with `factor`.
+    pub fn channel_color(&self, side: AudioChannelSide, factor: f64) -> Color {
+        let (r, g, b) = match side {
+            AudioChannelSide::Center => self.center,
+            AudioChannelSide::Left => self.left,
+            AudioChannelSide::NotLocalized => self.not_localized,
+            AudioChannelSide::Right => self.right,
+        };
+
+        (r * factor, g * factor, b * factor)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
@@ -9,22 +9,37 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use media::pipeline;
+use application::{ThemeColors, CONFIG};
+use media::{pipeline, Subtitle};
 use metadata::{Duration, MediaInfo};
 use renderers::{
-    generic::prelude::*, DoubleWaveformRenderer, ImagePositions, Timestamp, WaveformRenderer,
+    generic::prelude::*, AmplitudeScale, DoubleWaveformRenderer, ImagePositions, LaneMode, Theme,
+    Timestamp, WaveformRenderer,
 };
 
-use super::WaveformWithOverlay;
-use crate::{audio, info::ChaptersBoundaries, main_panel, playback, prelude::*};
+use super::{Region, RegionAction, RegionPlayback, WaveformWithOverlay};
+use crate::{
+    audio,
+    info::{ChaptersBoundaries, Markers, SceneCandidates},
+    main_panel, playback,
+    prelude::*,
+};
 
-const INIT_REQ_DURATION_FOR_1000PX: Duration = Duration::from_secs(4);
 const MIN_REQ_DURATION_FOR_1000PX: Duration = Duration::from_nanos(1_953_125); // 4s / 2^11
 const MAX_REQ_DURATION_FOR_1000PX: Duration = Duration::from_secs(32);
 const REQ_DURATION_SCALE_FACTOR: u64 = 2;
 
 const SEEK_STEP_DURATION_DIVISOR: u64 = 2;
 
+/// Width under which dragging on the waveform is considered a click.
+const MIN_REGION_WIDTH_PX: f64 = 3f64;
+
+/// Returns the duration for 1000px to use when a media is opened.
+fn init_req_duration_for_1000px() -> Duration {
+    Duration::from_millis(CONFIG.read().unwrap().preferences.zoom_duration_ms)
+        .clamp(MIN_REQ_DURATION_FOR_1000PX, MAX_REQ_DURATION_FOR_1000PX)
+}
+
 #[derive(Debug, PartialEq)]
 pub enum State {
     Disabled,
@@ -49,13 +64,24 @@ pub struct Controller {
     pub(crate) dbl_renderer_impl: Option<Box<dyn DoubleRendererImpl>>,
     pub(super) positions: Rc<RefCell<ImagePositions>>,
     boundaries: Rc<RefCell<ChaptersBoundaries>>,
+    scene_candidates: Rc<RefCell<SceneCandidates>>,
+    markers: Rc<RefCell<Markers>>,
+    region: Rc<RefCell<Region>>,
 
     container: gtk::Box,
     pub(super) drawingarea: gtk::DrawingArea,
+    subtitle_lbl: gtk::Label,
+    subtitle_range: Option<(Timestamp, Option<Timestamp>)>,
     zoom_in_btn: gtk::ToolButton,
     pub(super) zoom_in_action: gio::SimpleAction,
     zoom_out_btn: gtk::ToolButton,
     pub(super) zoom_out_action: gio::SimpleAction,
+    pub(super) lanes_btn: gtk::ToggleToolButton,
+    pub(super) toggle_lanes_action: gio::SimpleAction,
+    pub(super) dbfs_btn: gtk::ToggleToolButton,
+    pub(super) toggle_dbfs_action: gio::SimpleAction,
+    pub(super) snap_btn: gtk::ToggleToolButton,
+    pub(super) toggle_snap_action: gio::SimpleAction,
 
     pub(super) step_forward_action: gio::SimpleAction,
     pub(super) step_back_action: gio::SimpleAction,
@@ -68,6 +94,8 @@ pub struct Controller {
 
     requested_duration: Duration,
     pub(crate) seek_step: Duration,
+    lane_mode: LaneMode,
+    amplitude_scale: AmplitudeScale,
 
     tick_cb_id: Option<gtk::TickCallbackId>,
 
@@ -104,11 +132,20 @@ impl UIController for Controller {
         self.zoom_in_action.set_enabled(false);
         self.zoom_out_btn.set_sensitive(false);
         self.zoom_out_action.set_enabled(false);
+        self.lanes_btn.set_sensitive(false);
+        self.toggle_lanes_action.set_enabled(false);
+        self.dbfs_btn.set_sensitive(false);
+        self.toggle_dbfs_action.set_enabled(false);
+        self.snap_btn.set_sensitive(false);
+        self.toggle_snap_action.set_enabled(false);
         self.step_forward_action.set_enabled(false);
         self.step_back_action.set_enabled(false);
-        self.requested_duration = INIT_REQ_DURATION_FOR_1000PX;
-        self.seek_step = INIT_REQ_DURATION_FOR_1000PX / SEEK_STEP_DURATION_DIVISOR;
+        self.clear_subtitle();
+        self.subtitle_lbl.hide();
+        self.requested_duration = init_req_duration_for_1000px();
+        self.seek_step = self.requested_duration / SEEK_STEP_DURATION_DIVISOR;
         *self.positions.borrow_mut() = ImagePositions::default();
+        self.region.borrow_mut().clear();
         // Controller accesses self.boundaries as readonly
         // clearing it is under the responsiblity of ChapterTreeManager
         self.update_conditions(None);
@@ -116,12 +153,24 @@ impl UIController for Controller {
     }
 
     fn streams_changed(&mut self, info: &MediaInfo) {
+        // Subtitles are overlaid on the video when available
+        let must_show_subtitles =
+            !info.streams.is_video_selected() && info.streams.selected_text().is_some();
+        self.clear_subtitle();
+        self.subtitle_lbl.set_visible(must_show_subtitles);
+
         if info.streams.is_audio_selected() {
             debug!("streams_changed audio selected");
             self.zoom_in_btn.set_sensitive(true);
             self.zoom_in_action.set_enabled(true);
             self.zoom_out_btn.set_sensitive(true);
             self.zoom_out_action.set_enabled(true);
+            self.lanes_btn.set_sensitive(true);
+            self.toggle_lanes_action.set_enabled(true);
+            self.dbfs_btn.set_sensitive(true);
+            self.toggle_dbfs_action.set_enabled(true);
+            self.snap_btn.set_sensitive(true);
+            self.toggle_snap_action.set_enabled(true);
             self.container.show();
         } else {
             debug!("streams_changed audio not selected");
@@ -131,21 +180,38 @@ impl UIController for Controller {
 }
 
 impl Controller {
-    pub fn new(builder: &gtk::Builder, boundaries: Rc<RefCell<ChaptersBoundaries>>) -> Self {
+    pub fn new(
+        builder: &gtk::Builder,
+        boundaries: Rc<RefCell<ChaptersBoundaries>>,
+        scene_candidates: Rc<RefCell<SceneCandidates>>,
+        markers: Rc<RefCell<Markers>>,
+    ) -> Self {
         let dbl_waveform = Box::<DoubleWaveformRenderer>::default();
+        let requested_duration = init_req_duration_for_1000px();
 
         let mut ctrl = Controller {
             exposed_renderer: dbl_waveform.exposed(),
             dbl_renderer_impl: Some(dbl_waveform),
             positions: Default::default(),
             boundaries,
+            scene_candidates,
+            markers,
+            region: Default::default(),
 
             container: builder.object("audio-container").unwrap(),
             drawingarea: builder.object("audio-drawingarea").unwrap(),
+            subtitle_lbl: builder.object("subtitle-lbl").unwrap(),
+            subtitle_range: None,
             zoom_in_btn: builder.object("audio_zoom_in-toolbutton").unwrap(),
             zoom_in_action: gio::SimpleAction::new("zoom_in", None),
             zoom_out_btn: builder.object("audio_zoom_out-toolbutton").unwrap(),
             zoom_out_action: gio::SimpleAction::new("zoom_out", None),
+            lanes_btn: builder.object("audio_lanes-toolbutton").unwrap(),
+            toggle_lanes_action: gio::SimpleAction::new("toggle_lanes", None),
+            dbfs_btn: builder.object("audio_dbfs-toolbutton").unwrap(),
+            toggle_dbfs_action: gio::SimpleAction::new("toggle_dbfs", None),
+            snap_btn: builder.object("audio_snap-toolbutton").unwrap(),
+            toggle_snap_action: gio::SimpleAction::new("toggle_snap", None),
 
             step_forward_action: gio::SimpleAction::new("step_forward", None),
             step_back_action: gio::SimpleAction::new("step_back", None),
@@ -156,8 +222,10 @@ impl Controller {
 
             state: State::Disabled,
 
-            requested_duration: INIT_REQ_DURATION_FOR_1000PX,
-            seek_step: INIT_REQ_DURATION_FOR_1000PX / SEEK_STEP_DURATION_DIVISOR,
+            requested_duration,
+            seek_step: requested_duration / SEEK_STEP_DURATION_DIVISOR,
+            lane_mode: LaneMode::default(),
+            amplitude_scale: AmplitudeScale::default(),
 
             tick_cb_id: None,
 
@@ -166,6 +234,10 @@ impl Controller {
 
         ctrl.cleanup();
 
+        ctrl.snap_btn
+            .set_active(CONFIG.read().unwrap().editing.is_snapping_enabled);
+        ctrl.update_theme();
+
         ctrl
     }
 
@@ -174,6 +246,9 @@ impl Controller {
             &self.exposed_renderer,
             &self.positions,
             &self.boundaries,
+            &self.scene_candidates,
+            &self.markers,
+            &self.region,
             &self.ref_lbl,
         )
     }
@@ -260,6 +335,16 @@ impl Controller {
         }
     }
 
+    /// Returns the duration of an audio sample if a waveform is rendered.
+    pub fn sample_duration(&self) -> Option<Duration> {
+        let sample_duration = self.positions.borrow().sample_duration;
+        if sample_duration != Duration::default() {
+            Some(sample_duration)
+        } else {
+            None
+        }
+    }
+
     pub fn boundary_at(&self, x: f64) -> Option<Timestamp> {
         let ts = match self.ts_at(x) {
             Some(ts) => ts,
@@ -345,6 +430,95 @@ impl Controller {
         self.seek_step = self.requested_duration / SEEK_STEP_DURATION_DIVISOR;
     }
 
+    pub fn requested_duration(&self) -> Duration {
+        self.requested_duration
+    }
+
+    pub fn set_requested_duration(&mut self, requested_duration: Duration) {
+        self.requested_duration =
+            requested_duration.clamp(MIN_REQ_DURATION_FOR_1000PX, MAX_REQ_DURATION_FOR_1000PX);
+        self.seek_step = self.requested_duration / SEEK_STEP_DURATION_DIVISOR;
+        self.update_conditions(None);
+    }
+
+    pub fn set_lane_mode(&mut self, lane_mode: LaneMode) {
+        self.lane_mode = lane_mode;
+        self.update_display_mode();
+    }
+
+    pub fn set_amplitude_scale(&mut self, amplitude_scale: AmplitudeScale) {
+        self.amplitude_scale = amplitude_scale;
+        self.update_display_mode();
+    }
+
+    fn update_display_mode(&mut self) {
+        self.exposed_renderer
+            .lock()
+            .unwrap()
+            .set_display_mode(self.lane_mode, self.amplitude_scale);
+        self.update_conditions(None);
+    }
+
+    /// Applies the theme from the configuration.
+    ///
+    /// This must be called when the GTK theme changes
+    /// since the default theme follows the GTK dark preference.
+    pub fn update_theme(&mut self) {
+        let theme = match CONFIG.read().unwrap().ui.theme {
+            application::Theme::System => {
+                if Self::is_gtk_theme_dark() {
+                    Theme::DARK
+                } else {
+                    Theme::LIGHT
+                }
+            }
+            application::Theme::Dark => Theme::DARK,
+            application::Theme::Light => Theme::LIGHT,
+            application::Theme::HighContrast => Theme::HIGH_CONTRAST,
+            application::Theme::Custom(colors) => {
+                let ThemeColors {
+                    background,
+                    axis,
+                    center,
+                    left,
+                    right,
+                    not_localized,
+                    cursor,
+                    boundaries,
+                    selection,
+                } = colors;
+
+                Theme {
+                    background,
+                    axis,
+                    center,
+                    left,
+                    right,
+                    not_localized,
+                    cursor,
+                    boundaries,
+                    selection,
+                }
+            }
+        };
+
+        self.exposed_renderer.lock().unwrap().set_theme(theme);
+        self.update_conditions(None);
+    }
+
+    fn is_gtk_theme_dark() -> bool {
+        gtk::Settings::default().map_or(false, |settings| {
+            settings.is_gtk_application_prefer_dark_theme()
+                || settings
+                    .gtk_theme_name()
+                    .map_or(false, |name| name.to_lowercase().contains("dark"))
+        })
+    }
+
+    pub fn toggle_snap(&self, is_enabled: bool) {
+        CONFIG.write().unwrap().editing.is_snapping_enabled = is_enabled;
+    }
+
     pub fn redraw(&self) {
         self.drawingarea.queue_draw();
     }
@@ -375,6 +549,98 @@ impl Controller {
                 self.redraw();
             }
         }
+
+        self.update_subtitle();
+        self.update_region_playback();
+    }
+
+    /// Loops over the region or pauses at its end, depending on the region playback.
+    fn update_region_playback(&mut self) {
+        let cursor_ts = match self.positions.borrow().cursor.as_ref() {
+            Some(cursor) => cursor.ts,
+            None => return,
+        };
+
+        let action = self.region.borrow_mut().tick(cursor_ts);
+        match action {
+            Some(RegionAction::Seek(target)) => playback::seek(target, gst::SeekFlags::ACCURATE),
+            Some(RegionAction::Pause) => playback::pause(),
+            None => (),
+        }
+    }
+
+    /// Loops over the region, or stops looping if it was already.
+    ///
+    /// Returns the position to play from when the loop starts.
+    pub fn toggle_loop(&mut self) -> Option<Timestamp> {
+        let start = {
+            let mut region = self.region.borrow_mut();
+            if region.is_looping() {
+                region.stop();
+                None
+            } else {
+                let preroll = Duration::from_millis(CONFIG.read().unwrap().editing.loop_preroll_ms);
+                region.play(RegionPlayback::Loop { preroll })
+            }
+        };
+        self.redraw();
+
+        start
+    }
+
+    /// Selects `radius` before and after the `boundary` and plays it once.
+    ///
+    /// Returns the position to play from.
+    pub fn audition(&mut self, boundary: Timestamp, radius: Duration) -> Option<Timestamp> {
+        let start = {
+            let mut region = self.region.borrow_mut();
+            region.set_range(boundary.saturating_sub(radius), boundary + radius);
+            region.play(RegionPlayback::Once)
+        };
+        self.redraw();
+
+        start
+    }
+
+    /// Returns the range of the region selected on the waveform, if any.
+    pub fn region(&self) -> Option<(Timestamp, Timestamp)> {
+        self.region.borrow().range()
+    }
+
+    pub fn clear_region(&mut self) {
+        self.region.borrow_mut().clear();
+        self.redraw();
+    }
+
+    pub fn show_subtitle(&mut self, subtitle: Subtitle) {
+        if subtitle.is_markup {
+            self.subtitle_lbl.set_markup(&subtitle.text);
+        } else {
+            self.subtitle_lbl.set_text(&subtitle.text);
+        }
+        self.subtitle_range = Some((subtitle.start, subtitle.end));
+    }
+
+    fn clear_subtitle(&mut self) {
+        self.subtitle_lbl.set_text("");
+        self.subtitle_range = None;
+    }
+
+    /// Clears the subtitle if the cursor moved out of its range.
+    fn update_subtitle(&mut self) {
+        let (start, end) = match self.subtitle_range {
+            Some(range) => range,
+            None => return,
+        };
+
+        let cursor_ts = match self.positions.borrow().cursor.as_ref() {
+            Some(cursor) => cursor.ts,
+            None => return,
+        };
+
+        if cursor_ts < start || end.map_or(false, |end| cursor_ts >= end) {
+            self.clear_subtitle();
+        }
     }
 
     pub fn motion_notify(
@@ -383,6 +649,21 @@ impl Controller {
     ) -> Option<(Timestamp, Timestamp)> {
         let (x, _y) = event_motion.position();
 
+        if self.region.borrow().is_dragging() {
+            let min_duration = {
+                let positions = self.positions.borrow();
+                positions.sample_duration
+                    * ((MIN_REGION_WIDTH_PX * positions.sample_step).round() as u64)
+            };
+            if let Some(ts) = self.ts_at(x) {
+                if self.region.borrow_mut().drag_to(ts, min_duration) {
+                    self.redraw();
+                }
+            }
+
+            return None;
+        }
+
         match self.state {
             State::Playing => (),
             State::MovingBoundary(boundary) => {
@@ -409,6 +690,8 @@ impl Controller {
     }
 
     pub fn leave_drawing_area(&mut self) {
+        self.region.borrow_mut().end_drag();
+
         match self.state {
             State::Playing => (),
             State::Paused => (),
@@ -431,6 +714,8 @@ impl Controller {
                         | State::Paused
                         | State::PausedPlayingRange => {
                             playback::seek(ts, gst::SeekFlags::ACCURATE);
+                            // Dragging selects a region
+                            self.region.borrow_mut().start_drag(ts);
                         }
                         State::CursorAboveBoundary(boundary) => {
                             self.state = State::MovingBoundary(boundary);
@@ -449,12 +734,20 @@ impl Controller {
         }
     }
 
-    pub fn button_released(&mut self, event_button: gdk::EventButton) {
+    /// Returns the boundary which was being moved, if any.
+    pub fn button_released(&mut self, event_button: gdk::EventButton) -> Option<Timestamp> {
+        if 1 == event_button.button() {
+            self.region.borrow_mut().end_drag();
+        }
+
         if let State::MovingBoundary(boundary) = self.state {
             if 1 == event_button.button() {
                 // left button
                 self.state = State::CursorAboveBoundary(boundary);
+                return Some(boundary);
             }
         }
+
+        None
     }
 }
@@ -3,14 +3,17 @@ use futures::{
     prelude::*,
 };
 
-use gtk::{gdk, prelude::*};
+use gtk::{gdk, gio, prelude::*};
 
 use log::{debug, trace};
 
 use std::cell::RefCell;
 
+use application::CONFIG;
+use metadata::Duration;
+use renderers::{AmplitudeScale, LaneMode, Timestamp};
+
 use crate::{audio, info::PositionStatus, main_panel, prelude::*};
-use renderers::Timestamp;
 
 use super::AreaEvent;
 
@@ -66,6 +69,12 @@ impl UIDispatcher for Dispatcher {
             Inhibit(true)
         });
 
+        // Follow the GTK dark preference
+        if let Some(settings) = gtk::Settings::default() {
+            settings.connect_gtk_application_prefer_dark_theme_notify(|_| audio::update_theme());
+            settings.connect_gtk_theme_name_notify(|_| audio::update_theme());
+        }
+
         // Register Zoom in action
         app.add_action(&audio.zoom_in_action);
         audio
@@ -78,6 +87,36 @@ impl UIDispatcher for Dispatcher {
             .zoom_out_action
             .connect_activate(|_, _| audio::zoom_out());
 
+        // Register Toggle separate lanes action
+        app.add_action(&audio.toggle_lanes_action);
+        let lanes_btn = audio.lanes_btn.clone();
+        audio.toggle_lanes_action.connect_activate(move |_, _| {
+            lanes_btn.set_active(!lanes_btn.is_active());
+        });
+        audio
+            .lanes_btn
+            .connect_toggled(|button| audio::toggle_lanes(button.is_active()));
+
+        // Register Toggle dBFS scale action
+        app.add_action(&audio.toggle_dbfs_action);
+        let dbfs_btn = audio.dbfs_btn.clone();
+        audio.toggle_dbfs_action.connect_activate(move |_, _| {
+            dbfs_btn.set_active(!dbfs_btn.is_active());
+        });
+        audio
+            .dbfs_btn
+            .connect_toggled(|button| audio::toggle_dbfs(button.is_active()));
+
+        // Register Toggle boundaries snapping action
+        app.add_action(&audio.toggle_snap_action);
+        let snap_btn = audio.snap_btn.clone();
+        audio.toggle_snap_action.connect_activate(move |_, _| {
+            snap_btn.set_active(!snap_btn.is_active());
+        });
+        audio
+            .snap_btn
+            .connect_toggled(|button| audio::toggle_snap(button.is_active()));
+
         // Register Step forward action
         app.add_action(&audio.step_forward_action);
         audio
@@ -89,6 +128,19 @@ impl UIDispatcher for Dispatcher {
         audio
             .step_back_action
             .connect_activate(|_, _| audio::step_back());
+
+        // Register region playback actions
+        let toggle_loop = gio::SimpleAction::new("toggle_loop_region", None);
+        app.add_action(&toggle_loop);
+        toggle_loop.connect_activate(|_, _| audio::toggle_loop());
+
+        let clear_region = gio::SimpleAction::new("clear_region", None);
+        app.add_action(&clear_region);
+        clear_region.connect_activate(|_, _| audio::clear_region());
+
+        let audition_boundary = gio::SimpleAction::new("audition_boundary", None);
+        app.add_action(&audition_boundary);
+        audition_boundary.connect_activate(|_, _| audio::audition_boundary());
     }
 
     fn handle_event(
@@ -105,36 +157,45 @@ impl UIDispatcher for Dispatcher {
         }
         match event {
             Area(event) => Self::area_event(main_ctrl, event),
+            AuditionBoundary => {
+                if let Some(boundary) = main_ctrl.info.edited_boundary() {
+                    let radius =
+                        Duration::from_millis(CONFIG.read().unwrap().editing.audition_radius_ms);
+                    if let Some(start) = main_ctrl.audio.audition(boundary, radius) {
+                        return async move { main_ctrl.play_from(start).await }.boxed_local();
+                    }
+                }
+            }
+            ClearRegion => main_ctrl.audio.clear_region(),
             UpdateRenderingCndt(dimensions) => main_ctrl.audio.update_conditions(dimensions),
             Refresh => main_ctrl.audio.refresh(),
+            ShowSubtitle(subtitle) => main_ctrl.audio.show_subtitle(subtitle),
             StepBack => Self::step_back(main_ctrl),
             StepForward => Self::step_forward(main_ctrl),
             Tick => main_ctrl.audio.tick(),
+            ToggleDbfs(is_dbfs) => main_ctrl.audio.set_amplitude_scale(if is_dbfs {
+                AmplitudeScale::Dbfs
+            } else {
+                AmplitudeScale::Linear
+            }),
+            ToggleLanes(is_separate) => main_ctrl.audio.set_lane_mode(if is_separate {
+                LaneMode::Separate
+            } else {
+                LaneMode::Overlaid
+            }),
+            ToggleLoop => {
+                if let Some(start) = main_ctrl.audio.toggle_loop() {
+                    return async move { main_ctrl.play_from(start).await }.boxed_local();
+                }
+            }
+            ToggleSnap(is_enabled) => main_ctrl.audio.toggle_snap(is_enabled),
+            UpdateTheme => main_ctrl.audio.update_theme(),
             ZoomIn => main_ctrl.audio.zoom_in(),
             ZoomOut => main_ctrl.audio.zoom_out(),
         }
 
         future::ready(()).boxed_local()
     }
-
-    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
-        use UIFocusContext::*;
-
-        match ctx {
-            PlaybackPage => {
-                app.set_accels_for_action("app.zoom_in", &["z"]);
-                app.set_accels_for_action("app.zoom_out", &["<Shift>z"]);
-                app.set_accels_for_action("app.step_forward", &["Right"]);
-                app.set_accels_for_action("app.step_back", &["Left"]);
-            }
-            ExportPage | InfoBar | StreamsPage | SplitPage | TextEntry => {
-                app.set_accels_for_action("app.zoom_in", &[]);
-                app.set_accels_for_action("app.zoom_out", &[]);
-                app.set_accels_for_action("app.step_forward", &[]);
-                app.set_accels_for_action("app.step_back", &[]);
-            }
-        }
-    }
 }
 
 impl Dispatcher {
@@ -144,7 +205,11 @@ impl Dispatcher {
         match event {
             Button(event) => match event.event_type() {
                 gdk::EventType::ButtonPress => main_ctrl.audio.button_pressed(event),
-                gdk::EventType::ButtonRelease => main_ctrl.audio.button_released(event),
+                gdk::EventType::ButtonRelease => {
+                    if let Some(boundary) = main_ctrl.audio.button_released(event) {
+                        main_ctrl.info.refresh_thumbnail(boundary);
+                    }
+                }
                 gdk::EventType::Scroll => {
                     // FIXME zoom in / out
                 }
@@ -153,6 +218,7 @@ impl Dispatcher {
             Leaving => main_ctrl.audio.leave_drawing_area(),
             Motion(event) => {
                 if let Some((boundary, target)) = main_ctrl.audio.motion_notify(event) {
+                    let target = main_ctrl.snap(target);
                     if let PositionStatus::ChapterChanged { .. } =
                         main_ctrl.info.move_chapter_boundary(boundary, target)
                     {
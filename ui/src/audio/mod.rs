@@ -4,20 +4,33 @@ pub use controller::{AreaEvent, Controller};
 mod dispatcher;
 pub use dispatcher::Dispatcher;
 
+mod region;
+pub use region::{Region, RegionAction, RegionPlayback};
+
 mod waveform_with_overlay;
 pub use waveform_with_overlay::WaveformWithOverlay;
 
+use media::Subtitle;
+
 use crate::UIEventChannel;
 
 #[derive(Debug)]
 pub enum Event {
     Area(AreaEvent),
+    AuditionBoundary,
+    ClearRegion,
     UpdateRenderingCndt(Option<(f64, f64)>),
     Refresh,
+    ShowSubtitle(Subtitle),
     // FIXME those 2 are not audio specific, rather for a dedicated playback
     StepBack,
     StepForward,
     Tick,
+    ToggleDbfs(bool),
+    ToggleLanes(bool),
+    ToggleLoop,
+    ToggleSnap(bool),
+    UpdateTheme,
     ZoomIn,
     ZoomOut,
 }
@@ -26,6 +39,14 @@ fn area_event(event: AreaEvent) {
     UIEventChannel::send(Event::Area(event));
 }
 
+fn audition_boundary() {
+    UIEventChannel::send(Event::AuditionBoundary);
+}
+
+fn clear_region() {
+    UIEventChannel::send(Event::ClearRegion);
+}
+
 pub fn update_rendering_cndt(dimensions: Option<(f64, f64)>) {
     UIEventChannel::send(Event::UpdateRenderingCndt(dimensions));
 }
@@ -34,6 +55,10 @@ pub fn refresh() {
     UIEventChannel::send(Event::Refresh);
 }
 
+pub fn show_subtitle(subtitle: Subtitle) {
+    UIEventChannel::send(Event::ShowSubtitle(subtitle));
+}
+
 pub fn step_back() {
     UIEventChannel::send(Event::StepBack);
 }
@@ -46,6 +71,26 @@ pub fn tick() {
     UIEventChannel::send(Event::Tick);
 }
 
+fn toggle_dbfs(is_dbfs: bool) {
+    UIEventChannel::send(Event::ToggleDbfs(is_dbfs));
+}
+
+fn toggle_lanes(is_separate: bool) {
+    UIEventChannel::send(Event::ToggleLanes(is_separate));
+}
+
+fn toggle_loop() {
+    UIEventChannel::send(Event::ToggleLoop);
+}
+
+fn toggle_snap(is_enabled: bool) {
+    UIEventChannel::send(Event::ToggleSnap(is_enabled));
+}
+
+fn update_theme() {
+    UIEventChannel::send(Event::UpdateTheme);
+}
+
 fn zoom_in() {
     UIEventChannel::send(Event::ZoomIn);
 }
@@ -0,0 +1,215 @@
+use metadata::Duration;
+use renderers::Timestamp;
+
+/// How the playback goes through the region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionPlayback {
+    /// Starts over from the region's start, `preroll` before it.
+    Loop { preroll: Duration },
+    /// Pauses at the region's end.
+    Once,
+}
+
+/// What to do with the playback when it reaches a position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionAction {
+    Seek(Timestamp),
+    Pause,
+}
+
+/// An A/B region selected by dragging on the waveform.
+#[derive(Debug, Default)]
+pub struct Region {
+    range: Option<(Timestamp, Timestamp)>,
+    /// Position where the pointer was pressed for the current drag.
+    anchor: Option<Timestamp>,
+    playback: Option<RegionPlayback>,
+    /// The playback was sent back to the start and hasn't left the end yet.
+    is_restarting: bool,
+}
+
+impl Region {
+    pub fn clear(&mut self) {
+        *self = Region::default();
+    }
+
+    pub fn range(&self) -> Option<(Timestamp, Timestamp)> {
+        self.range
+    }
+
+    pub fn is_looping(&self) -> bool {
+        matches!(self.playback, Some(RegionPlayback::Loop { .. }))
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    pub fn start_drag(&mut self, ts: Timestamp) {
+        self.anchor = Some(ts);
+    }
+
+    /// Selects the range between the drag anchor and `ts`.
+    ///
+    /// The range is only changed when it is at least `min_duration` long,
+    /// so that a click doesn't replace the current region.
+    /// Returns `true` if the range changed.
+    pub fn drag_to(&mut self, ts: Timestamp, min_duration: Duration) -> bool {
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            None => return false,
+        };
+
+        let range = (anchor.min(ts), anchor.max(ts));
+        if range.1 - range.0 < min_duration || self.range == Some(range) {
+            return false;
+        }
+
+        self.range = Some(range);
+        true
+    }
+
+    pub fn end_drag(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Replaces the range, e.g. to audition a boundary.
+    pub fn set_range(&mut self, start: Timestamp, end: Timestamp) {
+        self.range = Some((start, end));
+    }
+
+    /// Starts going through the region.
+    ///
+    /// Returns the position to play from.
+    pub fn play(&mut self, playback: RegionPlayback) -> Option<Timestamp> {
+        let (start, _) = self.range?;
+
+        self.playback = Some(playback);
+        self.is_restarting = false;
+
+        Some(match playback {
+            RegionPlayback::Loop { preroll } => start.saturating_sub(preroll),
+            RegionPlayback::Once => start,
+        })
+    }
+
+    pub fn stop(&mut self) {
+        self.playback = None;
+        self.is_restarting = false;
+    }
+
+    /// Returns what to do now that the playback reached `ts`.
+    pub fn tick(&mut self, ts: Timestamp) -> Option<RegionAction> {
+        let (start, end) = self.range?;
+        let playback = self.playback?;
+
+        if ts < end {
+            self.is_restarting = false;
+            return None;
+        }
+
+        if self.is_restarting {
+            // Wait for the seek to the start
+            return None;
+        }
+
+        match playback {
+            RegionPlayback::Loop { preroll } => {
+                self.is_restarting = true;
+                Some(RegionAction::Seek(start.saturating_sub(preroll)))
+            }
+            RegionPlayback::Once => {
+                self.playback = None;
+                Some(RegionAction::Pause)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata::Duration;
+    use renderers::Timestamp;
+
+    use super::{Region, RegionAction, RegionPlayback};
+
+    const MIN: Duration = Duration::from_nanos(5);
+
+    #[test]
+    fn drag_region() {
+        let mut region = Region::default();
+        assert!(!region.drag_to(Timestamp::new(40), MIN));
+
+        region.start_drag(Timestamp::new(30));
+        assert!(region.is_dragging());
+        // Too short: a click
+        assert!(!region.drag_to(Timestamp::new(32), MIN));
+        assert!(region.range().is_none());
+
+        // Dragging backward
+        assert!(region.drag_to(Timestamp::new(10), MIN));
+        assert_eq!(
+            region.range(),
+            Some((Timestamp::new(10), Timestamp::new(30)))
+        );
+        assert!(region.drag_to(Timestamp::new(50), MIN));
+        assert!(!region.drag_to(Timestamp::new(50), MIN));
+        region.end_drag();
+        assert!(!region.is_dragging());
+
+        // A click keeps the current region
+        region.start_drag(Timestamp::new(20));
+        assert!(!region.drag_to(Timestamp::new(21), MIN));
+        region.end_drag();
+        assert_eq!(
+            region.range(),
+            Some((Timestamp::new(30), Timestamp::new(50)))
+        );
+
+        region.clear();
+        assert!(region.range().is_none());
+    }
+
+    #[test]
+    fn loop_region() {
+        let mut region = Region::default();
+        let preroll = Duration::from_nanos(5);
+        assert!(region.play(RegionPlayback::Loop { preroll }).is_none());
+
+        region.set_range(Timestamp::new(20), Timestamp::new(40));
+        assert_eq!(
+            region.play(RegionPlayback::Loop { preroll }),
+            Some(Timestamp::new(15))
+        );
+        assert!(region.is_looping());
+
+        assert!(region.tick(Timestamp::new(30)).is_none());
+        assert_eq!(
+            region.tick(Timestamp::new(41)),
+            Some(RegionAction::Seek(Timestamp::new(15)))
+        );
+        // Still waiting for the seek
+        assert!(region.tick(Timestamp::new(42)).is_none());
+        assert!(region.tick(Timestamp::new(16)).is_none());
+        assert_eq!(
+            region.tick(Timestamp::new(40)),
+            Some(RegionAction::Seek(Timestamp::new(15)))
+        );
+
+        region.stop();
+        assert!(!region.is_looping());
+        assert!(region.tick(Timestamp::new(41)).is_none());
+    }
+
+    #[test]
+    fn play_region_once() {
+        let mut region = Region::default();
+        region.set_range(Timestamp::new(20), Timestamp::new(40));
+        assert_eq!(region.play(RegionPlayback::Once), Some(Timestamp::new(20)));
+        assert!(!region.is_looping());
+
+        assert!(region.tick(Timestamp::new(39)).is_none());
+        assert_eq!(region.tick(Timestamp::new(40)), Some(RegionAction::Pause));
+        assert!(region.tick(Timestamp::new(41)).is_none());
+    }
+}
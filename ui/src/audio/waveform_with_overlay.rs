@@ -10,9 +10,10 @@ use std::{
 };
 
 use metadata::Duration;
-use renderers::{ImagePositions, SampleIndexRange, Timestamp, WaveformRenderer, BACKGROUND_COLOR};
+use renderers::{ImagePositions, LaneMode, SampleIndexRange, Timestamp, WaveformRenderer};
 
-use crate::info::{self, ChaptersBoundaries};
+use super::Region;
+use crate::info::{self, ChaptersBoundaries, Coverage, Markers, SceneCandidates};
 
 // Use this text to compute the largest text box for the waveform limits
 // This is required to position the labels in such a way they don't
@@ -88,6 +89,9 @@ pub struct WaveformWithOverlay {
     waveform_renderer_mtx: Arc<Mutex<Box<WaveformRenderer>>>,
     text_metrics: TextMetrics,
     boundaries: Rc<RefCell<ChaptersBoundaries>>,
+    scene_candidates: Rc<RefCell<SceneCandidates>>,
+    markers: Rc<RefCell<Markers>>,
+    region: Rc<RefCell<Region>>,
     positions: Rc<RefCell<ImagePositions>>,
     last_other_ui_refresh: Timestamp,
 }
@@ -97,22 +101,29 @@ impl WaveformWithOverlay {
         waveform_renderer_mtx: &Arc<Mutex<Box<WaveformRenderer>>>,
         positions: &Rc<RefCell<ImagePositions>>,
         boundaries: &Rc<RefCell<ChaptersBoundaries>>,
+        scene_candidates: &Rc<RefCell<SceneCandidates>>,
+        markers: &Rc<RefCell<Markers>>,
+        region: &Rc<RefCell<Region>>,
         ref_lbl: &gtk::Label,
     ) -> Self {
         WaveformWithOverlay {
             waveform_renderer_mtx: Arc::clone(waveform_renderer_mtx),
             text_metrics: TextMetrics::new(ref_lbl.clone()),
             boundaries: Rc::clone(boundaries),
+            scene_candidates: Rc::clone(scene_candidates),
+            markers: Rc::clone(markers),
+            region: Rc::clone(region),
             positions: Rc::clone(positions),
             last_other_ui_refresh: Timestamp::default(),
         }
     }
 
     pub fn draw(&mut self, da: &gtk::DrawingArea, cr: &cairo::Context) {
-        cr.set_source_rgb(BACKGROUND_COLOR.0, BACKGROUND_COLOR.1, BACKGROUND_COLOR.2);
+        let theme = self.waveform_renderer_mtx.lock().unwrap().image.theme();
+        cr.set_source_rgb(theme.background.0, theme.background.1, theme.background.2);
         cr.paint().unwrap();
 
-        let (positions, state) = {
+        let (positions, state, lane_labels) = {
             let waveform_renderer = &mut *self.waveform_renderer_mtx.lock().unwrap();
             // FIXME send an event?
             //self.playback_needs_refresh = waveform_renderer.playback_needs_refresh();
@@ -139,11 +150,16 @@ impl WaveformWithOverlay {
                 cr.paint().unwrap();
             });
 
-            (positions, state)
+            let lane_labels = match waveform_renderer.lane_mode() {
+                LaneMode::Overlaid => None,
+                LaneMode::Separate => Some(waveform_renderer.image.channels()),
+            };
+
+            (positions, state, lane_labels)
         };
 
         cr.scale(1f64, 1f64);
-        cr.set_source_rgb(1f64, 1f64, 0f64);
+        cr.set_source_rgb(theme.cursor.0, theme.cursor.1, theme.cursor.2);
 
         self.text_metrics.set_text_metrics(cr);
 
@@ -173,19 +189,97 @@ impl WaveformWithOverlay {
             cr.show_text(&last_text).unwrap();
         }
 
-        // Draw in-range chapters boundaries
-        let boundaries = self.boundaries.borrow();
-
-        let chapter_range =
-            boundaries.range((Included(&positions.offset.ts), Included(&positions.last.ts)));
+        // Label each channel lane
+        if let Some(lane_labels) = lane_labels.filter(|labels| !labels.is_empty()) {
+            let lane_height = f64::from(da.allocated_height()) / lane_labels.len() as f64;
+            for (lane, (label, (r, g, b))) in lane_labels.iter().enumerate() {
+                cr.set_source_rgb(*r, *g, *b);
+                cr.move_to(
+                    2f64,
+                    lane_height * (lane as f64 + 0.5f64) - self.text_metrics.half_font_size,
+                );
+                cr.show_text(label).unwrap();
+            }
+        }
 
         let allocation = da.allocation();
         let (area_width, area_height) = (allocation.width() as f64, allocation.width() as f64);
 
-        cr.set_source_rgb(0.5f64, 0.6f64, 1f64);
         cr.set_line_width(1f64);
         let boundary_y0 = self.text_metrics.twice_font_size + 5f64;
+
+        // Draw in-range scene change candidates, more opaque with higher confidence
+        let scene_candidates = self.scene_candidates.borrow();
+        let candidate_range =
+            scene_candidates.range((Included(&positions.offset.ts), Included(&positions.last.ts)));
+
+        cr.set_dash(&[4f64, 4f64], 0f64);
+        for (ts, confidence) in candidate_range {
+            let x = SampleIndexRange::from_duration(
+                *ts - positions.offset.ts,
+                positions.sample_duration,
+            )
+            .as_f64()
+                / positions.sample_step;
+            cr.set_source_rgba(
+                theme.boundaries.0,
+                theme.boundaries.1,
+                theme.boundaries.2,
+                0.3f64 + 0.7f64 * confidence,
+            );
+            cr.move_to(x, boundary_y0);
+            cr.line_to(x, area_height);
+            cr.stroke().unwrap();
+        }
+        cr.set_dash(&[], 0f64);
+
+        // Shade in-range gaps & overlaps between chapters
+        let boundaries = self.boundaries.borrow();
+        let ts_to_x = |ts: Timestamp| {
+            SampleIndexRange::from_duration(ts - positions.offset.ts, positions.sample_duration)
+                .as_f64()
+                / positions.sample_step
+        };
+
+        for (range, coverage) in boundaries.gaps_and_overlaps() {
+            if range.end <= positions.offset.ts || range.start >= positions.last.ts {
+                continue;
+            }
+
+            let (color, alpha) = match coverage {
+                Coverage::Gap => (theme.boundaries, 0.15f64),
+                Coverage::Overlap => (theme.selection, 0.25f64),
+            };
+            cr.set_source_rgba(color.0, color.1, color.2, alpha);
+
+            let start_x = ts_to_x(range.start.max(positions.offset.ts));
+            let end_x = ts_to_x(range.end.min(positions.last.ts));
+            cr.rectangle(start_x, boundary_y0, end_x - start_x, area_height);
+            cr.fill().unwrap();
+        }
+
+        // Shade the A/B region, more opaque when looping
+        let region = self.region.borrow();
+        if let Some((start, end)) = region.range() {
+            if end > positions.offset.ts && start < positions.last.ts {
+                let alpha = if region.is_looping() { 0.3f64 } else { 0.15f64 };
+                cr.set_source_rgba(theme.cursor.0, theme.cursor.1, theme.cursor.2, alpha);
+
+                let start_x = ts_to_x(start.max(positions.offset.ts));
+                let end_x = ts_to_x(end.min(positions.last.ts));
+                cr.rectangle(start_x, 0f64, end_x - start_x, area_height);
+                cr.fill().unwrap();
+            }
+        }
+
+        // Draw in-range chapters boundaries
+
+        let chapter_range =
+            boundaries.range((Included(&positions.offset.ts), Included(&positions.last.ts)));
+
+        cr.set_source_rgb(theme.boundaries.0, theme.boundaries.1, theme.boundaries.2);
         let text_base = allocation.height() as f64 - self.text_metrics.half_font_size;
+        let selected_boundary = boundaries.selected();
 
         for (boundary, chapters) in chapter_range {
             if *boundary >= positions.offset.ts {
@@ -195,9 +289,21 @@ impl WaveformWithOverlay {
                 )
                 .as_f64()
                     / positions.sample_step;
-                cr.move_to(x, boundary_y0);
-                cr.line_to(x, area_height);
-                cr.stroke().unwrap();
+
+                if selected_boundary == Some(*boundary) {
+                    // Highlight the boundary selected for keyboard editing
+                    cr.set_source_rgb(theme.selection.0, theme.selection.1, theme.selection.2);
+                    cr.set_line_width(3f64);
+                    cr.move_to(x, boundary_y0);
+                    cr.line_to(x, area_height);
+                    cr.stroke().unwrap();
+                    cr.set_line_width(1f64);
+                    cr.set_source_rgb(theme.boundaries.0, theme.boundaries.1, theme.boundaries.2);
+                } else {
+                    cr.move_to(x, boundary_y0);
+                    cr.line_to(x, area_height);
+                    cr.stroke().unwrap();
+                }
 
                 if let Some(ref prev_chapter) = chapters.prev {
                     cr.move_to(
@@ -214,9 +320,37 @@ impl WaveformWithOverlay {
             }
         }
 
+        // Draw in-range markers with their label next to a flag
+        let markers = self.markers.borrow();
+        let marker_range =
+            markers.range((Included(&positions.offset.ts), Included(&positions.last.ts)));
+
+        for (ts, marker) in marker_range {
+            let x = ts_to_x(*ts);
+            let (r, g, b) = marker.color.rgb();
+
+            cr.set_source_rgba(r, g, b, 0.8f64);
+            cr.move_to(x, boundary_y0);
+            cr.line_to(x, area_height);
+            cr.stroke().unwrap();
+
+            cr.set_source_rgb(r, g, b);
+            cr.move_to(x, boundary_y0);
+            cr.line_to(x + self.text_metrics.half_font_size, boundary_y0);
+            cr.line_to(x, boundary_y0 + self.text_metrics.half_font_size);
+            cr.close_path();
+            cr.fill().unwrap();
+
+            cr.move_to(
+                x + self.text_metrics.half_font_size + 2f64,
+                boundary_y0 + self.text_metrics.font_size,
+            );
+            cr.show_text(&marker.label).unwrap();
+        }
+
         if let Some(cursor) = &positions.cursor {
             // draw current pos
-            cr.set_source_rgb(1f64, 1f64, 0f64);
+            cr.set_source_rgb(theme.cursor.0, theme.cursor.1, theme.cursor.2);
 
             let cursor_text = cursor.ts.for_humans().with_micro().to_string();
             let cursor_text_end = if cursor.ts < ONE_HOUR {
@@ -242,9 +376,13 @@ impl WaveformWithOverlay {
             // update other UI position
             // Note: we go through the audio controller here in order
             // to reduce position queries on the ref gst element
+            // The refresh period is expressed in stream time, hence the rate.
+            let refresh_period = Duration::from_nanos(
+                (OTHER_UI_REFRESH_PERIOD.as_f64() * positions.rate.abs()) as u64,
+            );
             if !state.is_playing()
                 || cursor.ts < self.last_other_ui_refresh
-                || cursor.ts > self.last_other_ui_refresh + OTHER_UI_REFRESH_PERIOD
+                || cursor.ts > self.last_other_ui_refresh + refresh_period
             {
                 info::refresh(cursor_ts);
                 self.last_other_ui_refresh = cursor_ts;
@@ -0,0 +1,437 @@
+use futures::{
+    channel::mpsc as async_mpsc,
+    future::{self, abortable, AbortHandle},
+    prelude::*,
+};
+use gtk::{glib, prelude::*};
+use log::warn;
+
+use std::path::PathBuf;
+
+use application::{gettext, ngettext, CONFIG};
+use media::batch::{Job, JobEvent, JobStatus};
+use metadata::{Factory, Format};
+
+use crate::{batch, info_bar, prelude::*, spawn};
+
+const MEDIA_COL: u32 = 0;
+const TOC_COL: u32 = 1;
+const FORMAT_COL: u32 = 2;
+const PROGRESS_COL: u32 = 3;
+const STATUS_COL: u32 = 4;
+const TOOLTIP_COL: u32 = 5;
+
+pub fn format_label(format: Format) -> String {
+    match format {
+        Format::CueSheet => gettext("Cue Sheet"),
+        Format::Description => gettext("Chapters list"),
+        Format::FFMetadata => gettext("FFmpeg metadata"),
+        Format::Flac => gettext("Split to FLAC"),
+        Format::Id3v2 => gettext("MP3 with ID3v2 chapters"),
+        Format::Matroska => gettext("Matroska container"),
+        Format::MKVMergeText => gettext("mkvmerge simple chapters"),
+        Format::MP3 => gettext("Split to MP3"),
+        Format::Opus => gettext("Split to Opus"),
+        Format::PodcastChapters => gettext("Podcast chapters (JSON)"),
+        Format::Srt => gettext("SubRip"),
+        Format::Vorbis => gettext("Split to Vorbis"),
+        Format::Wave => gettext("Split to wave"),
+        Format::WebVtt => gettext("WebVTT chapters"),
+    }
+}
+
+/// Queue of jobs exporting or splitting several media.
+pub struct Controller {
+    pub(super) dialog: gtk::Dialog,
+    treeview: gtk::TreeView,
+    store: gtk::ListStore,
+
+    formats: Vec<Format>,
+    format_combo: gtk::ComboBoxText,
+    output_dir_chooser: gtk::FileChooserButton,
+    max_parallel_spin: gtk::SpinButton,
+    overwrite_btn: gtk::CheckButton,
+
+    pub(super) add_btn: gtk::Button,
+    pub(super) toc_btn: gtk::Button,
+    pub(super) remove_btn: gtk::Button,
+    pub(super) clear_btn: gtk::Button,
+    pub(super) start_btn: gtk::Button,
+
+    pub(super) media_dlg: gtk::FileChooserNative,
+    pub(super) toc_dlg: gtk::FileChooserNative,
+
+    jobs: Vec<Job>,
+    statuses: Vec<JobStatus>,
+    pub(super) is_busy: bool,
+    abort_handle: Option<AbortHandle>,
+}
+
+impl Controller {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let dialog: gtk::Dialog = builder.object("batch-dialog").unwrap();
+
+        let treeview: gtk::TreeView = builder.object("batch-treeview").unwrap();
+        Self::add_text_column(&treeview, &gettext("Media"), MEDIA_COL, true);
+        Self::add_text_column(&treeview, &gettext("Table of contents"), TOC_COL, false);
+        Self::add_text_column(&treeview, &gettext("Format"), FORMAT_COL, false);
+        Self::add_progress_column(&treeview, &gettext("Status"));
+
+        let format_combo: gtk::ComboBoxText = builder.object("batch_format-combo").unwrap();
+        let formats: Vec<Format> = [
+            Format::MKVMergeText,
+            Format::CueSheet,
+            Format::WebVtt,
+            Format::Srt,
+            Format::Description,
+            Format::PodcastChapters,
+            Format::FFMetadata,
+            Format::Id3v2,
+            Format::Matroska,
+            Format::Flac,
+            Format::Wave,
+            Format::Opus,
+            Format::Vorbis,
+            Format::MP3,
+        ]
+        .into_iter()
+        .filter(|format| match Job::check_requirements(*format) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("{}", err);
+                false
+            }
+        })
+        .collect();
+        for format in formats.iter() {
+            format_combo.append_text(&format_label(*format));
+        }
+        format_combo.set_active(Some(0));
+
+        let output_dir_chooser: gtk::FileChooserButton =
+            builder.object("batch_output_dir-chooser").unwrap();
+        if let Some(output_dir) = CONFIG.read().unwrap().preferences.output_dir.as_ref() {
+            output_dir_chooser.set_filename(output_dir);
+        }
+
+        dialog.connect_delete_event(|dialog, _| {
+            dialog.hide();
+            Inhibit(true)
+        });
+        dialog.connect_response(|dialog, _| dialog.hide());
+
+        let media_dlg = gtk::FileChooserNative::builder()
+            .title(&gettext("Add media files"))
+            .transient_for(&dialog)
+            .modal(true)
+            .accept_label(&gettext("Add"))
+            .cancel_label(&gettext("Cancel"))
+            .build();
+        media_dlg.set_select_multiple(true);
+
+        let toc_dlg = gtk::FileChooserNative::builder()
+            .title(&gettext("Select a table of contents file"))
+            .transient_for(&dialog)
+            .modal(true)
+            .accept_label(&gettext("Select"))
+            .cancel_label(&gettext("Cancel"))
+            .build();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Table of contents")));
+        for (extension, _) in Factory::extensions() {
+            filter.add_pattern(&format!("*.{}", extension));
+        }
+        toc_dlg.add_filter(&filter);
+
+        Controller {
+            dialog,
+            treeview,
+            store: builder.object("batch-liststore").unwrap(),
+
+            formats,
+            format_combo,
+            output_dir_chooser,
+            max_parallel_spin: builder.object("batch_max_parallel-spin").unwrap(),
+            overwrite_btn: builder.object("batch_overwrite-check").unwrap(),
+
+            add_btn: builder.object("batch_add-btn").unwrap(),
+            toc_btn: builder.object("batch_toc-btn").unwrap(),
+            remove_btn: builder.object("batch_remove-btn").unwrap(),
+            clear_btn: builder.object("batch_clear-btn").unwrap(),
+            start_btn: builder.object("batch_start-btn").unwrap(),
+
+            media_dlg,
+            toc_dlg,
+
+            jobs: Vec::new(),
+            statuses: Vec::new(),
+            is_busy: false,
+            abort_handle: None,
+        }
+    }
+
+    fn add_text_column(treeview: &gtk::TreeView, title: &str, col_id: u32, can_expand: bool) {
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(title);
+        col.set_resizable(true);
+        col.set_expand(can_expand);
+
+        let renderer = gtk::CellRendererText::new();
+        renderer.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        CellLayoutExt::pack_start(&col, &renderer, true);
+        CellLayoutExt::add_attribute(&col, &renderer, "text", col_id as i32);
+
+        treeview.append_column(&col);
+    }
+
+    fn add_progress_column(treeview: &gtk::TreeView, title: &str) {
+        let col = gtk::TreeViewColumn::new();
+        col.set_title(title);
+        col.set_min_width(120);
+
+        let renderer = gtk::CellRendererProgress::new();
+        CellLayoutExt::pack_start(&col, &renderer, true);
+        CellLayoutExt::add_attribute(&col, &renderer, "value", PROGRESS_COL as i32);
+        CellLayoutExt::add_attribute(&col, &renderer, "text", STATUS_COL as i32);
+
+        treeview.append_column(&col);
+    }
+
+    pub fn present(&self) {
+        self.dialog.present();
+    }
+
+    pub fn add_media(&mut self, paths: Vec<PathBuf>) {
+        let format = match self.format_combo.active() {
+            Some(idx) => self.formats[idx as usize],
+            None => return,
+        };
+        let output_dir = self.output_dir_chooser.filename();
+
+        for path in paths {
+            let media_name = path
+                .file_name()
+                .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+                .to_string();
+            let iter = self.store.insert_with_values(
+                None,
+                &[
+                    (MEDIA_COL, &media_name),
+                    (TOC_COL, &gettext("automatic")),
+                    (FORMAT_COL, &format_label(format)),
+                ],
+            );
+
+            let job = Job {
+                output_dir: output_dir.clone(),
+                ..Job::new(path, format)
+            };
+            self.jobs.push(job);
+            self.statuses.push(JobStatus::Pending);
+            self.update_row(&iter, self.jobs.len() - 1);
+        }
+
+        self.start_btn.set_sensitive(!self.jobs.is_empty());
+    }
+
+    /// Uses the table of contents at `path` for the selected jobs.
+    pub fn set_toc(&mut self, path: PathBuf) {
+        let toc_name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            .to_string();
+
+        for idx in self.selected_indices() {
+            self.jobs[idx].toc_path = Some(path.clone());
+            self.statuses[idx] = JobStatus::Pending;
+
+            let iter = self.iter(idx);
+            self.store.set(&iter, &[(TOC_COL, &toc_name)]);
+            self.update_row(&iter, idx);
+        }
+    }
+
+    pub fn remove_selected(&mut self) {
+        // Remove from the last so that the indices remain valid
+        let mut indices = self.selected_indices();
+        indices.sort_unstable();
+        for idx in indices.into_iter().rev() {
+            self.store.remove(&self.iter(idx));
+            self.jobs.remove(idx);
+            self.statuses.remove(idx);
+        }
+
+        self.start_btn.set_sensitive(!self.jobs.is_empty());
+    }
+
+    pub fn clear_finished(&mut self) {
+        for idx in (0..self.jobs.len()).rev() {
+            if self.statuses[idx].is_done() {
+                self.store.remove(&self.iter(idx));
+                self.jobs.remove(idx);
+                self.statuses.remove(idx);
+            }
+        }
+
+        self.start_btn.set_sensitive(!self.jobs.is_empty());
+    }
+
+    /// Runs the jobs which are not done yet.
+    pub fn start(&mut self) {
+        let overwrite = self.overwrite_btn.is_active();
+        let jobs: Vec<(usize, Job)> = self
+            .jobs
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.statuses[*idx].is_done())
+            .map(|(idx, job)| {
+                (
+                    idx,
+                    Job {
+                        overwrite,
+                        ..job.clone()
+                    },
+                )
+            })
+            .collect();
+
+        if jobs.is_empty() {
+            return;
+        }
+
+        for (idx, _) in jobs.iter() {
+            self.job_updated(JobEvent {
+                idx: *idx,
+                status: JobStatus::Pending,
+            });
+        }
+
+        self.switch_to_busy();
+
+        let (sender, receiver) = async_mpsc::unbounded();
+        let max_parallel = self.max_parallel_spin.value_as_int() as usize;
+        let (run, abort_handle) = abortable(media::batch::run(jobs, max_parallel, sender));
+        self.abort_handle = Some(abort_handle);
+
+        spawn(async move {
+            let forward = receiver.for_each(|event| {
+                batch::job_updated(event);
+                future::ready(())
+            });
+            let _ = future::join(run, forward).await;
+
+            batch::queue_over();
+        });
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(abort_handle) = self.abort_handle.take() {
+            abort_handle.abort();
+        }
+    }
+
+    pub fn job_updated(&mut self, event: JobEvent) {
+        if event.idx >= self.jobs.len() {
+            return;
+        }
+
+        self.statuses[event.idx] = event.status;
+        self.update_row(&self.iter(event.idx), event.idx);
+    }
+
+    pub fn queue_over(&mut self) {
+        self.switch_to_available();
+
+        let failed = self
+            .statuses
+            .iter()
+            .filter(|status| matches!(status, JobStatus::Failed(_)))
+            .count();
+        if failed > 0 {
+            info_bar::show_error(
+                ngettext("{} batch job failed", "{} batch jobs failed", failed as u32).replacen(
+                    "{}",
+                    &failed.to_string(),
+                    1,
+                ),
+            );
+        } else if self.statuses.iter().all(JobStatus::is_done) {
+            info_bar::show_info(gettext("Batch processing completed succesfully"));
+        }
+    }
+
+    fn update_row(&self, iter: &gtk::TreeIter, idx: usize) {
+        let job = &self.jobs[idx];
+        let (progress, status, details) = match &self.statuses[idx] {
+            JobStatus::Pending => (0f64, gettext("Pending"), None),
+            JobStatus::Running(progress) => (*progress, gettext("Running"), None),
+            JobStatus::Done => (1f64, gettext("Done"), None),
+            JobStatus::Failed(err) => (0f64, gettext("Failed"), Some(err.clone())),
+            JobStatus::Canceled => (0f64, gettext("Canceled"), None),
+        };
+
+        let mut tooltip = job.media_path.to_string_lossy().to_string();
+        if let Some(toc_path) = job.toc_path.as_ref() {
+            tooltip += &format!("\n{}", toc_path.to_string_lossy());
+        }
+        if let Some(details) = details {
+            tooltip += &format!("\n\n{}", details);
+        }
+
+        self.store.set(
+            iter,
+            &[
+                (
+                    PROGRESS_COL,
+                    &((progress.clamp(0f64, 1f64) * 100f64) as i32),
+                ),
+                (STATUS_COL, &status),
+                (TOOLTIP_COL, &glib::markup_escape_text(&tooltip).as_str()),
+            ],
+        );
+    }
+
+    fn iter(&self, idx: usize) -> gtk::TreeIter {
+        self.store
+            .iter_nth_child(None, idx as i32)
+            .expect("inconsistent batch job index")
+    }
+
+    fn selected_indices(&self) -> Vec<usize> {
+        let (paths, _) = self.treeview.selection().selected_rows();
+        paths
+            .iter()
+            .filter_map(|path| path.indices().first().map(|idx| *idx as usize))
+            .collect()
+    }
+
+    fn switch_to_busy(&mut self) {
+        self.is_busy = true;
+
+        self.start_btn.set_label(&gettext("Cancel"));
+        self.set_edition_sensitive(false);
+    }
+
+    fn switch_to_available(&mut self) {
+        self.abort_handle = None;
+        self.is_busy = false;
+
+        self.start_btn.set_label(&gettext("Start"));
+        self.set_edition_sensitive(true);
+    }
+
+    fn set_edition_sensitive(&self, is_sensitive: bool) {
+        self.add_btn.set_sensitive(is_sensitive);
+        self.toc_btn.set_sensitive(is_sensitive);
+        self.remove_btn.set_sensitive(is_sensitive);
+        self.clear_btn.set_sensitive(is_sensitive);
+        self.max_parallel_spin.set_sensitive(is_sensitive);
+        self.overwrite_btn.set_sensitive(is_sensitive);
+    }
+}
+
+impl UIController for Controller {
+    fn cleanup(&mut self) {
+        // The batch doesn't depend on the media being played
+    }
+}
@@ -0,0 +1,77 @@
+use futures::{
+    future::{self, LocalBoxFuture},
+    prelude::*,
+};
+
+use gtk::prelude::*;
+
+use log::debug;
+
+use crate::{batch, main_panel, prelude::*};
+
+pub struct Dispatcher;
+
+impl UIDispatcher for Dispatcher {
+    type Controller = batch::Controller;
+    type Event = batch::Event;
+
+    fn setup(ctrl: &mut batch::Controller, _app: &gtk::Application) {
+        ctrl.add_btn.connect_clicked(|_| batch::select_media());
+        ctrl.toc_btn.connect_clicked(|_| batch::select_toc());
+        ctrl.remove_btn
+            .connect_clicked(|_| batch::remove_selected());
+        ctrl.clear_btn.connect_clicked(|_| batch::clear_finished());
+        ctrl.start_btn.connect_clicked(|_| batch::trigger_action());
+
+        ctrl.media_dlg.connect_response(|media_dlg, response| {
+            media_dlg.hide();
+            if response == gtk::ResponseType::Accept {
+                let paths = media_dlg
+                    .filenames()
+                    .into_iter()
+                    .filter(|path| path.is_file())
+                    .collect::<Vec<_>>();
+                if !paths.is_empty() {
+                    batch::add_media(paths);
+                }
+            }
+        });
+
+        ctrl.toc_dlg.connect_response(|toc_dlg, response| {
+            toc_dlg.hide();
+            if let (gtk::ResponseType::Accept, Some(path)) = (response, toc_dlg.filename()) {
+                batch::set_toc(path);
+            }
+        });
+    }
+
+    fn handle_event(
+        main_ctrl: &mut main_panel::Controller,
+        event: impl Into<Self::Event>,
+    ) -> LocalBoxFuture<'_, ()> {
+        use batch::Event::*;
+
+        let event = event.into();
+        debug!("handling {:?}", event);
+        match event {
+            AddMedia(paths) => main_ctrl.batch.add_media(paths),
+            ClearFinished => main_ctrl.batch.clear_finished(),
+            JobUpdated(event) => main_ctrl.batch.job_updated(event),
+            QueueOver => main_ctrl.batch.queue_over(),
+            RemoveSelected => main_ctrl.batch.remove_selected(),
+            SelectMedia => main_ctrl.batch.media_dlg.show(),
+            SelectToc => main_ctrl.batch.toc_dlg.show(),
+            SetToc(path) => main_ctrl.batch.set_toc(path),
+            Show => main_ctrl.batch.present(),
+            TriggerAction => {
+                if main_ctrl.batch.is_busy {
+                    main_ctrl.batch.cancel();
+                } else {
+                    main_ctrl.batch.start();
+                }
+            }
+        }
+
+        future::ready(()).boxed_local()
+    }
+}
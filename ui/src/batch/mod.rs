@@ -0,0 +1,65 @@
+mod controller;
+pub use self::controller::{format_label, Controller};
+
+mod dispatcher;
+pub use self::dispatcher::Dispatcher;
+
+use std::path::PathBuf;
+
+use media::batch::JobEvent;
+
+use crate::UIEventChannel;
+
+#[derive(Debug)]
+pub enum Event {
+    AddMedia(Vec<PathBuf>),
+    ClearFinished,
+    JobUpdated(JobEvent),
+    QueueOver,
+    RemoveSelected,
+    SelectMedia,
+    SelectToc,
+    SetToc(PathBuf),
+    Show,
+    TriggerAction,
+}
+
+fn add_media(paths: Vec<PathBuf>) {
+    UIEventChannel::send(Event::AddMedia(paths));
+}
+
+fn clear_finished() {
+    UIEventChannel::send(Event::ClearFinished);
+}
+
+fn job_updated(event: JobEvent) {
+    UIEventChannel::send(Event::JobUpdated(event));
+}
+
+fn queue_over() {
+    UIEventChannel::send(Event::QueueOver);
+}
+
+fn remove_selected() {
+    UIEventChannel::send(Event::RemoveSelected);
+}
+
+fn select_media() {
+    UIEventChannel::send(Event::SelectMedia);
+}
+
+fn select_toc() {
+    UIEventChannel::send(Event::SelectToc);
+}
+
+fn set_toc(path: PathBuf) {
+    UIEventChannel::send(Event::SetToc(path));
+}
+
+pub fn show() {
+    UIEventChannel::send(Event::Show);
+}
+
+fn trigger_action() {
+    UIEventChannel::send(Event::TriggerAction);
+}
@@ -37,6 +37,12 @@ pub struct ControllerImpl {
     mkvmerge_txt_row: gtk::ListBoxRow,
     mkvmerge_txt_warning_lbl: gtk::Label,
     cue_row: gtk::ListBoxRow,
+    web_vtt_row: gtk::ListBoxRow,
+    srt_row: gtk::ListBoxRow,
+    description_row: gtk::ListBoxRow,
+    podcast_chapters_row: gtk::ListBoxRow,
+    ffmetadata_row: gtk::ListBoxRow,
+    id3v2_row: gtk::ListBoxRow,
     mkv_row: gtk::ListBoxRow,
 
     export_btn: gtk::Button,
@@ -52,15 +58,9 @@ impl OutputControllerImpl for ControllerImpl {
     const PROGRESS_BAR_NAME: &'static str = "export-progress";
 
     fn new_processor(&self) -> Processor {
-        let format = if self.mkvmerge_txt_row.is_selected() {
-            Format::MKVMergeText
-        } else if self.cue_row.is_selected() {
-            Format::CueSheet
-        } else if self.mkv_row.is_selected() {
-            Format::Matroska
-        } else {
-            unreachable!("export::ControllerImpl::get_selected_format unknown export type");
-        };
+        let format = self
+            .selected_format()
+            .expect("export::ControllerImpl::selected_format unknown export type");
 
         Processor {
             src_info: Arc::clone(self.src_info.as_ref().unwrap()),
@@ -72,11 +72,35 @@ impl OutputControllerImpl for ControllerImpl {
             toc_setter_pipeline: None,
         }
     }
+
+    fn selected_format(&self) -> Option<Format> {
+        self.format_rows()
+            .into_iter()
+            .find(|(_, row)| row.is_selected())
+            .map(|(format, _)| format)
+    }
+
+    fn select_format(&self, format: Format) {
+        if let Some((_, row)) = self
+            .format_rows()
+            .into_iter()
+            .find(|(row_format, row)| *row_format == format && row.is_sensitive())
+        {
+            self.export_list.select_row(Some(row));
+        }
+    }
 }
 
 impl UIController for ControllerImpl {
     fn new_media(&mut self, pipeline: &pipeline::Playback) {
         self.src_info = Some(Arc::clone(&pipeline.info));
+
+        // Chapters can only be added to the ID3v2 tag of an mp3 file
+        let is_mp3 = pipeline.info.read().unwrap().is_mp3();
+        self.id3v2_row.set_sensitive(is_mp3);
+        if !is_mp3 && self.id3v2_row.is_selected() {
+            self.export_list.select_row(Some(&self.mkvmerge_txt_row));
+        }
     }
 
     fn cleanup(&mut self) {
@@ -93,6 +117,12 @@ impl ControllerImpl {
             mkvmerge_txt_row: builder.object("mkvmerge_text_export-row").unwrap(),
             mkvmerge_txt_warning_lbl: builder.object("mkvmerge_text_warning-lbl").unwrap(),
             cue_row: builder.object("cue_sheet_export-row").unwrap(),
+            web_vtt_row: builder.object("web_vtt_export-row").unwrap(),
+            srt_row: builder.object("srt_export-row").unwrap(),
+            description_row: builder.object("description_export-row").unwrap(),
+            podcast_chapters_row: builder.object("podcast_chapters_export-row").unwrap(),
+            ffmetadata_row: builder.object("ffmetadata_export-row").unwrap(),
+            id3v2_row: builder.object("id3v2_export-row").unwrap(),
             mkv_row: builder.object("matroska_export-row").unwrap(),
 
             export_btn: builder.object(Self::BTN_NAME).unwrap(),
@@ -111,6 +141,20 @@ impl ControllerImpl {
 
         ctrl
     }
+
+    fn format_rows(&self) -> [(Format, &gtk::ListBoxRow); 9] {
+        [
+            (Format::MKVMergeText, &self.mkvmerge_txt_row),
+            (Format::CueSheet, &self.cue_row),
+            (Format::WebVtt, &self.web_vtt_row),
+            (Format::Srt, &self.srt_row),
+            (Format::Description, &self.description_row),
+            (Format::PodcastChapters, &self.podcast_chapters_row),
+            (Format::FFMetadata, &self.ffmetadata_row),
+            (Format::Id3v2, &self.id3v2_row),
+            (Format::Matroska, &self.mkv_row),
+        ]
+    }
 }
 
 pub struct Processor {
@@ -140,7 +184,14 @@ impl MediaProcessorImpl for Processor {
     fn process(&mut self, output_path: &Path) -> Result<ProcessingType, MediaProcessorError> {
         let format = self.export_file_info.as_ref().unwrap().format;
         match format {
-            Format::MKVMergeText | Format::CueSheet => {
+            Format::MKVMergeText
+            | Format::CueSheet
+            | Format::Description
+            | Format::FFMetadata
+            | Format::Id3v2
+            | Format::PodcastChapters
+            | Format::Srt
+            | Format::WebVtt => {
                 self.export_file_info = None;
 
                 let src_info = self.src_info.read().unwrap();
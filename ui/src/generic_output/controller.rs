@@ -16,7 +16,7 @@ use std::{
     time::Duration,
 };
 
-use application::gettext;
+use application::{gettext, CONFIG};
 use media::{pipeline, MediaEvent};
 use metadata::{Format, MediaInfo};
 
@@ -243,6 +243,15 @@ pub trait OutputControllerImpl: UIController {
     const PROGRESS_BAR_NAME: &'static str;
 
     fn new_processor(&self) -> Self::MediaProcessorImplType;
+
+    fn selected_format(&self) -> Option<Format>;
+    /// Selects `format` if it is available.
+    fn select_format(&self, format: Format);
+
+    /// Notifies the excerpt to export, if any, before the processor is created.
+    ///
+    /// The excerpt is the region selected on the waveform or the selected chapter.
+    fn set_excerpt(&mut self, _excerpt: Option<gst::TocEntry>) {}
 }
 
 pub struct OutputMediaFileInfo {
@@ -258,12 +267,24 @@ impl OutputMediaFileInfo {
         let extension = metadata::Factory::extension(format, content).to_owned();
 
         OutputMediaFileInfo {
-            path: src_info.path.with_extension(&extension).into(),
+            path: Self::output_path(src_info, &extension),
             extension,
             format,
             stream_ids: Arc::new(RwLock::new(stream_ids)),
         }
     }
+
+    /// Returns the path of the output for `src_info` with the given `extension`.
+    pub fn output_path(src_info: &MediaInfo, extension: &str) -> Rc<Path> {
+        let mut path = src_info.path.with_extension(extension);
+        if let Some(output_dir) = CONFIG.read().unwrap().preferences.output_dir.as_ref() {
+            if let Some(file_name) = path.file_name() {
+                path = output_dir.join(file_name);
+            }
+        }
+
+        path.into()
+    }
 }
 
 pub struct Controller<Impl> {
@@ -318,6 +339,14 @@ impl<Impl: OutputControllerImpl + 'static> Controller<Impl> {
         );
     }
 
+    pub fn selected_format(&self) -> Option<Format> {
+        self.impl_.selected_format()
+    }
+
+    pub fn select_format(&self, format: Format) {
+        self.impl_.select_format(format);
+    }
+
     pub fn cancel(&mut self) {
         if let Some(abort_handle) = self.processor_abort_handle.take() {
             abort_handle.abort();
@@ -61,6 +61,18 @@ impl<T: OutputDispatcher> UIDispatcher for T {
                         main_ctrl
                             .info
                             .export_chapters(&mut pipeline.info.write().unwrap());
+
+                        let chapter_manager = &main_ctrl.info.chapter_manager;
+                        let excerpt = match main_ctrl.audio.region() {
+                            Some((start, end)) => {
+                                Some(chapter_manager.range_as_toc_entry(start, end))
+                            }
+                            None => chapter_manager
+                                .selected()
+                                .map(|chapter| chapter.as_toc_entry()),
+                        };
+                        T::ctrl_mut(main_ctrl).impl_.set_excerpt(excerpt);
+
                         return T::ctrl_mut(main_ctrl).start().boxed_local();
                     }
                 } else {
@@ -1,5 +1,6 @@
 use bitflags::bitflags;
 use gtk::{
+    gdk, gdk_pixbuf,
     glib::{self, GString},
     prelude::*,
 };
@@ -7,16 +8,17 @@ use gtk::{
 use std::{borrow::Cow, cell::RefCell, rc::Rc, string::ToString};
 
 use application::gettext;
-use metadata::{default_chapter_title, Duration, Timestamp4Humans, TocVisitor};
-use renderers::Timestamp;
+use metadata::{chapter_title, default_chapter_title, Duration, Timestamp4Humans, TocVisitor};
+use renderers::{Image, Timestamp};
 
-use super::{ChapterTimestamps, ChaptersBoundaries};
+use super::{ChapterEdge, ChapterTimestamps, ChaptersBoundaries};
 
 const START_COL: u32 = 0;
 const END_COL: u32 = 1;
 const TITLE_COL: u32 = 2;
 const START_STR_COL: u32 = 3;
 const END_STR_COL: u32 = 4;
+const THUMBNAIL_COL: u32 = 5;
 
 pub struct ChapterIterStart {
     pub iter: gtk::TreeIter,
@@ -86,6 +88,11 @@ impl<'entry> ChapterEntry<'entry> {
             .set_value(&self.iter, TITLE_COL, &glib::Value::from(title));
     }
 
+    fn set_thumbnail(&self, thumbnail: Option<gdk_pixbuf::Pixbuf>) {
+        self.store
+            .set_value(&self.iter, THUMBNAIL_COL, &thumbnail.to_value());
+    }
+
     pub fn start(&self) -> Timestamp {
         self.store
             .value(&self.iter, START_COL as i32)
@@ -332,31 +339,44 @@ impl ChapterTree {
             }
             None => {
                 match self.iter_timestamps() {
-                    Some(prev_ts) => {
+                    Some(iter_ts) if target < iter_ts.start => {
                         // chapters are available, but none is selected:
-                        // either position is before the first chapter
-                        // or in a gap between two chapters
-                        if target > prev_ts.start {
+                        // position is before the chapter in iter
+                        let new_iter = self.store.insert_before(
+                            None,
+                            Some(self.iter.as_ref().expect("inconsistency with iter")),
+                        );
+
+                        // iter_start is the new chapter's end
+                        (
+                            new_iter,
+                            iter_ts.start,
+                            iter_ts.start.for_humans().to_string(),
+                        )
+                    }
+                    Some(iter_ts) => {
+                        if target < iter_ts.end {
                             panic!(
                                 concat!(
                                     "ChapterTree::add_chapter inconsistent target",
                                     " {} with regard to current iter [{}, {}]",
                                 ),
-                                target, prev_ts.start, prev_ts.end,
+                                target, iter_ts.start, iter_ts.end,
                             );
                         }
 
-                        let new_iter = self.store.insert_before(
-                            None,
-                            Some(self.iter.as_ref().expect("inconsistency with iter")),
-                        );
+                        // position is in a gap after the chapter in iter
+                        // or after the last chapter
+                        let iter = self.iter.expect("inconsistency with iter");
+                        let next_iter = iter;
+                        let end = if self.store.iter_next(&next_iter) {
+                            ChapterEntry::new(&self.store, &next_iter).start()
+                        } else {
+                            duration.into()
+                        };
 
-                        // prev_start is the new chapter's end
-                        (
-                            new_iter,
-                            prev_ts.start,
-                            prev_ts.start.for_humans().to_string(),
-                        )
+                        let new_iter = self.store.insert_after(None, Some(&iter));
+                        (new_iter, end, end.for_humans().to_string())
                     }
                     None => {
                         // No chapter in iter:
@@ -423,7 +443,10 @@ impl ChapterTree {
                 let found_previous = self
                     .store
                     .iter_previous(self.iter.as_ref().expect("inconsistency with iter"));
-                if found_previous {
+                // A previous chapter followed by a gap or an overlap keeps its end
+                let is_previous_adjacent = found_previous
+                    && self.iter_timestamps().expect("inconsistency with iter").end == rem_ts.start;
+                if is_previous_adjacent {
                     self.store.set(
                         self.iter.as_ref().expect("inconsistency with iter"),
                         &[
@@ -513,6 +536,45 @@ impl ChapterTree {
         }
     }
 
+    /// Returns the exclusive range in which the edges of `prev` and `next` can be moved.
+    ///
+    /// The chapters must keep their order and can only overlap their neighbours.
+    fn move_range(
+        &self,
+        prev: &Option<ChapterIterStart>,
+        next: &Option<ChapterIterEnd>,
+    ) -> (Option<Timestamp>, Option<Timestamp>) {
+        let mut lower = None;
+        let mut upper = None;
+
+        if let Some(prev) = prev {
+            lower = Some(prev.start);
+
+            let after_prev = prev.iter;
+            if self.store.iter_next(&after_prev) {
+                upper = Some(ChapterEntry::new(&self.store, &after_prev).end());
+            }
+        }
+
+        if let Some(next) = next {
+            upper = Some(upper.map_or(next.end, |upper| upper.min(next.end)));
+
+            let before_next = next.iter;
+            if self.store.iter_previous(&before_next) {
+                let before_start = ChapterEntry::new(&self.store, &before_next).start();
+                lower = Some(lower.map_or(before_start, |lower| lower.max(before_start)));
+            }
+
+            let after_next = next.iter;
+            if self.store.iter_next(&after_next) {
+                let after_start = ChapterEntry::new(&self.store, &after_next).start();
+                upper = Some(upper.map_or(after_start, |upper| upper.min(after_start)));
+            }
+        }
+
+        (lower, upper)
+    }
+
     fn move_boundary(
         &self,
         target: Timestamp,
@@ -538,6 +600,11 @@ impl ChapterTree {
 pub struct ChapterTreeManager {
     tree: ChapterTree,
     pub title_renderer: Option<gtk::CellRendererText>,
+    pub start_renderer: Option<gtk::CellRendererText>,
+    pub end_renderer: Option<gtk::CellRendererText>,
+    start_col: Option<gtk::TreeViewColumn>,
+    end_col: Option<gtk::TreeViewColumn>,
+    thumbnail_col: Option<gtk::TreeViewColumn>,
     boundaries: Rc<RefCell<ChaptersBoundaries>>,
 }
 
@@ -546,25 +613,57 @@ impl ChapterTreeManager {
         ChapterTreeManager {
             tree: ChapterTree::new(store),
             title_renderer: None,
+            start_renderer: None,
+            end_renderer: None,
+            start_col: None,
+            end_col: None,
+            thumbnail_col: None,
             boundaries,
         }
     }
 
     pub fn init_treeview(&mut self, treeview: &gtk::TreeView) {
         treeview.set_model(Some(self.tree.store()));
-        self.title_renderer = Some(self.add_column(
+
+        // Thumbnails are only relevant for video media
+        let thumbnail_col = gtk::TreeViewColumn::new();
+        let thumbnail_renderer = gtk::CellRendererPixbuf::new();
+        CellLayoutExt::pack_start(&thumbnail_col, &thumbnail_renderer, false);
+        CellLayoutExt::add_attribute(
+            &thumbnail_col,
+            &thumbnail_renderer,
+            "pixbuf",
+            THUMBNAIL_COL as i32,
+        );
+        thumbnail_col.set_visible(false);
+        treeview.append_column(&thumbnail_col);
+        self.thumbnail_col = Some(thumbnail_col);
+
+        let (_, title_renderer) = self.add_column(
             treeview,
             &gettext("Title"),
             TITLE_COL,
             ColumnOptions::CAN_EXPAND | ColumnOptions::IS_EDITABLE,
-        ));
-        self.add_column(
+        );
+        self.title_renderer = Some(title_renderer);
+
+        let (start_col, start_renderer) = self.add_column(
             treeview,
             &gettext("Start"),
             START_STR_COL,
-            ColumnOptions::NONE,
+            ColumnOptions::IS_EDITABLE,
+        );
+        self.start_col = Some(start_col);
+        self.start_renderer = Some(start_renderer);
+
+        let (end_col, end_renderer) = self.add_column(
+            treeview,
+            &gettext("End"),
+            END_STR_COL,
+            ColumnOptions::IS_EDITABLE,
         );
-        self.add_column(treeview, &gettext("End"), END_STR_COL, ColumnOptions::NONE);
+        self.end_col = Some(end_col);
+        self.end_renderer = Some(end_renderer);
     }
 
     fn add_column(
@@ -573,7 +672,7 @@ impl ChapterTreeManager {
         title: &str,
         col_id: u32,
         options: ColumnOptions,
-    ) -> gtk::CellRendererText {
+    ) -> (gtk::TreeViewColumn, gtk::CellRendererText) {
         let col = gtk::TreeViewColumn::new();
         col.set_title(title);
 
@@ -591,13 +690,40 @@ impl ChapterTreeManager {
         }
         treeview.append_column(&col);
 
-        renderer
+        (col, renderer)
     }
 
     pub fn selected(&self) -> Option<ChapterEntry<'_>> {
         self.tree.selected_chapter()
     }
 
+    /// Returns an entry for the range `[start, end]`, e.g. to export an excerpt.
+    ///
+    /// The range is titled after the chapter it starts in, if any.
+    pub fn range_as_toc_entry(&self, start: Timestamp, end: Timestamp) -> gst::TocEntry {
+        let mut toc_entry =
+            gst::TocEntry::new(gst::TocEntryType::Chapter, &format!("{}", start.as_u64()));
+        toc_entry
+            .get_mut()
+            .unwrap()
+            .set_start_stop_times(start.as_i64(), end.as_i64());
+
+        let title = self
+            .iter()
+            .find(|chapter| chapter.start() <= start && start < chapter.end())
+            .map(|chapter| chapter.title());
+        if let Some(title) = title {
+            let mut tag_list = gst::TagList::new();
+            tag_list
+                .get_mut()
+                .unwrap()
+                .add::<gst::tags::Title>(&title.as_str(), gst::TagMergeMode::Replace);
+            toc_entry.get_mut().unwrap().set_tags(tag_list);
+        }
+
+        toc_entry
+    }
+
     pub fn selected_path(&self) -> Option<gtk::TreePath> {
         self.tree.selected_path()
     }
@@ -610,6 +736,69 @@ impl ChapterTreeManager {
         self.tree.unselect();
     }
 
+    pub fn selected_boundary(&self) -> Option<Timestamp> {
+        self.boundaries.borrow().selected()
+    }
+
+    /// Selects the boundary after the selected one or after `ts` if none is selected.
+    pub fn select_next_boundary(&mut self, ts: Timestamp) -> Option<Timestamp> {
+        self.boundaries.borrow_mut().select_next(ts)
+    }
+
+    /// Selects the boundary before the selected one or before `ts` if none is selected.
+    pub fn select_previous_boundary(&mut self, ts: Timestamp) -> Option<Timestamp> {
+        self.boundaries.borrow_mut().select_previous(ts)
+    }
+
+    pub fn unselect_boundary(&mut self) {
+        self.boundaries.borrow_mut().unselect();
+    }
+
+    /// Returns the path and column of the cell displaying `boundary`.
+    ///
+    /// The start cells are preferred, so an end cell is only used for the last chapter.
+    pub fn boundary_cell(
+        &self,
+        boundary: Timestamp,
+    ) -> Option<(gtk::TreePath, gtk::TreeViewColumn)> {
+        let (iter, col) = match self.iter().find(|chapter| chapter.start() == boundary) {
+            Some(chapter) => (*chapter.iter(), self.start_col.as_ref()?),
+            None => {
+                let chapter = self.iter().find(|chapter| chapter.end() == boundary)?;
+                (*chapter.iter(), self.end_col.as_ref()?)
+            }
+        };
+
+        Some((self.tree.store().path(&iter)?, col.clone()))
+    }
+
+    pub fn show_thumbnails(&self, must_show: bool) {
+        if let Some(thumbnail_col) = self.thumbnail_col.as_ref() {
+            thumbnail_col.set_visible(must_show);
+        }
+    }
+
+    /// Sets the thumbnail of the chapter starting at `start`, if any.
+    pub fn set_thumbnail(&self, start: Timestamp, thumbnail: &Image) {
+        let chapter = match self.iter().find(|chapter| chapter.start() == start) {
+            Some(chapter) => chapter,
+            // The chapter was removed or its start was moved in the meantime
+            None => return,
+        };
+
+        let mut pixbuf = None;
+        thumbnail.with_surface(|surface| {
+            pixbuf =
+                gdk::pixbuf_get_from_surface(surface, 0, 0, thumbnail.width(), thumbnail.height());
+        });
+
+        chapter.set_thumbnail(pixbuf);
+    }
+
+    pub fn clear_thumbnails(&self) {
+        self.iter().for_each(|chapter| chapter.set_thumbnail(None));
+    }
+
     pub fn clear(&mut self) {
         self.tree.clear();
         self.boundaries.borrow_mut().clear();
@@ -639,13 +828,7 @@ impl ChapterTreeManager {
                 if let Some((start, end)) = chapter.start_stop_times() {
                     let ts = ChapterTimestamps::new_from_u64(start as u64, end as u64);
 
-                    let title = chapter
-                        .tags()
-                        .and_then(|tags| {
-                            tags.get::<gst::tags::Title>()
-                                .map(|tag| tag.get().to_string())
-                        })
-                        .unwrap_or_else(default_chapter_title);
+                    let title = chapter_title(&chapter);
 
                     let iter = self.tree.add_unchecked(ts, &title);
                     self.boundaries.borrow_mut().add_chapter(ts, title, iter);
@@ -687,63 +870,93 @@ impl ChapterTreeManager {
         })
     }
 
+    fn chapters_at(
+        &self,
+        boundary: Timestamp,
+    ) -> (Option<ChapterIterStart>, Option<ChapterIterEnd>) {
+        let boundaries = self.boundaries.borrow();
+        boundaries.get(&boundary).map_or((None, None), |chapters| {
+            (
+                chapters.prev.as_ref().map(|prev| ChapterIterStart {
+                    iter: prev.iter,
+                    start: ChapterEntry::new(self.tree.store(), &prev.iter).start(),
+                }),
+                chapters.next.as_ref().map(|next| ChapterIterEnd {
+                    iter: next.iter,
+                    end: ChapterEntry::new(self.tree.store(), &next.iter).end(),
+                }),
+            )
+        })
+    }
+
+    /// Moves the chapters edges at `boundary` to `target`.
     pub fn move_chapter_boundary(
         &mut self,
         boundary: Timestamp,
         target: Timestamp,
     ) -> PositionStatus {
-        if boundary == target {
-            return PositionStatus::ChapterNotChanged;
-        }
+        let (prev_chapter, next_chapter) = self.chapters_at(boundary);
+        self.move_edges(boundary, target, prev_chapter, next_chapter, None)
+    }
 
-        let (prev_chapter, next_chapter) = {
-            let boundaries = self.boundaries.borrow();
-            boundaries.get(&boundary).map_or((None, None), |chapters| {
-                (
-                    chapters.prev.as_ref().map(|prev| ChapterIterStart {
-                        iter: prev.iter,
-                        start: prev.ts.start,
-                    }),
-                    chapters.next.as_ref().map(|next| ChapterIterEnd {
-                        iter: next.iter,
-                        end: next.ts.end,
-                    }),
-                )
-            })
+    /// Moves the `edge` of the chapter at `boundary` to `target`.
+    ///
+    /// Contrary to [`Self::move_chapter_boundary`], the chapter which shares
+    /// the boundary, if any, is left untouched.
+    pub fn move_chapter_edge(
+        &mut self,
+        boundary: Timestamp,
+        edge: ChapterEdge,
+        target: Timestamp,
+    ) -> PositionStatus {
+        let (prev_chapter, next_chapter) = match (edge, self.chapters_at(boundary)) {
+            (ChapterEdge::Start, (_, next_chapter)) => (None, next_chapter),
+            (ChapterEdge::End, (prev_chapter, _)) => (prev_chapter, None),
         };
+        self.move_edges(boundary, target, prev_chapter, next_chapter, Some(edge))
+    }
 
-        if prev_chapter.is_none() && next_chapter.is_none() {
+    fn move_edges(
+        &mut self,
+        boundary: Timestamp,
+        target: Timestamp,
+        prev_chapter: Option<ChapterIterStart>,
+        next_chapter: Option<ChapterIterEnd>,
+        edge: Option<ChapterEdge>,
+    ) -> PositionStatus {
+        if boundary == target || (prev_chapter.is_none() && next_chapter.is_none()) {
             return PositionStatus::ChapterNotChanged;
         }
 
-        // prevent moving past previous chapter's start
-        let target = prev_chapter.as_ref().map_or(target, |prev_chapter| {
-            if target > prev_chapter.start {
-                target
-            } else {
-                boundary
-            }
-        });
+        // prevent moving past the neighbour chapters
+        let (lower, upper) = self.tree.move_range(&prev_chapter, &next_chapter);
+        if lower.map_or(false, |lower| target <= lower)
+            || upper.map_or(false, |upper| target >= upper)
+        {
+            return PositionStatus::ChapterNotChanged;
+        }
 
-        // prevent moving past next chapter's end
-        let target = next_chapter.as_ref().map_or(target, |next_chapter| {
-            if target < next_chapter.end {
-                target
-            } else {
-                boundary
+        // an end can only join a start and vice versa
+        if let Some(chapters) = self.boundaries.borrow().get(&target) {
+            if (prev_chapter.is_some() && chapters.prev.is_some())
+                || (next_chapter.is_some() && chapters.next.is_some())
+            {
+                return PositionStatus::ChapterNotChanged;
             }
-        });
-
-        if target != boundary {
-            // do the actual move
-            self.tree
-                .move_boundary(target, &prev_chapter, &next_chapter);
-            self.boundaries.borrow_mut().move_boundary(boundary, target);
+        }
 
-            PositionStatus::ChapterChanged { prev_chapter }
-        } else {
-            PositionStatus::ChapterNotChanged
+        // do the actual move
+        self.tree
+            .move_boundary(target, &prev_chapter, &next_chapter);
+        match edge {
+            None => self.boundaries.borrow_mut().move_boundary(boundary, target),
+            Some(edge) => self
+                .boundaries
+                .borrow_mut()
+                .move_edge(boundary, edge, target),
         }
+
+        PositionStatus::ChapterChanged { prev_chapter }
     }
 
     // FIXME: handle hierarchical Tocs
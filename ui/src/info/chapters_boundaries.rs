@@ -1,9 +1,18 @@
 use log::debug;
 
-use std::{collections::BTreeMap, fmt, ops::Deref};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    ops::{
+        Bound::{Excluded, Included, Unbounded},
+        Deref,
+    },
+};
 
 use renderers::Timestamp;
 
+use super::ChapterEdge;
+
 #[derive(Clone, Copy, Debug)]
 pub struct ChapterTimestamps {
     pub start: Timestamp,
@@ -53,15 +62,72 @@ pub struct SuccessiveChapters {
     pub next: Option<Chapter>,
 }
 
-pub struct ChaptersBoundaries(BTreeMap<Timestamp, SuccessiveChapters>);
+/// A range which is not covered by exactly one chapter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coverage {
+    /// No chapter between two chapters, e.g. a pregap.
+    Gap,
+    /// Several chapters.
+    Overlap,
+}
+
+pub struct ChaptersBoundaries {
+    boundaries: BTreeMap<Timestamp, SuccessiveChapters>,
+    /// The boundary selected for keyboard editing, if any.
+    selected: Option<Timestamp>,
+}
 
 impl ChaptersBoundaries {
     pub fn new() -> Self {
-        ChaptersBoundaries(BTreeMap::new())
+        ChaptersBoundaries {
+            boundaries: BTreeMap::new(),
+            selected: None,
+        }
     }
 
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.boundaries.clear();
+        self.selected = None;
+    }
+
+    pub fn selected(&self) -> Option<Timestamp> {
+        self.selected
+    }
+
+    /// Selects the first boundary after the selected one.
+    ///
+    /// If no boundary is selected, selects the first boundary at or after `ts`.
+    /// Returns the newly selected boundary, if any.
+    pub fn select_next(&mut self, ts: Timestamp) -> Option<Timestamp> {
+        let next = match self.selected {
+            Some(selected) => self.boundaries.range((Excluded(selected), Unbounded)),
+            None => self.boundaries.range((Included(ts), Unbounded)),
+        }
+        .next()
+        .map(|(boundary, _)| *boundary)?;
+
+        self.selected = Some(next);
+        self.selected
+    }
+
+    /// Selects the last boundary before the selected one.
+    ///
+    /// If no boundary is selected, selects the last boundary at or before `ts`.
+    /// Returns the newly selected boundary, if any.
+    pub fn select_previous(&mut self, ts: Timestamp) -> Option<Timestamp> {
+        let previous = match self.selected {
+            Some(selected) => self.boundaries.range((Unbounded, Excluded(selected))),
+            None => self.boundaries.range((Unbounded, Included(ts))),
+        }
+        .next_back()
+        .map(|(boundary, _)| *boundary)?;
+
+        self.selected = Some(previous);
+        self.selected
+    }
+
+    pub fn unselect(&mut self) {
+        self.selected = None;
     }
 
     pub fn add_chapter<Title>(&mut self, ts: ChapterTimestamps, title: Title, iter: gtk::TreeIter)
@@ -73,7 +139,7 @@ impl ChaptersBoundaries {
 
         // the chapter to add can share at most one boundary with a previous chapter
         let (found_start, prev_next_chapter) =
-            self.0
+            self.boundaries
                 .get_mut(&ts.start)
                 .map_or((false, None), |boundary_at_start| {
                     let prev_next_chapter = boundary_at_start.next.take();
@@ -86,16 +152,24 @@ impl ChaptersBoundaries {
                 });
 
         if found_start {
-            self.0.insert(
-                ts.end,
-                SuccessiveChapters {
-                    prev: Some(Chapter { title, ts, iter }),
-                    next: prev_next_chapter,
-                },
-            );
+            match self.boundaries.get_mut(&ts.end) {
+                Some(chapters_at_end) => {
+                    // the chapter fills a gap up to the start of next chapter
+                    chapters_at_end.prev = Some(Chapter { title, ts, iter });
+                }
+                None => {
+                    self.boundaries.insert(
+                        ts.end,
+                        SuccessiveChapters {
+                            prev: Some(Chapter { title, ts, iter }),
+                            next: prev_next_chapter,
+                        },
+                    );
+                }
+            }
         } else {
             // no chapter at start
-            let (end_exists, prev_chapter) = match self.0.get_mut(&ts.end) {
+            let (end_exists, prev_chapter) = match self.boundaries.get_mut(&ts.end) {
                 Some(chapters_at_end) => {
                     // a boundary already exists at end
                     let prev_chapter = chapters_at_end.prev.take();
@@ -109,7 +183,7 @@ impl ChaptersBoundaries {
                 None => (false, None),
             };
 
-            self.0.insert(
+            self.boundaries.insert(
                 ts.start,
                 SuccessiveChapters {
                     prev: prev_chapter,
@@ -122,7 +196,7 @@ impl ChaptersBoundaries {
             );
 
             if !end_exists {
-                self.0.insert(
+                self.boundaries.insert(
                     ts.end,
                     SuccessiveChapters {
                         prev: Some(Chapter { title, ts, iter }),
@@ -134,15 +208,21 @@ impl ChaptersBoundaries {
     }
 
     pub fn remove_chapter(&mut self, ts: ChapterTimestamps) {
-        let prev_chapter = self.0.get_mut(&ts.start).unwrap().prev.take();
-        self.0.remove(&ts.start);
+        let prev_chapter = self.boundaries.get_mut(&ts.start).unwrap().prev.take();
+        self.boundaries.remove(&ts.start);
 
-        let boundary_at_end = self.0.get_mut(&ts.end).unwrap();
+        let boundary_at_end = self.boundaries.get_mut(&ts.end).unwrap();
         if prev_chapter.is_none() && boundary_at_end.next.is_none() {
-            self.0.remove(&ts.end);
+            self.boundaries.remove(&ts.end);
         } else {
             boundary_at_end.prev = prev_chapter;
         }
+
+        if let Some(selected) = self.selected {
+            if !self.boundaries.contains_key(&selected) {
+                self.selected = None;
+            }
+        }
     }
 
     pub fn rename_chapter<Title>(&mut self, ts: ChapterTimestamps, new_title: Title)
@@ -152,14 +232,14 @@ impl ChaptersBoundaries {
         let new_title = new_title.to_string();
         debug!("rename_chapter {}, {}", ts, new_title);
 
-        self.0
+        self.boundaries
             .get_mut(&ts.start)
             .unwrap()
             .next
             .as_mut()
             .unwrap()
             .title = new_title.clone();
-        self.0
+        self.boundaries
             .get_mut(&ts.end)
             .unwrap()
             .prev
@@ -169,13 +249,95 @@ impl ChaptersBoundaries {
     }
 
     pub fn move_boundary(&mut self, boundary: Timestamp, target: Timestamp) {
-        let chapters = self.0.remove(&boundary).unwrap();
-        if self.0.insert(target, chapters).is_some() {
-            panic!(
-                "ChaptersBoundaries::move_boundary attempt to replace entry at {}",
-                target
-            );
+        let chapters = self.boundaries.remove(&boundary).unwrap();
+        self.insert_or_merge(target, chapters);
+
+        if self.selected == Some(boundary) {
+            self.selected = Some(target);
+        }
+    }
+
+    /// Moves the `edge` of the chapters at `boundary` without moving the other chapter.
+    ///
+    /// If `boundary` is shared by two chapters, this opens a gap or an overlap.
+    pub fn move_edge(&mut self, boundary: Timestamp, edge: ChapterEdge, target: Timestamp) {
+        let chapters_at_boundary = self.boundaries.get_mut(&boundary).unwrap();
+        let moved = match edge {
+            ChapterEdge::Start => SuccessiveChapters {
+                prev: None,
+                next: chapters_at_boundary.next.take(),
+            },
+            ChapterEdge::End => SuccessiveChapters {
+                prev: chapters_at_boundary.prev.take(),
+                next: None,
+            },
+        };
+
+        if chapters_at_boundary.prev.is_none() && chapters_at_boundary.next.is_none() {
+            self.boundaries.remove(&boundary);
+            if self.selected == Some(boundary) {
+                self.selected = Some(target);
+            }
+        }
+
+        self.insert_or_merge(target, moved);
+    }
+
+    /// Inserts `chapters` at `target`, joining the chapters already there if any.
+    fn insert_or_merge(&mut self, target: Timestamp, chapters: SuccessiveChapters) {
+        match self.boundaries.get_mut(&target) {
+            Some(chapters_at_target) => {
+                if (chapters.prev.is_some() && chapters_at_target.prev.is_some())
+                    || (chapters.next.is_some() && chapters_at_target.next.is_some())
+                {
+                    panic!(
+                        "ChaptersBoundaries::insert_or_merge attempt to replace entry at {}",
+                        target
+                    );
+                }
+
+                if chapters.prev.is_some() {
+                    chapters_at_target.prev = chapters.prev;
+                }
+                if chapters.next.is_some() {
+                    chapters_at_target.next = chapters.next;
+                }
+            }
+            None => {
+                self.boundaries.insert(target, chapters);
+            }
+        }
+    }
+
+    /// Returns the ranges between the first and last boundaries
+    /// which are not covered by exactly one chapter.
+    pub fn gaps_and_overlaps(&self) -> Vec<(ChapterTimestamps, Coverage)> {
+        let mut ranges = Vec::new();
+        let mut covering_count = 0usize;
+        let mut range_start = None;
+
+        for (boundary, chapters) in self.boundaries.iter() {
+            if let Some(range_start) = range_start {
+                let coverage = match covering_count {
+                    0 => Some(Coverage::Gap),
+                    1 => None,
+                    _ => Some(Coverage::Overlap),
+                };
+                if let Some(coverage) = coverage {
+                    ranges.push((ChapterTimestamps::new(range_start, *boundary), coverage));
+                }
+            }
+
+            if chapters.prev.is_some() {
+                covering_count = covering_count.saturating_sub(1);
+            }
+            if chapters.next.is_some() {
+                covering_count += 1;
+            }
+            range_start = Some(*boundary);
         }
+
+        ranges
     }
 }
 
@@ -183,7 +345,7 @@ impl Deref for ChaptersBoundaries {
     type Target = BTreeMap<Timestamp, SuccessiveChapters>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.boundaries
     }
 }
 
@@ -530,4 +692,144 @@ mod tests {
             boundaries.get(&Timestamp::new(4)),
         );
     }
+
+    #[test]
+    fn select_boundaries() {
+        if gtk::init().is_err() {
+            // GTK initialization failure on Travis-CI's linux host
+            return;
+        }
+        // fake store
+        let store = gtk::TreeStore::new(&[glib::Type::BOOL]);
+
+        let mut boundaries = ChaptersBoundaries::new();
+        assert_eq!(None, boundaries.select_next(Timestamp::new(0)));
+
+        for (title, start, end) in [("1", 0, 2), ("2", 2, 4), ("3", 4, 8)] {
+            let chapter = new_chapter(&store, title, ChapterTimestamps::new_from_u64(start, end));
+            boundaries.add_chapter(chapter.ts, &chapter.title, chapter.iter);
+        }
+        assert_eq!(None, boundaries.selected());
+
+        // Without a selection, start from the reference timestamp
+        assert_eq!(
+            Some(Timestamp::new(4)),
+            boundaries.select_next(Timestamp::new(3))
+        );
+        boundaries.unselect();
+        assert_eq!(
+            Some(Timestamp::new(2)),
+            boundaries.select_previous(Timestamp::new(2))
+        );
+
+        // With a selection, the reference timestamp is ignored
+        assert_eq!(
+            Some(Timestamp::new(4)),
+            boundaries.select_next(Timestamp::new(0))
+        );
+        assert_eq!(
+            Some(Timestamp::new(8)),
+            boundaries.select_next(Timestamp::new(0))
+        );
+        assert_eq!(None, boundaries.select_next(Timestamp::new(0)));
+        assert_eq!(Some(Timestamp::new(8)), boundaries.selected());
+
+        // The selection follows the boundary
+        boundaries.move_boundary(Timestamp::new(8), Timestamp::new(9));
+        assert_eq!(Some(Timestamp::new(9)), boundaries.selected());
+        assert_eq!(
+            Some(Timestamp::new(4)),
+            boundaries.select_previous(Timestamp::new(9))
+        );
+
+        // The selection is dropped with its boundary
+        boundaries.remove_chapter(ChapterTimestamps::new_from_u64(4, 9));
+        assert_eq!(None, boundaries.selected());
+        assert_eq!(
+            Some(Timestamp::new(9)),
+            boundaries.select_previous(Timestamp::new(9))
+        );
+
+        boundaries.clear();
+        assert_eq!(None, boundaries.selected());
+    }
+
+    #[test]
+    fn gaps_and_overlaps() {
+        if gtk::init().is_err() {
+            // GTK initialization failure on Travis-CI's linux host
+            return;
+        }
+        // fake store
+        let store = gtk::TreeStore::new(&[glib::Type::BOOL]);
+
+        let mut boundaries = ChaptersBoundaries::new();
+        let chapter_1 = new_chapter(&store, "1", ChapterTimestamps::new_from_u64(0, 2));
+        boundaries.add_chapter(chapter_1.ts, &chapter_1.title, chapter_1.iter);
+        let chapter_2 = new_chapter(&store, "2", ChapterTimestamps::new_from_u64(2, 4));
+        boundaries.add_chapter(chapter_2.ts, &chapter_2.title, chapter_2.iter);
+        assert!(boundaries.gaps_and_overlaps().is_empty());
+
+        // Open a gap by moving the end of first chapter only
+        boundaries.move_edge(Timestamp::new(2), ChapterEdge::End, Timestamp::new(1));
+        assert_eq!(4, boundaries.len());
+        assert_eq!(
+            Some(&SuccessiveChapters {
+                prev: Some(chapter_1.clone()),
+                next: None,
+            }),
+            boundaries.get(&Timestamp::new(1)),
+        );
+        assert_eq!(
+            Some(&SuccessiveChapters {
+                prev: None,
+                next: Some(chapter_2.clone()),
+            }),
+            boundaries.get(&Timestamp::new(2)),
+        );
+        let ranges = boundaries.gaps_and_overlaps();
+        assert_eq!(1, ranges.len());
+        assert_eq!(Timestamp::new(1), ranges[0].0.start);
+        assert_eq!(Timestamp::new(2), ranges[0].0.end);
+        assert_eq!(Coverage::Gap, ranges[0].1);
+
+        // Add a chapter in the gap, joining the surrounding chapters
+        let chapter_3 = new_chapter(&store, "3", ChapterTimestamps::new_from_u64(1, 2));
+        boundaries.add_chapter(chapter_3.ts, &chapter_3.title, chapter_3.iter);
+        assert_eq!(4, boundaries.len());
+        assert_eq!(
+            Some(&SuccessiveChapters {
+                prev: Some(chapter_3.clone()),
+                next: Some(chapter_2.clone()),
+            }),
+            boundaries.get(&Timestamp::new(2)),
+        );
+        assert!(boundaries.gaps_and_overlaps().is_empty());
+
+        // Removing the chapter extends the previous one
+        boundaries.remove_chapter(chapter_3.ts);
+        assert_eq!(3, boundaries.len());
+        assert!(boundaries.gaps_and_overlaps().is_empty());
+
+        // Overlap by moving the start of second chapter only
+        boundaries.move_edge(Timestamp::new(2), ChapterEdge::Start, Timestamp::new(1));
+        assert_eq!(4, boundaries.len());
+        let ranges = boundaries.gaps_and_overlaps();
+        assert_eq!(1, ranges.len());
+        assert_eq!(Timestamp::new(1), ranges[0].0.start);
+        assert_eq!(Timestamp::new(2), ranges[0].0.end);
+        assert_eq!(Coverage::Overlap, ranges[0].1);
+
+        // Join the end of first chapter to the start of second chapter
+        boundaries.move_edge(Timestamp::new(2), ChapterEdge::End, Timestamp::new(1));
+        assert_eq!(3, boundaries.len());
+        assert_eq!(
+            Some(&SuccessiveChapters {
+                prev: Some(chapter_1.clone()),
+                next: Some(chapter_2.clone()),
+            }),
+            boundaries.get(&Timestamp::new(1)),
+        );
+        assert!(boundaries.gaps_and_overlaps().is_empty());
+    }
 }
@@ -1,18 +1,45 @@
-use gtk::{cairo, gio, glib, prelude::*};
+use futures::{
+    channel::mpsc as async_mpsc,
+    future::{abortable, AbortHandle},
+    prelude::*,
+};
+use gtk::{cairo, gdk, gio, glib, prelude::*};
 use log::{info, warn};
 
-use std::{cell::RefCell, fs::File, rc::Rc};
-
-use application::{gettext, CONFIG};
-use media::pipeline;
-use metadata::{Duration, MediaInfo, Timestamp4Humans};
+use std::{
+    cell::RefCell,
+    fs::File,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use application::{gettext, ngettext, NudgeStep, CONFIG};
+use media::{pipeline, MediaEvent};
+use metadata::{
+    parse_timestamp, CueSelection, Duration, Format, MarkerColor, MarkersSidecar, MediaInfo,
+    SubtitleCue, SubtitleFormat, Timestamp4Humans,
+};
 use renderers::{Image, Timestamp};
 
-use super::{ChapterTreeManager, ChaptersBoundaries, PositionStatus};
-use crate::{info_bar, main_panel, playback, prelude::*};
+use super::{
+    ChapterEdge, ChapterTreeManager, ChaptersBoundaries, CueImportDialog, CueImportRequest,
+    CueSource, MarkerDialog, Markers, PositionStatus, SceneCandidates,
+};
+use crate::{info, info_bar, main_panel, playback, prelude::*, spawn};
 
 const EMPTY_REPLACEMENT: &str = "-";
 const GO_TO_PREV_CHAPTER_THRESHOLD: Duration = Duration::from_secs(1);
+/// Lets the playback move past a marker before going back to the previous one.
+const GO_TO_PREV_MARKER_THRESHOLD: Duration = Duration::from_millis(500);
+
+fn nudge_step_label(step: NudgeStep) -> String {
+    match step {
+        NudgeStep::Sample => gettext("1 sample"),
+        NudgeStep::Millisecond => gettext("1 ms"),
+        NudgeStep::TenMilliseconds => gettext("10 ms"),
+        NudgeStep::Frame => gettext("1 video frame"),
+    }
+}
 
 pub struct Controller {
     pub(super) info_container: gtk::Grid,
@@ -39,9 +66,32 @@ pub struct Controller {
     pub(super) next_chapter_action: gio::SimpleAction,
     pub(super) previous_chapter_action: gio::SimpleAction,
 
+    pub(super) detect_scenes_btn: gtk::ToggleToolButton,
+    pub(super) accept_scene_action: gio::SimpleAction,
+    scene_candidates: Rc<RefCell<SceneCandidates>>,
+    scene_detector: Option<pipeline::SceneDetector>,
+    scene_detector_abort_handle: Option<AbortHandle>,
+
+    markers: Rc<RefCell<Markers>>,
+    /// The sidecar file where the markers are saved.
+    markers_path: Option<PathBuf>,
+    marker_dialog: MarkerDialog,
+
+    pub(super) import_cues_action: gio::SimpleAction,
+    pub(super) copy_chapters_action: gio::SimpleAction,
+    pub(super) paste_chapters_action: gio::SimpleAction,
+    cue_import_dialog: CueImportDialog,
+    subtitle_extractor: Option<(pipeline::SubtitleExtractor, CueSelection)>,
+    subtitle_extractor_abort_handle: Option<AbortHandle>,
+
+    chapter_thumbnail_tx: Option<async_mpsc::UnboundedSender<Timestamp>>,
+    thumbnailer_abort_handle: Option<AbortHandle>,
+
     thumbnail_handler: Option<glib::SignalHandlerId>,
 
     pub(crate) chapter_manager: ChapterTreeManager,
+    /// The chapters as they were when the media was opened.
+    initial_chapters: Vec<(Timestamp, Timestamp, String)>,
 
     duration: Duration,
     pub(crate) repeat_chapter: bool,
@@ -96,6 +146,15 @@ impl UIController for Controller {
             self.container_lbl
                 .set_label(info.container().unwrap_or(EMPTY_REPLACEMENT));
 
+            let markers_path = MarkersSidecar::sidecar_path(&info.path);
+            if markers_path.is_file() {
+                match MarkersSidecar::load(&markers_path) {
+                    Ok(markers) => self.markers.borrow_mut().replace_with(markers),
+                    Err(err) => info_bar::show_error(err),
+                }
+            }
+            self.markers_path = Some(markers_path);
+
             let extern_toc = toc_candidates
                 .next()
                 .and_then(|(toc_path, format)| match File::open(toc_path.clone()) {
@@ -134,12 +193,23 @@ impl UIController for Controller {
 
             if extern_toc.is_some() {
                 self.chapter_manager.replace_with(&extern_toc);
+            } else if info.toc.is_none() && info.is_mp3() {
+                // ID3v2 chapters are not exposed by GStreamer
+                let id3v2_toc = File::open(&info.path).ok().and_then(|mut mp3_file| {
+                    metadata::Factory::reader(Format::Id3v2)
+                        .read(&info, &mut mp3_file)
+                        .map_err(|err| warn!("{}", err))
+                        .ok()
+                        .flatten()
+                });
+                self.chapter_manager.replace_with(&id3v2_toc);
             } else {
                 self.chapter_manager.replace_with(&info.toc);
             }
         }
 
         self.update_marks();
+        self.initial_chapters = self.chapters_summary();
 
         self.repeat_btn.set_sensitive(true);
         self.add_chapter_btn.set_sensitive(true);
@@ -163,6 +233,9 @@ impl UIController for Controller {
 
         self.next_chapter_action.set_enabled(true);
         self.previous_chapter_action.set_enabled(true);
+        self.import_cues_action.set_enabled(true);
+        self.copy_chapters_action.set_enabled(true);
+        self.paste_chapters_action.set_enabled(true);
 
         if self.thumbnail_handler.is_some() {
             self.drawingarea.show();
@@ -185,13 +258,26 @@ impl UIController for Controller {
             glib::signal_handler_disconnect(&self.drawingarea, thumbnail_handler);
         }
         self.chapter_treeview.selection().unselect_all();
+        self.stop_thumbnailer();
         self.chapter_manager.clear();
+        self.initial_chapters.clear();
         self.add_chapter_btn.set_sensitive(false);
         self.add_chapter_action.set_enabled(false);
         self.del_chapter_btn.set_sensitive(false);
         self.del_chapter_action.set_enabled(false);
         self.next_chapter_action.set_enabled(false);
         self.previous_chapter_action.set_enabled(false);
+        self.cancel_scene_detection();
+        self.scene_candidates.borrow_mut().clear();
+        self.detect_scenes_btn.set_active(false);
+        self.detect_scenes_btn.set_sensitive(false);
+        self.accept_scene_action.set_enabled(false);
+        self.markers.borrow_mut().clear();
+        self.markers_path = None;
+        self.cancel_cue_extraction();
+        self.import_cues_action.set_enabled(false);
+        self.copy_chapters_action.set_enabled(false);
+        self.paste_chapters_action.set_enabled(false);
         self.timeline_scale.clear_marks();
         self.timeline_scale.set_value(0f64);
         self.duration = Duration::default();
@@ -211,6 +297,15 @@ impl UIController for Controller {
             .set_label(info.streams.audio_codec().unwrap_or(EMPTY_REPLACEMENT));
         self.video_codec_lbl
             .set_label(info.streams.video_codec().unwrap_or(EMPTY_REPLACEMENT));
+
+        self.detect_scenes_btn
+            .set_sensitive(info.streams.is_video_selected());
+
+        if info.streams.is_video_selected() {
+            self.start_thumbnailer(info);
+        } else {
+            self.stop_thumbnailer();
+        }
     }
 
     fn grab_focus(&self) {
@@ -248,7 +343,12 @@ impl UIController for Controller {
 }
 
 impl Controller {
-    pub fn new(builder: &gtk::Builder, boundaries: Rc<RefCell<ChaptersBoundaries>>) -> Self {
+    pub fn new(
+        builder: &gtk::Builder,
+        boundaries: Rc<RefCell<ChaptersBoundaries>>,
+        scene_candidates: Rc<RefCell<SceneCandidates>>,
+        markers: Rc<RefCell<Markers>>,
+    ) -> Self {
         let mut chapter_manager =
             ChapterTreeManager::new(builder.object("chapters-tree-store").unwrap(), boundaries);
         let chapter_treeview: gtk::TreeView = builder.object("chapter-treeview").unwrap();
@@ -279,9 +379,30 @@ impl Controller {
             next_chapter_action: gio::SimpleAction::new("next_chapter", None),
             previous_chapter_action: gio::SimpleAction::new("previous_chapter", None),
 
+            detect_scenes_btn: builder.object("detect_scenes-toolbutton").unwrap(),
+            accept_scene_action: gio::SimpleAction::new("accept_scene_candidate", None),
+            scene_candidates,
+            scene_detector: None,
+            scene_detector_abort_handle: None,
+
+            markers,
+            markers_path: None,
+            marker_dialog: MarkerDialog::new(builder),
+
+            import_cues_action: gio::SimpleAction::new("import_cues", None),
+            copy_chapters_action: gio::SimpleAction::new("copy_chapters", None),
+            paste_chapters_action: gio::SimpleAction::new("paste_chapters", None),
+            cue_import_dialog: CueImportDialog::new(builder),
+            subtitle_extractor: None,
+            subtitle_extractor_abort_handle: None,
+
+            chapter_thumbnail_tx: None,
+            thumbnailer_abort_handle: None,
+
             thumbnail_handler: None,
 
             chapter_manager,
+            initial_chapters: Vec::new(),
 
             duration: Duration::default(),
             repeat_chapter: false,
@@ -345,6 +466,25 @@ impl Controller {
         self.chapter_manager.iter().for_each(move |chapter| {
             timeline_scale.add_mark(chapter.start().as_f64(), gtk::PositionType::Top, None);
         });
+
+        for ts in self.scene_candidates.borrow().keys() {
+            self.timeline_scale
+                .add_mark(ts.as_f64(), gtk::PositionType::Bottom, None);
+        }
+
+        for (ts, marker) in self.markers.borrow().iter() {
+            let (r, g, b) = marker.color.rgb();
+            self.timeline_scale.add_mark(
+                ts.as_f64(),
+                gtk::PositionType::Bottom,
+                Some(&format!(
+                    "<span foreground=\"#{:02x}{:02x}{:02x}\">◆</span>",
+                    (r * 255f64) as u8,
+                    (g * 255f64) as u8,
+                    (b * 255f64) as u8,
+                )),
+            );
+        }
     }
 
     fn repeat_at(&self, ts: Timestamp) {
@@ -413,17 +553,162 @@ impl Controller {
         self.chapter_manager.move_chapter_boundary(boundary, target)
     }
 
-    pub fn add_chapter(&mut self, ts: Timestamp) {
+    /// Returns the selected boundary or else the selected chapter's start.
+    pub fn edited_boundary(&self) -> Option<Timestamp> {
+        match self.chapter_manager.selected_boundary() {
+            Some(boundary) => Some(boundary),
+            None => self
+                .chapter_manager
+                .selected()
+                .map(|chapter| chapter.start()),
+        }
+    }
+
+    /// Moves the selected boundary by `step` in `direction`.
+    ///
+    /// If no boundary is selected, the selected chapter's start is moved.
+    /// Returns the new boundary if it could be moved.
+    pub fn nudge_selected_boundary(
+        &mut self,
+        direction: pipeline::Direction,
+        step: Duration,
+    ) -> Option<Timestamp> {
+        let boundary = self.edited_boundary()?;
+        let target = match direction {
+            pipeline::Direction::Forward => boundary + step,
+            pipeline::Direction::Backward => boundary.saturating_sub(step),
+        };
+
+        let status = self.chapter_manager.move_chapter_boundary(boundary, target);
+        self.boundary_moved(status, target)
+    }
+
+    fn boundary_moved(&self, status: PositionStatus, target: Timestamp) -> Option<Timestamp> {
+        match status {
+            PositionStatus::ChapterChanged { .. } => {
+                self.update_marks();
+                Some(target)
+            }
+            PositionStatus::ChapterNotChanged => None,
+        }
+    }
+
+    /// Selects the next or previous boundary, starting from `ts` if none is selected.
+    pub fn select_boundary(
+        &mut self,
+        direction: pipeline::Direction,
+        ts: Timestamp,
+    ) -> Option<Timestamp> {
+        match direction {
+            pipeline::Direction::Forward => self.chapter_manager.select_next_boundary(ts),
+            pipeline::Direction::Backward => self.chapter_manager.select_previous_boundary(ts),
+        }
+    }
+
+    pub fn unselect_boundary(&mut self) {
+        self.chapter_manager.unselect_boundary();
+    }
+
+    /// Starts editing the timestamp of the selected boundary in the chapters list.
+    ///
+    /// If no boundary is selected, the selected chapter's start is edited.
+    pub fn edit_selected_boundary(&self) {
+        let boundary = match self.chapter_manager.selected_boundary() {
+            Some(boundary) => boundary,
+            None => match self.chapter_manager.selected() {
+                Some(chapter) => chapter.start(),
+                None => return,
+            },
+        };
+
+        if let Some((tree_path, col)) = self.chapter_manager.boundary_cell(boundary) {
+            if !self.show_chapters_btn.is_active() {
+                self.show_chapters_btn.set_active(true);
+            }
+
+            self.chapter_treeview.grab_focus();
+            self.chapter_treeview
+                .set_cursor(&tree_path, Some(&col), true);
+        }
+    }
+
+    /// Moves the `edge` of the chapter at `tree_path` to the timestamp in `text`.
+    ///
+    /// The adjacent chapter is left untouched, so this can open a gap or an overlap.
+    /// Returns the new boundary if it could be moved.
+    pub fn set_chapter_edge(
+        &mut self,
+        tree_path: &gtk::TreePath,
+        edge: ChapterEdge,
+        text: &str,
+    ) -> Option<Timestamp> {
+        let boundary = {
+            let chapter = self.chapter_manager.chapter_from_path(tree_path)?;
+            match edge {
+                ChapterEdge::Start => chapter.start(),
+                ChapterEdge::End => chapter.end(),
+            }
+        };
+
+        let target = match parse_timestamp(text.trim()) {
+            Ok(("", ts)) => Timestamp::new(ts.nano_total()),
+            _ => {
+                info_bar::show_error(gettext("Invalid timestamp \"{}\"").replacen("{}", text, 1));
+                return None;
+            }
+        };
+
+        if target == boundary {
+            return None;
+        }
+
+        let status = self
+            .chapter_manager
+            .move_chapter_edge(boundary, edge, target);
+        let res = self.boundary_moved(status, target);
+        if res.is_none() {
+            info_bar::show_error(gettext(
+                "The boundary can't be moved beyond the adjacent chapters.",
+            ));
+        }
+
+        res
+    }
+
+    /// Selects the next nudge step and saves it in the configuration.
+    pub fn cycle_nudge_step(&self) {
+        let step = {
+            let editing = &mut CONFIG.write().unwrap().editing;
+            editing.nudge_step = editing.nudge_step.next();
+            editing.nudge_step
+        };
+
+        info_bar::show_info(gettext("Boundaries nudge step: {}").replacen(
+            "{}",
+            &nudge_step_label(step),
+            1,
+        ));
+    }
+
+    /// Adds a chapter starting at `ts`.
+    ///
+    /// Returns `true` if the chapter was added and selected.
+    pub fn add_chapter(&mut self, ts: Timestamp) -> bool {
         if ts >= self.duration {
             // can't add a chapter starting at last position
-            return;
+            return false;
         }
 
-        if let Some(new_iter) = self.chapter_manager.add_chapter(ts, self.duration) {
-            self.chapter_treeview.selection().select_iter(&new_iter);
-            self.update_marks();
-            self.del_chapter_btn.set_sensitive(true);
-            self.del_chapter_action.set_enabled(true);
+        match self.chapter_manager.add_chapter(ts, self.duration) {
+            Some(new_iter) => {
+                self.chapter_treeview.selection().select_iter(&new_iter);
+                self.update_marks();
+                self.refresh_thumbnail(ts);
+                self.del_chapter_btn.set_sensitive(true);
+                self.del_chapter_action.set_enabled(true);
+                true
+            }
+            None => false,
         }
     }
 
@@ -440,6 +725,570 @@ impl Controller {
         self.update_marks();
     }
 
+    /// Starts capturing the chapters thumbnails from the selected video stream.
+    fn start_thumbnailer(&mut self, info: &MediaInfo) {
+        self.stop_thumbnailer();
+
+        let stream_id = if info.streams.collection(gst::StreamType::VIDEO).len() > 1 {
+            info.streams
+                .selected_video()
+                .map(|stream| stream.id.to_string())
+        } else {
+            // See `split::Controller::process`
+            None
+        };
+
+        let path = info.path.clone();
+        let (chapter_thumbnail_tx, mut chapter_thumbnail_rx) = async_mpsc::unbounded();
+        let (thumbnailer_hdl, abort_handle) = abortable(async move {
+            let mut thumbnailer = match pipeline::Thumbnailer::try_new(&path, stream_id).await {
+                Ok(thumbnailer) => thumbnailer,
+                Err(err) => {
+                    warn!("{}", err);
+                    return;
+                }
+            };
+
+            while let Some(ts) = chapter_thumbnail_rx.next().await {
+                match thumbnailer.snapshot(ts).await {
+                    Ok(thumbnail) => info::thumbnail_ready(ts, thumbnail),
+                    Err(err) => warn!("{}", err),
+                }
+            }
+        });
+        self.thumbnailer_abort_handle = Some(abort_handle);
+        spawn(thumbnailer_hdl.map(|_| ()));
+
+        self.chapter_thumbnail_tx = Some(chapter_thumbnail_tx);
+        self.chapter_manager.show_thumbnails(true);
+        self.chapter_manager.clear_thumbnails();
+        for chapter in self.chapter_manager.iter() {
+            self.refresh_thumbnail(chapter.start());
+        }
+    }
+
+    fn stop_thumbnailer(&mut self) {
+        if let Some(abort_handle) = self.thumbnailer_abort_handle.take() {
+            abort_handle.abort();
+        }
+
+        self.chapter_thumbnail_tx = None;
+        self.chapter_manager.show_thumbnails(false);
+    }
+
+    /// Captures the thumbnail for the chapter starting at `start`.
+    ///
+    /// This is a no-op if no video stream is selected.
+    pub fn refresh_thumbnail(&self, start: Timestamp) {
+        if let Some(chapter_thumbnail_tx) = self.chapter_thumbnail_tx.as_ref() {
+            let _ = chapter_thumbnail_tx.unbounded_send(start);
+        }
+    }
+
+    /// Starts detecting scene changes in the selected video stream.
+    pub fn start_scene_detection(&mut self, info: &MediaInfo) {
+        self.cancel_scene_detection();
+
+        let stream_id = if info.streams.collection(gst::StreamType::VIDEO).len() > 1 {
+            info.streams
+                .selected_video()
+                .map(|stream| stream.id.to_string())
+        } else {
+            // See `split::Controller::process`
+            None
+        };
+
+        let (sender, mut receiver) = async_mpsc::channel(1);
+        match pipeline::SceneDetector::try_new(&info.path, stream_id, sender) {
+            Ok(scene_detector) => {
+                self.scene_detector = Some(scene_detector);
+
+                let (media_evt_handler, abort_handle) = abortable(async move {
+                    while let Some(event) = receiver.next().await {
+                        match event {
+                            MediaEvent::Eos => {
+                                info::scene_detection_done();
+                                break;
+                            }
+                            MediaEvent::Error(err) => {
+                                info::scene_detection_failed(err);
+                                break;
+                            }
+                            _ => (),
+                        }
+                    }
+                });
+                self.scene_detector_abort_handle = Some(abort_handle);
+                spawn(media_evt_handler.map(|_| ()));
+            }
+            Err(err) => {
+                self.detect_scenes_btn.set_active(false);
+                info_bar::show_error(err);
+            }
+        }
+    }
+
+    pub fn cancel_scene_detection(&mut self) {
+        if let Some(abort_handle) = self.scene_detector_abort_handle.take() {
+            abort_handle.abort();
+        }
+
+        if let Some(scene_detector) = self.scene_detector.take() {
+            scene_detector.cancel();
+        }
+    }
+
+    pub fn scene_detection_done(&mut self) {
+        self.scene_detector_abort_handle = None;
+
+        if let Some(scene_detector) = self.scene_detector.take() {
+            let candidates = scene_detector.candidates();
+            let count = candidates.len();
+
+            self.scene_candidates.borrow_mut().replace_with(candidates);
+            self.accept_scene_action.set_enabled(count > 0);
+            self.update_marks();
+
+            info_bar::show_info(
+                ngettext(
+                    "{} scene change candidate found",
+                    "{} scene change candidates found",
+                    count as u32,
+                )
+                .replacen("{}", &count.to_string(), 1),
+            );
+        }
+
+        self.detect_scenes_btn.set_active(false);
+    }
+
+    pub fn scene_detection_failed(&mut self, err: String) {
+        self.cancel_scene_detection();
+        self.detect_scenes_btn.set_active(false);
+        info_bar::show_error(gettext("Failed to detect scene changes. {}").replacen("{}", &err, 1));
+    }
+
+    /// Adds a chapter at the scene change candidate nearest to `ts`.
+    ///
+    /// Returns `true` if a candidate was accepted.
+    pub fn accept_scene_candidate(&mut self, ts: Timestamp) -> bool {
+        let candidate = self.scene_candidates.borrow_mut().take_nearest(ts);
+        match candidate {
+            Some(candidate) => {
+                self.add_chapter(candidate);
+                self.accept_scene_action
+                    .set_enabled(!self.scene_candidates.borrow().is_empty());
+                self.update_marks();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drops a marker at `ts`.
+    pub fn add_marker(&mut self, ts: Timestamp) {
+        let label =
+            gettext("Marker {}").replacen("{}", &(self.markers.borrow().len() + 1).to_string(), 1);
+        if self
+            .markers
+            .borrow_mut()
+            .add(ts, label, MarkerColor::default())
+        {
+            self.markers_changed();
+        }
+    }
+
+    /// Returns the position of the next or previous marker from `ts`.
+    pub fn marker_from(&self, direction: pipeline::Direction, ts: Timestamp) -> Option<Timestamp> {
+        let markers = self.markers.borrow();
+        match direction {
+            pipeline::Direction::Forward => markers.next_after(ts),
+            pipeline::Direction::Backward => {
+                markers.previous_before(ts.saturating_sub(GO_TO_PREV_MARKER_THRESHOLD))
+            }
+        }
+    }
+
+    /// Opens the dialog to change the label and colour of the marker nearest to `ts`.
+    pub fn edit_marker(&self, ts: Timestamp) {
+        let markers = self.markers.borrow();
+        if let Some(marker_ts) = markers.nearest(ts) {
+            self.marker_dialog.present(marker_ts, &markers[&marker_ts]);
+        }
+    }
+
+    /// Applies the changes from the marker dialog.
+    pub fn update_marker(&mut self) {
+        if let Some((ts, label, color)) = self.marker_dialog.request() {
+            if self.markers.borrow_mut().update(ts, label, color) {
+                self.markers_changed();
+            }
+        }
+    }
+
+    /// Removes the marker nearest to `ts`.
+    ///
+    /// Returns `true` if a marker was removed.
+    pub fn remove_marker(&mut self, ts: Timestamp) -> bool {
+        let removed = {
+            let mut markers = self.markers.borrow_mut();
+            markers
+                .nearest(ts)
+                .and_then(|marker_ts| markers.remove(marker_ts))
+        };
+
+        match removed {
+            Some(_) => {
+                self.markers_changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the marker nearest to `ts` with a chapter titled after its label.
+    ///
+    /// Returns `true` if a marker was converted.
+    pub fn convert_marker(&mut self, ts: Timestamp) -> bool {
+        let marker = {
+            let mut markers = self.markers.borrow_mut();
+            markers
+                .nearest(ts)
+                .and_then(|marker_ts| markers.remove(marker_ts))
+        };
+
+        match marker {
+            Some(marker) => {
+                self.add_marker_chapter(&marker);
+                self.markers_changed();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces all the markers with chapters titled after their labels.
+    pub fn convert_all_markers(&mut self) {
+        let markers = self.markers.borrow().to_vec();
+        if markers.is_empty() {
+            return;
+        }
+
+        self.markers.borrow_mut().clear();
+        for marker in markers.iter() {
+            self.add_marker_chapter(marker);
+        }
+        self.markers_changed();
+
+        info_bar::show_info(
+            ngettext(
+                "{} marker converted to a chapter",
+                "{} markers converted to chapters",
+                markers.len() as u32,
+            )
+            .replacen("{}", &markers.len().to_string(), 1),
+        );
+    }
+
+    fn add_marker_chapter(&mut self, marker: &metadata::Marker) {
+        if self.add_chapter(Timestamp::new(marker.ts)) && !marker.label.is_empty() {
+            self.chapter_manager.rename_selected(&marker.label);
+        }
+    }
+
+    /// Saves the markers to their sidecar file and updates the timeline.
+    fn markers_changed(&self) {
+        if let Some(markers_path) = self.markers_path.as_ref() {
+            if let Err(err) = MarkersSidecar::save(markers_path, &self.markers.borrow().to_vec()) {
+                info_bar::show_error(err);
+            }
+        }
+
+        self.update_marks();
+    }
+
+    pub fn show_cue_import(&self, info: &MediaInfo) {
+        self.cue_import_dialog
+            .present(&info.path, info.streams.selected_text().is_some());
+    }
+
+    /// Generates chapters from the cues selected in the import dialog.
+    pub fn import_cues(&mut self, info: &MediaInfo) {
+        let CueImportRequest { source, selection } = match self.cue_import_dialog.request() {
+            Ok(request) => request,
+            Err(err) => {
+                info_bar::show_error(err);
+                return;
+            }
+        };
+
+        match source {
+            CueSource::File(path) => {
+                let format = path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .and_then(SubtitleFormat::from_extension);
+                let format = match format {
+                    Some(format) => format,
+                    None => {
+                        info_bar::show_error(gettext("Unsupported subtitle file \"{}\"").replacen(
+                            "{}",
+                            &path.to_string_lossy(),
+                            1,
+                        ));
+                        return;
+                    }
+                };
+
+                let cues = File::open(&path)
+                    .map_err(|_| gettext("Failed to open subtitle file."))
+                    .and_then(|mut file| metadata::read_cues(format, &mut file));
+                match cues {
+                    Ok(cues) => self.apply_cues(&cues, &selection),
+                    Err(err) => info_bar::show_error(
+                        gettext("Error opening subtitle file \"{}\":\n{}")
+                            .replacen("{}", path.file_name().unwrap().to_str().unwrap(), 1)
+                            .replacen("{}", &err, 1),
+                    ),
+                }
+            }
+            CueSource::SelectedStream => self.start_cue_extraction(info, selection),
+        }
+    }
+
+    /// Starts reading the cues from the selected text stream.
+    fn start_cue_extraction(&mut self, info: &MediaInfo, selection: CueSelection) {
+        self.cancel_cue_extraction();
+
+        let stream_id = if info.streams.collection(gst::StreamType::TEXT).len() > 1 {
+            info.streams
+                .selected_text()
+                .map(|stream| stream.id.to_string())
+        } else {
+            // See `split::Controller::process`
+            None
+        };
+
+        let (sender, mut receiver) = async_mpsc::channel(1);
+        match pipeline::SubtitleExtractor::try_new(&info.path, stream_id, sender) {
+            Ok(subtitle_extractor) => {
+                self.subtitle_extractor = Some((subtitle_extractor, selection));
+                self.import_cues_action.set_enabled(false);
+
+                let (media_evt_handler, abort_handle) = abortable(async move {
+                    while let Some(event) = receiver.next().await {
+                        match event {
+                            MediaEvent::Eos => {
+                                info::cue_extraction_done();
+                                break;
+                            }
+                            MediaEvent::Error(err) => {
+                                info::cue_extraction_failed(err);
+                                break;
+                            }
+                            _ => (),
+                        }
+                    }
+                });
+                self.subtitle_extractor_abort_handle = Some(abort_handle);
+                spawn(media_evt_handler.map(|_| ()));
+            }
+            Err(err) => info_bar::show_error(err),
+        }
+    }
+
+    fn cancel_cue_extraction(&mut self) {
+        if let Some(abort_handle) = self.subtitle_extractor_abort_handle.take() {
+            abort_handle.abort();
+        }
+
+        if let Some((subtitle_extractor, _)) = self.subtitle_extractor.take() {
+            subtitle_extractor.cancel();
+        }
+    }
+
+    pub fn cue_extraction_done(&mut self) {
+        self.subtitle_extractor_abort_handle = None;
+        self.import_cues_action.set_enabled(true);
+
+        if let Some((subtitle_extractor, selection)) = self.subtitle_extractor.take() {
+            self.apply_cues(&subtitle_extractor.cues(), &selection);
+        }
+    }
+
+    pub fn cue_extraction_failed(&mut self, err: String) {
+        self.cancel_cue_extraction();
+        self.import_cues_action.set_enabled(true);
+        info_bar::show_error(gettext("Failed to read subtitles. {}").replacen("{}", &err, 1));
+    }
+
+    /// Replaces the chapters with those built from the selected `cues`.
+    fn apply_cues(&mut self, cues: &[SubtitleCue], selection: &CueSelection) {
+        let toc = match metadata::cue_starts_to_toc(&selection.select(cues), self.duration) {
+            Some(toc) => toc,
+            None => {
+                info_bar::show_info(gettext("No cue selected"));
+                return;
+            }
+        };
+
+        let count = self.replace_chapters(toc);
+        info_bar::show_info(
+            ngettext(
+                "{} chapter generated from the cues",
+                "{} chapters generated from the cues",
+                count as u32,
+            )
+            .replacen("{}", &count.to_string(), 1),
+        );
+    }
+
+    /// Replaces the chapters with those listed in `text`.
+    ///
+    /// See `metadata::DescriptionFormat` for the expected layout.
+    pub fn paste_chapters(&mut self, info: &MediaInfo, text: &str) {
+        let toc =
+            match metadata::Factory::reader(Format::Description).read(info, &mut text.as_bytes()) {
+                Ok(Some(toc)) => toc,
+                Ok(None) => {
+                    info_bar::show_info(gettext("No chapters found in the pasted text"));
+                    return;
+                }
+                Err(err) => {
+                    info_bar::show_error(err);
+                    return;
+                }
+            };
+
+        let count = self.replace_chapters(toc);
+        info_bar::show_info(
+            ngettext("{} chapter pasted", "{} chapters pasted", count as u32).replacen(
+                "{}",
+                &count.to_string(),
+                1,
+            ),
+        );
+    }
+
+    /// Copies the chapters to the clipboard using the layout of `paste_chapters`.
+    pub fn copy_chapters(&self) {
+        let (toc, count) = match self.chapter_manager.toc() {
+            Some(toc) => toc,
+            None => {
+                info_bar::show_info(gettext("No chapters to copy"));
+                return;
+            }
+        };
+
+        let info = MediaInfo {
+            toc: Some(toc),
+            chapter_count: Some(count),
+            ..MediaInfo::default()
+        };
+
+        let mut text = Vec::new();
+        if let Err(err) = metadata::Factory::writer(Format::Description).write(&info, &mut text) {
+            info_bar::show_error(err);
+            return;
+        }
+
+        gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).set_text(&String::from_utf8_lossy(&text));
+        info_bar::show_info(
+            ngettext("{} chapter copied", "{} chapters copied", count as u32).replacen(
+                "{}",
+                &count.to_string(),
+                1,
+            ),
+        );
+    }
+
+    /// Replaces the chapters with those of the table of contents file at `path`.
+    pub fn import_toc(&mut self, info: &MediaInfo, path: &Path) {
+        let file_name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            .to_string();
+
+        let format = match metadata::Factory::format_for(path) {
+            Some(format) => format,
+            None => {
+                info_bar::show_error(
+                    gettext("Unsupported table of contents file \"{}\"")
+                        .replacen("{}", &file_name, 1),
+                );
+                return;
+            }
+        };
+
+        let toc = File::open(path)
+            .map_err(|_| gettext("Failed to open toc file."))
+            .and_then(|mut toc_file| metadata::Factory::reader(format).read(info, &mut toc_file));
+        match toc {
+            Ok(Some(toc)) => {
+                let count = self.replace_chapters(toc);
+                info_bar::show_info(
+                    ngettext("{} chapter imported", "{} chapters imported", count as u32).replacen(
+                        "{}",
+                        &count.to_string(),
+                        1,
+                    ),
+                );
+            }
+            Ok(None) => {
+                info_bar::show_info(gettext("No toc in file \"{}\"").replacen("{}", &file_name, 1))
+            }
+            Err(err) => info_bar::show_error(
+                gettext("Error opening toc file \"{}\":\n{}")
+                    .replacen("{}", &file_name, 1)
+                    .replacen("{}", &err, 1),
+            ),
+        }
+    }
+
+    /// Replaces the chapters with those of `toc` and returns the new chapter count.
+    fn replace_chapters(&mut self, toc: gst::Toc) -> usize {
+        self.chapter_treeview.selection().unselect_all();
+        self.chapter_manager.replace_with(&Some(toc));
+        self.update_marks();
+
+        for chapter in self.chapter_manager.iter() {
+            self.refresh_thumbnail(chapter.start());
+        }
+
+        main_panel::update_focus();
+
+        self.chapter_manager.iter().count()
+    }
+
+    /// Replaces the chapters with those saved in a project.
+    pub fn restore_chapters(&mut self, toc: Option<gst::Toc>) {
+        match toc {
+            Some(toc) => {
+                self.replace_chapters(toc);
+            }
+            None => {
+                self.chapter_treeview.selection().unselect_all();
+                self.chapter_manager.replace_with(&None);
+                self.update_marks();
+                main_panel::update_focus();
+            }
+        }
+
+        self.initial_chapters = self.chapters_summary();
+    }
+
+    fn chapters_summary(&self) -> Vec<(Timestamp, Timestamp, String)> {
+        self.chapter_manager
+            .iter()
+            .map(|chapter| (chapter.start(), chapter.end(), chapter.title().to_string()))
+            .collect()
+    }
+
+    /// Whether the chapters were changed since the media was opened.
+    pub fn is_toc_edited(&self) -> bool {
+        self.chapters_summary() != self.initial_chapters
+    }
+
     pub fn export_chapters(&self, info: &mut MediaInfo) {
         if let Some((toc, count)) = self.chapter_manager.toc() {
             info.toc = Some(toc);
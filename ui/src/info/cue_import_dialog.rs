@@ -0,0 +1,135 @@
+use gtk::prelude::*;
+
+use std::path::{Path, PathBuf};
+
+use application::gettext;
+use metadata::{CueSelection, SubtitleFormat};
+
+use crate::info;
+
+pub enum CueSource {
+    File(PathBuf),
+    SelectedStream,
+}
+
+pub struct CueImportRequest {
+    pub source: CueSource,
+    pub selection: CueSelection,
+}
+
+/// Lets the user choose the cues to convert into chapters.
+pub struct CueImportDialog {
+    dialog: gtk::Dialog,
+    file_radio: gtk::RadioButton,
+    file_chooser: gtk::FileChooserButton,
+    stream_radio: gtk::RadioButton,
+    every_nth_radio: gtk::RadioButton,
+    every_nth_spin: gtk::SpinButton,
+    matching_radio: gtk::RadioButton,
+    matching_entry: gtk::Entry,
+}
+
+impl CueImportDialog {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let dialog: gtk::Dialog = builder.object("cue_import-dialog").unwrap();
+
+        let file_chooser: gtk::FileChooserButton =
+            builder.object("cue_import_file-chooser").unwrap();
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("SubRip, WebVTT or LRC")));
+        for (extension, _) in SubtitleFormat::extensions() {
+            filter.add_pattern(&format!("*.{}", extension));
+        }
+        file_chooser.add_filter(&filter);
+
+        let file_radio: gtk::RadioButton = builder.object("cue_import_file-radio").unwrap();
+        let every_nth_radio: gtk::RadioButton =
+            builder.object("cue_import_every_nth-radio").unwrap();
+        let every_nth_spin: gtk::SpinButton = builder.object("cue_import_every_nth-spin").unwrap();
+        let matching_radio: gtk::RadioButton = builder.object("cue_import_matching-radio").unwrap();
+        let matching_entry: gtk::Entry = builder.object("cue_import_matching-entry").unwrap();
+
+        let file_chooser_cb = file_chooser.clone();
+        file_radio.connect_toggled(move |radio| file_chooser_cb.set_sensitive(radio.is_active()));
+        let every_nth_spin_cb = every_nth_spin.clone();
+        every_nth_radio
+            .connect_toggled(move |radio| every_nth_spin_cb.set_sensitive(radio.is_active()));
+        let matching_entry_cb = matching_entry.clone();
+        matching_radio
+            .connect_toggled(move |radio| matching_entry_cb.set_sensitive(radio.is_active()));
+
+        dialog.connect_delete_event(|dialog, _| {
+            dialog.hide();
+            Inhibit(true)
+        });
+
+        dialog.connect_response(|dialog, response| {
+            dialog.hide();
+            if response == gtk::ResponseType::Accept {
+                info::import_cues();
+            }
+        });
+
+        CueImportDialog {
+            dialog,
+            file_radio,
+            file_chooser,
+            stream_radio: builder.object("cue_import_stream-radio").unwrap(),
+            every_nth_radio,
+            every_nth_spin,
+            matching_radio,
+            matching_entry,
+        }
+    }
+
+    /// Shows the dialog, proposing the sidecar subtitle file if any.
+    pub fn present(&self, media_path: &Path, has_text_stream: bool) {
+        let sidecar = SubtitleFormat::extensions()
+            .into_iter()
+            .map(|(extension, _)| media_path.with_extension(extension))
+            .find(|path| path.is_file());
+
+        self.file_chooser.unselect_all();
+        match sidecar {
+            Some(sidecar) => {
+                self.file_chooser.set_filename(sidecar);
+                self.file_radio.set_active(true);
+            }
+            None => {
+                if let Some(dir) = media_path.parent() {
+                    self.file_chooser.set_current_folder(dir);
+                }
+
+                if has_text_stream {
+                    self.stream_radio.set_active(true);
+                } else {
+                    self.file_radio.set_active(true);
+                }
+            }
+        }
+        self.stream_radio.set_sensitive(has_text_stream);
+
+        self.dialog.present();
+    }
+
+    pub fn request(&self) -> Result<CueImportRequest, String> {
+        let source = if self.stream_radio.is_active() {
+            CueSource::SelectedStream
+        } else {
+            match self.file_chooser.filename() {
+                Some(path) => CueSource::File(path),
+                None => return Err(gettext("No subtitle file selected")),
+            }
+        };
+
+        let selection = if self.every_nth_radio.is_active() {
+            CueSelection::EveryNth(self.every_nth_spin.value_as_int() as usize)
+        } else if self.matching_radio.is_active() {
+            CueSelection::matching(self.matching_entry.text().as_str())?
+        } else {
+            CueSelection::All
+        };
+
+        Ok(CueImportRequest { source, selection })
+    }
+}
@@ -3,12 +3,15 @@ use futures::{
     prelude::*,
 };
 
-use gtk::{gio, prelude::*};
+use gtk::{gdk, gio, prelude::*};
 
 use log::{debug, trace};
 
+use application::{NudgeStep, CONFIG};
+use media::pipeline::Direction;
+
 use crate::{
-    info::{self, ChapterEntry},
+    info::{self, ChapterEdge, ChapterEntry},
     main_panel, playback,
     prelude::*,
 };
@@ -58,6 +61,27 @@ impl UIDispatcher for Dispatcher {
             });
         }
 
+        // Type the chapters boundaries in the list
+        for (renderer, edge) in [
+            (&info.chapter_manager.start_renderer, ChapterEdge::Start),
+            (&info.chapter_manager.end_renderer, ChapterEdge::End),
+        ] {
+            if let Some(renderer) = renderer {
+                renderer.connect_editing_started(|_, _, _| {
+                    main_panel::temporarily_switch_to(UIFocusContext::TextEntry);
+                });
+
+                renderer.connect_editing_canceled(|_| {
+                    main_panel::restore_context();
+                });
+
+                renderer.connect_edited(move |_, tree_path, text| {
+                    info::set_chapter_edge(tree_path, edge, text);
+                    main_panel::restore_context();
+                });
+            }
+        }
+
         // Register add chapter action
         app.add_action(&info.add_chapter_action);
         info.add_chapter_action.connect_activate(|_, _| {
@@ -72,6 +96,48 @@ impl UIDispatcher for Dispatcher {
             main_panel::update_focus();
         });
 
+        // Register boundary selection actions
+        for (name, direction) in [
+            ("select_next_boundary", Direction::Forward),
+            ("select_previous_boundary", Direction::Backward),
+        ] {
+            let select_boundary = gio::SimpleAction::new(name, None);
+            app.add_action(&select_boundary);
+            select_boundary.connect_activate(move |_, _| info::select_boundary(direction));
+        }
+
+        let unselect_boundary = gio::SimpleAction::new("unselect_boundary", None);
+        app.add_action(&unselect_boundary);
+        unselect_boundary.connect_activate(|_, _| info::unselect_boundary());
+
+        let edit_boundary = gio::SimpleAction::new("edit_boundary", None);
+        app.add_action(&edit_boundary);
+        edit_boundary.connect_activate(|_, _| info::edit_selected_boundary());
+
+        // Register nudge selected boundary actions
+        for (name, direction, step) in [
+            ("nudge_boundary_forward", Direction::Forward, None),
+            ("nudge_boundary_back", Direction::Backward, None),
+            (
+                "nudge_boundary_next_frame",
+                Direction::Forward,
+                Some(NudgeStep::Frame),
+            ),
+            (
+                "nudge_boundary_previous_frame",
+                Direction::Backward,
+                Some(NudgeStep::Frame),
+            ),
+        ] {
+            let nudge_boundary = gio::SimpleAction::new(name, None);
+            app.add_action(&nudge_boundary);
+            nudge_boundary.connect_activate(move |_, _| info::nudge_boundary(direction, step));
+        }
+
+        let cycle_nudge_step = gio::SimpleAction::new("cycle_nudge_step", None);
+        app.add_action(&cycle_nudge_step);
+        cycle_nudge_step.connect_activate(|_, _| info::cycle_nudge_step());
+
         // Register Toggle repeat current chapter action
         let toggle_repeat_chapter = gio::SimpleAction::new("toggle_repeat_chapter", None);
         app.add_action(&toggle_repeat_chapter);
@@ -83,6 +149,73 @@ impl UIDispatcher for Dispatcher {
         info.repeat_btn
             .connect_clicked(|button| info::toggle_repeat(button.is_active()));
 
+        // Scene changes detection
+        info.detect_scenes_btn
+            .connect_toggled(|button| info::detect_scenes(button.is_active()));
+
+        // Register accept scene change candidate action
+        app.add_action(&info.accept_scene_action);
+        info.accept_scene_action.connect_activate(|_, _| {
+            info::accept_scene_candidate();
+            main_panel::update_focus();
+        });
+
+        // Register markers actions
+        let add_marker = gio::SimpleAction::new("add_marker", None);
+        app.add_action(&add_marker);
+        add_marker.connect_activate(|_, _| info::add_marker());
+
+        for (name, direction) in [
+            ("next_marker", Direction::Forward),
+            ("previous_marker", Direction::Backward),
+        ] {
+            let go_to_marker = gio::SimpleAction::new(name, None);
+            app.add_action(&go_to_marker);
+            go_to_marker.connect_activate(move |_, _| info::go_to_marker(direction));
+        }
+
+        let edit_marker = gio::SimpleAction::new("edit_marker", None);
+        app.add_action(&edit_marker);
+        edit_marker.connect_activate(|_, _| info::edit_marker());
+
+        let remove_marker = gio::SimpleAction::new("remove_marker", None);
+        app.add_action(&remove_marker);
+        remove_marker.connect_activate(|_, _| info::remove_marker());
+
+        let convert_marker = gio::SimpleAction::new("convert_marker", None);
+        app.add_action(&convert_marker);
+        convert_marker.connect_activate(|_, _| {
+            info::convert_marker();
+            main_panel::update_focus();
+        });
+
+        let convert_all_markers = gio::SimpleAction::new("convert_all_markers", None);
+        app.add_action(&convert_all_markers);
+        convert_all_markers.connect_activate(|_, _| {
+            info::convert_all_markers();
+            main_panel::update_focus();
+        });
+
+        // Register import cues action
+        app.add_action(&info.import_cues_action);
+        info.import_cues_action
+            .connect_activate(|_, _| info::show_cue_import());
+
+        // Register copy chapters action
+        app.add_action(&info.copy_chapters_action);
+        info.copy_chapters_action
+            .connect_activate(|_, _| info::copy_chapters());
+
+        // Register paste chapters action
+        app.add_action(&info.paste_chapters_action);
+        info.paste_chapters_action.connect_activate(|_, _| {
+            gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD).request_text(|_, text| {
+                if let Some(text) = text {
+                    info::paste_chapters(text.to_string());
+                }
+            });
+        });
+
         // Register next chapter action
         app.add_action(&info.next_chapter_action);
         info.next_chapter_action
@@ -107,11 +240,25 @@ impl UIDispatcher for Dispatcher {
         }
 
         match event {
+            AcceptSceneCandidate => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    if main_ctrl.info.accept_scene_candidate(ts) {
+                        main_ctrl.redraw();
+                    }
+                }
+            }
             AddChapter => {
                 if let Some(ts) = main_ctrl.current_ts() {
+                    let ts = main_ctrl.snap(ts);
                     main_ctrl.info.add_chapter(ts);
                 }
             }
+            AddMarker => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    main_ctrl.info.add_marker(ts);
+                    main_ctrl.redraw();
+                }
+            }
             ChapterClicked(chapter_path) => {
                 let seek_ts = main_ctrl
                     .info
@@ -120,6 +267,10 @@ impl UIDispatcher for Dispatcher {
                     .as_ref()
                     .map(ChapterEntry::start);
 
+                // Back to editing the selected chapter's start
+                main_ctrl.info.unselect_boundary();
+                main_ctrl.redraw();
+
                 if let Some(seek_ts) = seek_ts {
                     return async move {
                         let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
@@ -127,42 +278,132 @@ impl UIDispatcher for Dispatcher {
                     .boxed_local();
                 }
             }
+            ConvertAllMarkers => {
+                main_ctrl.info.convert_all_markers();
+                main_ctrl.redraw();
+            }
+            ConvertMarker => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    if main_ctrl.info.convert_marker(ts) {
+                        main_ctrl.redraw();
+                    }
+                }
+            }
+            CopyChapters => main_ctrl.info.copy_chapters(),
+            CueExtractionDone => {
+                main_ctrl.info.cue_extraction_done();
+                main_ctrl.redraw();
+            }
+            CueExtractionFailed(err) => main_ctrl.info.cue_extraction_failed(err),
+            CycleNudgeStep => main_ctrl.info.cycle_nudge_step(),
+            DetectScenes(true) => match main_ctrl.pipeline.as_ref() {
+                Some(pipeline) => {
+                    let info = pipeline.info.read().unwrap();
+                    main_ctrl.info.start_scene_detection(&info);
+                }
+                None => main_ctrl.info.detect_scenes_btn.set_active(false),
+            },
+            DetectScenes(false) => main_ctrl.info.cancel_scene_detection(),
+            EditMarker => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    main_ctrl.info.edit_marker(ts);
+                }
+            }
+            EditSelectedBoundary => main_ctrl.info.edit_selected_boundary(),
+            GoToMarker(direction) => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    if let Some(marker) = main_ctrl.info.marker_from(direction, ts) {
+                        return async move {
+                            let _ = main_ctrl.seek(marker, gst::SeekFlags::ACCURATE).await;
+                        }
+                        .boxed_local();
+                    }
+                }
+            }
+            ImportCues => {
+                if let Some(pipeline) = main_ctrl.pipeline.as_ref() {
+                    let info = pipeline.info.read().unwrap();
+                    main_ctrl.info.import_cues(&info);
+                }
+                main_ctrl.redraw();
+            }
+            NudgeBoundary(direction, step) => {
+                let step = step.unwrap_or_else(|| CONFIG.read().unwrap().editing.nudge_step);
+                let boundary = main_ctrl
+                    .nudge_duration(step)
+                    .and_then(|step| main_ctrl.info.nudge_selected_boundary(direction, step));
+                if let Some(boundary) = boundary {
+                    main_ctrl.info.refresh_thumbnail(boundary);
+                    main_ctrl.redraw();
+                }
+            }
+            PasteChapters(text) => {
+                if let Some(pipeline) = main_ctrl.pipeline.as_ref() {
+                    let info = pipeline.info.read().unwrap();
+                    main_ctrl.info.paste_chapters(&info, &text);
+                }
+                main_ctrl.redraw();
+            }
             Refresh(ts) => main_ctrl.info.tick(ts, main_ctrl.state),
             RemoveChapter => main_ctrl.info.remove_chapter(),
+            RemoveMarker => {
+                if let Some(ts) = main_ctrl.current_ts() {
+                    if main_ctrl.info.remove_marker(ts) {
+                        main_ctrl.redraw();
+                    }
+                }
+            }
             RenameChapter(new_title) => {
                 main_ctrl.info.chapter_manager.rename_selected(&new_title);
                 // reflect title modification in other parts of the UI (audio waveform)
                 main_ctrl.redraw();
             }
-            ToggleChapterList(must_show) => main_ctrl.info.toggle_chapter_list(must_show),
-            ToggleRepeat(must_repeat) => main_ctrl.info.repeat_chapter = must_repeat,
-        }
-
-        future::ready(()).boxed_local()
-    }
-
-    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
-        use UIFocusContext::*;
+            SceneDetectionDone => {
+                main_ctrl.info.scene_detection_done();
+                main_ctrl.redraw();
+            }
+            SceneDetectionFailed(err) => main_ctrl.info.scene_detection_failed(err),
+            SelectBoundary(direction) => {
+                if main_ctrl.pipeline.is_none() {
+                    return future::ready(()).boxed_local();
+                }
 
-        match ctx {
-            PlaybackPage => {
-                app.set_accels_for_action("app.toggle_show_list", &["l"]);
-                app.set_accels_for_action("app.add_chapter", &["plus", "KP_Add"]);
-                app.set_accels_for_action("app.del_chapter", &["minus", "KP_Subtract"]);
-                app.set_accels_for_action("app.toggle_repeat_chapter", &["r"]);
+                let ts = main_ctrl.current_ts().unwrap_or_default();
+                if let Some(boundary) = main_ctrl.info.select_boundary(direction, ts) {
+                    main_ctrl.redraw();
+                    return async move {
+                        let _ = main_ctrl.seek(boundary, gst::SeekFlags::ACCURATE).await;
+                    }
+                    .boxed_local();
+                }
+            }
+            SetChapterEdge(tree_path, edge, text) => {
+                if let Some(boundary) = main_ctrl.info.set_chapter_edge(&tree_path, edge, &text) {
+                    main_ctrl.info.refresh_thumbnail(boundary);
+                    main_ctrl.redraw();
+                }
+            }
+            ShowCueImport => {
+                if let Some(pipeline) = main_ctrl.pipeline.as_ref() {
+                    let info = pipeline.info.read().unwrap();
+                    main_ctrl.info.show_cue_import(&info);
+                }
+            }
+            ThumbnailReady(ts, thumbnail) => {
+                main_ctrl.info.chapter_manager.set_thumbnail(ts, &thumbnail)
             }
-            ExportPage | SplitPage | StreamsPage => {
-                app.set_accels_for_action("app.toggle_show_list", &["l"]);
-                app.set_accels_for_action("app.add_chapter", &[]);
-                app.set_accels_for_action("app.del_chapter", &[]);
-                app.set_accels_for_action("app.toggle_repeat_chapter", &["r"]);
+            ToggleChapterList(must_show) => main_ctrl.info.toggle_chapter_list(must_show),
+            ToggleRepeat(must_repeat) => main_ctrl.info.repeat_chapter = must_repeat,
+            UnselectBoundary => {
+                main_ctrl.info.unselect_boundary();
+                main_ctrl.redraw();
             }
-            TextEntry | InfoBar => {
-                app.set_accels_for_action("app.toggle_show_list", &[]);
-                app.set_accels_for_action("app.add_chapter", &[]);
-                app.set_accels_for_action("app.del_chapter", &[]);
-                app.set_accels_for_action("app.toggle_repeat_chapter", &[]);
+            UpdateMarker => {
+                main_ctrl.info.update_marker();
+                main_ctrl.redraw();
             }
         }
+
+        future::ready(()).boxed_local()
     }
 }
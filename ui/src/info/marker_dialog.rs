@@ -0,0 +1,84 @@
+use gtk::prelude::*;
+
+use std::cell::Cell;
+
+use application::gettext;
+use metadata::{Marker, MarkerColor};
+use renderers::Timestamp;
+
+use crate::info;
+
+fn marker_color_title(color: MarkerColor) -> String {
+    match color {
+        MarkerColor::Red => gettext("Red"),
+        MarkerColor::Orange => gettext("Orange"),
+        MarkerColor::Yellow => gettext("Yellow"),
+        MarkerColor::Green => gettext("Green"),
+        MarkerColor::Blue => gettext("Blue"),
+        MarkerColor::Purple => gettext("Purple"),
+    }
+}
+
+/// Lets the user change the label and colour of a marker.
+pub struct MarkerDialog {
+    dialog: gtk::Dialog,
+    label_entry: gtk::Entry,
+    color_combo: gtk::ComboBoxText,
+    marker_ts: Cell<Option<Timestamp>>,
+}
+
+impl MarkerDialog {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let dialog: gtk::Dialog = builder.object("marker-dialog").unwrap();
+
+        let color_combo: gtk::ComboBoxText = builder.object("marker_color-combo").unwrap();
+        for color in MarkerColor::all() {
+            color_combo.append_text(&marker_color_title(color));
+        }
+
+        dialog.connect_delete_event(|dialog, _| {
+            dialog.hide();
+            Inhibit(true)
+        });
+
+        dialog.connect_response(|dialog, response| {
+            dialog.hide();
+            if response == gtk::ResponseType::Accept {
+                info::update_marker();
+            }
+        });
+
+        MarkerDialog {
+            dialog,
+            label_entry: builder.object("marker_label-entry").unwrap(),
+            color_combo,
+            marker_ts: Cell::new(None),
+        }
+    }
+
+    pub fn present(&self, ts: Timestamp, marker: &Marker) {
+        self.marker_ts.set(Some(ts));
+
+        self.label_entry.set_text(&marker.label);
+        let color_idx = MarkerColor::all()
+            .iter()
+            .position(|color| *color == marker.color)
+            .unwrap_or_default();
+        self.color_combo.set_active(Some(color_idx as u32));
+
+        self.label_entry.grab_focus();
+        self.dialog.present();
+    }
+
+    /// Returns the edited marker's position, its new label and colour.
+    pub fn request(&self) -> Option<(Timestamp, String, MarkerColor)> {
+        let ts = self.marker_ts.take()?;
+        let color = self
+            .color_combo
+            .active()
+            .and_then(|idx| MarkerColor::all().get(idx as usize).copied())
+            .unwrap_or_default();
+
+        Some((ts, self.label_entry.text().to_string(), color))
+    }
+}
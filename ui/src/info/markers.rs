@@ -0,0 +1,151 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use metadata::{Marker, MarkerColor};
+use renderers::Timestamp;
+
+/// Markers dropped while listening, independently of the chapters.
+#[derive(Debug, Default)]
+pub struct Markers(BTreeMap<Timestamp, Marker>);
+
+impl Markers {
+    pub fn new() -> Self {
+        Markers::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn replace_with(&mut self, markers: impl IntoIterator<Item = Marker>) {
+        self.0 = markers
+            .into_iter()
+            .map(|marker| (Timestamp::new(marker.ts), marker))
+            .collect();
+    }
+
+    pub fn to_vec(&self) -> Vec<Marker> {
+        self.0.values().cloned().collect()
+    }
+
+    /// Adds a marker at `ts` unless there is already one at this position.
+    pub fn add(&mut self, ts: Timestamp, label: impl ToString, color: MarkerColor) -> bool {
+        if self.0.contains_key(&ts) {
+            return false;
+        }
+
+        self.0.insert(ts, Marker::new(ts.as_u64(), label, color));
+        true
+    }
+
+    pub fn update(&mut self, ts: Timestamp, label: impl ToString, color: MarkerColor) -> bool {
+        match self.0.get_mut(&ts) {
+            Some(marker) => {
+                marker.label = label.to_string();
+                marker.color = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, ts: Timestamp) -> Option<Marker> {
+        self.0.remove(&ts)
+    }
+
+    /// Returns the first marker strictly after `ts`.
+    pub fn next_after(&self, ts: Timestamp) -> Option<Timestamp> {
+        use std::ops::Bound::{Excluded, Unbounded};
+
+        self.0
+            .range((Excluded(ts), Unbounded))
+            .next()
+            .map(|(next, _)| *next)
+    }
+
+    /// Returns the last marker strictly before `ts`.
+    pub fn previous_before(&self, ts: Timestamp) -> Option<Timestamp> {
+        self.0.range(..ts).next_back().map(|(prev, _)| *prev)
+    }
+
+    /// Returns the position of the marker nearest to `ts`.
+    pub fn nearest(&self, ts: Timestamp) -> Option<Timestamp> {
+        let next = self.0.range(ts..).next().map(|(next, _)| *next);
+        let prev = self.0.range(..ts).next_back().map(|(prev, _)| *prev);
+
+        match (prev, next) {
+            (Some(prev), Some(next)) => {
+                if ts - prev < next - ts {
+                    Some(prev)
+                } else {
+                    Some(next)
+                }
+            }
+            (Some(prev), None) => Some(prev),
+            (None, next) => next,
+        }
+    }
+}
+
+impl Deref for Markers {
+    type Target = BTreeMap<Timestamp, Marker>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metadata::MarkerColor;
+    use renderers::Timestamp;
+
+    use super::Markers;
+
+    #[test]
+    fn navigate_markers() {
+        let mut markers = Markers::new();
+        assert!(markers.nearest(Timestamp::new(10)).is_none());
+
+        assert!(markers.add(Timestamp::new(40), "ad", MarkerColor::Blue));
+        assert!(markers.add(Timestamp::new(10), "check", MarkerColor::Red));
+        assert!(markers.add(Timestamp::new(20), "intro", MarkerColor::Red));
+        assert!(!markers.add(Timestamp::new(20), "duplicate", MarkerColor::Green));
+        assert_eq!(markers.get(&Timestamp::new(20)).unwrap().label, "intro");
+
+        assert_eq!(
+            markers.next_after(Timestamp::new(10)),
+            Some(Timestamp::new(20))
+        );
+        assert_eq!(markers.next_after(Timestamp::new(40)), None);
+        assert_eq!(
+            markers.previous_before(Timestamp::new(20)),
+            Some(Timestamp::new(10))
+        );
+        assert_eq!(markers.previous_before(Timestamp::new(10)), None);
+
+        assert_eq!(
+            markers.nearest(Timestamp::new(32)),
+            Some(Timestamp::new(40))
+        );
+        assert_eq!(markers.nearest(Timestamp::new(0)), Some(Timestamp::new(10)));
+        assert_eq!(
+            markers.nearest(Timestamp::new(99)),
+            Some(Timestamp::new(40))
+        );
+
+        assert!(markers.update(Timestamp::new(40), "ad break", MarkerColor::Green));
+        assert!(!markers.update(Timestamp::new(41), "none", MarkerColor::Green));
+        let marker = markers.remove(Timestamp::new(40)).unwrap();
+        assert_eq!(marker.label, "ad break");
+        assert_eq!(marker.color, MarkerColor::Green);
+
+        let saved = markers.to_vec();
+        assert_eq!(saved.len(), 2);
+        markers.clear();
+        markers.replace_with(saved);
+        assert_eq!(
+            markers.keys().copied().collect::<Vec<_>>(),
+            [Timestamp::new(10), Timestamp::new(20)]
+        );
+    }
+}
@@ -1,9 +1,21 @@
 mod chapters_boundaries;
-pub use self::chapters_boundaries::{ChapterTimestamps, ChaptersBoundaries};
+pub use self::chapters_boundaries::{ChapterTimestamps, ChaptersBoundaries, Coverage};
 
 mod chapter_tree_manager;
 pub use self::chapter_tree_manager::{ChapterEntry, ChapterTreeManager, PositionStatus};
 
+mod cue_import_dialog;
+pub use self::cue_import_dialog::{CueImportDialog, CueImportRequest, CueSource};
+
+mod marker_dialog;
+pub use self::marker_dialog::MarkerDialog;
+
+mod markers;
+pub use self::markers::Markers;
+
+mod scene_candidates;
+pub use self::scene_candidates::SceneCandidates;
+
 mod controller;
 pub use self::controller::Controller;
 
@@ -11,27 +23,133 @@ mod dispatcher;
 pub use self::dispatcher::Dispatcher;
 
 use crate::UIEventChannel;
-use renderers::Timestamp;
+use application::NudgeStep;
+use media::pipeline::Direction;
+use renderers::{Image, Timestamp};
+
+/// The boundary of a chapter edited in the chapters list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChapterEdge {
+    Start,
+    End,
+}
 
 #[derive(Debug)]
 pub enum Event {
+    AcceptSceneCandidate,
     AddChapter,
+    AddMarker,
     ChapterClicked(gtk::TreePath),
+    ConvertAllMarkers,
+    ConvertMarker,
+    CopyChapters,
+    CueExtractionDone,
+    CueExtractionFailed(String),
+    CycleNudgeStep,
+    DetectScenes(bool),
+    EditMarker,
+    EditSelectedBoundary,
+    GoToMarker(Direction),
+    ImportCues,
+    /// Nudges the selected boundary, by the configured step if `None`.
+    NudgeBoundary(Direction, Option<NudgeStep>),
+    PasteChapters(String),
     Refresh(Timestamp),
     RemoveChapter,
+    RemoveMarker,
     RenameChapter(String),
+    SelectBoundary(Direction),
+    SetChapterEdge(gtk::TreePath, ChapterEdge, String),
+    SceneDetectionDone,
+    SceneDetectionFailed(String),
+    ShowCueImport,
+    ThumbnailReady(Timestamp, Image),
     ToggleChapterList(bool),
     ToggleRepeat(bool),
+    UnselectBoundary,
+    UpdateMarker,
+}
+
+fn accept_scene_candidate() {
+    UIEventChannel::send(Event::AcceptSceneCandidate);
 }
 
 fn add_chapter() {
     UIEventChannel::send(Event::AddChapter);
 }
 
+fn add_marker() {
+    UIEventChannel::send(Event::AddMarker);
+}
+
 fn chapter_clicked(tree_path: gtk::TreePath) {
     UIEventChannel::send(Event::ChapterClicked(tree_path));
 }
 
+fn convert_all_markers() {
+    UIEventChannel::send(Event::ConvertAllMarkers);
+}
+
+fn convert_marker() {
+    UIEventChannel::send(Event::ConvertMarker);
+}
+
+fn copy_chapters() {
+    UIEventChannel::send(Event::CopyChapters);
+}
+
+fn cue_extraction_done() {
+    UIEventChannel::send(Event::CueExtractionDone);
+}
+
+fn cue_extraction_failed(err: String) {
+    UIEventChannel::send(Event::CueExtractionFailed(err));
+}
+
+fn cycle_nudge_step() {
+    UIEventChannel::send(Event::CycleNudgeStep);
+}
+
+fn detect_scenes(must_detect: bool) {
+    UIEventChannel::send(Event::DetectScenes(must_detect));
+}
+
+fn edit_marker() {
+    UIEventChannel::send(Event::EditMarker);
+}
+
+fn edit_selected_boundary() {
+    UIEventChannel::send(Event::EditSelectedBoundary);
+}
+
+fn go_to_marker(direction: Direction) {
+    UIEventChannel::send(Event::GoToMarker(direction));
+}
+
+fn import_cues() {
+    UIEventChannel::send(Event::ImportCues);
+}
+
+fn nudge_boundary(direction: Direction, step: Option<NudgeStep>) {
+    UIEventChannel::send(Event::NudgeBoundary(direction, step));
+}
+
+fn scene_detection_done() {
+    UIEventChannel::send(Event::SceneDetectionDone);
+}
+
+fn scene_detection_failed(err: String) {
+    UIEventChannel::send(Event::SceneDetectionFailed(err));
+}
+
+fn show_cue_import() {
+    UIEventChannel::send(Event::ShowCueImport);
+}
+
+fn thumbnail_ready(ts: Timestamp, thumbnail: Image) {
+    UIEventChannel::send(Event::ThumbnailReady(ts, thumbnail));
+}
+
 pub fn refresh(ts: Timestamp) {
     UIEventChannel::send(Event::Refresh(ts));
 }
@@ -40,10 +158,26 @@ fn rename_chapter(new_title: impl ToString) {
     UIEventChannel::send(Event::RenameChapter(new_title.to_string()));
 }
 
+pub fn paste_chapters(text: String) {
+    UIEventChannel::send(Event::PasteChapters(text));
+}
+
 fn remove_chapter() {
     UIEventChannel::send(Event::RemoveChapter);
 }
 
+fn remove_marker() {
+    UIEventChannel::send(Event::RemoveMarker);
+}
+
+fn select_boundary(direction: Direction) {
+    UIEventChannel::send(Event::SelectBoundary(direction));
+}
+
+fn set_chapter_edge(tree_path: gtk::TreePath, edge: ChapterEdge, text: impl ToString) {
+    UIEventChannel::send(Event::SetChapterEdge(tree_path, edge, text.to_string()));
+}
+
 fn toggle_chapter_list(must_show: bool) {
     UIEventChannel::send(Event::ToggleChapterList(must_show));
 }
@@ -51,3 +185,11 @@ fn toggle_chapter_list(must_show: bool) {
 fn toggle_repeat(must_show: bool) {
     UIEventChannel::send(Event::ToggleRepeat(must_show));
 }
+
+fn unselect_boundary() {
+    UIEventChannel::send(Event::UnselectBoundary);
+}
+
+fn update_marker() {
+    UIEventChannel::send(Event::UpdateMarker);
+}
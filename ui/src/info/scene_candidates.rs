@@ -0,0 +1,92 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use media::pipeline::SceneCandidate;
+use renderers::Timestamp;
+
+/// Scene changes proposed as chapter boundaries, with their confidence.
+#[derive(Debug, Default)]
+pub struct SceneCandidates(BTreeMap<Timestamp, f64>);
+
+impl SceneCandidates {
+    pub fn new() -> Self {
+        SceneCandidates::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn replace_with(&mut self, candidates: impl IntoIterator<Item = SceneCandidate>) {
+        self.0 = candidates
+            .into_iter()
+            .map(|candidate| (candidate.ts, candidate.confidence))
+            .collect();
+    }
+
+    /// Removes and returns the candidate nearest to `ts`.
+    pub fn take_nearest(&mut self, ts: Timestamp) -> Option<Timestamp> {
+        let next = self.0.range(ts..).next().map(|(next, _)| *next);
+        let prev = self.0.range(..ts).next_back().map(|(prev, _)| *prev);
+
+        let nearest = match (prev, next) {
+            (Some(prev), Some(next)) => {
+                if ts - prev < next - ts {
+                    prev
+                } else {
+                    next
+                }
+            }
+            (Some(prev), None) => prev,
+            (None, Some(next)) => next,
+            (None, None) => return None,
+        };
+
+        self.0.remove(&nearest);
+        Some(nearest)
+    }
+}
+
+impl Deref for SceneCandidates {
+    type Target = BTreeMap<Timestamp, f64>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use media::pipeline::SceneCandidate;
+    use renderers::Timestamp;
+
+    use super::SceneCandidates;
+
+    fn candidate(ts: u64) -> SceneCandidate {
+        SceneCandidate {
+            ts: Timestamp::new(ts),
+            confidence: 0.5,
+        }
+    }
+
+    #[test]
+    fn take_nearest() {
+        let mut candidates = SceneCandidates::new();
+        assert!(candidates.take_nearest(Timestamp::new(10)).is_none());
+
+        candidates.replace_with([candidate(10), candidate(20), candidate(40)]);
+
+        assert_eq!(
+            candidates.take_nearest(Timestamp::new(32)),
+            Some(Timestamp::new(40))
+        );
+        assert_eq!(
+            candidates.take_nearest(Timestamp::new(12)),
+            Some(Timestamp::new(10))
+        );
+        assert_eq!(
+            candidates.take_nearest(Timestamp::new(0)),
+            Some(Timestamp::new(20))
+        );
+        assert!(candidates.is_empty());
+    }
+}
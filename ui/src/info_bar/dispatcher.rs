@@ -56,23 +56,4 @@ impl UIDispatcher for Dispatcher {
 
         future::ready(()).boxed_local()
     }
-
-    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
-        use UIFocusContext::*;
-
-        match ctx {
-            PlaybackPage => {
-                app.set_accels_for_action("app.close_info_bar", &[]);
-            }
-            ExportPage | SplitPage | StreamsPage => {
-                app.set_accels_for_action("app.close_info_bar", &[]);
-            }
-            TextEntry => {
-                app.set_accels_for_action("app.close_info_bar", &[]);
-            }
-            InfoBar => {
-                app.set_accels_for_action("app.close_info_bar", &["Escape"]);
-            }
-        }
-    }
 }
@@ -1,4 +1,5 @@
 mod audio;
+mod batch;
 mod export;
 mod generic_output;
 mod info;
@@ -6,6 +7,9 @@ mod info_bar;
 mod main_panel;
 mod perspective;
 mod playback;
+mod preferences;
+mod recent;
+mod shortcuts;
 mod split;
 mod streams;
 mod video;
@@ -63,6 +67,8 @@ pub enum UIFocusContext {
     ExportPage,
     InfoBar,
     PlaybackPage,
+    /// The keys for a new shortcut are being captured.
+    ShortcutCapture,
     SplitPage,
     StreamsPage,
     TextEntry,
@@ -2,21 +2,25 @@ use futures::{
     future::{abortable, AbortHandle},
     prelude::*,
 };
-use gtk::{glib, prelude::*};
+use gtk::{gdk, gio, glib, prelude::*};
 use log::{error, info};
 
 use std::{borrow::ToOwned, cell::RefCell, path::PathBuf, rc::Rc, sync::Arc};
 
-use application::{gettext, ngettext, CommandLineArguments, APP_ID, CONFIG};
-use media::{pipeline, MediaEvent, MissingPlugins, OpenError, SeekError, SelectStreamsError};
+use application::{gettext, ngettext, CommandLineArguments, NudgeStep, SnapMode, APP_ID, CONFIG};
+use media::{
+    pipeline::{self, Direction},
+    MediaEvent, MissingPlugins, OpenError, SeekError, SelectStreamsError,
+};
+use metadata::{Duration, Format, Project, ProjectOutput, ProjectView};
 use renderers::Timestamp;
 
 use crate::{
-    audio, export,
-    info::{self, ChaptersBoundaries},
-    info_bar, main_panel, perspective, playback,
+    audio, batch, export,
+    info::{self, ChaptersBoundaries, Markers, SceneCandidates},
+    info_bar, main_panel, perspective, playback, preferences,
     prelude::*,
-    spawn, split, streams, video,
+    recent, shortcuts, spawn, split, streams, video,
 };
 
 const PAUSE_ICON: &str = "media-playback-pause-symbolic";
@@ -47,14 +51,21 @@ pub struct Controller {
     header_bar: gtk::HeaderBar,
     pub(super) playback_paned: gtk::Paned,
     pub(crate) play_pause_btn: gtk::ToolButton,
+    playback_rate_lbl: gtk::Label,
     file_dlg: gtk::FileChooserNative,
+    pub(super) save_project_action: gio::SimpleAction,
+    project_path: Option<PathBuf>,
 
     pub(crate) perspective: perspective::Controller,
     pub(crate) video: video::Controller,
     pub(crate) info: info::Controller,
     pub(crate) info_bar: info_bar::Controller,
     pub(crate) audio: audio::Controller,
+    pub(crate) batch: batch::Controller,
     pub(crate) export: export::Controller,
+    pub(crate) preferences: preferences::Controller,
+    pub(crate) recent: recent::Controller,
+    pub(crate) shortcuts: shortcuts::Controller,
     pub(crate) split: split::Controller,
     pub(crate) streams: streams::Controller,
 
@@ -72,6 +83,8 @@ impl Controller {
         builder: &gtk::Builder,
     ) -> Self {
         let chapters_boundaries = Rc::new(RefCell::new(ChaptersBoundaries::new()));
+        let scene_candidates = Rc::new(RefCell::new(SceneCandidates::new()));
+        let markers = Rc::new(RefCell::new(Markers::new()));
 
         let file_dlg = gtk::FileChooserNative::builder()
             .title(&gettext("Open a media file"))
@@ -96,14 +109,26 @@ impl Controller {
             header_bar: builder.object("header-bar").unwrap(),
             playback_paned: builder.object("playback-paned").unwrap(),
             play_pause_btn: builder.object("play_pause-toolbutton").unwrap(),
+            playback_rate_lbl: builder.object("playback_rate-lbl").unwrap(),
             file_dlg,
+            save_project_action: gio::SimpleAction::new("save_project", None),
+            project_path: None,
 
             perspective: perspective::Controller::new(builder),
             video: video::Controller::new(builder, args),
-            info: info::Controller::new(builder, Rc::clone(&chapters_boundaries)),
+            info: info::Controller::new(
+                builder,
+                Rc::clone(&chapters_boundaries),
+                Rc::clone(&scene_candidates),
+                Rc::clone(&markers),
+            ),
             info_bar: info_bar::Controller::new(builder),
-            audio: audio::Controller::new(builder, chapters_boundaries),
+            audio: audio::Controller::new(builder, chapters_boundaries, scene_candidates, markers),
+            batch: batch::Controller::new(builder),
             export: export::Controller::new(builder),
+            preferences: preferences::Controller::new(builder),
+            recent: recent::Controller::new(builder),
+            shortcuts: shortcuts::Controller::new(builder),
             split: split::Controller::new(builder),
             streams: streams::Controller::new(builder),
 
@@ -137,10 +162,12 @@ impl Controller {
     }
 
     pub fn quit(&mut self) {
+        self.update_recent();
         if let Some(mut pipeline) = self.pipeline.take() {
             let _ = pipeline.stop();
         }
 
+        self.batch.cancel();
         self.export.cancel();
         self.split.cancel();
 
@@ -165,6 +192,11 @@ impl Controller {
 
         match self.state {
             Paused => {
+                // Resume playing forward, possibly after reverse playback or frame steps
+                if self.set_direction(Direction::Forward).await.is_err() {
+                    return;
+                }
+
                 self.play_pause_btn.set_icon_name(Some(PAUSE_ICON));
                 self.state = Playing;
                 self.pipeline.as_mut().unwrap().play().await.unwrap();
@@ -207,6 +239,108 @@ impl Controller {
         }
     }
 
+    /// Pauses if playing forward or backward.
+    pub async fn pause(&mut self) {
+        if let State::Playing = self.state {
+            self.play_pause().await;
+        }
+    }
+
+    /// Seeks to `target` and starts playing if paused.
+    pub async fn play_from(&mut self, target: Timestamp) {
+        match self.state {
+            State::Paused | State::Playing | State::EosPaused | State::EosPlaying => (),
+            _ => return,
+        }
+
+        if self.seek(target, gst::SeekFlags::ACCURATE).await.is_err() {
+            return;
+        }
+
+        if let State::Paused = self.state {
+            self.play_pause().await;
+        }
+    }
+
+    /// Plays backward from current position.
+    pub async fn play_backward(&mut self) {
+        use State::*;
+
+        match self.state {
+            Paused | Playing => (),
+            _ => return,
+        }
+
+        if self.set_direction(Direction::Backward).await.is_err() {
+            return;
+        }
+
+        if let Paused = self.state {
+            self.play_pause_btn.set_icon_name(Some(PAUSE_ICON));
+            self.state = Playing;
+            self.pipeline.as_mut().unwrap().play().await.unwrap();
+            self.audio.play();
+        }
+    }
+
+    async fn set_direction(&mut self, direction: Direction) -> Result<(), ()> {
+        match self
+            .pipeline
+            .as_mut()
+            .unwrap()
+            .set_direction(direction)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(SeekError::Eos) => {
+                self.eos().await;
+                Err(())
+            }
+            Err(SeekError::Unrecoverable) => {
+                self.stop();
+                Err(())
+            }
+        }
+    }
+
+    /// Steps one video frame in the `direction`.
+    ///
+    /// Frames can only be stepped in paused mode with a video stream selected.
+    pub async fn step_frame(&mut self, direction: Direction) {
+        use State::*;
+
+        match self.state {
+            Paused | EosPaused => (),
+            _ => return,
+        }
+
+        let pipeline = self.pipeline.as_mut().unwrap();
+        if !pipeline.can_step_frames() {
+            return;
+        }
+
+        match pipeline.step_frame(direction).await {
+            Ok(()) => {
+                if let EosPaused = self.state {
+                    self.state = Paused;
+                }
+            }
+            Err(SeekError::Eos) => {
+                self.eos().await;
+                return;
+            }
+            Err(SeekError::Unrecoverable) => {
+                self.stop();
+                return;
+            }
+        }
+
+        if let Some(ts) = self.current_ts() {
+            self.info.tick(ts, self.state);
+        }
+        self.audio.refresh();
+    }
+
     pub async fn seek(&mut self, target: Timestamp, flags: gst::SeekFlags) -> Result<(), ()> {
         use State::*;
 
@@ -255,6 +389,48 @@ impl Controller {
         Ok(())
     }
 
+    pub fn playback_rate(&self) -> f64 {
+        self.pipeline
+            .as_ref()
+            .map_or(1f64, pipeline::Playback::rate)
+    }
+
+    /// Changes the playback speed, preserving the pitch.
+    ///
+    /// The new rate is applied by seeking to the current position.
+    pub async fn set_playback_rate(&mut self, rate: f64) {
+        use State::*;
+
+        match self.state {
+            Playing | Paused | EosPlaying | EosPaused => (),
+            // Don't interfere with the play range seeks
+            PlayingRange | PausedPlayingRange => return,
+            PendingSelectMediaDecision | Stopped => return,
+        }
+
+        let pipeline = self.pipeline.as_mut().unwrap();
+        if (pipeline.rate() - rate).abs() < f64::EPSILON {
+            return;
+        }
+
+        pipeline.set_rate(rate);
+        let rate = pipeline.rate();
+        self.update_playback_rate_lbl(rate);
+
+        if let Some(ts) = self.current_ts() {
+            let _ = self.seek(ts, gst::SeekFlags::ACCURATE).await;
+        }
+    }
+
+    fn update_playback_rate_lbl(&self, rate: f64) {
+        if (rate - 1f64).abs() < f64::EPSILON {
+            self.playback_rate_lbl.hide();
+        } else {
+            self.playback_rate_lbl.set_text(&format!("{:.2}×", rate));
+            self.playback_rate_lbl.show();
+        }
+    }
+
     fn stop(&mut self) {
         if let Some(mut pipeline) = self.pipeline.take() {
             let _ = pipeline.stop();
@@ -269,13 +445,60 @@ impl Controller {
     }
 
     pub fn current_ts(&mut self) -> Option<Timestamp> {
-        self.pipeline.as_mut().unwrap().current_ts()
+        self.pipeline.as_mut()?.current_ts()
     }
 
     pub fn redraw(&mut self) {
         self.audio.redraw();
     }
 
+    /// Snaps a chapter boundary at `ts` to the audio signal.
+    ///
+    /// Snapping is temporarily disabled while `Shift` is held.
+    pub fn snap(&self, ts: Timestamp) -> Timestamp {
+        let pipeline = match self.pipeline.as_ref() {
+            Some(pipeline) => pipeline,
+            None => return ts,
+        };
+
+        let (mode, radius) = {
+            let editing = &CONFIG.read().unwrap().editing;
+            if !editing.is_snapping_enabled {
+                return ts;
+            }
+
+            let mode = match editing.snap_mode {
+                SnapMode::ZeroCrossing => renderers::SnapMode::ZeroCrossing,
+                SnapMode::Silence => renderers::SnapMode::Silence,
+                SnapMode::Onset => renderers::SnapMode::Onset,
+            };
+
+            (mode, Duration::from_millis(editing.snap_radius_ms))
+        };
+
+        let is_shift_held = gdk::Display::default()
+            .and_then(|display| gdk::Keymap::for_display(&display))
+            .map_or(false, |keymap| {
+                gdk::ModifierType::from_bits_truncate(keymap.modifier_state())
+                    .contains(gdk::ModifierType::SHIFT_MASK)
+            });
+        if is_shift_held {
+            return ts;
+        }
+
+        pipeline.snap(ts, radius, mode)
+    }
+
+    /// Returns the duration of the boundaries nudge `step` for current media.
+    pub fn nudge_duration(&self, step: NudgeStep) -> Option<Duration> {
+        match step {
+            NudgeStep::Sample => self.audio.sample_duration(),
+            NudgeStep::Millisecond => Some(Duration::from_millis(1)),
+            NudgeStep::TenMilliseconds => Some(Duration::from_millis(10)),
+            NudgeStep::Frame => self.pipeline.as_ref()?.frame_duration(),
+        }
+    }
+
     pub async fn select_streams(&mut self, stream_ids: &[Arc<str>]) {
         let res = self
             .pipeline
@@ -381,6 +604,86 @@ impl Controller {
     }
 
     pub async fn open_media(&mut self, path: PathBuf) {
+        let res = if Project::is_project(&path) {
+            self.open_project(path.clone()).await
+        } else {
+            self.open(path.clone()).await
+        };
+
+        if res.is_ok() {
+            CONFIG.write().unwrap().media.add_recent(&path);
+            self.recent.refresh();
+        }
+    }
+
+    /// Opens the first dropped media and imports the first dropped table of contents.
+    pub async fn drop_files(&mut self, paths: Vec<PathBuf>) {
+        let (toc_paths, media_paths): (Vec<PathBuf>, Vec<PathBuf>) =
+            paths.into_iter().partition(|path| {
+                !Project::is_project(path) && metadata::Factory::format_for(path).is_some()
+            });
+
+        if let Some(media_path) = media_paths.into_iter().next() {
+            self.open_media(media_path).await;
+        }
+
+        if let Some(toc_path) = toc_paths.first() {
+            match self.pipeline.as_ref() {
+                Some(pipeline) => {
+                    let info = pipeline.info.read().unwrap();
+                    self.info.import_toc(&info, toc_path);
+                }
+                None => {
+                    info_bar::show_error(gettext(
+                        "Open a media before importing its table of contents",
+                    ));
+                    return;
+                }
+            }
+            self.redraw();
+        }
+    }
+
+    /// Opens a media from the recent list and resumes playback where it was left.
+    pub async fn open_recent(&mut self, path: PathBuf) {
+        self.open_media(path.clone()).await;
+        if self.pipeline.is_none() {
+            return;
+        }
+
+        let position = CONFIG
+            .read()
+            .unwrap()
+            .media
+            .recent(&path)
+            .map_or(0, |recent| recent.position);
+        if position > 0 {
+            let _ = self
+                .seek(Timestamp::new(position), gst::SeekFlags::ACCURATE)
+                .await;
+        }
+    }
+
+    /// Records the position and chapters state of current media in the recent list.
+    fn update_recent(&mut self) {
+        let path = match self.pipeline.as_ref() {
+            Some(pipeline) => self
+                .project_path
+                .clone()
+                .unwrap_or_else(|| pipeline.info.read().unwrap().path.clone()),
+            None => return,
+        };
+
+        let position = self.current_ts().map_or(0, Timestamp::as_u64);
+        CONFIG
+            .write()
+            .unwrap()
+            .media
+            .update_recent(&path, position, self.info.is_toc_edited());
+        self.recent.refresh();
+    }
+
+    async fn open(&mut self, path: PathBuf) -> Result<(), ()> {
         self.cleanup();
 
         CONFIG.write().unwrap().media.last_path = path.parent().map(ToOwned::to_owned);
@@ -388,6 +691,7 @@ impl Controller {
             "{}",
             gettext("Opening {}...").replacen("{}", path.to_str().unwrap(), 1)
         );
+        self.recent.show_start_page(false);
 
         match pipeline::Playback::try_new(
             path.as_ref(),
@@ -396,6 +700,7 @@ impl Controller {
                 .take()
                 .expect("Couldn't take double visu renderer"),
             &self.video.video_sink(),
+            Self::buffer_sizes(),
         )
         .await
         {
@@ -409,6 +714,7 @@ impl Controller {
 
                 self.header_bar
                     .set_subtitle(Some(pipeline.info.read().unwrap().file_name.as_str()));
+                self.update_playback_rate_lbl(pipeline.rate());
 
                 self.audio.new_media(&pipeline);
                 self.export.new_media(&pipeline);
@@ -436,6 +742,7 @@ impl Controller {
                             }
                             MustRefresh => audio::refresh(),
                             PlayRangeDone => playback::play_range_done(),
+                            Subtitle(subtitle) => audio::show_subtitle(subtitle),
                             other => unreachable!("{:?}", other),
                         }
                     }
@@ -449,7 +756,10 @@ impl Controller {
 
                 self.audio.pause();
                 self.state = State::Paused;
+                self.save_project_action.set_enabled(true);
                 main_panel::reset_cursor();
+
+                Ok(())
             }
             Err(error) => {
                 main_panel::reset_cursor();
@@ -461,7 +771,7 @@ impl Controller {
                     StateChange => gettext("Failed to switch the media to Paused"),
                     GLSinkError => {
                         let mut config = CONFIG.write().expect("Failed to get CONFIG as mut");
-                        config.media.is_gl_disabled = true;
+                        config.preferences.is_gl_disabled = true;
                         config.save();
 
                         gettext(
@@ -470,9 +780,172 @@ impl Controller {
                     }
                 };
 
+                self.recent.show_start_page(true);
                 info_bar::show_error(gettext("Error opening file. {}").replace("{}", &error));
+
+                Err(())
+            }
+        }
+    }
+
+    async fn open_project(&mut self, project_path: PathBuf) -> Result<(), ()> {
+        let project = match Project::load(&project_path) {
+            Ok(project) => project,
+            Err(err) => {
+                main_panel::reset_cursor();
+                info_bar::show_error(err);
+                return Err(());
             }
         };
+
+        self.open(project.media_path.clone()).await?;
+
+        self.project_path = Some(project_path);
+        self.restore_project(project).await;
+
+        Ok(())
+    }
+
+    async fn restore_project(&mut self, project: Project) {
+        self.info.restore_chapters(project.toc);
+
+        let stream_ids: Vec<Arc<str>> = {
+            let mut info = self.pipeline.as_ref().unwrap().info.write().unwrap();
+
+            for stream_id in project.streams.not_exported.iter() {
+                for type_ in [
+                    gst::StreamType::AUDIO,
+                    gst::StreamType::VIDEO,
+                    gst::StreamType::TEXT,
+                ] {
+                    if let Some(stream) = info.streams.collection_mut(type_).get_mut(stream_id) {
+                        stream.must_export = false;
+                    }
+                }
+            }
+
+            // The media might have changed since the project was saved
+            project
+                .streams
+                .selected
+                .iter()
+                .filter(|stream_id| {
+                    info.streams.audio.contains(stream_id)
+                        || info.streams.video.contains(stream_id)
+                        || info.streams.text.contains(stream_id)
+                })
+                .map(|stream_id| stream_id.as_str().into())
+                .collect()
+        };
+
+        self.streams.restore(&project.streams);
+        if !stream_ids.is_empty() {
+            self.select_streams(&stream_ids).await;
+        }
+
+        if let Some(format) = project.output.export_format {
+            self.export.select_format(format);
+        }
+        if let Some(format) = project.output.split_format {
+            self.split.select_format(format);
+        }
+
+        if let Some(duration_per_1000px) = project.view.duration_per_1000px() {
+            self.audio.set_requested_duration(duration_per_1000px);
+        }
+
+        let position = project.view.position();
+        if position > Duration::default() {
+            let _ = self.seek(position.into(), gst::SeekFlags::ACCURATE).await;
+        }
+    }
+
+    /// Saves the editing session next to the media or in the project it was opened from.
+    pub fn save_project(&mut self) {
+        if self.pipeline.is_none() {
+            return;
+        }
+
+        let position = self.current_ts().map_or(0, Timestamp::as_u64);
+
+        let (mut project, project_path) = {
+            let info = self.pipeline.as_ref().unwrap().info.read().unwrap();
+
+            let mut project = Project::new(&info.path);
+            project.streams.not_exported = [
+                gst::StreamType::VIDEO,
+                gst::StreamType::AUDIO,
+                gst::StreamType::TEXT,
+            ]
+            .into_iter()
+            .flat_map(|type_| info.streams.collection(type_).sorted())
+            .filter(|stream| !stream.must_export)
+            .map(|stream| stream.id.to_string())
+            .collect();
+
+            let project_path = self
+                .project_path
+                .clone()
+                .unwrap_or_else(|| Project::default_path(&info.path));
+
+            (project, project_path)
+        };
+
+        project.toc = self.info.chapter_manager.toc().map(|(toc, _count)| toc);
+        project.streams.selected = self
+            .streams
+            .selected_streams()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        project.output = ProjectOutput {
+            export_format: self.export.selected_format(),
+            split_format: self.split.selected_format(),
+        };
+        project.view = ProjectView {
+            position,
+            duration_per_1000px: Some(self.audio.requested_duration().as_u64()),
+        };
+
+        match project.save(&project_path) {
+            Ok(()) => {
+                info_bar::show_info(gettext("Project saved to {}").replacen(
+                    "{}",
+                    &project_path.to_string_lossy(),
+                    1,
+                ));
+                self.project_path = Some(project_path);
+            }
+            Err(err) => info_bar::show_error(err),
+        }
+    }
+
+    /// Selects the export & split formats defined in the preferences.
+    pub fn select_default_formats(&self) {
+        let preferences = &CONFIG.read().unwrap().preferences;
+        if let Some(format) = preferences
+            .export_format
+            .as_deref()
+            .and_then(Format::from_name)
+        {
+            self.export.select_format(format);
+        }
+        if let Some(format) = preferences
+            .split_format
+            .as_deref()
+            .and_then(Format::from_name)
+        {
+            self.split.select_format(format);
+        }
+    }
+
+    /// Returns the buffer sizes defined in the preferences.
+    fn buffer_sizes() -> pipeline::BufferSizes {
+        let preferences = &CONFIG.read().unwrap().preferences;
+        pipeline::BufferSizes {
+            queue: Duration::from_millis(preferences.queue_size_ms),
+            renderer: Duration::from_millis(preferences.buffer_size_ms),
+        }
     }
 
     fn format_missing_plugins(plugins: &MissingPlugins) -> String {
@@ -497,7 +970,10 @@ impl Controller {
 
 impl UIController for Controller {
     fn cleanup(&mut self) {
+        self.update_recent();
         self.stop();
+        self.project_path = None;
+        self.save_project_action.set_enabled(false);
 
         self.info.cleanup();
         self.audio.cleanup();
@@ -1,18 +1,23 @@
 use futures::prelude::*;
 use gtk::{
-    gdk::{Cursor, CursorType},
+    gdk::{self, Cursor, CursorType},
     gio,
     prelude::*,
 };
 
+use std::path::PathBuf;
+
 use application::{gettext, CommandLineArguments, APP_PATH, CONFIG};
 use media::pipeline;
 
 use crate::{
-    audio, export, info, info_bar, main_panel, perspective, playback, prelude::*, spawn, split,
-    streams, video, UIEvent,
+    audio, batch, export, info, info_bar, main_panel, perspective, playback, preferences,
+    prelude::*, recent, shortcuts, spawn, split, streams, video, UIEvent,
 };
 
+const URI_LIST_TARGET: u32 = 0;
+const TEXT_TARGET: u32 = 1;
+
 pub struct Dispatcher {
     app: gtk::Application,
     main_ctrl: main_panel::Controller,
@@ -54,16 +59,31 @@ impl Dispatcher {
         let about = gio::SimpleAction::new("about", None);
         app.add_action(&about);
         about.connect_activate(|_, _| main_panel::about());
-        app.set_accels_for_action("app.about", &["<Ctrl>A"]);
         app_section.append(Some(&gettext("About")), Some("app.about"));
 
         // Quit
         let quit = gio::SimpleAction::new("quit", None);
         app.add_action(&quit);
         quit.connect_activate(|_, _| main_panel::quit());
-        app.set_accels_for_action("app.quit", &["<Ctrl>Q"]);
         app_section.append(Some(&gettext("Quit")), Some("app.quit"));
 
+        // Preferences
+        preferences::Dispatcher::setup(&mut this.main_ctrl.preferences, app);
+        app_section.insert(0, Some(&gettext("Preferences")), Some("app.preferences"));
+
+        // Keyboard shortcuts
+        shortcuts::Dispatcher::setup(&mut this.main_ctrl.shortcuts, app);
+        app_section.insert(
+            1,
+            Some(&gettext("Keyboard shortcuts")),
+            Some("app.shortcuts"),
+        );
+        app_section.insert(
+            2,
+            Some(&gettext("Shortcuts help")),
+            Some("app.shortcuts_help"),
+        );
+
         if gst_init_res.is_ok() {
             let _ = pipeline::Playback::check_requirements().map_err(info_bar::show_error);
 
@@ -75,7 +95,51 @@ impl Dispatcher {
             app.add_action(&open);
             open.connect_activate(|_, _| main_panel::select_media());
             main_section.append(Some(&gettext("Open media file")), Some("app.open"));
-            app.set_accels_for_action("app.open", &["<Ctrl>O"]);
+
+            // Register Save project action
+            let save_project = &this.main_ctrl.save_project_action;
+            app.add_action(save_project);
+            save_project.connect_activate(|_, _| main_panel::save_project());
+            save_project.set_enabled(false);
+            main_section.append(Some(&gettext("Save project")), Some("app.save_project"));
+
+            // Register Batch action
+            let batch = gio::SimpleAction::new("batch", None);
+            app.add_action(&batch);
+            batch.connect_activate(|_, _| batch::show());
+            main_section.append(Some(&gettext("Batch processing")), Some("app.batch"));
+
+            // Accept media & toc files as well as chapters lists dropped on the window
+            window.drag_dest_set(
+                gtk::DestDefaults::ALL,
+                &[
+                    gtk::TargetEntry::new(
+                        "text/uri-list",
+                        gtk::TargetFlags::OTHER_APP,
+                        URI_LIST_TARGET,
+                    ),
+                    gtk::TargetEntry::new("text/plain", gtk::TargetFlags::OTHER_APP, TEXT_TARGET),
+                ],
+                gdk::DragAction::COPY,
+            );
+            window.connect_drag_data_received(|_, _, _, _, data, target, _| match target {
+                URI_LIST_TARGET => {
+                    let paths: Vec<PathBuf> = data
+                        .uris()
+                        .iter()
+                        .filter_map(|uri| gio::File::for_uri(uri).path())
+                        .filter(|path| path.is_file())
+                        .collect();
+                    if !paths.is_empty() {
+                        main_panel::drop_files(paths);
+                    }
+                }
+                _ => {
+                    if let Some(text) = data.text() {
+                        info::paste_chapters(text.to_string());
+                    }
+                }
+            });
 
             let display_page: gtk::Box = builder.object("display-box").unwrap();
             display_page.connect_map(|_| main_panel::switch_to(UIFocusContext::PlaybackPage));
@@ -85,11 +149,15 @@ impl Dispatcher {
             info::Dispatcher::setup(&mut this.main_ctrl.info, app);
             info_bar::Dispatcher::setup(&mut this.main_ctrl.info_bar, app);
             audio::Dispatcher::setup(&mut this.main_ctrl.audio, app);
+            batch::Dispatcher::setup(&mut this.main_ctrl.batch, app);
             export::Dispatcher::setup(&mut this.main_ctrl.export, app);
+            recent::Dispatcher::setup(&mut this.main_ctrl.recent, app);
             split::Dispatcher::setup(&mut this.main_ctrl.split, app);
             streams::Dispatcher::setup(&mut this.main_ctrl.streams, app);
             playback::Dispatcher::setup(&mut this.main_ctrl, app);
 
+            this.main_ctrl.select_default_formats();
+
             main_panel::switch_to(UIFocusContext::PlaybackPage);
 
             {
@@ -110,9 +178,12 @@ impl Dispatcher {
 
             if let Some(input_file) = args.input_file.to_owned() {
                 main_panel::open_media(input_file);
+            } else if let Some(last_path) = recent::Controller::last_to_reopen() {
+                recent::open(last_path);
             }
         } else {
             // GStreamer initialization failed
+            this.bind_accels_for(UIFocusContext::PlaybackPage);
             Self::spawn_event_handlers(this);
             main_panel::show_all();
 
@@ -139,6 +210,7 @@ impl Dispatcher {
 
         match event {
             Audio(event) => audio::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
+            Batch(event) => batch::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
             Export(event) => export::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
             Info(event) => info::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
             InfoBar(event) => info_bar::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
@@ -147,13 +219,21 @@ impl Dispatcher {
                 match event {
                     About => self.main_ctrl.about(),
                     CancelSelectMedia => self.main_ctrl.cancel_select_media(),
+                    DropFiles(paths) => self.main_ctrl.drop_files(paths).await,
                     OpenMedia(path) => self.main_ctrl.open_media(path).await,
                     Quit => {
                         self.main_ctrl.quit();
                         return Err(());
                     }
+                    RefreshAccels => {
+                        // Keep the accels disabled while they are temporarily overridden
+                        if self.saved_context.is_none() {
+                            self.bind_accels_for(self.focus);
+                        }
+                    }
                     ResetCursor => self.reset_cursor(),
                     RestoreContext => self.restore_context(),
+                    SaveProject => self.main_ctrl.save_project(),
                     SelectMedia => self.main_ctrl.select_media().await,
                     SetCursorDoubleArrow => self.set_cursor_double_arrow(),
                     SetCursorWaiting => self.set_cursor_waiting(),
@@ -167,6 +247,13 @@ impl Dispatcher {
                 }
             }
             Playback(event) => playback::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
+            Preferences(event) => {
+                preferences::Dispatcher::handle_event(&mut self.main_ctrl, event).await
+            }
+            Recent(event) => recent::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
+            Shortcuts(event) => {
+                shortcuts::Dispatcher::handle_event(&mut self.main_ctrl, event).await
+            }
             Split(event) => split::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
             Streams(event) => streams::Dispatcher::handle_event(&mut self.main_ctrl, event).await,
         }
@@ -201,15 +288,8 @@ impl Dispatcher {
     }
 
     fn bind_accels_for(&self, ctx: UIFocusContext) {
-        perspective::Dispatcher::bind_accels_for(ctx, &self.app);
-        video::Dispatcher::bind_accels_for(ctx, &self.app);
-        info::Dispatcher::bind_accels_for(ctx, &self.app);
-        info_bar::Dispatcher::bind_accels_for(ctx, &self.app);
-        audio::Dispatcher::bind_accels_for(ctx, &self.app);
-        export::Dispatcher::bind_accels_for(ctx, &self.app);
-        split::Dispatcher::bind_accels_for(ctx, &self.app);
-        streams::Dispatcher::bind_accels_for(ctx, &self.app);
-        playback::Dispatcher::bind_accels_for(ctx, &self.app);
+        // All the accels are defined in the user customisable keymap
+        shortcuts::Dispatcher::bind_accels_for(ctx, &self.app);
     }
 
     fn update_focus(&mut self, ctx: UIFocusContext) {
@@ -12,10 +12,13 @@ use crate::{UIEventChannel, UIFocusContext};
 pub enum Event {
     About,
     CancelSelectMedia,
+    DropFiles(Vec<PathBuf>),
     OpenMedia(PathBuf),
     Quit,
+    RefreshAccels,
     ResetCursor,
     RestoreContext,
+    SaveProject,
     SelectMedia,
     SetCursorDoubleArrow,
     SetCursorWaiting,
@@ -33,6 +36,10 @@ fn cancel_select_media() {
     UIEventChannel::send(Event::CancelSelectMedia);
 }
 
+fn drop_files(paths: Vec<PathBuf>) {
+    UIEventChannel::send(Event::DropFiles(paths));
+}
+
 fn open_media(path: impl Into<PathBuf>) {
     UIEventChannel::send(Event::OpenMedia(path.into()));
 }
@@ -41,6 +48,10 @@ pub fn quit() {
     UIEventChannel::send(Event::Quit);
 }
 
+pub fn refresh_accels() {
+    UIEventChannel::send(Event::RefreshAccels);
+}
+
 pub fn reset_cursor() {
     UIEventChannel::send(Event::ResetCursor);
 }
@@ -49,6 +60,10 @@ pub fn restore_context() {
     UIEventChannel::send(Event::RestoreContext);
 }
 
+fn save_project() {
+    UIEventChannel::send(Event::SaveProject);
+}
+
 fn select_media() {
     UIEventChannel::send(Event::SelectMedia);
 }
@@ -4,6 +4,8 @@ use gtk::{gio, prelude::*};
 
 use log::debug;
 
+use media::pipeline::Direction;
+
 use crate::{info::ChapterEntry, main_panel, playback, prelude::*};
 
 pub struct Dispatcher;
@@ -19,6 +21,33 @@ impl UIDispatcher for Dispatcher {
         app.add_action(&play_pause);
         play_pause.connect_activate(|_, _| playback::play_pause());
         main_ctrl.play_pause_btn.set_sensitive(true);
+
+        // Register Play backward action
+        let play_backward = gio::SimpleAction::new("play_backward", None);
+        app.add_action(&play_backward);
+        play_backward.connect_activate(|_, _| playback::play_backward());
+
+        // Register frame stepping actions
+        let next_frame = gio::SimpleAction::new("next_frame", None);
+        app.add_action(&next_frame);
+        next_frame.connect_activate(|_, _| playback::step_frame(Direction::Forward));
+
+        let previous_frame = gio::SimpleAction::new("previous_frame", None);
+        app.add_action(&previous_frame);
+        previous_frame.connect_activate(|_, _| playback::step_frame(Direction::Backward));
+
+        // Register playback speed actions
+        let speed_up = gio::SimpleAction::new("speed_up", None);
+        app.add_action(&speed_up);
+        speed_up.connect_activate(|_, _| playback::speed_up());
+
+        let slow_down = gio::SimpleAction::new("slow_down", None);
+        app.add_action(&slow_down);
+        slow_down.connect_activate(|_, _| playback::slow_down());
+
+        let reset_speed = gio::SimpleAction::new("reset_speed", None);
+        app.add_action(&reset_speed);
+        reset_speed.connect_activate(|_, _| playback::reset_speed());
     }
 
     fn handle_event(
@@ -44,11 +73,22 @@ impl UIDispatcher for Dispatcher {
                         let _ = main_ctrl.seek(seek_ts, gst::SeekFlags::ACCURATE).await;
                     }
                 }
+                Pause => main_ctrl.pause().await,
+                PlayBackward => main_ctrl.play_backward().await,
                 PlayPause => main_ctrl.play_pause().await,
                 PlayRange { start } => {
                     let _ = main_ctrl.play_range(start).await;
                 }
                 PlayRangeDone => main_ctrl.play_range_done().await,
+                ResetSpeed => main_ctrl.set_playback_rate(1f64).await,
+                SlowDown => {
+                    let rate = playback::previous_rate(main_ctrl.playback_rate());
+                    main_ctrl.set_playback_rate(rate).await;
+                }
+                SpeedUp => {
+                    let rate = playback::next_rate(main_ctrl.playback_rate());
+                    main_ctrl.set_playback_rate(rate).await;
+                }
                 PreviousChapter => {
                     let seek_ts = main_ctrl
                         .current_ts()
@@ -58,6 +98,7 @@ impl UIDispatcher for Dispatcher {
                         .seek(seek_ts.unwrap_or_default(), gst::SeekFlags::ACCURATE)
                         .await;
                 }
+                StepFrame(direction) => main_ctrl.step_frame(direction).await,
                 ClearSeek => {
                     main_ctrl.seek_manager = playback::SeekManager::default();
                 }
@@ -73,31 +114,4 @@ impl UIDispatcher for Dispatcher {
         }
         .boxed_local()
     }
-
-    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
-        use UIFocusContext::*;
-
-        match ctx {
-            PlaybackPage => {
-                app.set_accels_for_action("app.play_pause", &["space", "AudioPlay"]);
-                app.set_accels_for_action("app.next_chapter", &["Down", "AudioNext"]);
-                app.set_accels_for_action("app.previous_chapter", &["Up", "AudioPrev"]);
-            }
-            ExportPage | SplitPage | StreamsPage => {
-                app.set_accels_for_action("app.play_pause", &["space", "AudioPlay"]);
-                app.set_accels_for_action("app.next_chapter", &["AudioNext"]);
-                app.set_accels_for_action("app.previous_chapter", &["AudioPrev"]);
-            }
-            TextEntry => {
-                app.set_accels_for_action("app.play_pause", &["AudioPlay"]);
-                app.set_accels_for_action("app.next_chapter", &[]);
-                app.set_accels_for_action("app.previous_chapter", &[]);
-            }
-            InfoBar => {
-                app.set_accels_for_action("app.play_pause", &["AudioPlay"]);
-                app.set_accels_for_action("app.next_chapter", &[]);
-                app.set_accels_for_action("app.previous_chapter", &[]);
-            }
-        }
-    }
 }
@@ -4,6 +4,7 @@ use std::time::{Duration, Instant};
 mod dispatcher;
 pub use self::dispatcher::Dispatcher;
 
+use media::pipeline::Direction;
 use renderers::Timestamp;
 
 use crate::UIEventChannel;
@@ -12,12 +13,18 @@ use crate::UIEventChannel;
 pub enum Event {
     Eos,
     NextChapter,
+    Pause,
     PreviousChapter,
+    PlayBackward,
     PlayPause,
     PlayRange {
         start: Timestamp,
     },
     PlayRangeDone,
+    ResetSpeed,
+    SlowDown,
+    SpeedUp,
+    StepFrame(Direction),
     ClearSeek,
     Seek {
         target: Timestamp,
@@ -37,10 +44,18 @@ pub fn next_chapter() {
     UIEventChannel::send(Event::NextChapter);
 }
 
+pub fn pause() {
+    UIEventChannel::send(Event::Pause);
+}
+
 pub fn previous_chapter() {
     UIEventChannel::send(Event::PreviousChapter);
 }
 
+pub fn play_backward() {
+    UIEventChannel::send(Event::PlayBackward);
+}
+
 pub fn play_pause() {
     UIEventChannel::send(Event::PlayPause);
 }
@@ -53,6 +68,44 @@ pub fn play_range_done() {
     UIEventChannel::send(Event::PlayRangeDone);
 }
 
+pub fn step_frame(direction: Direction) {
+    UIEventChannel::send(Event::StepFrame(direction));
+}
+
+pub fn reset_speed() {
+    UIEventChannel::send(Event::ResetSpeed);
+}
+
+pub fn slow_down() {
+    UIEventChannel::send(Event::SlowDown);
+}
+
+pub fn speed_up() {
+    UIEventChannel::send(Event::SpeedUp);
+}
+
+/// Playback rates selected using `slow_down` & `speed_up`.
+pub(crate) const RATE_STEPS: [f64; 9] = [0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0];
+
+/// Returns the step following `rate` in `RATE_STEPS`.
+pub(crate) fn next_rate(rate: f64) -> f64 {
+    RATE_STEPS
+        .iter()
+        .copied()
+        .find(|step| *step > rate + f64::EPSILON)
+        .unwrap_or(RATE_STEPS[RATE_STEPS.len() - 1])
+}
+
+/// Returns the step preceding `rate` in `RATE_STEPS`.
+pub(crate) fn previous_rate(rate: f64) -> f64 {
+    RATE_STEPS
+        .iter()
+        .rev()
+        .copied()
+        .find(|step| *step < rate - f64::EPSILON)
+        .unwrap_or(RATE_STEPS[0])
+}
+
 pub fn seek(target: impl Into<Timestamp>, flags: gst::SeekFlags) {
     UIEventChannel::send(Event::SeekRequest {
         target: target.into(),
@@ -160,3 +213,20 @@ impl Default for SeekManager {
         SeekManager::NotSeeking
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{next_rate, previous_rate};
+
+    #[test]
+    fn rate_steps() {
+        assert_eq!(next_rate(1.0), 1.25);
+        assert_eq!(next_rate(2.0), 2.5);
+        assert_eq!(next_rate(3.0), 3.0);
+        assert_eq!(next_rate(1.1), 1.25);
+
+        assert_eq!(previous_rate(1.0), 0.75);
+        assert_eq!(previous_rate(0.5), 0.5);
+        assert_eq!(previous_rate(1.1), 1.0);
+    }
+}
@@ -0,0 +1,183 @@
+use gtk::prelude::*;
+
+use application::{gettext, Preferences, CONFIG};
+use metadata::Format;
+
+use crate::{batch, prelude::*};
+
+const ZOOM_DURATIONS_MS: [u64; 6] = [1_000, 2_000, 4_000, 8_000, 16_000, 32_000];
+
+const EXPORT_FORMATS: [Format; 9] = [
+    Format::MKVMergeText,
+    Format::CueSheet,
+    Format::WebVtt,
+    Format::Srt,
+    Format::Description,
+    Format::PodcastChapters,
+    Format::FFMetadata,
+    Format::Id3v2,
+    Format::Matroska,
+];
+const SPLIT_FORMATS: [Format; 5] = [
+    Format::Flac,
+    Format::Wave,
+    Format::Opus,
+    Format::Vorbis,
+    Format::MP3,
+];
+
+/// Id of the format entry which keeps the first available format.
+const FIRST_FORMAT_ID: &str = "";
+
+/// Dialog to edit the user preferences.
+pub struct Controller {
+    pub(super) dialog: gtk::Dialog,
+
+    gl_disabled_btn: gtk::CheckButton,
+    queue_size_spin: gtk::SpinButton,
+    buffer_size_spin: gtk::SpinButton,
+    zoom_combo: gtk::ComboBoxText,
+    export_format_combo: gtk::ComboBoxText,
+    split_format_combo: gtk::ComboBoxText,
+    pub(super) same_dir_btn: gtk::CheckButton,
+    output_dir_chooser: gtk::FileChooserButton,
+}
+
+impl Controller {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let dialog: gtk::Dialog = builder.object("preferences-dialog").unwrap();
+        dialog.connect_delete_event(|dialog, _| {
+            dialog.hide();
+            Inhibit(true)
+        });
+
+        let zoom_combo: gtk::ComboBoxText = builder.object("preferences_zoom-combo").unwrap();
+        for duration_ms in ZOOM_DURATIONS_MS {
+            zoom_combo.append(
+                Some(&duration_ms.to_string()),
+                &gettext("{} s per 1000 pixels").replacen(
+                    "{}",
+                    &(duration_ms / 1_000).to_string(),
+                    1,
+                ),
+            );
+        }
+
+        let export_format_combo: gtk::ComboBoxText =
+            builder.object("preferences_export_format-combo").unwrap();
+        Self::fill_format_combo(&export_format_combo, &EXPORT_FORMATS);
+
+        let split_format_combo: gtk::ComboBoxText =
+            builder.object("preferences_split_format-combo").unwrap();
+        Self::fill_format_combo(&split_format_combo, &SPLIT_FORMATS);
+
+        Controller {
+            dialog,
+
+            gl_disabled_btn: builder.object("preferences_gl_disabled-check").unwrap(),
+            queue_size_spin: builder.object("preferences_queue_size-spin").unwrap(),
+            buffer_size_spin: builder.object("preferences_buffer_size-spin").unwrap(),
+            zoom_combo,
+            export_format_combo,
+            split_format_combo,
+            same_dir_btn: builder.object("preferences_same_dir-check").unwrap(),
+            output_dir_chooser: builder.object("preferences_output_dir-chooser").unwrap(),
+        }
+    }
+
+    fn fill_format_combo(combo: &gtk::ComboBoxText, formats: &[Format]) {
+        combo.append(Some(FIRST_FORMAT_ID), &gettext("First available"));
+        for format in formats {
+            combo.append(Some(&format.name()), &batch::format_label(*format));
+        }
+    }
+
+    /// Shows the dialog with the preferences from the configuration.
+    pub fn present(&self) {
+        let preferences = CONFIG.read().unwrap().preferences.clone();
+
+        self.gl_disabled_btn.set_active(preferences.is_gl_disabled);
+        self.queue_size_spin
+            .set_value(preferences.queue_size_ms as f64 / 1_000f64);
+        self.buffer_size_spin
+            .set_value(preferences.buffer_size_ms as f64 / 1_000f64);
+        if !self
+            .zoom_combo
+            .set_active_id(Some(&preferences.zoom_duration_ms.to_string()))
+        {
+            self.zoom_combo
+                .set_active_id(Some(&Preferences::default().zoom_duration_ms.to_string()));
+        }
+
+        Self::select_format(&self.export_format_combo, &preferences.export_format);
+        Self::select_format(&self.split_format_combo, &preferences.split_format);
+
+        self.same_dir_btn
+            .set_active(preferences.output_dir.is_none());
+        self.output_dir_chooser
+            .set_sensitive(preferences.output_dir.is_some());
+        match preferences.output_dir.as_ref() {
+            Some(output_dir) => {
+                self.output_dir_chooser.set_filename(output_dir);
+            }
+            None => self.output_dir_chooser.unselect_all(),
+        }
+
+        self.dialog.present();
+    }
+
+    fn select_format(combo: &gtk::ComboBoxText, format_name: &Option<String>) {
+        let format_name = format_name.as_deref().unwrap_or(FIRST_FORMAT_ID);
+        if !combo.set_active_id(Some(format_name)) {
+            combo.set_active_id(Some(FIRST_FORMAT_ID));
+        }
+    }
+
+    fn selected_format(combo: &gtk::ComboBoxText) -> Option<String> {
+        combo
+            .active_id()
+            .filter(|format_name| format_name.as_str() != FIRST_FORMAT_ID)
+            .map(|format_name| format_name.to_string())
+    }
+
+    pub fn toggle_same_dir(&self, is_same_dir: bool) {
+        self.output_dir_chooser.set_sensitive(!is_same_dir);
+    }
+
+    /// Saves the preferences from the dialog in the configuration.
+    ///
+    /// Returns `true` if a restart is required to apply the changes.
+    pub fn apply(&self) -> bool {
+        let mut config = CONFIG.write().unwrap();
+        let prev_preferences = config.preferences.clone();
+
+        config.preferences = Preferences {
+            is_gl_disabled: self.gl_disabled_btn.is_active(),
+            queue_size_ms: (self.queue_size_spin.value() * 1_000f64) as u64,
+            buffer_size_ms: (self.buffer_size_spin.value() * 1_000f64) as u64,
+            zoom_duration_ms: self
+                .zoom_combo
+                .active_id()
+                .and_then(|duration_ms| duration_ms.parse().ok())
+                .unwrap_or(prev_preferences.zoom_duration_ms),
+            export_format: Self::selected_format(&self.export_format_combo),
+            split_format: Self::selected_format(&self.split_format_combo),
+            output_dir: if self.same_dir_btn.is_active() {
+                None
+            } else {
+                self.output_dir_chooser
+                    .filename()
+                    .or_else(|| prev_preferences.output_dir.clone())
+            },
+        };
+        config.save();
+
+        config.preferences.is_gl_disabled != prev_preferences.is_gl_disabled
+    }
+}
+
+impl UIController for Controller {
+    fn cleanup(&mut self) {
+        // The preferences don't depend on the media
+    }
+}
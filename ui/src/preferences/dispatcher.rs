@@ -0,0 +1,73 @@
+use futures::{
+    future::{self, LocalBoxFuture},
+    prelude::*,
+};
+
+use gtk::{gio, prelude::*};
+
+use log::debug;
+
+use application::{gettext, CONFIG};
+
+use crate::{info_bar, main_panel, preferences, prelude::*};
+
+pub struct Dispatcher;
+
+impl UIDispatcher for Dispatcher {
+    type Controller = preferences::Controller;
+    type Event = preferences::Event;
+
+    fn setup(ctrl: &mut preferences::Controller, app: &gtk::Application) {
+        let show = gio::SimpleAction::new("preferences", None);
+        app.add_action(&show);
+        show.connect_activate(|_, _| preferences::show());
+
+        ctrl.dialog.connect_response(|dialog, response| {
+            dialog.hide();
+            if response == gtk::ResponseType::Apply {
+                preferences::apply();
+            }
+        });
+
+        ctrl.same_dir_btn
+            .connect_toggled(|btn| preferences::toggle_same_dir(btn.is_active()));
+    }
+
+    fn handle_event(
+        main_ctrl: &mut main_panel::Controller,
+        event: impl Into<Self::Event>,
+    ) -> LocalBoxFuture<'_, ()> {
+        use preferences::Event::*;
+
+        let event = event.into();
+        debug!("handling {:?}", event);
+        match event {
+            Apply => {
+                let prev_formats = default_formats();
+                let must_restart = main_ctrl.preferences.apply();
+                // Don't override the formats selected for current media needlessly
+                if default_formats() != prev_formats {
+                    main_ctrl.select_default_formats();
+                }
+                if must_restart {
+                    info_bar::show_info(gettext(
+                        "Please restart the application to apply the hardware acceleration setting.",
+                    ));
+                }
+            }
+            Show => main_ctrl.preferences.present(),
+            ToggleSameDir(is_same_dir) => main_ctrl.preferences.toggle_same_dir(is_same_dir),
+        }
+
+        future::ready(()).boxed_local()
+    }
+}
+
+/// Returns the export & split formats selected at startup.
+fn default_formats() -> (Option<String>, Option<String>) {
+    let preferences = &CONFIG.read().unwrap().preferences;
+    (
+        preferences.export_format.clone(),
+        preferences.split_format.clone(),
+    )
+}
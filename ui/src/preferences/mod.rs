@@ -0,0 +1,26 @@
+mod controller;
+pub use self::controller::Controller;
+
+mod dispatcher;
+pub use self::dispatcher::Dispatcher;
+
+use crate::UIEventChannel;
+
+#[derive(Debug)]
+pub enum Event {
+    Apply,
+    Show,
+    ToggleSameDir(bool),
+}
+
+fn apply() {
+    UIEventChannel::send(Event::Apply);
+}
+
+pub fn show() {
+    UIEventChannel::send(Event::Show);
+}
+
+fn toggle_same_dir(is_same_dir: bool) {
+    UIEventChannel::send(Event::ToggleSameDir(is_same_dir));
+}
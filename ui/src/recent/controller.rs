@@ -0,0 +1,190 @@
+use gtk::{gio, glib, prelude::*};
+
+use std::path::{Path, PathBuf};
+
+use application::{gettext, RecentMedia, CONFIG};
+use metadata::Timestamp4Humans;
+
+use crate::prelude::*;
+
+/// Most recently used media, listed in the header bar menu and on the start page.
+pub struct Controller {
+    recent_section: gio::Menu,
+
+    start_page: gtk::Box,
+    playback_paned: gtk::Paned,
+    pub(super) list_box: gtk::ListBox,
+
+    pub(super) open_recent_action: gio::SimpleAction,
+    pub(super) reopen_last_action: gio::SimpleAction,
+    pub(super) clear_action: gio::SimpleAction,
+
+    paths: Vec<PathBuf>,
+}
+
+impl Controller {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let reopen_last = CONFIG.read().unwrap().media.reopen_last;
+
+        let ctrl = Controller {
+            recent_section: builder.object("recent-section").unwrap(),
+
+            start_page: builder.object("recent-start_page-box").unwrap(),
+            playback_paned: builder.object("playback-paned").unwrap(),
+            list_box: builder.object("recent-list-box").unwrap(),
+
+            open_recent_action: gio::SimpleAction::new(
+                "open_recent",
+                Some(glib::VariantTy::STRING),
+            ),
+            reopen_last_action: gio::SimpleAction::new_stateful(
+                "reopen_last",
+                None,
+                &reopen_last.to_variant(),
+            ),
+            clear_action: gio::SimpleAction::new("clear_recent", None),
+
+            paths: Vec::new(),
+        };
+
+        ctrl.show_start_page(true);
+
+        ctrl
+    }
+
+    /// Returns the media to open at startup, if requested.
+    pub fn last_to_reopen() -> Option<PathBuf> {
+        let config = CONFIG.read().unwrap();
+        if !config.media.reopen_last {
+            return None;
+        }
+
+        config
+            .media
+            .recent
+            .first()
+            .map(|recent| recent.path.clone())
+            .filter(|path| path.is_file())
+    }
+
+    pub fn show_start_page(&self, must_show: bool) {
+        self.start_page.set_visible(must_show);
+        self.playback_paned.set_visible(!must_show);
+    }
+
+    pub fn path(&self, idx: i32) -> Option<PathBuf> {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.paths.get(idx))
+            .cloned()
+    }
+
+    pub fn clear(&mut self) {
+        CONFIG.write().unwrap().media.recent.clear();
+        self.refresh();
+    }
+
+    /// Updates the menu and the start page from the configuration.
+    pub fn refresh(&mut self) {
+        self.recent_section.remove_all();
+        for row in self.list_box.children() {
+            self.list_box.remove(&row);
+        }
+        self.paths.clear();
+
+        let recent_list: Vec<RecentMedia> = CONFIG
+            .read()
+            .unwrap()
+            .media
+            .recent
+            .iter()
+            .filter(|recent| recent.path.is_file())
+            .cloned()
+            .collect();
+
+        for recent in recent_list {
+            let path_str = match recent.path.to_str() {
+                Some(path_str) => path_str.to_string(),
+                None => continue,
+            };
+
+            let item = gio::MenuItem::new(Some(&Self::menu_label(&recent)), None);
+            item.set_action_and_target_value(Some("app.open_recent"), Some(&path_str.to_variant()));
+            self.recent_section.append_item(&item);
+
+            self.list_box.add(&Self::new_row(&recent));
+            self.paths.push(recent.path);
+        }
+
+        self.clear_action.set_enabled(!self.paths.is_empty());
+    }
+
+    fn file_name(path: &Path) -> String {
+        path.file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy())
+            .to_string()
+    }
+
+    fn position_label(recent: &RecentMedia) -> String {
+        let position = Timestamp4Humans::from_nano(recent.position).to_string();
+        if recent.is_toc_edited {
+            gettext("{} · chapters edited").replacen("{}", &position, 1)
+        } else {
+            position
+        }
+    }
+
+    fn menu_label(recent: &RecentMedia) -> String {
+        format!(
+            "{} ({})",
+            Self::file_name(&recent.path),
+            Self::position_label(recent)
+        )
+    }
+
+    fn new_row(recent: &RecentMedia) -> gtk::ListBoxRow {
+        let name_lbl = gtk::Label::new(None);
+        name_lbl.set_markup(&format!(
+            "<b>{}</b>",
+            glib::markup_escape_text(&Self::file_name(&recent.path))
+        ));
+        name_lbl.set_halign(gtk::Align::Start);
+        name_lbl.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+
+        let dir_lbl = gtk::Label::new(
+            recent
+                .path
+                .parent()
+                .map(|dir| dir.to_string_lossy())
+                .as_deref(),
+        );
+        dir_lbl.set_halign(gtk::Align::Start);
+        dir_lbl.set_ellipsize(gtk::pango::EllipsizeMode::Start);
+        dir_lbl.style_context().add_class("dim-label");
+
+        let text_box = gtk::Box::new(gtk::Orientation::Vertical, 2);
+        text_box.pack_start(&name_lbl, false, false, 0);
+        text_box.pack_start(&dir_lbl, false, false, 0);
+
+        let position_lbl = gtk::Label::new(Some(&Self::position_label(recent)));
+        position_lbl.style_context().add_class("dim-label");
+
+        let row_box = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        row_box.set_border_width(6);
+        row_box.pack_start(&text_box, true, true, 0);
+        row_box.pack_end(&position_lbl, false, false, 0);
+
+        let row = gtk::ListBoxRow::new();
+        row.set_tooltip_text(Some(&recent.path.to_string_lossy()));
+        row.add(&row_box);
+        row.show_all();
+
+        row
+    }
+}
+
+impl UIController for Controller {
+    fn cleanup(&mut self) {
+        // The recent list outlives the media
+    }
+}
@@ -0,0 +1,69 @@
+use futures::{
+    future::{self, LocalBoxFuture},
+    prelude::*,
+};
+
+use gtk::prelude::*;
+
+use log::debug;
+
+use application::CONFIG;
+
+use crate::{main_panel, prelude::*, recent};
+
+pub struct Dispatcher;
+
+impl UIDispatcher for Dispatcher {
+    type Controller = recent::Controller;
+    type Event = recent::Event;
+
+    fn setup(ctrl: &mut recent::Controller, app: &gtk::Application) {
+        app.add_action(&ctrl.open_recent_action);
+        ctrl.open_recent_action.connect_activate(|_, param| {
+            if let Some(path) = param.and_then(|param| param.get::<String>()) {
+                recent::open(path.into());
+            }
+        });
+
+        app.add_action(&ctrl.reopen_last_action);
+        ctrl.reopen_last_action
+            .connect_change_state(|action, state| {
+                if let Some(state) = state {
+                    action.set_state(state);
+                    CONFIG.write().unwrap().media.reopen_last =
+                        state.get::<bool>().unwrap_or(false);
+                }
+            });
+
+        app.add_action(&ctrl.clear_action);
+        ctrl.clear_action.connect_activate(|_, _| recent::clear());
+
+        ctrl.list_box
+            .connect_row_activated(|_, row| recent::open_row(row.index()));
+
+        ctrl.refresh();
+    }
+
+    fn handle_event(
+        main_ctrl: &mut main_panel::Controller,
+        event: impl Into<Self::Event>,
+    ) -> LocalBoxFuture<'_, ()> {
+        use recent::Event::*;
+
+        let event = event.into();
+        debug!("handling {:?}", event);
+        match event {
+            Clear => main_ctrl.recent.clear(),
+            Open(path) => {
+                return async move { main_ctrl.open_recent(path).await }.boxed_local();
+            }
+            OpenRow(idx) => {
+                if let Some(path) = main_ctrl.recent.path(idx) {
+                    return async move { main_ctrl.open_recent(path).await }.boxed_local();
+                }
+            }
+        }
+
+        future::ready(()).boxed_local()
+    }
+}
@@ -0,0 +1,28 @@
+mod controller;
+pub use self::controller::Controller;
+
+mod dispatcher;
+pub use self::dispatcher::Dispatcher;
+
+use std::path::PathBuf;
+
+use crate::UIEventChannel;
+
+#[derive(Debug)]
+pub enum Event {
+    Clear,
+    Open(PathBuf),
+    OpenRow(i32),
+}
+
+fn clear() {
+    UIEventChannel::send(Event::Clear);
+}
+
+pub fn open(path: PathBuf) {
+    UIEventChannel::send(Event::Open(path));
+}
+
+fn open_row(idx: i32) {
+    UIEventChannel::send(Event::OpenRow(idx));
+}
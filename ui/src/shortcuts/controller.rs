@@ -0,0 +1,322 @@
+use gtk::{gdk, glib, prelude::*};
+
+use application::{gettext, CONFIG};
+
+use super::keymap::{self, Group};
+use crate::{main_panel, prelude::*};
+
+const ACTION_COL: u32 = 0;
+const TITLE_COL: u32 = 1;
+const KEY_COL: u32 = 2;
+const MODS_COL: u32 = 3;
+const IS_SHORTCUT_COL: u32 = 4;
+const OTHER_ACCELS_COL: u32 = 5;
+
+/// Keyboard shortcuts editor and help window.
+pub struct Controller {
+    main_window: gtk::ApplicationWindow,
+    pub(super) dialog: gtk::Dialog,
+    pub(super) treeview: gtk::TreeView,
+    pub(super) store: gtk::TreeStore,
+    pub(super) accel_renderer: gtk::CellRendererAccel,
+
+    pub(super) reset_btn: gtk::Button,
+    pub(super) reset_all_btn: gtk::Button,
+}
+
+impl Controller {
+    pub fn new(builder: &gtk::Builder) -> Self {
+        let dialog: gtk::Dialog = builder.object("shortcuts-dialog").unwrap();
+        let treeview: gtk::TreeView = builder.object("shortcuts-treeview").unwrap();
+
+        let title_col = gtk::TreeViewColumn::new();
+        title_col.set_title(&gettext("Action"));
+        title_col.set_expand(true);
+        let title_renderer = gtk::CellRendererText::new();
+        title_renderer.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        title_col.pack_start(&title_renderer, true);
+        title_col.add_attribute(&title_renderer, "markup", TITLE_COL as i32);
+        treeview.append_column(&title_col);
+
+        let accel_col = gtk::TreeViewColumn::new();
+        accel_col.set_title(&gettext("Shortcut"));
+        let accel_renderer = gtk::CellRendererAccel::new();
+        accel_renderer.set_accel_mode(gtk::CellRendererAccelMode::Other);
+        accel_renderer.set_editable(true);
+        accel_col.pack_start(&accel_renderer, true);
+        accel_col.add_attribute(&accel_renderer, "accel-key", KEY_COL as i32);
+        accel_col.add_attribute(&accel_renderer, "accel-mods", MODS_COL as i32);
+        accel_col.add_attribute(&accel_renderer, "visible", IS_SHORTCUT_COL as i32);
+        treeview.append_column(&accel_col);
+
+        let other_col = gtk::TreeViewColumn::new();
+        other_col.set_title(&gettext("Also"));
+        let other_renderer = gtk::CellRendererText::new();
+        other_renderer.set_sensitive(false);
+        other_col.pack_start(&other_renderer, true);
+        other_col.add_attribute(&other_renderer, "text", OTHER_ACCELS_COL as i32);
+        treeview.append_column(&other_col);
+
+        dialog.connect_delete_event(|dialog, _| {
+            dialog.hide();
+            Inhibit(true)
+        });
+        dialog.connect_response(|dialog, _| dialog.hide());
+
+        let mut ctrl = Controller {
+            main_window: builder.object("application-window").unwrap(),
+            dialog,
+            treeview,
+            store: builder.object("shortcuts-tree-store").unwrap(),
+            accel_renderer,
+
+            reset_btn: builder.object("shortcuts_reset-btn").unwrap(),
+            reset_all_btn: builder.object("shortcuts_reset_all-btn").unwrap(),
+        };
+
+        ctrl.refresh();
+
+        ctrl
+    }
+
+    pub fn present(&mut self) {
+        self.refresh();
+        self.dialog.present();
+    }
+
+    /// Returns the action for the row at `tree_path`, if it is a shortcut.
+    pub(super) fn action_at(store: &gtk::TreeStore, tree_path: &gtk::TreePath) -> Option<String> {
+        let iter = store.iter(tree_path)?;
+        if !store
+            .value(&iter, IS_SHORTCUT_COL as i32)
+            .get::<bool>()
+            .ok()?
+        {
+            return None;
+        }
+
+        store.value(&iter, ACTION_COL as i32).get::<String>().ok()
+    }
+
+    pub fn selected_action(&self) -> Option<String> {
+        let (paths, _) = self.treeview.selection().selected_rows();
+        paths
+            .first()
+            .and_then(|tree_path| Self::action_at(&self.store, tree_path))
+    }
+
+    /// Binds `action` to `accel` only, after confirmation if `accel` is already used.
+    pub async fn set_accel(&mut self, action: &str, accel: &str) {
+        let conflict = keymap::conflict(&CONFIG.read().unwrap().keymap, action, accel);
+        if let Some(other) = conflict {
+            let dialog = gtk::MessageDialog::new(
+                Some(&self.dialog),
+                gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                gtk::MessageType::Question,
+                gtk::ButtonsType::None,
+                &gettext("“{}” is already used by “{}”.\nDo you want to reassign it?")
+                    .replacen("{}", &Self::accel_label(accel), 1)
+                    .replacen("{}", &other.title, 1),
+            );
+            dialog.add_buttons(&[
+                (&gettext("Cancel"), gtk::ResponseType::Cancel),
+                (&gettext("Reassign"), gtk::ResponseType::Accept),
+            ]);
+            let response = dialog.run_future().await;
+            dialog.close();
+
+            if response != gtk::ResponseType::Accept {
+                return;
+            }
+
+            let mut config = CONFIG.write().unwrap();
+            let normalized = keymap::normalize(accel);
+            let other_accels = other
+                .accels(&config.keymap)
+                .into_iter()
+                .filter(|other_accel| keymap::normalize(other_accel) != normalized)
+                .collect();
+            config
+                .keymap
+                .accels
+                .insert(other.action.to_string(), other_accels);
+        }
+
+        CONFIG
+            .write()
+            .unwrap()
+            .keymap
+            .accels
+            .insert(action.to_string(), vec![accel.to_string()]);
+
+        self.keymap_changed();
+    }
+
+    /// Disables the accelerators for `action`.
+    pub fn clear_accels(&mut self, action: &str) {
+        CONFIG
+            .write()
+            .unwrap()
+            .keymap
+            .accels
+            .insert(action.to_string(), Vec::new());
+
+        self.keymap_changed();
+    }
+
+    /// Restores the default accelerators for `action`.
+    pub fn reset(&mut self, action: &str) {
+        CONFIG.write().unwrap().keymap.accels.remove(action);
+        self.keymap_changed();
+    }
+
+    pub fn reset_all(&mut self) {
+        CONFIG.write().unwrap().keymap.accels.clear();
+        self.keymap_changed();
+    }
+
+    fn keymap_changed(&mut self) {
+        let selected_action = self.selected_action();
+
+        self.refresh();
+        main_panel::refresh_accels();
+
+        // Restore the selection
+        if let Some(selected_action) = selected_action {
+            self.store.foreach(|store, tree_path, iter| {
+                let action = store.value(iter, ACTION_COL as i32).get::<String>().ok();
+                if action.as_deref() == Some(selected_action.as_str()) {
+                    self.treeview.selection().select_path(tree_path);
+                    return true;
+                }
+                false
+            });
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.store.clear();
+
+        let keymap = &CONFIG.read().unwrap().keymap;
+        let mut group_iter: Option<(Group, gtk::TreeIter)> = None;
+        for shortcut in keymap::shortcuts() {
+            let parent = match group_iter {
+                Some((group, ref iter)) if group == shortcut.group => iter.clone(),
+                _ => {
+                    let iter = self.store.insert_with_values(
+                        None,
+                        None,
+                        &[
+                            (ACTION_COL, &""),
+                            (
+                                TITLE_COL,
+                                &format!(
+                                    "<b>{}</b>",
+                                    glib::markup_escape_text(&shortcut.group.title())
+                                ),
+                            ),
+                            (IS_SHORTCUT_COL, &false),
+                        ],
+                    );
+                    group_iter = Some((shortcut.group, iter.clone()));
+                    iter
+                }
+            };
+
+            let accels = shortcut.accels(keymap);
+            let (key, mods) = accels
+                .first()
+                .map_or((0, gdk::ModifierType::empty()), |accel| {
+                    gtk::accelerator_parse(accel)
+                });
+
+            let other_accels = accels
+                .iter()
+                .skip(1)
+                .map(String::as_str)
+                .chain(shortcut.media_keys.iter().copied())
+                .map(Self::accel_label)
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            self.store.insert_with_values(
+                Some(&parent),
+                None,
+                &[
+                    (ACTION_COL, &shortcut.action),
+                    (
+                        TITLE_COL,
+                        &glib::markup_escape_text(&shortcut.title).as_str(),
+                    ),
+                    (KEY_COL, &key),
+                    (MODS_COL, &mods),
+                    (IS_SHORTCUT_COL, &true),
+                    (OTHER_ACCELS_COL, &other_accels),
+                ],
+            );
+        }
+
+        self.treeview.expand_all();
+    }
+
+    fn accel_label(accel: &str) -> String {
+        let (key, mods) = gtk::accelerator_parse(accel);
+        gtk::accelerator_get_label(key, mods)
+            .map_or_else(|| accel.to_string(), |label| label.to_string())
+    }
+
+    /// Shows a help window listing the shortcuts from the current keymap.
+    pub fn show_help(&self) {
+        let section = gtk::ShortcutsSection::builder()
+            .section_name("shortcuts")
+            .visible(true)
+            .build();
+
+        let keymap = &CONFIG.read().unwrap().keymap;
+        let mut group: Option<(Group, gtk::ShortcutsGroup)> = None;
+        for shortcut in keymap::shortcuts() {
+            let mut accels = shortcut.accels(keymap);
+            accels.extend(shortcut.media_keys.iter().map(ToString::to_string));
+            if accels.is_empty() {
+                continue;
+            }
+
+            let shortcuts_group = match group {
+                Some((cur_group, ref shortcuts_group)) if cur_group == shortcut.group => {
+                    shortcuts_group.clone()
+                }
+                _ => {
+                    let shortcuts_group = gtk::ShortcutsGroup::builder()
+                        .title(&shortcut.group.title())
+                        .visible(true)
+                        .build();
+                    section.add(&shortcuts_group);
+                    group = Some((shortcut.group, shortcuts_group.clone()));
+                    shortcuts_group
+                }
+            };
+
+            shortcuts_group.add(
+                &gtk::ShortcutsShortcut::builder()
+                    .title(&shortcut.title)
+                    .accelerator(&accels.join(" "))
+                    .visible(true)
+                    .build(),
+            );
+        }
+
+        let help_window = gtk::ShortcutsWindow::builder()
+            .transient_for(&self.main_window)
+            .modal(true)
+            .build();
+        help_window.add(&section);
+        help_window.show_all();
+        help_window.present();
+    }
+}
+
+impl UIController for Controller {
+    fn cleanup(&mut self) {
+        // The keymap doesn't depend on the media
+    }
+}
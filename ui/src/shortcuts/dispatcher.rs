@@ -0,0 +1,97 @@
+use futures::{
+    future::{self, LocalBoxFuture},
+    prelude::*,
+};
+
+use gtk::{gio, prelude::*};
+
+use log::debug;
+
+use application::CONFIG;
+
+use super::keymap;
+use crate::{main_panel, prelude::*, shortcuts};
+
+pub struct Dispatcher;
+
+impl UIDispatcher for Dispatcher {
+    type Controller = shortcuts::Controller;
+    type Event = shortcuts::Event;
+
+    fn setup(ctrl: &mut shortcuts::Controller, app: &gtk::Application) {
+        let show = gio::SimpleAction::new("shortcuts", None);
+        app.add_action(&show);
+        show.connect_activate(|_, _| shortcuts::show());
+
+        let show_help = gio::SimpleAction::new("shortcuts_help", None);
+        app.add_action(&show_help);
+        show_help.connect_activate(|_, _| shortcuts::show_help());
+
+        // Don't trigger the actions while the new keys are pressed
+        ctrl.accel_renderer.connect_editing_started(|_, _, _| {
+            main_panel::temporarily_switch_to(UIFocusContext::ShortcutCapture);
+        });
+        ctrl.accel_renderer.connect_editing_canceled(|_| {
+            main_panel::restore_context();
+        });
+
+        let store = ctrl.store.clone();
+        ctrl.accel_renderer
+            .connect_accel_edited(move |_, tree_path, key, mods, _| {
+                let action = shortcuts::Controller::action_at(&store, &tree_path);
+                if let (Some(action), Some(accel)) = (action, gtk::accelerator_name(key, mods)) {
+                    shortcuts::set_accel(action, accel.to_string());
+                }
+                main_panel::restore_context();
+            });
+
+        let store = ctrl.store.clone();
+        ctrl.accel_renderer
+            .connect_accel_cleared(move |_, tree_path| {
+                if let Some(action) = shortcuts::Controller::action_at(&store, &tree_path) {
+                    shortcuts::clear_accels(action);
+                }
+                main_panel::restore_context();
+            });
+
+        ctrl.reset_btn.connect_clicked(|_| shortcuts::reset());
+        ctrl.reset_all_btn
+            .connect_clicked(|_| shortcuts::reset_all());
+    }
+
+    fn handle_event(
+        main_ctrl: &mut main_panel::Controller,
+        event: impl Into<Self::Event>,
+    ) -> LocalBoxFuture<'_, ()> {
+        use shortcuts::Event::*;
+
+        let event = event.into();
+        debug!("handling {:?}", event);
+        match event {
+            ClearAccels(action) => main_ctrl.shortcuts.clear_accels(&action),
+            Reset => {
+                if let Some(action) = main_ctrl.shortcuts.selected_action() {
+                    main_ctrl.shortcuts.reset(&action);
+                }
+            }
+            ResetAll => main_ctrl.shortcuts.reset_all(),
+            SetAccel { action, accel } => {
+                return async move { main_ctrl.shortcuts.set_accel(&action, &accel).await }
+                    .boxed_local();
+            }
+            Show => main_ctrl.shortcuts.present(),
+            ShowHelp => main_ctrl.shortcuts.show_help(),
+        }
+
+        future::ready(()).boxed_local()
+    }
+
+    fn bind_accels_for(ctx: UIFocusContext, app: &gtk::Application) {
+        let keymap = &CONFIG.read().unwrap().keymap;
+        for shortcut in keymap::shortcuts() {
+            let accels = shortcut.accels_for(ctx, keymap);
+            let accels: Vec<&str> = accels.iter().map(String::as_str).collect();
+            app.set_accels_for_action(&format!("app.{}", shortcut.action), &accels);
+        }
+    }
+}
@@ -0,0 +1,584 @@
+use application::{gettext, Keymap};
+
+use crate::UIFocusContext::{self, *};
+
+const ALL: &[UIFocusContext] = &[
+    ExportPage,
+    InfoBar,
+    PlaybackPage,
+    SplitPage,
+    StreamsPage,
+    TextEntry,
+];
+const PAGES: &[UIFocusContext] = &[ExportPage, PlaybackPage, SplitPage, StreamsPage];
+const PLAYBACK: &[UIFocusContext] = &[PlaybackPage];
+const INFO_BAR: &[UIFocusContext] = &[InfoBar];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Group {
+    Application,
+    Playback,
+    Waveform,
+    Chapters,
+    Markers,
+    InfoBar,
+}
+
+impl Group {
+    pub fn title(self) -> String {
+        match self {
+            Group::Application => gettext("Application"),
+            Group::Playback => gettext("Playback"),
+            Group::Waveform => gettext("Waveform"),
+            Group::Chapters => gettext("Chapters"),
+            Group::Markers => gettext("Markers"),
+            Group::InfoBar => gettext("Info bar"),
+        }
+    }
+}
+
+/// An action which can be bound to user defined accelerators.
+#[derive(Debug)]
+pub struct Shortcut {
+    pub action: &'static str,
+    pub title: String,
+    pub group: Group,
+    /// Focus contexts in which the accelerators are active.
+    pub contexts: &'static [UIFocusContext],
+    pub default_accels: &'static [&'static str],
+    /// Multimedia keys, which can't be changed.
+    pub media_keys: &'static [&'static str],
+    pub media_key_contexts: &'static [UIFocusContext],
+}
+
+impl Shortcut {
+    fn new(
+        action: &'static str,
+        title: String,
+        group: Group,
+        contexts: &'static [UIFocusContext],
+        default_accels: &'static [&'static str],
+    ) -> Self {
+        Shortcut {
+            action,
+            title,
+            group,
+            contexts,
+            default_accels,
+            media_keys: &[],
+            media_key_contexts: &[],
+        }
+    }
+
+    fn with_media_keys(
+        mut self,
+        media_keys: &'static [&'static str],
+        media_key_contexts: &'static [UIFocusContext],
+    ) -> Self {
+        self.media_keys = media_keys;
+        self.media_key_contexts = media_key_contexts;
+        self
+    }
+
+    /// Returns the user defined accelerators or the default ones.
+    pub fn accels(&self, keymap: &Keymap) -> Vec<String> {
+        keymap.accels.get(self.action).cloned().unwrap_or_else(|| {
+            self.default_accels
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        })
+    }
+
+    /// Returns the accelerators to bind when the focus is in `ctx`.
+    pub fn accels_for(&self, ctx: UIFocusContext, keymap: &Keymap) -> Vec<String> {
+        let mut accels = if self.contexts.contains(&ctx) {
+            self.accels(keymap)
+        } else {
+            Vec::new()
+        };
+
+        if self.media_key_contexts.contains(&ctx) {
+            accels.extend(self.media_keys.iter().map(ToString::to_string));
+        }
+
+        accels
+    }
+
+    fn shares_context_with(&self, other: &Shortcut) -> bool {
+        self.contexts.iter().any(|ctx| other.contexts.contains(ctx))
+    }
+}
+
+/// Returns the shortcuts in display order.
+pub fn shortcuts() -> Vec<Shortcut> {
+    use Group::*;
+
+    vec![
+        Shortcut::new(
+            "open",
+            gettext("Open media dialog"),
+            Application,
+            ALL,
+            &["<Ctrl>O"],
+        ),
+        Shortcut::new(
+            "save_project",
+            gettext("Save the project"),
+            Application,
+            ALL,
+            &["<Ctrl>S"],
+        ),
+        Shortcut::new(
+            "preferences",
+            gettext("Open the preferences"),
+            Application,
+            ALL,
+            &["<Ctrl>comma"],
+        ),
+        Shortcut::new(
+            "shortcuts_help",
+            gettext("Show the keyboard shortcuts"),
+            Application,
+            ALL,
+            &["<Ctrl>question"],
+        ),
+        Shortcut::new(
+            "about",
+            gettext("Open the about dialog"),
+            Application,
+            ALL,
+            &["<Ctrl>A"],
+        ),
+        Shortcut::new(
+            "quit",
+            gettext("Quit the application"),
+            Application,
+            ALL,
+            &["<Ctrl>Q"],
+        ),
+        Shortcut::new(
+            "play_pause",
+            gettext("Play/Pause"),
+            Playback,
+            PAGES,
+            &["space"],
+        )
+        .with_media_keys(&["AudioPlay"], ALL),
+        Shortcut::new(
+            "play_backward",
+            gettext("Play backward"),
+            Playback,
+            PLAYBACK,
+            &["<Shift>space"],
+        ),
+        Shortcut::new(
+            "step_forward",
+            gettext("Step forward"),
+            Playback,
+            PLAYBACK,
+            &["Right"],
+        ),
+        Shortcut::new(
+            "step_back",
+            gettext("Step back"),
+            Playback,
+            PLAYBACK,
+            &["Left"],
+        ),
+        Shortcut::new(
+            "next_frame",
+            gettext("Next video frame (paused)"),
+            Playback,
+            PLAYBACK,
+            &["period"],
+        ),
+        Shortcut::new(
+            "previous_frame",
+            gettext("Previous video frame (paused)"),
+            Playback,
+            PLAYBACK,
+            &["comma"],
+        ),
+        Shortcut::new(
+            "next_chapter",
+            gettext("Go to next chapter"),
+            Playback,
+            PLAYBACK,
+            &["Down"],
+        )
+        .with_media_keys(&["AudioNext"], PAGES),
+        Shortcut::new(
+            "previous_chapter",
+            gettext("Go to the beginning of current chapter or previous chapter"),
+            Playback,
+            PLAYBACK,
+            &["Up"],
+        )
+        .with_media_keys(&["AudioPrev"], PAGES),
+        Shortcut::new(
+            "speed_up",
+            gettext("Speed up playback"),
+            Playback,
+            PAGES,
+            &["bracketright"],
+        ),
+        Shortcut::new(
+            "slow_down",
+            gettext("Slow down playback"),
+            Playback,
+            PAGES,
+            &["bracketleft"],
+        ),
+        Shortcut::new(
+            "reset_speed",
+            gettext("Reset the playback speed"),
+            Playback,
+            PLAYBACK,
+            &["BackSpace"],
+        ),
+        Shortcut::new(
+            "toggle_loop_region",
+            gettext("Toggle looping the region selected on the waveform"),
+            Playback,
+            PLAYBACK,
+            &["<Shift>r"],
+        ),
+        Shortcut::new(
+            "clear_region",
+            gettext("Clear the region selected on the waveform"),
+            Playback,
+            PLAYBACK,
+            &["<Shift>BackSpace"],
+        ),
+        Shortcut::new(
+            "zoom_in",
+            gettext("Zoom the waveform in"),
+            Waveform,
+            PLAYBACK,
+            &["z"],
+        ),
+        Shortcut::new(
+            "zoom_out",
+            gettext("Zoom the waveform out"),
+            Waveform,
+            PLAYBACK,
+            &["<Shift>z"],
+        ),
+        Shortcut::new(
+            "toggle_lanes",
+            gettext("Toggle one lane per channel in the waveform"),
+            Waveform,
+            PLAYBACK,
+            &["<Shift>l"],
+        ),
+        Shortcut::new(
+            "toggle_dbfs",
+            gettext("Toggle the dBFS amplitude scale in the waveform"),
+            Waveform,
+            PLAYBACK,
+            &["d"],
+        ),
+        Shortcut::new(
+            "toggle_snap",
+            gettext("Toggle chapters boundaries snapping"),
+            Waveform,
+            PLAYBACK,
+            &["s"],
+        ),
+        Shortcut::new(
+            "add_chapter",
+            gettext("Add a chapter at current position"),
+            Chapters,
+            PLAYBACK,
+            &["plus", "KP_Add"],
+        ),
+        Shortcut::new(
+            "del_chapter",
+            gettext("Remove the chapter at current position"),
+            Chapters,
+            PLAYBACK,
+            &["minus", "KP_Subtract"],
+        ),
+        Shortcut::new(
+            "select_next_boundary",
+            gettext("Select the next chapter boundary"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>Down"],
+        ),
+        Shortcut::new(
+            "select_previous_boundary",
+            gettext("Select the previous chapter boundary"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>Up"],
+        ),
+        Shortcut::new(
+            "unselect_boundary",
+            gettext("Unselect the chapter boundary"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>BackSpace"],
+        ),
+        Shortcut::new(
+            "nudge_boundary_forward",
+            gettext("Move the selected boundary forward by the nudge step"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>Right"],
+        ),
+        Shortcut::new(
+            "nudge_boundary_back",
+            gettext("Move the selected boundary back by the nudge step"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>Left"],
+        ),
+        Shortcut::new(
+            "cycle_nudge_step",
+            gettext("Change the nudge step (1 sample, 1 ms, 10 ms, 1 frame)"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>n"],
+        ),
+        Shortcut::new(
+            "nudge_boundary_next_frame",
+            gettext("Move the selected boundary to the next video frame"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>period"],
+        ),
+        Shortcut::new(
+            "nudge_boundary_previous_frame",
+            gettext("Move the selected boundary to the previous video frame"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>comma"],
+        ),
+        Shortcut::new(
+            "edit_boundary",
+            gettext("Type the timestamp of the selected boundary"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>Return"],
+        ),
+        Shortcut::new(
+            "audition_boundary",
+            gettext("Play around the selected boundary"),
+            Chapters,
+            PLAYBACK,
+            &["<Alt>a"],
+        ),
+        Shortcut::new(
+            "toggle_show_list",
+            gettext("Toggle show/hide chapters list"),
+            Chapters,
+            PAGES,
+            &["l"],
+        ),
+        Shortcut::new(
+            "toggle_repeat_chapter",
+            gettext("Toggle repeat current chapter"),
+            Chapters,
+            PAGES,
+            &["r"],
+        ),
+        Shortcut::new(
+            "accept_scene_candidate",
+            gettext("Add a chapter at the nearest scene change candidate"),
+            Chapters,
+            PLAYBACK,
+            &["c"],
+        ),
+        Shortcut::new(
+            "import_cues",
+            gettext("Generate chapters from subtitle or lyric cues"),
+            Chapters,
+            PLAYBACK,
+            &["<Ctrl>I"],
+        ),
+        Shortcut::new(
+            "copy_chapters",
+            gettext("Copy the chapters list to the clipboard"),
+            Chapters,
+            PLAYBACK,
+            &["<Ctrl>C"],
+        ),
+        Shortcut::new(
+            "paste_chapters",
+            gettext("Replace the chapters with the list from the clipboard"),
+            Chapters,
+            PLAYBACK,
+            &["<Ctrl>V"],
+        ),
+        Shortcut::new(
+            "add_marker",
+            gettext("Drop a marker at current position"),
+            Markers,
+            PLAYBACK,
+            &["m"],
+        ),
+        Shortcut::new(
+            "next_marker",
+            gettext("Go to next marker"),
+            Markers,
+            PLAYBACK,
+            &["<Shift>Down"],
+        ),
+        Shortcut::new(
+            "previous_marker",
+            gettext("Go to previous marker"),
+            Markers,
+            PLAYBACK,
+            &["<Shift>Up"],
+        ),
+        Shortcut::new(
+            "edit_marker",
+            gettext("Edit the label and colour of the nearest marker"),
+            Markers,
+            PLAYBACK,
+            &["<Shift>m"],
+        ),
+        Shortcut::new(
+            "remove_marker",
+            gettext("Remove the nearest marker"),
+            Markers,
+            PLAYBACK,
+            &["<Ctrl>m"],
+        ),
+        Shortcut::new(
+            "convert_marker",
+            gettext("Convert the nearest marker to a chapter"),
+            Markers,
+            PLAYBACK,
+            &["<Alt>m"],
+        ),
+        Shortcut::new(
+            "convert_all_markers",
+            gettext("Convert all the markers to chapters"),
+            Markers,
+            PLAYBACK,
+            &["<Ctrl><Shift>m"],
+        ),
+        Shortcut::new(
+            "close_info_bar",
+            gettext("Close the info bar"),
+            Group::InfoBar,
+            INFO_BAR,
+            &["Escape"],
+        ),
+    ]
+}
+
+/// Returns a representation of `accel` which doesn't depend on the modifiers
+/// order, their aliases or the key case.
+pub fn normalize(accel: &str) -> String {
+    let mut modifiers = Vec::new();
+    let mut rest = accel.trim();
+    while let Some(stripped) = rest.strip_prefix('<') {
+        let (modifier, tail) = match stripped.split_once('>') {
+            Some(split) => split,
+            None => break,
+        };
+
+        let modifier = match modifier.to_lowercase().as_str() {
+            "ctrl" | "control" | "ctl" | "primary" => "ctrl".to_string(),
+            "alt" | "mod1" => "alt".to_string(),
+            other => other.to_string(),
+        };
+        modifiers.push(modifier);
+        rest = tail;
+    }
+
+    modifiers.sort();
+    modifiers.dedup();
+
+    let mut normalized = String::new();
+    for modifier in modifiers {
+        normalized.push('<');
+        normalized.push_str(&modifier);
+        normalized.push('>');
+    }
+    normalized.push_str(&rest.to_lowercase());
+
+    normalized
+}
+
+/// Returns the shortcut which already uses `accel` in a focus context
+/// shared with `action`.
+pub fn conflict(keymap: &Keymap, action: &str, accel: &str) -> Option<Shortcut> {
+    let mut shortcuts = shortcuts();
+    let idx = shortcuts
+        .iter()
+        .position(|shortcut| shortcut.action == action)?;
+    let target = shortcuts.remove(idx);
+
+    let accel = normalize(accel);
+    shortcuts.into_iter().find(|shortcut| {
+        shortcut.shares_context_with(&target)
+            && shortcut
+                .accels(keymap)
+                .iter()
+                .any(|other| normalize(other) == accel)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_accels() {
+        assert_eq!(normalize("<Ctrl>O"), normalize("<Primary>o"));
+        assert_eq!(normalize("<Shift><Control>z"), normalize("<Ctrl><Shift>Z"));
+        assert_eq!(normalize("<Mod1>Left"), normalize("<Alt>Left"));
+        assert_ne!(normalize("z"), normalize("<Shift>z"));
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut keymap = Keymap::default();
+
+        // Same context
+        let shortcut = conflict(&keymap, "zoom_in", "d").unwrap();
+        assert_eq!(shortcut.action, "toggle_dbfs");
+
+        // Global shortcut vs. playback page
+        let shortcut = conflict(&keymap, "zoom_in", "<Primary>o").unwrap();
+        assert_eq!(shortcut.action, "open");
+
+        // No shared context: the info bar is only active on its own
+        assert!(conflict(&keymap, "zoom_in", "Escape").is_none());
+
+        // Unused accelerator
+        assert!(conflict(&keymap, "zoom_in", "<Ctrl><Shift>F12").is_none());
+
+        // User defined accelerators replace the default ones
+        keymap
+            .accels
+            .insert("toggle_dbfs".to_string(), vec!["<Shift>d".to_string()]);
+        assert!(conflict(&keymap, "zoom_in", "d").is_none());
+        let shortcut = conflict(&keymap, "zoom_in", "<Shift>D").unwrap();
+        assert_eq!(shortcut.action, "toggle_dbfs");
+    }
+
+    #[test]
+    fn accels_for_contexts() {
+        let keymap = Keymap::default();
+        let play_pause = shortcuts()
+            .into_iter()
+            .find(|shortcut| shortcut.action == "play_pause")
+            .unwrap();
+
+        assert_eq!(
+            play_pause.accels_for(PlaybackPage, &keymap),
+            vec!["space".to_string(), "AudioPlay".to_string()]
+        );
+        assert_eq!(
+            play_pause.accels_for(TextEntry, &keymap),
+            vec!["AudioPlay".to_string()]
+        );
+        assert!(play_pause.accels_for(ShortcutCapture, &keymap).is_empty());
+    }
+}
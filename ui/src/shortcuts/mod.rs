@@ -0,0 +1,43 @@
+mod controller;
+pub use self::controller::Controller;
+
+mod dispatcher;
+pub use self::dispatcher::Dispatcher;
+
+mod keymap;
+
+use crate::UIEventChannel;
+
+#[derive(Debug)]
+pub enum Event {
+    ClearAccels(String),
+    Reset,
+    ResetAll,
+    SetAccel { action: String, accel: String },
+    Show,
+    ShowHelp,
+}
+
+fn clear_accels(action: String) {
+    UIEventChannel::send(Event::ClearAccels(action));
+}
+
+fn reset() {
+    UIEventChannel::send(Event::Reset);
+}
+
+fn reset_all() {
+    UIEventChannel::send(Event::ResetAll);
+}
+
+fn set_accel(action: String, accel: String) {
+    UIEventChannel::send(Event::SetAccel { action, accel });
+}
+
+pub fn show() {
+    UIEventChannel::send(Event::Show);
+}
+
+pub fn show_help() {
+    UIEventChannel::send(Event::ShowHelp);
+}
@@ -9,8 +9,12 @@ use std::{
 };
 
 use application::gettext;
-use media::{pipeline, MediaEvent};
-use metadata::{default_chapter_title, Duration, Format, MediaInfo, Stream, TocVisitor};
+use media::{
+    pipeline::{self, AudiogramFormat},
+    MediaEvent,
+};
+use metadata::{Duration, Format, MediaInfo, Stream, TocVisitor};
+use renderers::Timestamp;
 
 use crate::{
     generic_output::{self, prelude::*},
@@ -20,6 +24,13 @@ use crate::{
 
 pub type Controller = generic_output::Controller<ControllerImpl>;
 
+/// Output of the split perspective: audio files or audiogram videos.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SplitFormat {
+    Audio(Format),
+    Audiogram(AudiogramFormat),
+}
+
 impl Controller {
     pub fn new(builder: &gtk::Builder) -> Self {
         generic_output::Controller::<ControllerImpl>::new_generic(
@@ -31,7 +42,10 @@ impl Controller {
 
 macro_rules! update_list_with_format(
     ($self_:expr, $format:expr, $row:ident, $label:ident) => {
-        match pipeline::Splitter::check_requirements($format) {
+        update_list_with_format!($self_, pipeline::Splitter, $format, $row, $label)
+    };
+    ($self_:expr, $pipeline:ty, $format:expr, $row:ident, $label:ident) => {
+        match <$pipeline>::check_requirements($format) {
             Ok(_) => if !$self_.is_usable {
                 $self_.split_list.select_row(Some(&$self_.$row));
                 $self_.is_usable = true;
@@ -50,6 +64,7 @@ pub struct ControllerImpl {
 
     src_info: Option<Arc<RwLock<MediaInfo>>>,
     selected_audio: Option<Stream>,
+    excerpt: Option<gst::TocEntry>,
 
     split_list: gtk::ListBox,
     split_to_flac_row: gtk::ListBoxRow,
@@ -62,6 +77,10 @@ pub struct ControllerImpl {
     vorbis_warning_lbl: gtk::Label,
     split_to_mp3_row: gtk::ListBoxRow,
     mp3_warning_lbl: gtk::Label,
+    split_to_audiogram_mp4_row: gtk::ListBoxRow,
+    audiogram_mp4_warning_lbl: gtk::Label,
+    split_to_audiogram_webm_row: gtk::ListBoxRow,
+    audiogram_webm_warning_lbl: gtk::Label,
 
     split_btn: gtk::Button,
 }
@@ -76,47 +95,85 @@ impl OutputControllerImpl for ControllerImpl {
     const PROGRESS_BAR_NAME: &'static str = "split-progress";
 
     fn new_processor(&self) -> Processor {
-        let format = if self.split_to_flac_row.is_selected() {
-            Format::Flac
-        } else if self.split_to_wave_row.is_selected() {
-            Format::Wave
-        } else if self.split_to_opus_row.is_selected() {
-            Format::Opus
-        } else if self.split_to_vorbis_row.is_selected() {
-            Format::Vorbis
-        } else if self.split_to_mp3_row.is_selected() {
-            Format::MP3
-        } else {
-            unreachable!("`SplitController`: unknown split type");
-        };
+        let format = self
+            .selected_split_format()
+            .expect("`SplitController`: unknown split type");
 
         // Split button is not sensitive when no audio
         // stream is selected (see `streams_changed`)
         debug_assert!(self.selected_audio.is_some());
 
-        Processor {
-            src_info: Arc::clone(self.src_info.as_ref().unwrap()),
-            selected_audio: self.selected_audio.clone(),
-            split_file_info: Some({
-                let src_info = self.src_info.as_ref().unwrap().read().unwrap();
-                OutputMediaFileInfo::new(format, &src_info)
-            }),
-            toc_visitor: self
-                .src_info
+        // Audiograms are meant for excerpts: only export the selected region
+        // or chapter if any
+        let excerpt = self
+            .excerpt
+            .clone()
+            .filter(|_| matches!(format, SplitFormat::Audiogram(_)));
+        let toc_visitor = if excerpt.is_none() {
+            self.src_info
                 .as_ref()
                 .unwrap()
                 .read()
                 .unwrap()
                 .toc
                 .as_ref()
-                .map(TocVisitor::new),
-            splitter_pipeline: None,
+                .map(TocVisitor::new)
+        } else {
+            None
+        };
+
+        let (output_path, extension) = {
+            let src_info = self.src_info.as_ref().unwrap().read().unwrap();
+            match format {
+                SplitFormat::Audio(format) => {
+                    let file_info = OutputMediaFileInfo::new(format, &src_info);
+                    (file_info.path, file_info.extension)
+                }
+                SplitFormat::Audiogram(format) => {
+                    let extension = format.extension();
+                    (
+                        OutputMediaFileInfo::output_path(&src_info, extension),
+                        extension.to_owned(),
+                    )
+                }
+            }
+        };
+
+        Processor {
+            src_info: Arc::clone(self.src_info.as_ref().unwrap()),
+            selected_audio: self.selected_audio.clone(),
+            format,
+            output_path: Some(output_path),
+            extension,
+            toc_visitor,
+            excerpt,
+            pipeline: None,
             idx: 0,
             current_chapter: None,
             current_path: None,
             last_progress: 0f64,
         }
     }
+
+    fn selected_format(&self) -> Option<Format> {
+        // Audiograms are excerpts, not a split format to keep
+        match self.selected_split_format()? {
+            SplitFormat::Audio(format) => Some(format),
+            SplitFormat::Audiogram(_) => None,
+        }
+    }
+
+    fn select_format(&self, format: Format) {
+        if let Some((_, row)) = self.format_rows().into_iter().find(|(row_format, row)| {
+            *row_format == SplitFormat::Audio(format) && row.is_sensitive()
+        }) {
+            self.split_list.select_row(Some(row));
+        }
+    }
+
+    fn set_excerpt(&mut self, excerpt: Option<gst::TocEntry>) {
+        self.excerpt = excerpt;
+    }
 }
 
 impl UIController for ControllerImpl {
@@ -128,6 +185,7 @@ impl UIController for ControllerImpl {
     fn cleanup(&mut self) {
         self.src_info = None;
         self.selected_audio = None;
+        self.excerpt = None;
     }
 
     fn streams_changed(&mut self, info: &MediaInfo) {
@@ -145,6 +203,7 @@ impl ControllerImpl {
 
             src_info: None,
             selected_audio: None,
+            excerpt: None,
 
             split_list: builder.object(Self::LIST_NAME).unwrap(),
             split_to_flac_row: builder.object("flac_split-row").unwrap(),
@@ -157,6 +216,10 @@ impl ControllerImpl {
             vorbis_warning_lbl: builder.object("vorbis_warning-lbl").unwrap(),
             split_to_mp3_row: builder.object("mp3_split-row").unwrap(),
             mp3_warning_lbl: builder.object("mp3_warning-lbl").unwrap(),
+            split_to_audiogram_mp4_row: builder.object("audiogram_mp4_split-row").unwrap(),
+            audiogram_mp4_warning_lbl: builder.object("audiogram_mp4_warning-lbl").unwrap(),
+            split_to_audiogram_webm_row: builder.object("audiogram_webm_split-row").unwrap(),
+            audiogram_webm_warning_lbl: builder.object("audiogram_webm_warning-lbl").unwrap(),
 
             split_btn: builder.object(Self::BTN_NAME).unwrap(),
         };
@@ -171,22 +234,89 @@ impl ControllerImpl {
             vorbis_warning_lbl
         );
         update_list_with_format!(ctrl, Format::MP3, split_to_mp3_row, mp3_warning_lbl);
+        update_list_with_format!(
+            ctrl,
+            pipeline::Audiogram,
+            AudiogramFormat::MP4,
+            split_to_audiogram_mp4_row,
+            audiogram_mp4_warning_lbl
+        );
+        update_list_with_format!(
+            ctrl,
+            pipeline::Audiogram,
+            AudiogramFormat::WebM,
+            split_to_audiogram_webm_row,
+            audiogram_webm_warning_lbl
+        );
 
         ctrl.split_list.set_sensitive(ctrl.is_usable);
         ctrl.split_btn.set_sensitive(ctrl.is_usable);
 
         ctrl
     }
+
+    fn format_rows(&self) -> [(SplitFormat, &gtk::ListBoxRow); 7] {
+        [
+            (SplitFormat::Audio(Format::Flac), &self.split_to_flac_row),
+            (SplitFormat::Audio(Format::Wave), &self.split_to_wave_row),
+            (SplitFormat::Audio(Format::Opus), &self.split_to_opus_row),
+            (
+                SplitFormat::Audio(Format::Vorbis),
+                &self.split_to_vorbis_row,
+            ),
+            (SplitFormat::Audio(Format::MP3), &self.split_to_mp3_row),
+            (
+                SplitFormat::Audiogram(AudiogramFormat::MP4),
+                &self.split_to_audiogram_mp4_row,
+            ),
+            (
+                SplitFormat::Audiogram(AudiogramFormat::WebM),
+                &self.split_to_audiogram_webm_row,
+            ),
+        ]
+    }
+
+    fn selected_split_format(&self) -> Option<SplitFormat> {
+        self.format_rows()
+            .into_iter()
+            .find(|(_, row)| row.is_selected())
+            .map(|(format, _)| format)
+    }
+}
+
+enum SplitPipeline {
+    Audio(pipeline::Splitter),
+    Audiogram(pipeline::Audiogram),
+}
+
+impl SplitPipeline {
+    fn current_ts(&self) -> Option<Timestamp> {
+        match self {
+            SplitPipeline::Audio(splitter) => splitter.current_ts(),
+            SplitPipeline::Audiogram(audiogram) => audiogram.current_ts(),
+        }
+    }
+
+    fn cancel(&self) {
+        match self {
+            SplitPipeline::Audio(splitter) => splitter.cancel(),
+            SplitPipeline::Audiogram(audiogram) => audiogram.cancel(),
+        }
+    }
 }
 
 pub struct Processor {
     src_info: Arc<RwLock<MediaInfo>>,
     selected_audio: Option<Stream>,
 
-    split_file_info: Option<OutputMediaFileInfo>,
+    format: SplitFormat,
+    /// `None` once all the files are split.
+    output_path: Option<Rc<Path>>,
+    extension: String,
     idx: usize,
     toc_visitor: Option<TocVisitor>,
-    splitter_pipeline: Option<pipeline::Splitter>,
+    excerpt: Option<gst::TocEntry>,
+    pipeline: Option<SplitPipeline>,
     last_progress: f64,
     current_chapter: Option<gst::TocEntry>,
     current_path: Option<Rc<Path>>,
@@ -194,55 +324,17 @@ pub struct Processor {
 
 impl Processor {
     fn split_path(&self, chapter: &gst::TocEntry) -> Rc<Path> {
-        let mut split_name = String::new();
-
-        let src_info = self.src_info.read().unwrap();
-
-        // TODO: make format customisable
-        let artist = src_info
-            .media_artist_sortname()
-            .or_else(|| src_info.media_artist());
-        if let Some(artist) = artist {
-            split_name += &format!("{} - ", artist);
-        }
-
-        let album_title = src_info
-            .media_title_sortname()
-            .or_else(|| src_info.media_title());
-        if let Some(album_title) = album_title {
-            split_name += &format!("{} - ", album_title);
-        }
-
-        if self.toc_visitor.is_some() {
-            split_name += &format!("{:02}. ", self.idx);
-        }
-
-        let track_title = chapter
-            .tags()
-            .and_then(|tags| {
-                tags.get::<gst::tags::Title>()
-                    .map(|tag| tag.get().to_string())
-            })
-            .unwrap_or_else(default_chapter_title);
-
-        split_name += &track_title;
-
-        let lang = self.selected_audio.as_ref().and_then(|stream| {
-            stream
-                .tags
-                .index::<gst::tags::LanguageName>(0)
-                .or_else(|| stream.tags.index::<gst::tags::LanguageCode>(0))
-                .map(|value| value.get().to_string())
-        });
-        if let Some(lang) = lang {
-            split_name += &format!(" ({})", lang);
-        }
-
-        let split_file_info = self.split_file_info.as_ref().unwrap();
-
-        split_name += &format!(".{}", split_file_info.extension);
+        let split_name = self.src_info.read().unwrap().split_file_name(
+            chapter,
+            self.toc_visitor.as_ref().map(|_| self.idx),
+            &self.extension,
+        );
 
-        split_file_info.path.with_file_name(split_name).into()
+        self.output_path
+            .as_ref()
+            .unwrap()
+            .with_file_name(split_name)
+            .into()
     }
 }
 
@@ -254,6 +346,7 @@ impl Iterator for Processor {
             .toc_visitor
             .as_mut()
             .and_then(TocVisitor::next_chapter)
+            .or_else(|| self.excerpt.take())
             .or_else(|| {
                 if self.idx == 0 {
                     // No chapter defined => build a fake chapter corresponding to the whole file
@@ -279,7 +372,7 @@ impl Iterator for Processor {
             });
 
         if chapter.is_none() {
-            self.split_file_info = None;
+            self.output_path = None;
             return None;
         }
 
@@ -305,8 +398,6 @@ impl MediaProcessorImpl for Processor {
     fn process(&mut self, output_path: &Path) -> Result<ProcessingType, MediaProcessorError> {
         let (res, receiver) = {
             let src_info = self.src_info.read().unwrap();
-            let split_file_info = self.split_file_info.as_ref().unwrap();
-
             let stream_id = if src_info.streams.collection(gst::StreamType::AUDIO).len() > 1 {
                 Some(self.selected_audio.as_ref().unwrap().id.to_string())
             } else {
@@ -317,19 +408,32 @@ impl MediaProcessorImpl for Processor {
 
             let (sender, receiver) = async_mpsc::channel(MEDIA_EVENT_CHANNEL_CAPACITY);
 
-            let res = pipeline::Splitter::try_new(
-                &src_info.path,
-                output_path,
-                stream_id,
-                split_file_info.format,
-                self.current_chapter.take().expect("no current_chapter"),
-                sender,
-            );
+            let chapter = self.current_chapter.take().expect("no current_chapter");
+            let res = match self.format {
+                SplitFormat::Audiogram(format) => pipeline::Audiogram::try_new(
+                    &src_info.path,
+                    output_path,
+                    stream_id,
+                    format,
+                    chapter,
+                    sender,
+                )
+                .map(SplitPipeline::Audiogram),
+                SplitFormat::Audio(format) => pipeline::Splitter::try_new(
+                    &src_info.path,
+                    output_path,
+                    stream_id,
+                    format,
+                    chapter,
+                    sender,
+                )
+                .map(SplitPipeline::Audio),
+            };
 
             (res, receiver)
         };
 
-        self.splitter_pipeline =
+        self.pipeline =
             Some(res.map_err(|err| {
                 gettext("Failed to prepare for split. {}").replacen("{}", &err, 1)
             })?);
@@ -338,7 +442,7 @@ impl MediaProcessorImpl for Processor {
     }
 
     fn cancel(&mut self) {
-        if let Some(pipeline) = self.splitter_pipeline.as_mut() {
+        if let Some(pipeline) = self.pipeline.as_mut() {
             pipeline.cancel();
 
             if let Some(current_path) = self.current_path.take() {
@@ -359,7 +463,7 @@ impl MediaProcessorImpl for Processor {
             MediaEvent::Eos => {
                 self.current_chapter = None;
                 self.current_path = None;
-                self.splitter_pipeline = None;
+                self.pipeline = None;
                 Ok(MediaEventHandling::Done)
             }
             MediaEvent::FailedToExport(err) => Err(gettext("Failed to split media. {}")
@@ -374,11 +478,7 @@ impl MediaProcessorImpl for Processor {
         if duration > Duration::default() {
             // With some formats, we can't retrieve a proper ts between 2 files
             // so, just report known last progress in this case
-            if let Some(ts) = self
-                .splitter_pipeline
-                .as_ref()
-                .and_then(pipeline::Splitter::current_ts)
-            {
+            if let Some(ts) = self.pipeline.as_ref().and_then(SplitPipeline::current_ts) {
                 self.last_progress = ts.as_f64() / duration.as_f64()
             }
 
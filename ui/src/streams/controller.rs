@@ -140,6 +140,35 @@ impl<Impl: UIStreamImpl> UIStream<Impl> {
         });
     }
 
+    /// Restores the selection and export flags saved in a project.
+    fn restore(&mut self, project_streams: &metadata::ProjectStreams) {
+        let iter = match self.store.iter_first() {
+            Some(iter) => iter,
+            None => return,
+        };
+
+        loop {
+            let stream_id = self
+                .store
+                .value(&iter, STREAM_ID_COL as i32)
+                .get::<String>()
+                .unwrap();
+
+            let must_export = !project_streams.not_exported.contains(&stream_id);
+            self.store
+                .set_value(&iter, EXPORT_FLAG_COL, &glib::Value::from(&must_export));
+
+            if project_streams.selected.contains(&stream_id) {
+                self.treeview.selection().select_iter(&iter);
+                self.selected = Some(stream_id.into());
+            }
+
+            if !self.store.iter_next(&iter) {
+                break;
+            }
+        }
+    }
+
     fn add_stream(&self, stream: &metadata::Stream) -> gtk::TreeIter {
         let id_parts: Vec<&str> = stream.id.split('/').collect();
         let stream_id_display = if id_parts.len() == 2 {
@@ -442,6 +471,12 @@ impl Controller {
         }
     }
 
+    pub fn restore(&mut self, project_streams: &metadata::ProjectStreams) {
+        self.video.restore(project_streams);
+        self.audio.restore(project_streams);
+        self.text.restore(project_streams);
+    }
+
     pub fn selected_streams(&self) -> Vec<Arc<str>> {
         let mut streams: Vec<Arc<str>> = Vec::new();
         if let Some(stream) = self.video.selected.as_ref() {
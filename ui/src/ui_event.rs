@@ -2,7 +2,10 @@ use futures::channel::mpsc as async_mpsc;
 
 use std::cell::{Cell, RefCell};
 
-use crate::{audio, export, info, info_bar, main_panel, playback, split, streams};
+use crate::{
+    audio, batch, export, info, info_bar, main_panel, playback, preferences, recent, shortcuts,
+    split, streams,
+};
 
 thread_local! {
     pub static UI_EVENT_CHANNEL: UIEventChannel = UIEventChannel::new();
@@ -37,11 +40,15 @@ impl UIEventChannel {
 #[derive(Debug)]
 pub enum UIEvent {
     Audio(audio::Event),
+    Batch(batch::Event),
     Export(export::Event),
     Info(info::Event),
     InfoBar(info_bar::Event),
     Main(main_panel::Event),
     Playback(playback::Event),
+    Preferences(preferences::Event),
+    Recent(recent::Event),
+    Shortcuts(shortcuts::Event),
     Split(split::Event),
     Streams(streams::Event),
 }
@@ -52,6 +59,12 @@ impl From<audio::Event> for UIEvent {
     }
 }
 
+impl From<batch::Event> for UIEvent {
+    fn from(event: batch::Event) -> Self {
+        UIEvent::Batch(event)
+    }
+}
+
 impl From<export::Event> for UIEvent {
     fn from(event: export::Event) -> Self {
         UIEvent::Export(event)
@@ -82,6 +95,24 @@ impl From<playback::Event> for UIEvent {
     }
 }
 
+impl From<preferences::Event> for UIEvent {
+    fn from(event: preferences::Event) -> Self {
+        UIEvent::Preferences(event)
+    }
+}
+
+impl From<recent::Event> for UIEvent {
+    fn from(event: recent::Event) -> Self {
+        UIEvent::Recent(event)
+    }
+}
+
+impl From<shortcuts::Event> for UIEvent {
+    fn from(event: shortcuts::Event) -> Self {
+        UIEvent::Shortcuts(event)
+    }
+}
+
 impl From<split::Event> for UIEvent {
     fn from(event: split::Event) -> Self {
         UIEvent::Split(event)
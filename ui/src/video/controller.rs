@@ -60,40 +60,41 @@ impl Controller {
     pub fn new(builder: &gtk::Builder, args: &CommandLineArguments) -> Self {
         let container: gtk::Box = builder.object("video-container").unwrap();
 
-        let video_output = if !args.disable_gl && !CONFIG.read().unwrap().media.is_gl_disabled {
-            gst::ElementFactory::make("gtkglsink")
-                .name("gtkglsink")
-                .build()
-                .map(|gtkglsink| {
-                    let glsinkbin = gst::ElementFactory::make("glsinkbin")
-                        .name("video_sink")
-                        .build()
-                        .expect("PlaybackPipeline: couldn't get `glsinkbin` from `gtkglsink`");
-                    glsinkbin.set_property("sink", &gtkglsink);
-
-                    debug!("Using gtkglsink");
-                    VideoOutput {
-                        sink: glsinkbin,
-                        widget: gtkglsink.property::<gtk::Widget>("widget"),
-                    }
-                })
-                .ok()
-        } else {
-            None
-        }
-        .or_else(|| {
-            gst::ElementFactory::make("gtksink")
-                .name("video_sink")
-                .build()
-                .map(|sink| {
-                    debug!("Using gtksink");
-                    VideoOutput {
-                        sink: sink.clone(),
-                        widget: sink.property::<gtk::Widget>("widget"),
-                    }
-                })
-                .ok()
-        });
+        let video_output =
+            if !args.disable_gl && !CONFIG.read().unwrap().preferences.is_gl_disabled {
+                gst::ElementFactory::make("gtkglsink")
+                    .name("gtkglsink")
+                    .build()
+                    .map(|gtkglsink| {
+                        let glsinkbin = gst::ElementFactory::make("glsinkbin")
+                            .name("video_sink")
+                            .build()
+                            .expect("PlaybackPipeline: couldn't get `glsinkbin` from `gtkglsink`");
+                        glsinkbin.set_property("sink", &gtkglsink);
+
+                        debug!("Using gtkglsink");
+                        VideoOutput {
+                            sink: glsinkbin,
+                            widget: gtkglsink.property::<gtk::Widget>("widget"),
+                        }
+                    })
+                    .ok()
+            } else {
+                None
+            }
+            .or_else(|| {
+                gst::ElementFactory::make("gtksink")
+                    .name("video_sink")
+                    .build()
+                    .map(|sink| {
+                        debug!("Using gtksink");
+                        VideoOutput {
+                            sink: sink.clone(),
+                            widget: sink.property::<gtk::Widget>("widget"),
+                        }
+                    })
+                    .ok()
+            });
 
         if let Some(video_output) = video_output.as_ref() {
             container.pack_start(&video_output.widget, true, true, 0);